@@ -0,0 +1,122 @@
+//! Wake-ups for asynchronous tasks waiting on timer-driven channels.
+//!
+//! Nobody sends a notification when a deadline passes, so a task that waits for one hands its
+//! waker to a single background thread. The thread sleeps until the earliest deadline according
+//! to the crate's clock and wakes up the tasks whose deadlines have passed. It is started on first
+//! use and lives for the rest of the process.
+//!
+//! The thread is shared by all tasks rather than spawned per channel or per task, since there is
+//! no way to get woken up at a given time without one unless the executor provides a timer. Tasks
+//! take their waker back as soon as they stop waiting, so wakers don't pile up in the thread.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, Once};
+use std::task;
+use std::thread::{self, Thread};
+use std::time::Instant;
+
+use crate::clock;
+use crate::utils::Lazy;
+
+/// Identifies a waker handed to the background thread.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) struct AlarmId(usize);
+
+/// Wakers waiting for their deadlines, and the thread that wakes them.
+struct Alarms {
+    /// Wakers along with their ids and the instants at which to wake them.
+    pending: Mutex<Vec<(AlarmId, Instant, task::Waker)>>,
+
+    /// The id of the next waker.
+    next_id: AtomicUsize,
+
+    /// The background thread.
+    thread: Mutex<Option<Thread>>,
+}
+
+/// Returns the alarms, starting the background thread on first use.
+fn alarms() -> &'static Alarms {
+    static ALARMS: Lazy<Alarms> = Lazy::new();
+    static START: Once = Once::new();
+
+    let alarms = ALARMS.get_or_init(|| Alarms {
+        pending: Mutex::new(Vec::new()),
+        next_id: AtomicUsize::new(0),
+        thread: Mutex::new(None),
+    });
+    START.call_once(|| {
+        let handle = thread::Builder::new()
+            .name("crossbeam-channel-alarm".to_string())
+            .spawn(move || alarms.run())
+            .expect("failed to spawn the alarm thread");
+        *alarms.thread.lock().unwrap() = Some(handle.thread().clone());
+    });
+    alarms
+}
+
+/// Wakes up the task at the given instant.
+///
+/// The waker is held until the instant is reached or the returned id is passed to [`cancel`].
+pub(crate) fn wake_at(when: Instant, waker: &task::Waker) -> AlarmId {
+    let alarms = alarms();
+    let id = AlarmId(alarms.next_id.fetch_add(1, Ordering::Relaxed));
+    alarms
+        .pending
+        .lock()
+        .unwrap()
+        .push((id, when, waker.clone()));
+
+    // Let the thread recompute how long to sleep.
+    if let Some(thread) = &*alarms.thread.lock().unwrap() {
+        thread.unpark();
+    }
+    id
+}
+
+/// Drops the waker with the given id if it hasn't been woken up yet.
+pub(crate) fn cancel(id: AlarmId) {
+    let waker = {
+        let mut pending = alarms().pending.lock().unwrap();
+        match pending.iter().position(|(other, _, _)| *other == id) {
+            Some(i) => pending.swap_remove(i).2,
+            None => return,
+        }
+    };
+
+    // The waker is dropped outside the lock in case dropping it wakes up something else.
+    drop(waker);
+}
+
+impl Alarms {
+    /// Wakes up tasks as their deadlines pass, forever.
+    fn run(&self) {
+        let mut due = Vec::new();
+
+        loop {
+            let now = clock::now();
+            let mut next: Option<Instant> = None;
+            {
+                let mut pending = self.pending.lock().unwrap();
+                let mut i = 0;
+                while i < pending.len() {
+                    let when = pending[i].1;
+                    if when <= now {
+                        due.push(pending.swap_remove(i).2);
+                    } else {
+                        next = Some(next.map_or(when, |n| n.min(when)));
+                        i += 1;
+                    }
+                }
+            }
+
+            for waker in due.drain(..) {
+                waker.wake();
+            }
+
+            match next {
+                None => thread::park(),
+                Some(when) => clock::park_until(when),
+            }
+        }
+    }
+}
@@ -1,19 +1,35 @@
 //! The channel interface.
 
+use std::collections::VecDeque;
 use std::fmt;
+use std::future::Future;
 use std::iter::FusedIterator;
 use std::mem;
 use std::panic::{RefUnwindSafe, UnwindSafe};
-use std::sync::Arc;
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+use std::task::{self, Poll};
 use std::time::{Duration, Instant};
 
+#[cfg(feature = "futures")]
+use futures_core::Stream;
+#[cfg(feature = "futures")]
+use futures_sink::Sink;
+
+use crate::alarm;
+use crate::clock;
 use crate::context::Context;
 use crate::counter;
+use crate::custom::{Registration, RegistrationId, Selectable, Slot};
 use crate::err::{
     RecvError, RecvTimeoutError, SendError, SendTimeoutError, TryRecvError, TrySendError,
 };
 use crate::flavors;
-use crate::select::{Operation, SelectHandle, Token};
+use crate::metrics::{self, Metrics, Wait};
+use crate::select::{Operation, Select, SelectHandle, Token};
+use crate::waker::Waiter;
+#[cfg(feature = "metrics")]
+use crate::ChannelStats;
 
 /// Creates a channel of unbounded capacity.
 ///
@@ -44,12 +60,8 @@ use crate::select::{Operation, SelectHandle, Token};
 /// ```
 pub fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
     let (s, r) = counter::new(flavors::list::Channel::new());
-    let s = Sender {
-        flavor: SenderFlavor::List(s),
-    };
-    let r = Receiver {
-        flavor: ReceiverFlavor::List(r),
-    };
+    let s = Sender::new(SenderFlavor::List(s));
+    let r = Receiver::new(ReceiverFlavor::List(r));
     (s, r)
 }
 
@@ -106,25 +118,396 @@ pub fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
 pub fn bounded<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
     if cap == 0 {
         let (s, r) = counter::new(flavors::zero::Channel::new());
-        let s = Sender {
-            flavor: SenderFlavor::Zero(s),
-        };
-        let r = Receiver {
-            flavor: ReceiverFlavor::Zero(r),
-        };
+        let s = Sender::new(SenderFlavor::Zero(s));
+        let r = Receiver::new(ReceiverFlavor::Zero(r));
         (s, r)
     } else {
         let (s, r) = counter::new(flavors::array::Channel::with_capacity(cap));
-        let s = Sender {
-            flavor: SenderFlavor::Array(s),
-        };
-        let r = Receiver {
-            flavor: ReceiverFlavor::Array(r),
-        };
+        let s = Sender::new(SenderFlavor::Array(s));
+        let r = Receiver::new(ReceiverFlavor::Array(r));
+        (s, r)
+    }
+}
+
+/// Creates a channel of bounded capacity that never blocks senders.
+///
+/// This channel has a buffer that can hold at most `cap` messages at a time. When the buffer is
+/// full, send operations complete immediately anyway and a message gets evicted according to
+/// `overflow`: either the oldest message in the channel or the new message itself. Use
+/// [`Sender::force_send`] to get the evicted message back.
+///
+/// # Panics
+///
+/// Panics if `cap` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::{bounded_with_policy, Overflow};
+///
+/// let (s, r) = bounded_with_policy(2, Overflow::DropOldest);
+///
+/// s.send(1).unwrap();
+/// s.send(2).unwrap();
+///
+/// // The channel is full, so the oldest message gets evicted.
+/// assert_eq!(s.force_send(3), Ok(Some(1)));
+///
+/// assert_eq!(r.recv(), Ok(2));
+/// assert_eq!(r.recv(), Ok(3));
+/// ```
+pub fn bounded_with_policy<T>(cap: usize, overflow: Overflow) -> (Sender<T>, Receiver<T>) {
+    let (s, r) = counter::new(flavors::array::Channel::with_overflow(cap, overflow));
+    let s = Sender::new(SenderFlavor::Array(s));
+    let r = Receiver::new(ReceiverFlavor::Array(r));
+    (s, r)
+}
+
+/// The policy for sending a message into a full channel created by [`bounded_with_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Overflow {
+    /// The oldest message in the channel is evicted to make room for the new one.
+    ///
+    /// While the oldest message is being inspected by [`Receiver::try_peek_with`] or
+    /// [`Receiver::try_recv_if`], it can't be evicted, so the new message is discarded instead.
+    DropOldest,
+
+    /// The new message is discarded and the channel is left unchanged.
+    DropNewest,
+}
+
+/// Creates a channel of bounded capacity whose messages expire according to `expiry`.
+///
+/// This is the same as [`bounded`], except a message that is still in the channel when its
+/// time-to-live runs out is never received. Receive operations skip it and hand it to the expiry
+/// handler instead, if there is one, or drop it. Every message gets the time-to-live of `expiry`,
+/// unless it is sent with [`Sender::send_with_ttl`].
+///
+/// Expired messages are removed lazily, when a receive operation reaches them. Until then they
+/// count towards [`len`] and take up capacity, so senders may have to wait for a receiver even if
+/// every message in a full channel has expired.
+///
+/// A zero-capacity channel hands messages over to receivers directly, so its messages never
+/// expire.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use crossbeam_channel::{bounded_with_expiry, unbounded, Expiry};
+///
+/// // Collect expired jobs in a dead-letter channel.
+/// let (dead_s, dead_r) = unbounded();
+/// let expiry = Expiry::new()
+///     .ttl(Duration::from_millis(100))
+///     .on_expired(move |job| dead_s.send(job).unwrap());
+///
+/// let (s, r) = bounded_with_expiry(4, expiry);
+/// s.send("stale").unwrap();
+/// thread::sleep(Duration::from_millis(200));
+/// s.send("fresh").unwrap();
+///
+/// assert_eq!(r.recv(), Ok("fresh"));
+/// assert_eq!(dead_r.try_recv(), Ok("stale"));
+/// ```
+///
+/// [`len`]: Receiver::len
+pub fn bounded_with_expiry<T>(cap: usize, expiry: Expiry<T>) -> (Sender<T>, Receiver<T>) {
+    if cap == 0 {
+        bounded(0)
+    } else {
+        let (s, r) = counter::new(flavors::array::Channel::with_expiry(cap, expiry));
+        let s = Sender::new(SenderFlavor::Array(s));
+        let r = Receiver::new(ReceiverFlavor::Array(r));
         (s, r)
     }
 }
 
+/// Creates a channel of unbounded capacity whose messages expire according to `expiry`.
+///
+/// This is the same as [`unbounded`], except a message that is still in the channel when its
+/// time-to-live runs out is never received. Receive operations skip it and hand it to the expiry
+/// handler instead, if there is one, or drop it. Every message gets the time-to-live of `expiry`,
+/// unless it is sent with [`Sender::send_with_ttl`].
+///
+/// Expired messages are removed lazily, when a receive operation reaches them. Until then they
+/// count towards [`len`].
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use std::thread;
+/// use std::time::Duration;
+/// use crossbeam_channel::{unbounded_with_expiry, Expiry, TryRecvError};
+///
+/// let expired = Arc::new(AtomicUsize::new(0));
+/// let counter = expired.clone();
+/// let expiry = Expiry::new()
+///     .ttl(Duration::from_millis(100))
+///     .on_expired(move |_| {
+///         counter.fetch_add(1, Ordering::SeqCst);
+///     });
+///
+/// let (s, r) = unbounded_with_expiry(expiry);
+/// s.send(1).unwrap();
+/// s.send(2).unwrap();
+/// thread::sleep(Duration::from_millis(200));
+///
+/// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+/// assert_eq!(expired.load(Ordering::SeqCst), 2);
+/// ```
+///
+/// [`len`]: Receiver::len
+pub fn unbounded_with_expiry<T>(expiry: Expiry<T>) -> (Sender<T>, Receiver<T>) {
+    let (s, r) = counter::new(flavors::list::Channel::with_expiry(expiry));
+    let s = Sender::new(SenderFlavor::List(s));
+    let r = Receiver::new(ReceiverFlavor::List(r));
+    (s, r)
+}
+
+/// The expiry policy for a channel created by [`bounded_with_expiry`] or
+/// [`unbounded_with_expiry`].
+///
+/// The policy consists of a default time-to-live for messages and a handler that is called with
+/// every expired message. Both are optional: without a time-to-live, only messages sent with
+/// [`Sender::send_with_ttl`] expire, and without a handler, expired messages are dropped.
+///
+/// The handler runs on the thread of the receive operation that found the expired message, so it
+/// should be quick and must not block on the channel itself. To hand expired messages over to
+/// another part of the program, send them into a dead-letter channel, as in the example of
+/// [`bounded_with_expiry`].
+///
+/// Any operation that looks for a message can find an expired one, including [`try_recv`],
+/// [`try_peek_with`], and the attempts [`Select`] and [`select!`] make while checking whether an
+/// operation is ready. The handler is called from inside that operation, but only after the
+/// expired message has been taken out of the channel, so other receivers don't wait for it. If the
+/// handler panics, the panic propagates out of the operation that called it.
+///
+/// [`try_recv`]: Receiver::try_recv
+/// [`try_peek_with`]: Receiver::try_peek_with
+/// [`Select`]: crate::Select
+/// [`select!`]: crate::select
+pub struct Expiry<T> {
+    /// The time-to-live of messages sent without one.
+    ttl: Option<Duration>,
+
+    /// The handler for expired messages.
+    handler: Option<Box<dyn Fn(T) + Send + Sync>>,
+}
+
+impl<T> Expiry<T> {
+    /// Creates a policy without a default time-to-live and without a handler.
+    pub fn new() -> Expiry<T> {
+        Expiry {
+            ttl: None,
+            handler: None,
+        }
+    }
+
+    /// Sets the time-to-live of messages sent without one.
+    pub fn ttl(mut self, ttl: Duration) -> Expiry<T> {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the handler that is called with every expired message.
+    pub fn on_expired<F>(mut self, handler: F) -> Expiry<T>
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        self.handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Returns the deadline of a message sent now, given its own time-to-live.
+    pub(crate) fn deadline(&self, ttl: Option<Duration>) -> Option<Instant> {
+        ttl.or(self.ttl).map(|ttl| clock::now() + ttl)
+    }
+
+    /// Returns `true` if a message with the given deadline has expired.
+    pub(crate) fn is_expired(&self, deadline: Option<Instant>) -> bool {
+        match deadline {
+            Some(d) => clock::now() >= d,
+            None => false,
+        }
+    }
+
+    /// Disposes of an expired message.
+    pub(crate) fn expire(&self, msg: T) {
+        if let Some(handler) = &self.handler {
+            handler(msg);
+        }
+    }
+}
+
+impl<T> Default for Expiry<T> {
+    fn default() -> Expiry<T> {
+        Expiry::new()
+    }
+}
+
+impl<T> fmt::Debug for Expiry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Expiry").field("ttl", &self.ttl).finish()
+    }
+}
+
+/// Creates a channel of unbounded capacity that delivers messages in order of priority.
+///
+/// Receive operations always take the greatest message in the channel, as determined by its
+/// [`Ord`] implementation. Messages that compare equal are received in the order they were sent.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::priority_unbounded;
+///
+/// let (s, r) = priority_unbounded();
+///
+/// s.send(1).unwrap();
+/// s.send(3).unwrap();
+/// s.send(2).unwrap();
+///
+/// assert_eq!(r.recv(), Ok(3));
+/// assert_eq!(r.recv(), Ok(2));
+/// assert_eq!(r.recv(), Ok(1));
+/// ```
+pub fn priority_unbounded<T: Ord>() -> (Sender<T>, Receiver<T>) {
+    let (s, r) = counter::new(flavors::priority::Channel::with_capacity(None));
+    let s = Sender::new(SenderFlavor::Priority(s));
+    let r = Receiver::new(ReceiverFlavor::Priority(r));
+    (s, r)
+}
+
+/// Creates a channel of bounded capacity that delivers messages in order of priority.
+///
+/// This channel has a buffer that can hold at most `cap` messages at a time. Receive operations
+/// always take the greatest message in the channel, as determined by its [`Ord`] implementation.
+/// Messages that compare equal are received in the order they were sent.
+///
+/// # Panics
+///
+/// Panics if `cap` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::{priority_bounded, TrySendError};
+///
+/// let (s, r) = priority_bounded(2);
+///
+/// s.send("low").unwrap();
+/// s.send("urgent").unwrap();
+/// assert_eq!(s.try_send("late"), Err(TrySendError::Full("late")));
+///
+/// assert_eq!(r.recv(), Ok("urgent"));
+/// assert_eq!(r.recv(), Ok("low"));
+/// ```
+pub fn priority_bounded<T: Ord>(cap: usize) -> (Sender<T>, Receiver<T>) {
+    let (s, r) = counter::new(flavors::priority::Channel::with_capacity(Some(cap)));
+    let s = Sender::new(SenderFlavor::Priority(s));
+    let r = Receiver::new(ReceiverFlavor::Priority(r));
+    (s, r)
+}
+
+/// Creates a broadcast channel of bounded capacity.
+///
+/// Every message sent into this channel is delivered to every receiver. Cloning a [`Receiver`]
+/// creates a new receiver that starts at the same position as the original one and from then on
+/// receives messages independently of it.
+///
+/// The channel keeps the `cap` most recent messages. Send operations never block: if the buffer
+/// is full, the oldest message is evicted to make room for the new one. Receivers that haven't
+/// received the evicted message skip ahead to the oldest message that is still in the channel. Use
+/// [`broadcast_with_lagging`] to choose a different policy for such lagging receivers.
+///
+/// # Panics
+///
+/// Panics if `cap` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::broadcast;
+///
+/// let (s, r1) = broadcast(2);
+/// let r2 = r1.clone();
+///
+/// s.send(1).unwrap();
+/// s.send(2).unwrap();
+///
+/// assert_eq!(r1.recv(), Ok(1));
+/// assert_eq!(r1.recv(), Ok(2));
+///
+/// // The oldest message gets evicted, so `r2` skips ahead.
+/// s.send(3).unwrap();
+///
+/// assert_eq!(r2.recv(), Ok(2));
+/// assert_eq!(r2.recv(), Ok(3));
+/// assert_eq!(r1.recv(), Ok(3));
+/// ```
+pub fn broadcast<T: Clone>(cap: usize) -> (Sender<T>, Receiver<T>) {
+    broadcast_with_lagging(cap, Lagging::SkipAhead)
+}
+
+/// Creates a broadcast channel of bounded capacity with the given policy for lagging receivers.
+///
+/// This is the same as [`broadcast`], except a receiver that falls more than `cap` messages
+/// behind is treated according to `lagging`.
+///
+/// # Panics
+///
+/// Panics if `cap` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::{broadcast_with_lagging, Lagging, TryRecvError};
+///
+/// let (s, r1) = broadcast_with_lagging(1, Lagging::Disconnect);
+/// let r2 = r1.clone();
+///
+/// s.send(1).unwrap();
+/// assert_eq!(r1.recv(), Ok(1));
+///
+/// // `r2` misses the first message and gets disconnected.
+/// s.send(2).unwrap();
+///
+/// assert_eq!(r2.try_recv(), Err(TryRecvError::Disconnected));
+/// assert_eq!(r1.recv(), Ok(2));
+/// ```
+pub fn broadcast_with_lagging<T: Clone>(cap: usize, lagging: Lagging) -> (Sender<T>, Receiver<T>) {
+    let (s, r) = counter::new(flavors::broadcast::Channel::with_capacity(cap, lagging));
+    let s = Sender::new(SenderFlavor::Broadcast(s));
+    let r = Receiver::new(ReceiverFlavor::Broadcast(
+        r,
+        flavors::broadcast::Cursor::new(),
+    ));
+    (s, r)
+}
+
+/// The policy for receivers that fall too far behind in a broadcast channel.
+///
+/// A receiver is lagging when messages it hasn't received yet get evicted from the channel to make
+/// room for new ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lagging {
+    /// The receiver skips the evicted messages and continues with the oldest message that is still
+    /// in the channel.
+    SkipAhead,
+
+    /// The receiver gets disconnected from the channel.
+    ///
+    /// All further receive operations on it fail as if the channel were disconnected and empty,
+    /// returning the same errors as they would then. Use [`Receiver::is_lagged`] to tell whether
+    /// a receiver was disconnected for lagging behind rather than because all senders were
+    /// dropped.
+    Disconnect,
+}
+
 /// Creates a receiver that delivers a message after a certain duration of time.
 ///
 /// The channel is bounded with capacity of 1 and never gets disconnected. Exactly one message will
@@ -171,9 +554,9 @@ pub fn bounded<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
 /// assert!(eq(Instant::now(), start + ms(500)));
 /// ```
 pub fn after(duration: Duration) -> Receiver<Instant> {
-    Receiver {
-        flavor: ReceiverFlavor::At(Arc::new(flavors::at::Channel::new_timeout(duration))),
-    }
+    Receiver::new(ReceiverFlavor::At(Arc::new(
+        flavors::at::Channel::new_timeout(duration),
+    )))
 }
 
 /// Creates a receiver that delivers a message at a certain instant in time.
@@ -219,8 +602,191 @@ pub fn after(duration: Duration) -> Receiver<Instant> {
 /// assert!(Instant::now() > start + ms(100));
 /// ```
 pub fn at(when: Instant) -> Receiver<Instant> {
-    Receiver {
-        flavor: ReceiverFlavor::At(Arc::new(flavors::at::Channel::new_deadline(when))),
+    Receiver::new(ReceiverFlavor::At(Arc::new(
+        flavors::at::Channel::new_deadline(when),
+    )))
+}
+
+/// A timer whose deadline can be moved or cancelled.
+///
+/// A timer owns a receiver that works like the one created by [`at`]: a single message is
+/// delivered at the deadline, and the message is the deadline itself. Unlike [`at`], the deadline
+/// can be changed at any time with [`reset`] or removed with [`cancel`]. Changes take effect
+/// immediately, even for threads already blocked on the receiver or in [`select!`].
+///
+/// Resetting a timer after its message has been received arms it again, so the same receiver and
+/// [`Select`] can be reused over and over.
+///
+/// # Examples
+///
+/// A watchdog that fires unless it gets fed in time:
+///
+/// ```
+/// use std::thread;
+/// use std::time::{Duration, Instant};
+/// use crossbeam_channel::{select, unbounded, Timer};
+///
+/// let ms = |ms| Duration::from_millis(ms);
+///
+/// let (s, r) = unbounded();
+/// let watchdog = Timer::after(ms(200));
+///
+/// // The feeder stops after a few messages, but `s` is kept alive so that `r` doesn't get
+/// // disconnected and the watchdog gets to fire.
+/// let feeder = s.clone();
+/// thread::spawn(move || {
+///     for i in 0..3 {
+///         thread::sleep(ms(50));
+///         feeder.send(i).unwrap();
+///     }
+/// });
+///
+/// loop {
+///     select! {
+///         recv(r) -> msg => {
+///             println!("fed: {:?}", msg);
+///             watchdog.reset(Instant::now() + ms(200));
+///         }
+///         recv(watchdog.receiver()) -> _ => {
+///             println!("starving");
+///             break;
+///         }
+///     }
+/// }
+/// ```
+///
+/// [`reset`]: Timer::reset
+/// [`cancel`]: Timer::cancel
+/// [`Select`]: crate::Select
+/// [`select!`]: crate::select
+pub struct Timer {
+    /// The channel shared with the receiver.
+    chan: Arc<flavors::at::Channel>,
+
+    /// The receiver the message gets delivered to.
+    receiver: Receiver<Instant>,
+}
+
+impl Timer {
+    /// Creates a timer that fires at a certain instant in time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use crossbeam_channel::Timer;
+    ///
+    /// let when = Instant::now() + Duration::from_millis(100);
+    /// let timer = Timer::at(when);
+    ///
+    /// assert_eq!(timer.receiver().recv(), Ok(when));
+    /// ```
+    pub fn at(when: Instant) -> Timer {
+        let chan = Arc::new(flavors::at::Channel::new_deadline(when));
+        Timer {
+            receiver: Receiver::new(ReceiverFlavor::At(chan.clone())),
+            chan,
+        }
+    }
+
+    /// Creates a timer that fires after a certain duration of time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use crossbeam_channel::Timer;
+    ///
+    /// let start = Instant::now();
+    /// let timer = Timer::after(Duration::from_millis(100));
+    ///
+    /// let fired = timer.receiver().recv().unwrap();
+    /// assert!(fired >= start + Duration::from_millis(100));
+    /// ```
+    pub fn after(duration: Duration) -> Timer {
+        Timer::at(clock::now() + duration)
+    }
+
+    /// Returns the receiver the message gets delivered to.
+    ///
+    /// The receiver can be cloned, and all clones observe changes to the deadline.
+    pub fn receiver(&self) -> &Receiver<Instant> {
+        &self.receiver
+    }
+
+    /// Sets a new deadline.
+    ///
+    /// Any pending message is replaced by one delivered at `deadline`, including when the previous
+    /// message has already been received or the timer was cancelled. If `deadline` has already
+    /// passed, the message is delivered right away.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use crossbeam_channel::Timer;
+    ///
+    /// let timer = Timer::after(Duration::from_secs(60));
+    ///
+    /// let when = Instant::now();
+    /// timer.reset(when);
+    /// assert_eq!(timer.receiver().try_recv(), Ok(when));
+    ///
+    /// // The timer can be armed again after firing.
+    /// timer.reset(when);
+    /// assert_eq!(timer.receiver().try_recv(), Ok(when));
+    /// ```
+    pub fn reset(&self, deadline: Instant) {
+        self.chan.reset(Some(deadline));
+    }
+
+    /// Cancels the pending message, if any.
+    ///
+    /// The receiver won't get a message until the timer is [`reset`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Instant;
+    /// use crossbeam_channel::Timer;
+    ///
+    /// let timer = Timer::at(Instant::now());
+    /// timer.cancel();
+    ///
+    /// assert!(timer.receiver().try_recv().is_err());
+    /// assert_eq!(timer.deadline(), None);
+    /// ```
+    ///
+    /// [`reset`]: Timer::reset
+    pub fn cancel(&self) {
+        self.chan.reset(None);
+    }
+
+    /// Returns the instant at which the next message will be delivered.
+    ///
+    /// Returns `None` if the message has already been received or the timer was cancelled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use crossbeam_channel::Timer;
+    ///
+    /// let when = Instant::now() + Duration::from_millis(100);
+    /// let timer = Timer::at(when);
+    /// assert_eq!(timer.deadline(), Some(when));
+    ///
+    /// timer.receiver().recv().unwrap();
+    /// assert_eq!(timer.deadline(), None);
+    /// ```
+    pub fn deadline(&self) -> Option<Instant> {
+        self.chan.delivery_time()
+    }
+}
+
+impl fmt::Debug for Timer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Timer { .. }")
     }
 }
 
@@ -258,16 +824,55 @@ pub fn at(when: Instant) -> Receiver<Instant> {
 /// }
 /// ```
 pub fn never<T>() -> Receiver<T> {
-    Receiver {
-        flavor: ReceiverFlavor::Never(flavors::never::Channel::new()),
-    }
+    Receiver::new(ReceiverFlavor::Never(flavors::never::Channel::new()))
 }
 
-/// Creates a receiver that delivers messages periodically.
+/// Merges several receivers into one.
 ///
-/// The channel is bounded with capacity of 1 and never gets disconnected. Messages will be
-/// sent into the channel in intervals of `duration`. Each message is the instant at which it is
-/// sent.
+/// The returned receiver takes messages from whichever input has one ready, without a thread
+/// forwarding them in the background. Blocking receive operations wait on all inputs at once the
+/// same way [`Select`] does. Messages from the same input are received in the order they were
+/// sent, but there is no ordering between messages from different inputs.
+///
+/// The merged receiver is disconnected only once all of its inputs are disconnected. Dropping all
+/// merged receivers drops the inputs, and closing it closes all inputs. Merging an empty list of
+/// receivers creates a channel that is disconnected from the start.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::{merge, unbounded, RecvError};
+///
+/// let (s1, r1) = unbounded();
+/// let (s2, r2) = unbounded();
+/// let r = merge(vec![r1, r2]);
+///
+/// s1.send(1).unwrap();
+/// s2.send(2).unwrap();
+/// drop(s1);
+///
+/// let mut msgs = vec![r.recv().unwrap(), r.recv().unwrap()];
+/// msgs.sort();
+/// assert_eq!(msgs, [1, 2]);
+///
+/// // The merged receiver stays connected while any input is connected.
+/// s2.send(3).unwrap();
+/// assert_eq!(r.recv(), Ok(3));
+///
+/// drop(s2);
+/// assert_eq!(r.recv(), Err(RecvError));
+/// ```
+pub fn merge<T>(receivers: Vec<Receiver<T>>) -> Receiver<T> {
+    Receiver::new(ReceiverFlavor::Merge(Arc::new(
+        flavors::merge::Channel::new(receivers),
+    )))
+}
+
+/// Creates a receiver that delivers messages periodically.
+///
+/// The channel is bounded with capacity of 1 and never gets disconnected. Messages will be
+/// sent into the channel in intervals of `duration`. Each message is the instant at which it is
+/// sent.
 ///
 /// # Examples
 ///
@@ -317,9 +922,88 @@ pub fn never<T>() -> Receiver<T> {
 /// assert!(eq(Instant::now(), start + ms(700)));
 /// ```
 pub fn tick(duration: Duration) -> Receiver<Instant> {
-    Receiver {
-        flavor: ReceiverFlavor::Tick(Arc::new(flavors::tick::Channel::new(duration))),
-    }
+    tick_with(duration, MissedTick::Delay)
+}
+
+/// Creates a receiver that delivers messages periodically, handling missed ticks as specified.
+///
+/// This is like [`tick`], except that `missed` decides what happens when the receiver falls
+/// behind and one or more ticks are missed. [`tick`] itself uses [`MissedTick::Delay`].
+///
+/// # Examples
+///
+/// Catching up on missed ticks:
+///
+/// ```
+/// # #[cfg(feature = "test-util")] {
+/// use std::time::Duration;
+/// use crossbeam_channel::clock::MockClock;
+/// use crossbeam_channel::{tick_with, MissedTick};
+///
+/// let ms = |ms| Duration::from_millis(ms);
+///
+/// let clock = MockClock::install();
+/// let start = clock.now();
+/// let r = tick_with(ms(100), MissedTick::Burst);
+///
+/// clock.advance(ms(350));
+///
+/// // All three missed ticks are delivered right away.
+/// assert_eq!(r.try_recv(), Ok(start + ms(100)));
+/// assert_eq!(r.try_recv(), Ok(start + ms(200)));
+/// assert_eq!(r.try_recv(), Ok(start + ms(300)));
+/// assert!(r.try_recv().is_err());
+/// # }
+/// ```
+pub fn tick_with(duration: Duration, missed: MissedTick) -> Receiver<Instant> {
+    Receiver::new(ReceiverFlavor::Tick(Arc::new(flavors::tick::Channel::new(
+        duration, missed,
+    ))))
+}
+
+/// Creates a receiver that delivers messages periodically, starting at a certain instant.
+///
+/// The first message is sent at `start`, and the following ones at `start + period`,
+/// `start + 2 * period`, and so on. Missed ticks are skipped as with [`MissedTick::Skip`], so
+/// messages always stay aligned to this schedule.
+///
+/// If `start` is in the past, the first message is sent right away.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use crossbeam_channel::tick_at;
+///
+/// let start = Instant::now() + Duration::from_millis(100);
+/// let r = tick_at(start, Duration::from_millis(500));
+///
+/// assert_eq!(r.recv(), Ok(start));
+/// assert_eq!(r.recv(), Ok(start + Duration::from_millis(500)));
+/// ```
+pub fn tick_at(start: Instant, period: Duration) -> Receiver<Instant> {
+    Receiver::new(ReceiverFlavor::Tick(Arc::new(
+        flavors::tick::Channel::new_at(start, period, MissedTick::Skip),
+    )))
+}
+
+/// The policy for ticks missed by a receiver created by [`tick_with`].
+///
+/// A tick is missed when the receiver isn't ready to receive a message at the time it is due,
+/// possibly while waiting for the previous one to be received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MissedTick {
+    /// Missed ticks are delivered as fast as possible until the receiver catches up with the
+    /// original schedule.
+    Burst,
+
+    /// Missed ticks are skipped and the next message is sent at the next tick of the original
+    /// schedule.
+    Skip,
+
+    /// Missed ticks are coalesced into one message, and the schedule is shifted to count from the
+    /// moment that message was received.
+    Delay,
 }
 
 /// The sending side of a channel.
@@ -343,6 +1027,8 @@ pub fn tick(duration: Duration) -> Receiver<Instant> {
 /// ```
 pub struct Sender<T> {
     flavor: SenderFlavor<T>,
+    #[cfg(feature = "futures")]
+    sink: SinkState<T>,
 }
 
 /// Sender flavors.
@@ -355,15 +1041,100 @@ enum SenderFlavor<T> {
 
     /// Zero-capacity channel.
     Zero(counter::Sender<flavors::zero::Channel<T>>),
+
+    /// Broadcast channel.
+    Broadcast(counter::Sender<flavors::broadcast::Channel<T>>),
+
+    /// Channel ordered by message priority.
+    Priority(counter::Sender<flavors::priority::Channel<T>>),
 }
 
 unsafe impl<T: Send> Send for Sender<T> {}
 unsafe impl<T: Send> Sync for Sender<T> {}
 
+impl<T> Unpin for Sender<T> {}
+
 impl<T> UnwindSafe for Sender<T> {}
 impl<T> RefUnwindSafe for Sender<T> {}
 
+impl<T> SenderFlavor<T> {
+    /// Attempts to send a message into the channel without blocking.
+    ///
+    /// The time-to-live is only used by channels that support message expiry.
+    fn try_send(&self, msg: T, ttl: Option<Duration>) -> Result<(), TrySendError<T>> {
+        match self {
+            SenderFlavor::Array(chan) => chan.try_send(msg, ttl),
+            SenderFlavor::List(chan) => chan.try_send(msg, ttl),
+            SenderFlavor::Zero(chan) => chan.try_send(msg),
+            SenderFlavor::Broadcast(chan) => chan.try_send(msg),
+            SenderFlavor::Priority(chan) => chan.try_send(msg),
+        }
+    }
+
+    /// Returns the metrics recorded by the channel.
+    fn metrics(&self) -> &Metrics {
+        match self {
+            SenderFlavor::Array(chan) => chan.metrics(),
+            SenderFlavor::List(chan) => chan.metrics(),
+            SenderFlavor::Zero(chan) => chan.metrics(),
+            SenderFlavor::Broadcast(chan) => chan.metrics(),
+            SenderFlavor::Priority(chan) => chan.metrics(),
+        }
+    }
+
+    /// Polls a send operation on behalf of an asynchronous task.
+    fn poll_send(
+        &self,
+        msg: &mut Option<T>,
+        waiter: &mut Waiter,
+        waker: &task::Waker,
+    ) -> Poll<Result<(), SendError<T>>> {
+        match self {
+            SenderFlavor::Array(chan) => chan.poll_send(msg, waiter, waker),
+            SenderFlavor::List(chan) => Poll::Ready(
+                chan.try_send(msg.take().unwrap(), None)
+                    .map_err(|err| match err {
+                        TrySendError::Disconnected(msg) => msg,
+                        TrySendError::Full(_) => unreachable!(),
+                    }),
+            ),
+            SenderFlavor::Zero(chan) => chan.poll_send(msg, waiter, waker),
+            SenderFlavor::Broadcast(chan) => Poll::Ready(
+                chan.try_send(msg.take().unwrap())
+                    .map_err(|err| err.into_inner()),
+            ),
+            SenderFlavor::Priority(chan) => chan.poll_send(msg, waiter, waker),
+        }
+        .map_err(SendError)
+    }
+
+    /// Withdraws a send operation registered by an asynchronous task.
+    fn cancel_send(&self, waiter: &mut Waiter) {
+        match self {
+            SenderFlavor::Array(chan) => chan.cancel_send(waiter),
+            SenderFlavor::List(_) => {}
+            SenderFlavor::Zero(chan) => chan.cancel_send(waiter),
+            SenderFlavor::Broadcast(_) => {}
+            SenderFlavor::Priority(chan) => chan.cancel_send(waiter),
+        }
+    }
+}
+
 impl<T> Sender<T> {
+    /// Creates a sender of the given flavor.
+    fn new(flavor: SenderFlavor<T>) -> Sender<T> {
+        Sender {
+            flavor,
+            #[cfg(feature = "futures")]
+            sink: SinkState::new(),
+        }
+    }
+
+    /// Records `count` messages sent into the channel.
+    fn record_sent(&self, count: usize) {
+        self.flavor.metrics().sent(count, || self.len());
+    }
+
     /// Attempts to send a message into the channel without blocking.
     ///
     /// This method will either send a message into the channel immediately or return an error if
@@ -386,11 +1157,12 @@ impl<T> Sender<T> {
     /// assert_eq!(s.try_send(3), Err(TrySendError::Disconnected(3)));
     /// ```
     pub fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
-        match &self.flavor {
-            SenderFlavor::Array(chan) => chan.try_send(msg),
-            SenderFlavor::List(chan) => chan.try_send(msg),
-            SenderFlavor::Zero(chan) => chan.try_send(msg),
+        let res = self.flavor.try_send(msg, None);
+        match res {
+            Ok(()) => self.record_sent(1),
+            Err(_) => self.flavor.metrics().try_send_failed(),
         }
+        res
     }
 
     /// Blocks the current thread until a message is sent or the channel is disconnected.
@@ -422,12 +1194,7 @@ impl<T> Sender<T> {
     /// assert_eq!(s.send(3), Err(SendError(3)));
     /// ```
     pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
-        match &self.flavor {
-            SenderFlavor::Array(chan) => chan.send(msg, None),
-            SenderFlavor::List(chan) => chan.send(msg, None),
-            SenderFlavor::Zero(chan) => chan.send(msg, None),
-        }
-        .map_err(|err| match err {
+        self.send_until(msg, None, None).map_err(|err| match err {
             SendTimeoutError::Disconnected(msg) => SendError(msg),
             SendTimeoutError::Timeout(_) => unreachable!(),
         })
@@ -471,7 +1238,7 @@ impl<T> Sender<T> {
     /// );
     /// ```
     pub fn send_timeout(&self, msg: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
-        self.send_deadline(msg, Instant::now() + timeout)
+        self.send_deadline(msg, clock::now() + timeout)
     }
 
     /// Waits for a message to be sent into the channel, but only until a given deadline.
@@ -514,592 +1281,2571 @@ impl<T> Sender<T> {
     /// );
     /// ```
     pub fn send_deadline(&self, msg: T, deadline: Instant) -> Result<(), SendTimeoutError<T>> {
-        match &self.flavor {
-            SenderFlavor::Array(chan) => chan.send(msg, Some(deadline)),
-            SenderFlavor::List(chan) => chan.send(msg, Some(deadline)),
-            SenderFlavor::Zero(chan) => chan.send(msg, Some(deadline)),
-        }
+        self.send_until(msg, None, Some(deadline))
     }
 
-    /// Returns `true` if the channel is empty.
+    /// Blocks the current thread until a message that expires after `ttl` is sent or the channel
+    /// is disconnected.
     ///
-    /// Note: Zero-capacity channels are always empty.
+    /// This is the same as [`send`], except the message gets its own time-to-live instead of the
+    /// one of the channel's expiry policy. The time-to-live starts when the message enters the
+    /// channel. If the message is still in the channel when it runs out, receivers skip it and it
+    /// is handed to the expiry handler or dropped, as described in [`bounded_with_expiry`].
+    ///
+    /// Only channels created by [`bounded`], [`unbounded`] and their variants with an expiry
+    /// policy or an overflow policy support expiry. In other channels, the time-to-live is ignored
+    /// and this method is the same as [`send`].
     ///
     /// # Examples
     ///
     /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
     /// use crossbeam_channel::unbounded;
     ///
     /// let (s, r) = unbounded();
-    /// assert!(s.is_empty());
+    /// s.send_with_ttl("stale", Duration::from_millis(100)).unwrap();
+    /// s.send_with_ttl("fresh", Duration::from_secs(60)).unwrap();
+    /// s.send("forever").unwrap();
     ///
-    /// s.send(0).unwrap();
-    /// assert!(!s.is_empty());
+    /// thread::sleep(Duration::from_millis(200));
+    /// assert_eq!(r.recv(), Ok("fresh"));
+    /// assert_eq!(r.recv(), Ok("forever"));
     /// ```
-    pub fn is_empty(&self) -> bool {
-        match &self.flavor {
-            SenderFlavor::Array(chan) => chan.is_empty(),
-            SenderFlavor::List(chan) => chan.is_empty(),
-            SenderFlavor::Zero(chan) => chan.is_empty(),
+    ///
+    /// [`send`]: Sender::send
+    pub fn send_with_ttl(&self, msg: T, ttl: Duration) -> Result<(), SendError<T>> {
+        self.send_until(msg, Some(ttl), None)
+            .map_err(|err| match err {
+                SendTimeoutError::Disconnected(msg) => SendError(msg),
+                SendTimeoutError::Timeout(_) => unreachable!(),
+            })
+    }
+
+    /// Sends a message with the given time-to-live into the channel, blocking until the deadline
+    /// if there is one.
+    fn send_until(
+        &self,
+        mut msg: T,
+        ttl: Option<Duration>,
+        deadline: Option<Instant>,
+    ) -> Result<(), SendTimeoutError<T>> {
+        if metrics::ENABLED {
+            // Only count the operation as a wait if the message can't be sent right away.
+            match self.flavor.try_send(msg, ttl) {
+                Ok(()) => {
+                    self.record_sent(1);
+                    return Ok(());
+                }
+                Err(TrySendError::Full(m)) => msg = m,
+                Err(TrySendError::Disconnected(m)) => {
+                    return Err(SendTimeoutError::Disconnected(m))
+                }
+            }
+        }
+
+        let wait = Wait::start();
+        let res = match &self.flavor {
+            SenderFlavor::Array(chan) => chan.send(msg, ttl, deadline),
+            SenderFlavor::List(chan) => chan.send(msg, ttl, deadline),
+            SenderFlavor::Zero(chan) => chan.send(msg, deadline),
+            SenderFlavor::Broadcast(chan) => chan.send(msg, deadline),
+            SenderFlavor::Priority(chan) => chan.send(msg, deadline),
+        };
+        self.flavor.metrics().send_waited(wait);
+
+        if res.is_ok() {
+            self.record_sent(1);
         }
+        res
     }
 
-    /// Returns `true` if the channel is full.
+    /// Attempts to reserve capacity for one message without blocking.
     ///
-    /// Note: Zero-capacity channels are always full.
+    /// On success, a [`Permit`] is returned, which can later send a message without waiting for
+    /// capacity. Dropping the permit without sending releases the reserved capacity. If the
+    /// channel is full or disconnected, an error is returned.
+    ///
+    /// In a bounded channel, the permit holds a slot in the channel, and the message takes that
+    /// position in the order of messages: receivers wait for it before receiving messages sent
+    /// after the reservation. In a bounded priority channel, the permit holds a slot too, but the
+    /// message is ordered by priority once sent. Other channels never reserve capacity, and
+    /// [`Permit::send`] is then the same as [`send`]. In particular, a permit for a zero-capacity
+    /// channel still waits for a receive operation on the other side, and a permit for a channel
+    /// created by [`bounded_with_policy`] evicts a message if the channel is full when sending.
     ///
     /// # Examples
     ///
     /// ```
-    /// use crossbeam_channel::bounded;
+    /// use crossbeam_channel::{bounded, TrySendError};
     ///
     /// let (s, r) = bounded(1);
     ///
-    /// assert!(!s.is_full());
-    /// s.send(0).unwrap();
-    /// assert!(s.is_full());
+    /// let permit = s.try_reserve().unwrap();
+    /// assert_eq!(s.try_reserve().unwrap_err(), TrySendError::Full(()));
+    ///
+    /// permit.send(1).unwrap();
+    /// assert_eq!(r.recv(), Ok(1));
     /// ```
-    pub fn is_full(&self) -> bool {
+    ///
+    /// [`send`]: Sender::send
+    pub fn try_reserve(&self) -> Result<Permit<'_, T>, TrySendError<()>> {
         match &self.flavor {
-            SenderFlavor::Array(chan) => chan.is_full(),
-            SenderFlavor::List(chan) => chan.is_full(),
-            SenderFlavor::Zero(chan) => chan.is_full(),
+            SenderFlavor::Array(chan) if !chan.has_overflow() => {
+                let mut token = Token::default();
+                if let Err(err) = chan.try_reserve(&mut token) {
+                    self.flavor.metrics().try_send_failed();
+                    return Err(err);
+                }
+                Ok(Permit::new(self, Some(token)))
+            }
+            SenderFlavor::Priority(chan) if chan.capacity().is_some() => {
+                let mut token = Token::default();
+                if let Err(err) = chan.try_reserve(&mut token) {
+                    self.flavor.metrics().try_send_failed();
+                    return Err(err);
+                }
+                Ok(Permit::new(self, Some(token)))
+            }
+            _ if self.is_closed() => {
+                self.flavor.metrics().try_send_failed();
+                Err(TrySendError::Disconnected(()))
+            }
+            _ => Ok(Permit::new(self, None)),
         }
     }
 
-    /// Returns the number of messages in the channel.
+    /// Blocks the current thread until capacity for one message is reserved or the channel is
+    /// disconnected.
+    ///
+    /// If the channel is full and not disconnected, this call will block until a slot frees up. If
+    /// the channel becomes disconnected, this call will wake up and return an error.
+    ///
+    /// See [`try_reserve`] for what a reservation means for different kinds of channels.
     ///
     /// # Examples
     ///
+    /// Building a message only once there is room for it:
+    ///
     /// ```
-    /// use crossbeam_channel::unbounded;
+    /// use crossbeam_channel::bounded;
     ///
-    /// let (s, r) = unbounded();
-    /// assert_eq!(s.len(), 0);
+    /// let (s, r) = bounded(1);
+    ///
+    /// let permit = s.reserve().unwrap();
+    /// let msg = vec![0u8; 1024];
+    /// permit.send(msg).unwrap();
+    ///
+    /// assert_eq!(r.recv().unwrap().len(), 1024);
+    /// ```
+    ///
+    /// [`try_reserve`]: Sender::try_reserve
+    pub fn reserve(&self) -> Result<Permit<'_, T>, SendError<()>> {
+        self.reserve_until(None).map_err(|err| match err {
+            SendTimeoutError::Disconnected(()) => SendError(()),
+            SendTimeoutError::Timeout(()) => unreachable!(),
+        })
+    }
+
+    /// Waits for capacity for one message to be reserved, but only for a limited time.
+    ///
+    /// If the channel is full and not disconnected, this call will block until a slot frees up or
+    /// the operation times out. If the channel becomes disconnected, this call will wake up and
+    /// return an error.
+    ///
+    /// See [`try_reserve`] for what a reservation means for different kinds of channels.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded, SendTimeoutError};
     ///
+    /// let (s, r) = bounded(1);
     /// s.send(1).unwrap();
-    /// s.send(2).unwrap();
-    /// assert_eq!(s.len(), 2);
+    ///
+    /// assert_eq!(
+    ///     s.reserve_timeout(Duration::from_millis(100)).unwrap_err(),
+    ///     SendTimeoutError::Timeout(()),
+    /// );
+    ///
+    /// assert_eq!(r.recv(), Ok(1));
+    /// let permit = s.reserve_timeout(Duration::from_millis(100)).unwrap();
+    /// permit.send(2).unwrap();
     /// ```
-    pub fn len(&self) -> usize {
-        match &self.flavor {
-            SenderFlavor::Array(chan) => chan.len(),
-            SenderFlavor::List(chan) => chan.len(),
-            SenderFlavor::Zero(chan) => chan.len(),
-        }
+    ///
+    /// [`try_reserve`]: Sender::try_reserve
+    pub fn reserve_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<Permit<'_, T>, SendTimeoutError<()>> {
+        self.reserve_deadline(clock::now() + timeout)
     }
 
-    /// If the channel is bounded, returns its capacity.
+    /// Waits for capacity for one message to be reserved, but only until a given deadline.
+    ///
+    /// If the channel is full and not disconnected, this call will block until a slot frees up or
+    /// the operation times out. If the channel becomes disconnected, this call will wake up and
+    /// return an error.
+    ///
+    /// See [`try_reserve`] for what a reservation means for different kinds of channels.
     ///
     /// # Examples
     ///
     /// ```
-    /// use crossbeam_channel::{bounded, unbounded};
+    /// use std::time::{Duration, Instant};
+    /// use crossbeam_channel::{bounded, SendTimeoutError};
     ///
-    /// let (s, _) = unbounded::<i32>();
-    /// assert_eq!(s.capacity(), None);
+    /// let (s, r) = bounded::<i32>(1);
+    /// let permit = s.reserve().unwrap();
     ///
-    /// let (s, _) = bounded::<i32>(5);
-    /// assert_eq!(s.capacity(), Some(5));
+    /// let deadline = Instant::now() + Duration::from_millis(100);
+    /// assert_eq!(
+    ///     s.reserve_deadline(deadline).unwrap_err(),
+    ///     SendTimeoutError::Timeout(()),
+    /// );
     ///
-    /// let (s, _) = bounded::<i32>(0);
-    /// assert_eq!(s.capacity(), Some(0));
+    /// // Dropping the permit releases the slot.
+    /// drop(permit);
+    /// assert!(s.reserve_deadline(deadline).is_ok());
     /// ```
-    pub fn capacity(&self) -> Option<usize> {
+    ///
+    /// [`try_reserve`]: Sender::try_reserve
+    pub fn reserve_deadline(
+        &self,
+        deadline: Instant,
+    ) -> Result<Permit<'_, T>, SendTimeoutError<()>> {
+        self.reserve_until(Some(deadline))
+    }
+
+    /// Reserves capacity for one message, blocking until the deadline if there is one.
+    fn reserve_until(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Result<Permit<'_, T>, SendTimeoutError<()>> {
         match &self.flavor {
-            SenderFlavor::Array(chan) => chan.capacity(),
-            SenderFlavor::List(chan) => chan.capacity(),
-            SenderFlavor::Zero(chan) => chan.capacity(),
+            SenderFlavor::Array(chan) if !chan.has_overflow() => {
+                let mut token = Token::default();
+
+                if metrics::ENABLED {
+                    // Only count the operation as a wait if there is no free slot right away.
+                    match chan.try_reserve(&mut token) {
+                        Ok(()) => return Ok(Permit::new(self, Some(token))),
+                        Err(TrySendError::Disconnected(())) => {
+                            return Err(SendTimeoutError::Disconnected(()))
+                        }
+                        Err(TrySendError::Full(())) => {}
+                    }
+                }
+
+                let wait = Wait::start();
+                let res = chan.reserve(&mut token, deadline);
+                self.flavor.metrics().send_waited(wait);
+
+                res?;
+                Ok(Permit::new(self, Some(token)))
+            }
+            SenderFlavor::Priority(chan) if chan.capacity().is_some() => {
+                let mut token = Token::default();
+
+                if metrics::ENABLED {
+                    // Only count the operation as a wait if there is no free slot right away.
+                    match chan.try_reserve(&mut token) {
+                        Ok(()) => return Ok(Permit::new(self, Some(token))),
+                        Err(TrySendError::Disconnected(())) => {
+                            return Err(SendTimeoutError::Disconnected(()))
+                        }
+                        Err(TrySendError::Full(())) => {}
+                    }
+                }
+
+                let wait = Wait::start();
+                let res = chan.reserve(&mut token, deadline);
+                self.flavor.metrics().send_waited(wait);
+
+                res?;
+                Ok(Permit::new(self, Some(token)))
+            }
+            _ if self.is_closed() => Err(SendTimeoutError::Disconnected(())),
+            _ => Ok(Permit::new(self, None)),
         }
     }
 
-    /// Returns `true` if senders belong to the same channel.
+    /// Sends a message into the channel, returning the message that was evicted to make room for
+    /// it.
+    ///
+    /// On a channel created by [`bounded_with_policy`], this method never blocks. If the channel
+    /// is full, either the oldest message or `msg` itself is evicted, depending on the overflow
+    /// policy, and returned as `Ok(Some(_))`. On other channels, this method is the same as
+    /// [`send`] and never evicts messages.
+    ///
+    /// If the channel is disconnected, an error containing the original message is returned.
     ///
     /// # Examples
     ///
-    /// ```rust
-    /// use crossbeam_channel::unbounded;
+    /// ```
+    /// use crossbeam_channel::{bounded_with_policy, Overflow, SendError};
     ///
-    /// let (s, _) = unbounded::<usize>();
+    /// let (s, r) = bounded_with_policy(1, Overflow::DropNewest);
     ///
-    /// let s2 = s.clone();
-    /// assert!(s.same_channel(&s2));
+    /// assert_eq!(s.force_send(1), Ok(None));
+    /// assert_eq!(s.force_send(2), Ok(Some(2)));
+    /// assert_eq!(r.recv(), Ok(1));
     ///
-    /// let (s3, _) = unbounded();
-    /// assert!(!s.same_channel(&s3));
+    /// drop(r);
+    /// assert_eq!(s.force_send(3), Err(SendError(3)));
     /// ```
-    pub fn same_channel(&self, other: &Sender<T>) -> bool {
-        match (&self.flavor, &other.flavor) {
-            (SenderFlavor::Array(ref a), SenderFlavor::Array(ref b)) => a == b,
-            (SenderFlavor::List(ref a), SenderFlavor::List(ref b)) => a == b,
-            (SenderFlavor::Zero(ref a), SenderFlavor::Zero(ref b)) => a == b,
-            _ => false,
+    ///
+    /// [`send`]: Sender::send
+    pub fn force_send(&self, msg: T) -> Result<Option<T>, SendError<T>> {
+        match &self.flavor {
+            SenderFlavor::Array(chan) if chan.has_overflow() => {
+                let res = chan.send_evicting(msg).map_err(SendError);
+                if res.is_ok() {
+                    self.record_sent(1);
+                }
+                res
+            }
+            _ => self.send(msg).map(|()| None),
         }
     }
-}
 
-impl<T> Drop for Sender<T> {
-    fn drop(&mut self) {
-        unsafe {
-            match &self.flavor {
-                SenderFlavor::Array(chan) => chan.release(|c| c.disconnect()),
-                SenderFlavor::List(chan) => chan.release(|c| c.disconnect_senders()),
-                SenderFlavor::Zero(chan) => chan.release(|c| c.disconnect()),
-            }
+    /// Attempts to send messages from the front of `msgs` into the channel without blocking.
+    ///
+    /// As many messages as the channel has room for are sent and removed from `msgs`, in order.
+    /// On success, the number of sent messages is returned, which is at least one unless `msgs` is
+    /// empty. Messages that could not be sent are left in `msgs`.
+    ///
+    /// Slots for the messages are reserved all at once where possible, and blocked receivers are
+    /// woken up only after all messages are sent.
+    ///
+    /// If no message could be sent because the channel is full or disconnected, an error is
+    /// returned and `msgs` is left unchanged.
+    ///
+    /// If called on a zero-capacity channel, this method will send messages only as long as there
+    /// happen to be receive operations on the other side of the channel at the same time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::VecDeque;
+    /// use crossbeam_channel::{bounded, TrySendError};
+    ///
+    /// let (s, r) = bounded(3);
+    /// let mut msgs = (1..=5).collect::<VecDeque<_>>();
+    ///
+    /// assert_eq!(s.try_send_many(&mut msgs), Ok(3));
+    /// assert_eq!(msgs, [4, 5]);
+    /// assert_eq!(s.try_send_many(&mut msgs), Err(TrySendError::Full(())));
+    ///
+    /// drop(r);
+    /// assert_eq!(s.try_send_many(&mut msgs), Err(TrySendError::Disconnected(())));
+    /// assert_eq!(msgs, [4, 5]);
+    /// ```
+    pub fn try_send_many(&self, msgs: &mut VecDeque<T>) -> Result<usize, TrySendError<()>> {
+        let res = self.send_batch(msgs);
+        match res {
+            Ok(count) => self.record_sent(count),
+            Err(_) => self.flavor.metrics().try_send_failed(),
         }
+        res
     }
-}
 
-impl<T> Clone for Sender<T> {
-    fn clone(&self) -> Self {
-        let flavor = match &self.flavor {
-            SenderFlavor::Array(chan) => SenderFlavor::Array(chan.acquire()),
-            SenderFlavor::List(chan) => SenderFlavor::List(chan.acquire()),
-            SenderFlavor::Zero(chan) => SenderFlavor::Zero(chan.acquire()),
-        };
+    /// Sends messages from the front of `msgs` without blocking and without recording metrics.
+    fn send_batch(&self, msgs: &mut VecDeque<T>) -> Result<usize, TrySendError<()>> {
+        if msgs.is_empty() {
+            return Ok(0);
+        }
 
-        Sender { flavor }
+        match &self.flavor {
+            SenderFlavor::Array(chan) => chan.try_send_many(msgs),
+            SenderFlavor::List(chan) => chan.try_send_many(msgs),
+            SenderFlavor::Broadcast(chan) => chan.try_send_many(msgs),
+            SenderFlavor::Zero(chan) => {
+                let mut count = 0;
+                while let Some(msg) = msgs.pop_front() {
+                    if let Err(err) = chan.try_send(msg) {
+                        let err = match err {
+                            TrySendError::Full(msg) => {
+                                msgs.push_front(msg);
+                                TrySendError::Full(())
+                            }
+                            TrySendError::Disconnected(msg) => {
+                                msgs.push_front(msg);
+                                TrySendError::Disconnected(())
+                            }
+                        };
+                        if count == 0 {
+                            return Err(err);
+                        }
+                        break;
+                    }
+                    count += 1;
+                }
+                Ok(count)
+            }
+            SenderFlavor::Priority(chan) => chan.try_send_many(msgs),
+        }
     }
-}
 
-impl<T> fmt::Debug for Sender<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.pad("Sender { .. }")
-    }
-}
+    /// Sends all messages from an iterator into the channel, blocking whenever it is full.
+    ///
+    /// Messages are sent in batches, as with [`try_send_many`], so blocked receivers are woken up
+    /// once per batch rather than once per message. If the channel is full and not disconnected,
+    /// this call will block until more messages can be sent.
+    ///
+    /// If the channel becomes disconnected, this call will wake up and return an error containing
+    /// the first message that could not be sent. The remaining messages are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, r) = bounded(2);
+    ///
+    /// thread::spawn(move || {
+    ///     assert_eq!(s.send_iter(0..10), Ok(()));
+    /// });
+    ///
+    /// assert_eq!(r.iter().collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+    /// ```
+    ///
+    /// [`try_send_many`]: Sender::try_send_many
+    pub fn send_iter<I>(&self, iter: I) -> Result<(), SendError<T>>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        const BATCH: usize = 32;
+
+        let mut iter = iter.into_iter();
+        let mut msgs = VecDeque::new();
+
+        loop {
+            let room = BATCH - msgs.len();
+            msgs.extend(iter.by_ref().take(room));
+
+            match self.send_batch(&mut msgs) {
+                Ok(0) => return Ok(()),
+                Ok(count) => self.record_sent(count),
+                Err(TrySendError::Full(())) => {
+                    // Block until the front message is sent.
+                    let msg = msgs.pop_front().unwrap();
+                    self.send(msg)?;
+                }
+                Err(TrySendError::Disconnected(())) => {
+                    return Err(SendError(msgs.pop_front().unwrap()));
+                }
+            }
+        }
+    }
+
+    /// Returns a future that sends a message into the channel.
+    ///
+    /// The future completes once the message is sent or the channel becomes disconnected. Instead
+    /// of blocking the current thread, a task waiting on the future gets woken up by the channel,
+    /// so the same channel can be used from threads and asynchronous tasks at the same time.
+    ///
+    /// If the future is dropped before completion, the message is dropped with it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::future::Future;
+    /// use std::pin::Pin;
+    /// use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+    /// use crossbeam_channel::bounded;
+    ///
+    /// # fn noop_raw_waker() -> RawWaker {
+    /// #     fn clone(_: *const ()) -> RawWaker { noop_raw_waker() }
+    /// #     fn noop(_: *const ()) {}
+    /// #     static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    /// #     RawWaker::new(std::ptr::null(), &VTABLE)
+    /// # }
+    /// let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+    /// let mut cx = Context::from_waker(&waker);
+    ///
+    /// let (s, r) = bounded(1);
+    /// s.send(1).unwrap();
+    ///
+    /// // The channel is full, so the task has to wait.
+    /// let mut fut = s.send_async(2);
+    /// assert!(Pin::new(&mut fut).poll(&mut cx).is_pending());
+    ///
+    /// // Receiving a message makes room and wakes the task up.
+    /// assert_eq!(r.recv(), Ok(1));
+    /// assert_eq!(Pin::new(&mut fut).poll(&mut cx), Poll::Ready(Ok(())));
+    /// assert_eq!(r.recv(), Ok(2));
+    /// ```
+    pub fn send_async(&self, msg: T) -> SendFuture<'_, T> {
+        SendFuture {
+            sender: self,
+            msg: Some(msg),
+            waiter: Waiter::new(),
+        }
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// Note: Zero-capacity channels are always empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// assert!(s.is_empty());
+    ///
+    /// s.send(0).unwrap();
+    /// assert!(!s.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        match &self.flavor {
+            SenderFlavor::Array(chan) => chan.is_empty(),
+            SenderFlavor::List(chan) => chan.is_empty(),
+            SenderFlavor::Zero(chan) => chan.is_empty(),
+            SenderFlavor::Broadcast(chan) => chan.is_empty(),
+            SenderFlavor::Priority(chan) => chan.is_empty(),
+        }
+    }
+
+    /// Returns `true` if the channel is full.
+    ///
+    /// Note: Zero-capacity channels are always full.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, r) = bounded(1);
+    ///
+    /// assert!(!s.is_full());
+    /// s.send(0).unwrap();
+    /// assert!(s.is_full());
+    /// ```
+    pub fn is_full(&self) -> bool {
+        match &self.flavor {
+            SenderFlavor::Array(chan) => chan.is_full(),
+            SenderFlavor::List(chan) => chan.is_full(),
+            SenderFlavor::Zero(chan) => chan.is_full(),
+            SenderFlavor::Broadcast(chan) => chan.is_full(),
+            SenderFlavor::Priority(chan) => chan.is_full(),
+        }
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// assert_eq!(s.len(), 0);
+    ///
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(s.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        match &self.flavor {
+            SenderFlavor::Array(chan) => chan.len(),
+            SenderFlavor::List(chan) => chan.len(),
+            SenderFlavor::Zero(chan) => chan.len(),
+            SenderFlavor::Broadcast(chan) => chan.len(),
+            SenderFlavor::Priority(chan) => chan.len(),
+        }
+    }
+
+    /// If the channel is bounded, returns its capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, unbounded};
+    ///
+    /// let (s, _) = unbounded::<i32>();
+    /// assert_eq!(s.capacity(), None);
+    ///
+    /// let (s, _) = bounded::<i32>(5);
+    /// assert_eq!(s.capacity(), Some(5));
+    ///
+    /// let (s, _) = bounded::<i32>(0);
+    /// assert_eq!(s.capacity(), Some(0));
+    /// ```
+    pub fn capacity(&self) -> Option<usize> {
+        match &self.flavor {
+            SenderFlavor::Array(chan) => chan.capacity(),
+            SenderFlavor::List(chan) => chan.capacity(),
+            SenderFlavor::Zero(chan) => chan.capacity(),
+            SenderFlavor::Broadcast(chan) => chan.capacity(),
+            SenderFlavor::Priority(chan) => chan.capacity(),
+        }
+    }
+
+    /// Returns the number of senders connected to the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s1, _r) = unbounded::<i32>();
+    /// assert_eq!(s1.sender_count(), 1);
+    ///
+    /// let s2 = s1.clone();
+    /// assert_eq!(s1.sender_count(), 2);
+    ///
+    /// drop(s2);
+    /// assert_eq!(s1.sender_count(), 1);
+    /// ```
+    pub fn sender_count(&self) -> usize {
+        match &self.flavor {
+            SenderFlavor::Array(chan) => chan.sender_count(),
+            SenderFlavor::List(chan) => chan.sender_count(),
+            SenderFlavor::Zero(chan) => chan.sender_count(),
+            SenderFlavor::Broadcast(chan) => chan.sender_count(),
+            SenderFlavor::Priority(chan) => chan.sender_count(),
+        }
+    }
+
+    /// Returns the number of receivers connected to the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r1) = unbounded::<i32>();
+    /// let r2 = r1.clone();
+    /// assert_eq!(s.receiver_count(), 2);
+    ///
+    /// drop(r1);
+    /// drop(r2);
+    /// assert_eq!(s.receiver_count(), 0);
+    /// ```
+    pub fn receiver_count(&self) -> usize {
+        match &self.flavor {
+            SenderFlavor::Array(chan) => chan.receiver_count(),
+            SenderFlavor::List(chan) => chan.receiver_count(),
+            SenderFlavor::Zero(chan) => chan.receiver_count(),
+            SenderFlavor::Broadcast(chan) => chan.receiver_count(),
+            SenderFlavor::Priority(chan) => chan.receiver_count(),
+        }
+    }
+
+    /// Returns a snapshot of the statistics of the channel.
+    ///
+    /// See [`ChannelStats`] for what is counted. This method is only available with the `metrics`
+    /// feature enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s1, r) = unbounded();
+    /// let s2 = s1.clone();
+    /// s1.send(1).unwrap();
+    /// s2.send(2).unwrap();
+    ///
+    /// let stats = s1.stats();
+    /// assert_eq!(stats.sent(), 2);
+    /// assert_eq!(stats.senders(), 2);
+    /// assert_eq!(stats.receivers(), 1);
+    /// ```
+    #[cfg(feature = "metrics")]
+    pub fn stats(&self) -> ChannelStats {
+        match &self.flavor {
+            SenderFlavor::Array(chan) => chan.stats(),
+            SenderFlavor::List(chan) => chan.stats(),
+            SenderFlavor::Zero(chan) => chan.stats(),
+            SenderFlavor::Broadcast(chan) => chan.stats(),
+            SenderFlavor::Priority(chan) => chan.stats(),
+        }
+    }
+
+    /// Closes the channel without dropping any senders or receivers.
+    ///
+    /// After the channel is closed, send operations fail and receive operations fail once the
+    /// channel becomes empty, just as if all senders or all receivers were dropped. Messages that
+    /// are already in the channel can still be received. All blocked senders and receivers are
+    /// woken up.
+    ///
+    /// Returns `true` if this call closed the channel, or `false` if it was already closed or
+    /// disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, RecvError, SendError};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(1).unwrap();
+    ///
+    /// assert!(s.close());
+    /// assert!(!s.close());
+    ///
+    /// assert_eq!(s.send(2), Err(SendError(2)));
+    /// assert_eq!(r.recv(), Ok(1));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    pub fn close(&self) -> bool {
+        let closed = match &self.flavor {
+            SenderFlavor::Array(chan) => chan.disconnect(),
+            SenderFlavor::List(chan) => chan.disconnect_senders(),
+            SenderFlavor::Zero(chan) => chan.disconnect(),
+            SenderFlavor::Broadcast(chan) => chan.disconnect(),
+            SenderFlavor::Priority(chan) => chan.disconnect(),
+        };
+
+        if closed {
+            match &self.flavor {
+                SenderFlavor::Array(chan) => chan.notify_closed(),
+                SenderFlavor::List(chan) => chan.notify_closed(),
+                SenderFlavor::Zero(chan) => chan.notify_closed(),
+                SenderFlavor::Broadcast(chan) => chan.notify_closed(),
+                SenderFlavor::Priority(chan) => chan.notify_closed(),
+            }
+        }
+        closed
+    }
+
+    /// Returns `true` if the channel is closed or disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// assert!(!s.is_closed());
+    ///
+    /// drop(r);
+    /// assert!(s.is_closed());
+    /// ```
+    pub fn is_closed(&self) -> bool {
+        match &self.flavor {
+            SenderFlavor::Array(chan) => chan.is_disconnected(),
+            SenderFlavor::List(chan) => chan.is_disconnected(),
+            SenderFlavor::Zero(chan) => chan.is_disconnected(),
+            SenderFlavor::Broadcast(chan) => chan.is_disconnected(),
+            SenderFlavor::Priority(chan) => chan.is_disconnected(),
+        }
+    }
+
+    /// Blocks the current thread until the channel is closed or all receivers are dropped.
+    ///
+    /// This lets a producer stop working once nobody listens for its messages anymore, even if it
+    /// has nothing to send. To wait for this alongside other operations, use [`receivers_closed`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     drop(r);
+    /// });
+    ///
+    /// s.wait_for_receivers_closed();
+    /// assert!(s.is_closed());
+    /// ```
+    ///
+    /// [`receivers_closed`]: Sender::receivers_closed
+    pub fn wait_for_receivers_closed(&self) {
+        let closed = self.receivers_closed();
+        let mut sel = Select::new();
+        sel.recv_custom(&closed);
+        sel.select().recv_custom(&closed)
+    }
+
+    /// Returns a source for [`Select`] and [`select!`] that becomes ready once the channel is closed
+    /// or all receivers are dropped.
+    ///
+    /// See [`wait_for_receivers_closed`] for the blocking version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{select, tick, unbounded};
+    ///
+    /// let (s, r) = unbounded();
+    /// let ticker = tick(Duration::from_millis(10));
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     drop(r);
+    /// });
+    ///
+    /// // Send heartbeats for as long as somebody listens.
+    /// loop {
+    ///     select! {
+    ///         recv(ticker) -> _ => {
+    ///             let _ = s.send("heartbeat");
+    ///         }
+    ///         recv_custom(s.receivers_closed()) -> () => break,
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`select!`]: crate::select
+    /// [`wait_for_receivers_closed`]: Sender::wait_for_receivers_closed
+    pub fn receivers_closed(&self) -> ReceiversClosed<'_, T> {
+        ReceiversClosed { sender: self }
+    }
+
+    /// Creates a weak handle to the sending side of the channel.
+    ///
+    /// A [`WeakSender`] doesn't count as a sender, so it doesn't keep the channel connected. It can
+    /// be turned back into a sender with [`WeakSender::upgrade`] for as long as the channel is not
+    /// disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, RecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// let w = s.downgrade();
+    ///
+    /// w.upgrade().unwrap().send(1).unwrap();
+    ///
+    /// // The weak sender doesn't keep the channel connected.
+    /// drop(s);
+    /// assert_eq!(r.recv(), Ok(1));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// assert!(w.upgrade().is_none());
+    /// ```
+    pub fn downgrade(&self) -> WeakSender<T> {
+        let flavor = match &self.flavor {
+            SenderFlavor::Array(chan) => WeakSenderFlavor::Array(chan.downgrade()),
+            SenderFlavor::List(chan) => WeakSenderFlavor::List(chan.downgrade()),
+            SenderFlavor::Zero(chan) => WeakSenderFlavor::Zero(chan.downgrade()),
+            SenderFlavor::Broadcast(chan) => WeakSenderFlavor::Broadcast(chan.downgrade()),
+            SenderFlavor::Priority(chan) => WeakSenderFlavor::Priority(chan.downgrade()),
+        };
+
+        WeakSender { flavor }
+    }
+
+    /// Returns `true` if senders belong to the same channel.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, _) = unbounded::<usize>();
+    ///
+    /// let s2 = s.clone();
+    /// assert!(s.same_channel(&s2));
+    ///
+    /// let (s3, _) = unbounded();
+    /// assert!(!s.same_channel(&s3));
+    /// ```
+    pub fn same_channel(&self, other: &Sender<T>) -> bool {
+        match (&self.flavor, &other.flavor) {
+            (SenderFlavor::Array(ref a), SenderFlavor::Array(ref b)) => a == b,
+            (SenderFlavor::List(ref a), SenderFlavor::List(ref b)) => a == b,
+            (SenderFlavor::Zero(ref a), SenderFlavor::Zero(ref b)) => a == b,
+            (SenderFlavor::Broadcast(ref a), SenderFlavor::Broadcast(ref b)) => a == b,
+            (SenderFlavor::Priority(ref a), SenderFlavor::Priority(ref b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        #[cfg(feature = "futures")]
+        self.flavor.cancel_send(&mut self.sink.waiter);
+
+        unsafe {
+            match &self.flavor {
+                SenderFlavor::Array(chan) => chan.release(|c| c.disconnect()),
+                SenderFlavor::List(chan) => chan.release(|c| c.disconnect_senders()),
+                SenderFlavor::Zero(chan) => chan.release(|c| c.disconnect()),
+                SenderFlavor::Broadcast(chan) => chan.release(|c| c.disconnect()),
+                SenderFlavor::Priority(chan) => chan.release(|c| c.disconnect()),
+            }
+        }
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        let flavor = match &self.flavor {
+            SenderFlavor::Array(chan) => SenderFlavor::Array(chan.acquire()),
+            SenderFlavor::List(chan) => SenderFlavor::List(chan.acquire()),
+            SenderFlavor::Zero(chan) => SenderFlavor::Zero(chan.acquire()),
+            SenderFlavor::Broadcast(chan) => SenderFlavor::Broadcast(chan.acquire()),
+            SenderFlavor::Priority(chan) => SenderFlavor::Priority(chan.acquire()),
+        };
+
+        Sender::new(flavor)
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Sender { .. }")
+    }
+}
+
+/// Capacity reserved in a channel for sending one message.
+///
+/// Permits are created by [`Sender::reserve`] and its variants. Sending through a permit never
+/// waits for capacity, and dropping a permit without sending releases the reserved capacity.
+///
+/// # Examples
+///
+/// Aborting a send without side effects:
+///
+/// ```
+/// use crossbeam_channel::bounded;
+///
+/// let (s, r) = bounded::<String>(1);
+///
+/// let permit = s.reserve().unwrap();
+/// drop(permit);
+///
+/// assert!(r.try_recv().is_err());
+/// assert!(s.try_send(String::from("hello")).is_ok());
+/// ```
+pub struct Permit<'a, T> {
+    /// The sender the capacity was reserved through.
+    sender: &'a Sender<T>,
+
+    /// The slot reserved in the channel, if the channel reserves slots.
+    token: Option<Token>,
+}
+
+unsafe impl<T: Send> Send for Permit<'_, T> {}
+unsafe impl<T: Send> Sync for Permit<'_, T> {}
+
+impl<'a, T> Permit<'a, T> {
+    /// Creates a permit for the reserved slot.
+    fn new(sender: &'a Sender<T>, token: Option<Token>) -> Permit<'a, T> {
+        Permit { sender, token }
+    }
+
+    /// Sends a message using the reserved capacity.
+    ///
+    /// This never fails because the channel is full. If the channel is disconnected, an error
+    /// containing the original message is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, SendError};
+    ///
+    /// let (s, r) = bounded(1);
+    ///
+    /// let permit = s.reserve().unwrap();
+    /// assert_eq!(permit.send(1), Ok(()));
+    /// assert_eq!(r.recv(), Ok(1));
+    ///
+    /// let permit = s.reserve().unwrap();
+    /// drop(r);
+    /// assert_eq!(permit.send(2), Err(SendError(2)));
+    /// ```
+    pub fn send(mut self, msg: T) -> Result<(), SendError<T>> {
+        match (&self.sender.flavor, self.token.take()) {
+            (SenderFlavor::Array(chan), Some(mut token)) => {
+                unsafe { chan.write_reserved(&mut token, msg) }.map_err(SendError)?;
+                self.sender.record_sent(1);
+                Ok(())
+            }
+            (SenderFlavor::Priority(chan), Some(mut token)) => {
+                unsafe { chan.write_reserved(&mut token, msg) }.map_err(SendError)?;
+                self.sender.record_sent(1);
+                Ok(())
+            }
+            _ => self.sender.send(msg),
+        }
+    }
+}
+
+impl<T> Drop for Permit<'_, T> {
+    fn drop(&mut self) {
+        match (&self.sender.flavor, &mut self.token) {
+            (SenderFlavor::Array(chan), Some(token)) => unsafe { chan.cancel_reserved(token) },
+            (SenderFlavor::Priority(chan), Some(token)) => unsafe { chan.cancel_reserved(token) },
+            _ => {}
+        }
+    }
+}
+
+impl<T> fmt::Debug for Permit<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Permit { .. }")
+    }
+}
+
+/// A source that becomes ready once the channel is closed or all receivers are dropped.
+///
+/// This source is created by [`Sender::receivers_closed`] and can be used in [`Select`] and
+/// [`select!`] as a custom source.
+///
+/// [`select!`]: crate::select
+pub struct ReceiversClosed<'a, T> {
+    sender: &'a Sender<T>,
+}
+
+impl<T> Selectable for ReceiversClosed<'_, T> {
+    type Output = ();
+
+    fn try_select(&self, _slot: &mut Slot) -> bool {
+        self.is_ready()
+    }
+
+    fn register(&self, reg: &Registration) -> bool {
+        let is_closed = || self.sender.is_closed();
+        match &self.sender.flavor {
+            SenderFlavor::Array(chan) => chan.watch_closed(reg, is_closed),
+            SenderFlavor::List(chan) => chan.watch_closed(reg, is_closed),
+            SenderFlavor::Zero(chan) => chan.watch_closed(reg, is_closed),
+            SenderFlavor::Broadcast(chan) => chan.watch_closed(reg, is_closed),
+            SenderFlavor::Priority(chan) => chan.watch_closed(reg, is_closed),
+        }
+    }
+
+    fn unregister(&self, id: RegistrationId) {
+        match &self.sender.flavor {
+            SenderFlavor::Array(chan) => chan.unwatch_closed(id),
+            SenderFlavor::List(chan) => chan.unwatch_closed(id),
+            SenderFlavor::Zero(chan) => chan.unwatch_closed(id),
+            SenderFlavor::Broadcast(chan) => chan.unwatch_closed(id),
+            SenderFlavor::Priority(chan) => chan.unwatch_closed(id),
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    fn complete(&self, _slot: &mut Slot) {}
+}
+
+impl<T> fmt::Debug for ReceiversClosed<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("ReceiversClosed { .. }")
+    }
+}
+
+/// A weak handle to the sending side of a channel.
+///
+/// Unlike a [`Sender`], a weak sender doesn't keep the channel connected: once all senders or all
+/// receivers are dropped, the channel gets disconnected even if weak senders still exist. Weak
+/// senders are created by [`Sender::downgrade`] and can't send messages until they are upgraded.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::bounded;
+///
+/// let (s, r) = bounded(1);
+/// let w = s.downgrade();
+///
+/// if let Some(s) = w.upgrade() {
+///     s.send(1).unwrap();
+/// }
+/// assert_eq!(r.recv(), Ok(1));
+///
+/// drop(r);
+/// assert!(w.upgrade().is_none());
+/// ```
+pub struct WeakSender<T> {
+    flavor: WeakSenderFlavor<T>,
+}
+
+/// Weak sender flavors.
+enum WeakSenderFlavor<T> {
+    /// Bounded channel based on a preallocated array.
+    Array(counter::WeakSender<flavors::array::Channel<T>>),
+
+    /// Unbounded channel implemented as a linked list.
+    List(counter::WeakSender<flavors::list::Channel<T>>),
+
+    /// Zero-capacity channel.
+    Zero(counter::WeakSender<flavors::zero::Channel<T>>),
+
+    /// Broadcast channel.
+    Broadcast(counter::WeakSender<flavors::broadcast::Channel<T>>),
+
+    /// Channel ordered by message priority.
+    Priority(counter::WeakSender<flavors::priority::Channel<T>>),
+}
+
+unsafe impl<T: Send> Send for WeakSender<T> {}
+unsafe impl<T: Send> Sync for WeakSender<T> {}
+
+impl<T> UnwindSafe for WeakSender<T> {}
+impl<T> RefUnwindSafe for WeakSender<T> {}
+
+impl<T> WeakSender<T> {
+    /// Attempts to turn the weak handle back into a sender.
+    ///
+    /// Returns `None` if the channel is disconnected, which happens once all senders or all
+    /// receivers are dropped, or once the channel is closed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// let w = s.downgrade();
+    /// assert!(w.upgrade().is_some());
+    ///
+    /// r.close();
+    /// assert!(w.upgrade().is_none());
+    /// ```
+    pub fn upgrade(&self) -> Option<Sender<T>> {
+        let flavor = match &self.flavor {
+            WeakSenderFlavor::Array(chan) => SenderFlavor::Array(chan.upgrade()?),
+            WeakSenderFlavor::List(chan) => SenderFlavor::List(chan.upgrade()?),
+            WeakSenderFlavor::Zero(chan) => SenderFlavor::Zero(chan.upgrade()?),
+            WeakSenderFlavor::Broadcast(chan) => SenderFlavor::Broadcast(chan.upgrade()?),
+            WeakSenderFlavor::Priority(chan) => SenderFlavor::Priority(chan.upgrade()?),
+        };
+
+        // Senders are still alive, but the channel might have been disconnected from the other side.
+        let s = Sender::new(flavor);
+        if s.is_closed() {
+            None
+        } else {
+            Some(s)
+        }
+    }
+}
+
+impl<T> Drop for WeakSender<T> {
+    fn drop(&mut self) {
+        unsafe {
+            match &self.flavor {
+                WeakSenderFlavor::Array(chan) => chan.release(),
+                WeakSenderFlavor::List(chan) => chan.release(),
+                WeakSenderFlavor::Zero(chan) => chan.release(),
+                WeakSenderFlavor::Broadcast(chan) => chan.release(),
+                WeakSenderFlavor::Priority(chan) => chan.release(),
+            }
+        }
+    }
+}
+
+impl<T> Clone for WeakSender<T> {
+    fn clone(&self) -> Self {
+        let flavor = match &self.flavor {
+            WeakSenderFlavor::Array(chan) => WeakSenderFlavor::Array(chan.acquire()),
+            WeakSenderFlavor::List(chan) => WeakSenderFlavor::List(chan.acquire()),
+            WeakSenderFlavor::Zero(chan) => WeakSenderFlavor::Zero(chan.acquire()),
+            WeakSenderFlavor::Broadcast(chan) => WeakSenderFlavor::Broadcast(chan.acquire()),
+            WeakSenderFlavor::Priority(chan) => WeakSenderFlavor::Priority(chan.acquire()),
+        };
+
+        WeakSender { flavor }
+    }
+}
+
+impl<T> fmt::Debug for WeakSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("WeakSender { .. }")
+    }
+}
+
+/// The receiving side of a channel.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use crossbeam_channel::unbounded;
+///
+/// let (s, r) = unbounded();
+///
+/// thread::spawn(move || {
+///     let _ = s.send(1);
+///     thread::sleep(Duration::from_secs(1));
+///     let _ = s.send(2);
+/// });
+///
+/// assert_eq!(r.recv(), Ok(1)); // Received immediately.
+/// assert_eq!(r.recv(), Ok(2)); // Received after 1 second.
+/// ```
+pub struct Receiver<T> {
+    flavor: ReceiverFlavor<T>,
+    #[cfg(feature = "futures")]
+    stream: StreamState,
+}
+
+/// A receiver returned by [`Receiver::map`] or [`Receiver::filter`].
+///
+/// It is an ordinary [`Receiver`], so it can be passed to [`Select::recv`] and [`select!`], and
+/// mapped or filtered again.
+///
+/// [`select!`]: crate::select
+pub type MappedReceiver<T> = Receiver<T>;
+
+/// Receiver flavors.
+enum ReceiverFlavor<T> {
+    /// Bounded channel based on a preallocated array.
+    Array(counter::Receiver<flavors::array::Channel<T>>),
+
+    /// Unbounded channel implemented as a linked list.
+    List(counter::Receiver<flavors::list::Channel<T>>),
+
+    /// Zero-capacity channel.
+    Zero(counter::Receiver<flavors::zero::Channel<T>>),
+
+    /// Broadcast channel, along with the position of this receiver.
+    Broadcast(
+        counter::Receiver<flavors::broadcast::Channel<T>>,
+        flavors::broadcast::Cursor,
+    ),
+
+    /// Channel ordered by message priority.
+    Priority(counter::Receiver<flavors::priority::Channel<T>>),
+
+    /// Receivers merged into one.
+    Merge(Arc<flavors::merge::Channel<T>>),
+
+    /// An output of a tee, along with its index.
+    Tee(Arc<flavors::tee::Channel<T>>, usize),
+
+    /// Receiver with transformed messages.
+    Map(Arc<dyn flavors::map::Mapped<T>>),
+
+    /// Receiver with filtered messages.
+    Filter(Arc<flavors::filter::Channel<T>>),
+
+    /// The after flavor.
+    At(Arc<flavors::at::Channel>),
+
+    /// The tick flavor.
+    Tick(Arc<flavors::tick::Channel>),
+
+    /// The never flavor.
+    Never(flavors::never::Channel<T>),
+}
+
+unsafe impl<T: Send> Send for Receiver<T> {}
+unsafe impl<T: Send> Sync for Receiver<T> {}
+
+impl<T> Unpin for Receiver<T> {}
+
+impl<T> UnwindSafe for Receiver<T> {}
+impl<T> RefUnwindSafe for Receiver<T> {}
+
+impl<T> ReceiverFlavor<T> {
+    /// Attempts to receive a message from the channel without blocking.
+    fn try_recv(&self) -> Result<T, TryRecvError> {
+        match self {
+            ReceiverFlavor::Array(chan) => chan.try_recv(),
+            ReceiverFlavor::List(chan) => chan.try_recv(),
+            ReceiverFlavor::Zero(chan) => chan.try_recv(),
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.try_recv(cursor),
+            ReceiverFlavor::Priority(chan) => chan.try_recv(),
+            ReceiverFlavor::Merge(chan) => chan.try_recv(),
+            ReceiverFlavor::Tee(chan, index) => chan.try_recv(*index),
+            ReceiverFlavor::Map(chan) => chan.try_recv(),
+            ReceiverFlavor::Filter(chan) => chan.try_recv(),
+            ReceiverFlavor::At(chan) => {
+                let msg = chan.try_recv();
+                unsafe {
+                    mem::transmute_copy::<Result<Instant, TryRecvError>, Result<T, TryRecvError>>(
+                        &msg,
+                    )
+                }
+            }
+            ReceiverFlavor::Tick(chan) => {
+                let msg = chan.try_recv();
+                unsafe {
+                    mem::transmute_copy::<Result<Instant, TryRecvError>, Result<T, TryRecvError>>(
+                        &msg,
+                    )
+                }
+            }
+            ReceiverFlavor::Never(chan) => chan.try_recv(),
+        }
+    }
+
+    /// Returns the metrics recorded by the channel, if it records any.
+    fn metrics(&self) -> Option<&Metrics> {
+        match self {
+            ReceiverFlavor::Array(chan) => Some(chan.metrics()),
+            ReceiverFlavor::List(chan) => Some(chan.metrics()),
+            ReceiverFlavor::Zero(chan) => Some(chan.metrics()),
+            ReceiverFlavor::Broadcast(chan, _) => Some(chan.metrics()),
+            ReceiverFlavor::Priority(chan) => Some(chan.metrics()),
+            ReceiverFlavor::Merge(_)
+            | ReceiverFlavor::Tee(..)
+            | ReceiverFlavor::Map(_)
+            | ReceiverFlavor::Filter(_)
+            | ReceiverFlavor::At(_)
+            | ReceiverFlavor::Tick(_)
+            | ReceiverFlavor::Never(_) => None,
+        }
+    }
+
+    /// Polls a receive operation on behalf of an asynchronous task.
+    fn poll_recv(&self, waiter: &mut Waiter, waker: &task::Waker) -> Poll<Result<T, RecvError>> {
+        match self {
+            ReceiverFlavor::Array(chan) => chan.poll_recv(waiter, waker),
+            ReceiverFlavor::List(chan) => chan.poll_recv(waiter, waker),
+            ReceiverFlavor::Zero(chan) => chan.poll_recv(waiter, waker),
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.poll_recv(cursor, waiter, waker),
+            ReceiverFlavor::Priority(chan) => chan.poll_recv(waiter, waker),
+            ReceiverFlavor::Merge(chan) => poll_watch(&**chan, || chan.try_recv(), waiter, waker),
+            ReceiverFlavor::Tee(chan, index) => poll_watch(
+                &chan.receiver(*index),
+                || chan.try_recv(*index),
+                waiter,
+                waker,
+            ),
+            ReceiverFlavor::Map(chan) => poll_watch(&**chan, || chan.try_recv(), waiter, waker),
+            ReceiverFlavor::Filter(chan) => poll_watch(&**chan, || chan.try_recv(), waiter, waker),
+            ReceiverFlavor::At(chan) => poll_watch(&**chan, || self.try_recv(), waiter, waker),
+            ReceiverFlavor::Tick(chan) => poll_watch(&**chan, || self.try_recv(), waiter, waker),
+            ReceiverFlavor::Never(chan) => poll_watch(chan, || self.try_recv(), waiter, waker),
+        }
+        .map(|res| res.map_err(|_| RecvError))
+    }
+
+    /// Withdraws a receive operation registered by an asynchronous task.
+    fn cancel_recv(&self, waiter: &mut Waiter) {
+        match self {
+            ReceiverFlavor::Array(chan) => chan.cancel_recv(waiter),
+            ReceiverFlavor::List(chan) => chan.cancel_recv(waiter),
+            ReceiverFlavor::Zero(chan) => chan.cancel_recv(waiter),
+            ReceiverFlavor::Broadcast(chan, _) => chan.cancel_recv(waiter),
+            ReceiverFlavor::Priority(chan) => chan.cancel_recv(waiter),
+            ReceiverFlavor::Merge(chan) => cancel_watch(&**chan, waiter),
+            ReceiverFlavor::Tee(chan, index) => cancel_watch(&chan.receiver(*index), waiter),
+            ReceiverFlavor::Map(chan) => cancel_watch(&**chan, waiter),
+            ReceiverFlavor::Filter(chan) => cancel_watch(&**chan, waiter),
+            ReceiverFlavor::At(chan) => cancel_watch(&**chan, waiter),
+            ReceiverFlavor::Tick(chan) => cancel_watch(&**chan, waiter),
+            ReceiverFlavor::Never(chan) => cancel_watch(chan, waiter),
+        }
+    }
+}
+
+/// Polls a receive operation on a flavor that waits by watching other channels.
+fn poll_watch<T, H, F>(
+    handle: &H,
+    try_recv: F,
+    waiter: &mut Waiter,
+    waker: &task::Waker,
+) -> Poll<Result<T, ()>>
+where
+    H: SelectHandle + ?Sized,
+    F: Fn() -> Result<T, TryRecvError>,
+{
+    loop {
+        // Withdraw the registration left over from the previous poll.
+        cancel_watch(handle, waiter);
+
+        match try_recv() {
+            Ok(msg) => return Poll::Ready(Ok(msg)),
+            Err(TryRecvError::Disconnected) => return Poll::Ready(Err(())),
+            Err(TryRecvError::Empty) => {}
+        }
+
+        // Prepare for waiting until one of the watched channels wakes the task up.
+        let (oper, cx) = waiter.start(waker);
+        if !handle.watch(oper, &cx) {
+            // Timer-driven channels don't notify anyone when their time comes.
+            if let Some(deadline) = handle.deadline() {
+                waiter.alarm = Some(alarm::wake_at(deadline, waker));
+            }
+            return Poll::Pending;
+        }
+    }
+}
+
+/// Withdraws a receive operation registered by `poll_watch`.
+fn cancel_watch<H>(handle: &H, waiter: &mut Waiter)
+where
+    H: SelectHandle + ?Sized,
+{
+    if let Some(oper) = waiter.finish() {
+        handle.unwatch(oper);
+    }
+    if let Some(id) = waiter.alarm.take() {
+        alarm::cancel(id);
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Creates a receiver of the given flavor.
+    fn new(flavor: ReceiverFlavor<T>) -> Receiver<T> {
+        Receiver {
+            flavor,
+            #[cfg(feature = "futures")]
+            stream: StreamState::new(),
+        }
+    }
+
+    /// Records `count` messages received from the channel.
+    fn record_received(&self, count: usize) {
+        if let Some(metrics) = self.flavor.metrics() {
+            metrics.received(count);
+        }
+    }
+
+    /// Records a failed non-blocking receive operation.
+    fn record_try_recv_failed(&self) {
+        if let Some(metrics) = self.flavor.metrics() {
+            metrics.try_recv_failed();
+        }
+    }
+
+    /// Attempts to receive a message from the channel without blocking.
+    ///
+    /// This method will either receive a message from the channel immediately or return an error
+    /// if the channel is empty.
+    ///
+    /// If called on a zero-capacity channel, this method will receive a message only if there
+    /// happens to be a send operation on the other side of the channel at the same time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, TryRecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    ///
+    /// s.send(5).unwrap();
+    /// drop(s);
+    ///
+    /// assert_eq!(r.try_recv(), Ok(5));
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    /// ```
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let res = self.flavor.try_recv();
+        match res {
+            Ok(_) => self.record_received(1),
+            Err(_) => self.record_try_recv_failed(),
+        }
+        res
+    }
+
+    /// Attempts to inspect the next message in the channel without receiving it.
+    ///
+    /// If a message is ready, `f` is called with a reference to it and its result is returned,
+    /// while the message stays in the channel. Otherwise, this method returns the same error as
+    /// [`try_recv`] would.
+    ///
+    /// The message is inspected in place, so other receivers have to wait until `f` returns before
+    /// they can receive it. A zero-capacity channel doesn't hold messages, so there is never a
+    /// message to inspect in it.
+    ///
+    /// On a receiver created by [`map`], the next message is transformed so that `f` can inspect
+    /// it, and then kept in front of the source until it's received. The message is taken out of
+    /// the channel while `f` runs, so other receivers may receive the messages after it in the
+    /// meantime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, TryRecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// assert_eq!(r.try_peek_with(|msg: &String| msg.len()), Err(TryRecvError::Empty));
+    ///
+    /// s.send(String::from("hello")).unwrap();
+    /// assert_eq!(r.try_peek_with(|msg| msg.len()), Ok(5));
+    ///
+    /// // The message is still in the channel.
+    /// assert_eq!(r.try_recv(), Ok(String::from("hello")));
+    /// ```
+    ///
+    /// [`try_recv`]: Receiver::try_recv
+    /// [`map`]: Receiver::map
+    pub fn try_peek_with<F, R>(&self, f: F) -> Result<R, TryRecvError>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        let mut res = None;
+        self.try_recv_if(|msg| {
+            res = Some(f(msg));
+            false
+        })?;
+        Ok(res.unwrap())
+    }
+
+    /// Attempts to receive the next message from the channel if `pred` returns `true` for it.
+    ///
+    /// If a message is ready, `pred` is called with a reference to it. If `pred` returns `true`,
+    /// the message is received and returned. Otherwise, the message stays in the channel and
+    /// `None` is returned. If no message is ready, this method returns the same error as
+    /// [`try_recv`] would.
+    ///
+    /// As with [`try_peek_with`], other receivers have to wait until `pred` returns, except on a
+    /// receiver created by [`map`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, TryRecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(("billing", 1)).unwrap();
+    /// s.send(("shipping", 2)).unwrap();
+    ///
+    /// // Only take messages routed to billing.
+    /// assert_eq!(r.try_recv_if(|msg| msg.0 == "billing"), Ok(Some(("billing", 1))));
+    /// assert_eq!(r.try_recv_if(|msg| msg.0 == "billing"), Ok(None));
+    /// assert_eq!(r.try_recv(), Ok(("shipping", 2)));
+    ///
+    /// drop(s);
+    /// assert_eq!(r.try_recv_if(|_| true), Err(TryRecvError::Disconnected));
+    /// ```
+    ///
+    /// [`try_recv`]: Receiver::try_recv
+    /// [`try_peek_with`]: Receiver::try_peek_with
+    /// [`map`]: Receiver::map
+    pub fn try_recv_if<F>(&self, pred: F) -> Result<Option<T>, TryRecvError>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        let res = match &self.flavor {
+            ReceiverFlavor::Array(chan) => chan.try_recv_if(pred),
+            ReceiverFlavor::List(chan) => chan.try_recv_if(pred),
+            ReceiverFlavor::Zero(chan) => {
+                if chan.is_disconnected() {
+                    Err(TryRecvError::Disconnected)
+                } else {
+                    Err(TryRecvError::Empty)
+                }
+            }
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.try_recv_if(cursor, pred),
+            ReceiverFlavor::Priority(chan) => chan.try_recv_if(pred),
+            ReceiverFlavor::Merge(chan) => {
+                let mut pred = Some(pred);
+                chan.try_recv_if(&mut |msg| pred.take().unwrap()(msg))
+            }
+            ReceiverFlavor::Tee(chan, index) => chan.try_recv_if(*index, pred),
+            ReceiverFlavor::Map(chan) => {
+                let mut pred = Some(pred);
+                chan.try_recv_if(&mut |msg| pred.take().unwrap()(msg))
+            }
+            ReceiverFlavor::Filter(chan) => {
+                let mut pred = Some(pred);
+                chan.try_recv_if(&mut |msg| pred.take().unwrap()(msg))
+            }
+            ReceiverFlavor::At(chan) => {
+                let msg =
+                    chan.try_recv_if(|t| pred(unsafe { &*(t as *const Instant as *const T) }));
+                unsafe {
+                    mem::transmute_copy::<
+                        Result<Option<Instant>, TryRecvError>,
+                        Result<Option<T>, TryRecvError>,
+                    >(&msg)
+                }
+            }
+            ReceiverFlavor::Tick(chan) => {
+                let msg =
+                    chan.try_recv_if(|t| pred(unsafe { &*(t as *const Instant as *const T) }));
+                unsafe {
+                    mem::transmute_copy::<
+                        Result<Option<Instant>, TryRecvError>,
+                        Result<Option<T>, TryRecvError>,
+                    >(&msg)
+                }
+            }
+            ReceiverFlavor::Never(chan) => chan.try_recv().map(Some),
+        };
+        match res {
+            Ok(Some(_)) => self.record_received(1),
+            Ok(None) => {}
+            Err(_) => self.record_try_recv_failed(),
+        }
+        res
+    }
+
+    /// Blocks the current thread until a message is received or the channel is empty and
+    /// disconnected.
+    ///
+    /// If the channel is empty and not disconnected, this call will block until the receive
+    /// operation can proceed. If the channel is empty and becomes disconnected, this call will
+    /// wake up and return an error.
+    ///
+    /// If called on a zero-capacity channel, this method will wait for a send operation to appear
+    /// on the other side of the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, RecvError};
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_secs(1));
+    ///     s.send(5).unwrap();
+    ///     drop(s);
+    /// });
+    ///
+    /// assert_eq!(r.recv(), Ok(5));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.recv_until(None).map_err(|_| RecvError)
+    }
+
+    /// Waits for a message to be received from the channel, but only for a limited time.
+    ///
+    /// If the channel is empty and not disconnected, this call will block until the receive
+    /// operation can proceed or the operation times out. If the channel is empty and becomes
+    /// disconnected, this call will wake up and return an error.
+    ///
+    /// If called on a zero-capacity channel, this method will wait for a send operation to appear
+    /// on the other side of the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, RecvTimeoutError};
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_secs(1));
+    ///     s.send(5).unwrap();
+    ///     drop(s);
+    /// });
+    ///
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_millis(500)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_secs(1)),
+    ///     Ok(5),
+    /// );
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_secs(1)),
+    ///     Err(RecvTimeoutError::Disconnected),
+    /// );
+    /// ```
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_deadline(clock::now() + timeout)
+    }
+
+    /// Waits for a message to be received from the channel, but only before a given deadline.
+    ///
+    /// If the channel is empty and not disconnected, this call will block until the receive
+    /// operation can proceed or the operation times out. If the channel is empty and becomes
+    /// disconnected, this call will wake up and return an error.
+    ///
+    /// If called on a zero-capacity channel, this method will wait for a send operation to appear
+    /// on the other side of the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::{Instant, Duration};
+    /// use crossbeam_channel::{unbounded, RecvTimeoutError};
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_secs(1));
+    ///     s.send(5).unwrap();
+    ///     drop(s);
+    /// });
+    ///
+    /// let now = Instant::now();
+    ///
+    /// assert_eq!(
+    ///     r.recv_deadline(now + Duration::from_millis(500)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    /// assert_eq!(
+    ///     r.recv_deadline(now + Duration::from_millis(1500)),
+    ///     Ok(5),
+    /// );
+    /// assert_eq!(
+    ///     r.recv_deadline(now + Duration::from_secs(5)),
+    ///     Err(RecvTimeoutError::Disconnected),
+    /// );
+    /// ```
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Some(deadline))
+    }
 
-/// The receiving side of a channel.
-///
-/// # Examples
-///
-/// ```
-/// use std::thread;
-/// use std::time::Duration;
-/// use crossbeam_channel::unbounded;
-///
-/// let (s, r) = unbounded();
-///
-/// thread::spawn(move || {
-///     let _ = s.send(1);
-///     thread::sleep(Duration::from_secs(1));
-///     let _ = s.send(2);
-/// });
-///
-/// assert_eq!(r.recv(), Ok(1)); // Received immediately.
-/// assert_eq!(r.recv(), Ok(2)); // Received after 1 second.
-/// ```
-pub struct Receiver<T> {
-    flavor: ReceiverFlavor<T>,
-}
+    /// Receives a message from the channel, blocking until the deadline if there is one.
+    fn recv_until(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        if metrics::ENABLED {
+            // Only count the operation as a wait if no message is ready right away.
+            match self.flavor.try_recv() {
+                Ok(msg) => {
+                    self.record_received(1);
+                    return Ok(msg);
+                }
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => {}
+            }
+        }
 
-/// Receiver flavors.
-enum ReceiverFlavor<T> {
-    /// Bounded channel based on a preallocated array.
-    Array(counter::Receiver<flavors::array::Channel<T>>),
+        let wait = Wait::start();
+        let res = match &self.flavor {
+            ReceiverFlavor::Array(chan) => chan.recv(deadline),
+            ReceiverFlavor::List(chan) => chan.recv(deadline),
+            ReceiverFlavor::Zero(chan) => chan.recv(deadline),
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.recv(cursor, deadline),
+            ReceiverFlavor::Priority(chan) => chan.recv(deadline),
+            ReceiverFlavor::Merge(_) | ReceiverFlavor::Tee(..) | ReceiverFlavor::Map(_) => {
+                self.select_recv(deadline)
+            }
+            ReceiverFlavor::Filter(chan) => chan.recv(deadline),
+            ReceiverFlavor::At(chan) => {
+                let msg = chan.recv(deadline);
+                unsafe {
+                    mem::transmute_copy::<
+                        Result<Instant, RecvTimeoutError>,
+                        Result<T, RecvTimeoutError>,
+                    >(&msg)
+                }
+            }
+            ReceiverFlavor::Tick(chan) => {
+                let msg = chan.recv(deadline);
+                unsafe {
+                    mem::transmute_copy::<
+                        Result<Instant, RecvTimeoutError>,
+                        Result<T, RecvTimeoutError>,
+                    >(&msg)
+                }
+            }
+            ReceiverFlavor::Never(chan) => chan.recv(deadline),
+        };
+        if let Some(metrics) = self.flavor.metrics() {
+            metrics.recv_waited(wait);
+        }
 
-    /// Unbounded channel implemented as a linked list.
-    List(counter::Receiver<flavors::list::Channel<T>>),
+        if res.is_ok() {
+            self.record_received(1);
+        }
+        res
+    }
 
-    /// Zero-capacity channel.
-    Zero(counter::Receiver<flavors::zero::Channel<T>>),
+    /// Blocks on a flavor that waits by selecting over other channels.
+    fn select_recv(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        let mut sel = Select::new();
+        sel.recv(self);
+        let oper = match deadline {
+            None => sel.select(),
+            Some(deadline) => sel
+                .select_deadline(deadline)
+                .map_err(|_| RecvTimeoutError::Timeout)?,
+        };
+        oper.recv(self).map_err(|_| RecvTimeoutError::Disconnected)
+    }
 
-    /// The after flavor.
-    At(Arc<flavors::at::Channel>),
+    /// Attempts to receive up to `max` messages from the channel without blocking.
+    ///
+    /// Received messages are appended to `buf` in the order they were sent. On success, the number
+    /// of received messages is returned, which is at least one unless `max` is zero.
+    ///
+    /// If there are no messages in the channel, this method returns the same error as
+    /// [`try_recv`] would. Bounded and unbounded channels claim as many messages as possible with a
+    /// single atomic operation, which is cheaper than calling [`try_recv`] in a loop.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, TryRecvError};
+    ///
+    /// let (s, r) = unbounded();
+    /// let mut buf = Vec::new();
+    /// assert_eq!(r.try_recv_many(&mut buf, 10), Err(TryRecvError::Empty));
+    ///
+    /// for i in 0..5 {
+    ///     s.send(i).unwrap();
+    /// }
+    /// drop(s);
+    ///
+    /// assert_eq!(r.try_recv_many(&mut buf, 3), Ok(3));
+    /// assert_eq!(r.try_recv_many(&mut buf, 3), Ok(2));
+    /// assert_eq!(buf, [0, 1, 2, 3, 4]);
+    /// assert_eq!(r.try_recv_many(&mut buf, 3), Err(TryRecvError::Disconnected));
+    /// ```
+    ///
+    /// [`try_recv`]: Receiver::try_recv
+    pub fn try_recv_many(&self, buf: &mut Vec<T>, max: usize) -> Result<usize, TryRecvError> {
+        let res = self.recv_batch(buf, max);
+        match res {
+            Ok(count) => self.record_received(count),
+            Err(_) => self.record_try_recv_failed(),
+        }
+        res
+    }
 
-    /// The tick flavor.
-    Tick(Arc<flavors::tick::Channel>),
+    /// Receives up to `max` messages without blocking and without recording metrics.
+    fn recv_batch(&self, buf: &mut Vec<T>, max: usize) -> Result<usize, TryRecvError> {
+        if max == 0 {
+            return Ok(0);
+        }
 
-    /// The never flavor.
-    Never(flavors::never::Channel<T>),
-}
+        match &self.flavor {
+            ReceiverFlavor::Array(chan) => chan.try_recv_many(buf, max),
+            ReceiverFlavor::List(chan) => chan.try_recv_many(buf, max),
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.try_recv_many(cursor, buf, max),
+            ReceiverFlavor::Zero(_)
+            | ReceiverFlavor::Merge(_)
+            | ReceiverFlavor::Tee(..)
+            | ReceiverFlavor::Map(_)
+            | ReceiverFlavor::Filter(_)
+            | ReceiverFlavor::At(_)
+            | ReceiverFlavor::Tick(_)
+            | ReceiverFlavor::Never(_) => {
+                // These flavors hand over one message at a time.
+                let mut count = 0;
+                while count < max {
+                    match self.flavor.try_recv() {
+                        Ok(msg) => buf.push(msg),
+                        Err(err) if count == 0 => return Err(err),
+                        Err(_) => break,
+                    }
+                    count += 1;
+                }
+                Ok(count)
+            }
+            ReceiverFlavor::Priority(chan) => chan.try_recv_many(buf, max),
+        }
+    }
 
-unsafe impl<T: Send> Send for Receiver<T> {}
-unsafe impl<T: Send> Sync for Receiver<T> {}
+    /// Blocks the current thread until at least one message is received, then receives up to
+    /// `max` messages in total.
+    ///
+    /// Received messages are appended to `buf` in the order they were sent. On success, the number
+    /// of received messages is returned, which is at least one unless `max` is zero.
+    ///
+    /// If the channel is empty and not disconnected, this call will block until a message is sent
+    /// or the channel is disconnected. Once the first message is received, the remaining ones are
+    /// taken without blocking, as with [`try_recv_many`].
+    ///
+    /// If the channel is empty and disconnected, this call will wake up and return an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded, RecvError};
+    ///
+    /// let (s, r) = bounded(10);
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_secs(1));
+    ///     for i in 0..5 {
+    ///         s.send(i).unwrap();
+    ///     }
+    /// });
+    ///
+    /// let mut buf = Vec::new();
+    /// while r.recv_many(&mut buf, 2).is_ok() {}
+    ///
+    /// assert_eq!(buf, [0, 1, 2, 3, 4]);
+    /// assert_eq!(r.recv_many(&mut buf, 2), Err(RecvError));
+    /// ```
+    ///
+    /// [`try_recv_many`]: Receiver::try_recv_many
+    pub fn recv_many(&self, buf: &mut Vec<T>, max: usize) -> Result<usize, RecvError> {
+        match self.recv_batch(buf, max) {
+            Ok(count) => {
+                self.record_received(count);
+                return Ok(count);
+            }
+            Err(TryRecvError::Disconnected) => return Err(RecvError),
+            Err(TryRecvError::Empty) => {}
+        }
 
-impl<T> UnwindSafe for Receiver<T> {}
-impl<T> RefUnwindSafe for Receiver<T> {}
+        buf.push(self.recv()?);
+        let count = self.recv_batch(buf, max - 1).unwrap_or(0);
+        self.record_received(count);
+        Ok(1 + count)
+    }
+
+    /// Returns a future that receives a message from the channel.
+    ///
+    /// The future completes once a message is received or the channel becomes empty and
+    /// disconnected. Instead of blocking the current thread, a task waiting on the future gets
+    /// woken up by the channel, so the same channel can be used from threads and asynchronous
+    /// tasks at the same time.
+    ///
+    /// Dropping the future before completion doesn't lose messages, with one exception: on a
+    /// zero-capacity channel, a send operation may already have paired up with the waiting task,
+    /// in which case its message is dropped together with the future.
+    ///
+    /// Channels created by [`after`], [`at`] and [`tick`] generate messages lazily, so a task
+    /// waiting on them is woken up at the delivery time by a background thread shared by the whole
+    /// process. The thread is started the first time a task waits on such a channel, and lets go
+    /// of the task's waker as soon as the future is dropped. Channels created by [`never`] never
+    /// complete.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::future::Future;
+    /// use std::pin::Pin;
+    /// use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// # fn noop_raw_waker() -> RawWaker {
+    /// #     fn clone(_: *const ()) -> RawWaker { noop_raw_waker() }
+    /// #     fn noop(_: *const ()) {}
+    /// #     static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    /// #     RawWaker::new(std::ptr::null(), &VTABLE)
+    /// # }
+    /// let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+    /// let mut cx = Context::from_waker(&waker);
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// // The channel is empty, so the task has to wait.
+    /// let mut fut = r.recv_async();
+    /// assert!(Pin::new(&mut fut).poll(&mut cx).is_pending());
+    ///
+    /// // Sending a message wakes the task up.
+    /// s.send(5).unwrap();
+    /// assert_eq!(Pin::new(&mut fut).poll(&mut cx), Poll::Ready(Ok(5)));
+    /// ```
+    pub fn recv_async(&self) -> RecvFuture<'_, T> {
+        RecvFuture {
+            receiver: self,
+            waiter: Waiter::new(),
+        }
+    }
+
+    /// Returns `true` if the channel is empty.
+    ///
+    /// Note: Zero-capacity channels are always empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    ///
+    /// assert!(r.is_empty());
+    /// s.send(0).unwrap();
+    /// assert!(!r.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        match &self.flavor {
+            ReceiverFlavor::Array(chan) => chan.is_empty(),
+            ReceiverFlavor::List(chan) => chan.is_empty(),
+            ReceiverFlavor::Zero(chan) => chan.is_empty(),
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.pending(cursor) == 0,
+            ReceiverFlavor::Priority(chan) => chan.is_empty(),
+            ReceiverFlavor::Merge(chan) => chan.is_empty(),
+            ReceiverFlavor::Tee(chan, index) => chan.is_empty(*index),
+            ReceiverFlavor::Map(chan) => chan.is_empty(),
+            ReceiverFlavor::Filter(chan) => chan.source().is_empty(),
+            ReceiverFlavor::At(chan) => chan.is_empty(),
+            ReceiverFlavor::Tick(chan) => chan.is_empty(),
+            ReceiverFlavor::Never(chan) => chan.is_empty(),
+        }
+    }
+
+    /// Returns `true` if the channel is full.
+    ///
+    /// Note: Zero-capacity channels are always full.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::bounded;
+    ///
+    /// let (s, r) = bounded(1);
+    ///
+    /// assert!(!r.is_full());
+    /// s.send(0).unwrap();
+    /// assert!(r.is_full());
+    /// ```
+    pub fn is_full(&self) -> bool {
+        match &self.flavor {
+            ReceiverFlavor::Array(chan) => chan.is_full(),
+            ReceiverFlavor::List(chan) => chan.is_full(),
+            ReceiverFlavor::Zero(chan) => chan.is_full(),
+            ReceiverFlavor::Broadcast(chan, cursor) => {
+                Some(chan.pending(cursor)) == chan.capacity()
+            }
+            ReceiverFlavor::Priority(chan) => chan.is_full(),
+            ReceiverFlavor::Merge(chan) => chan.is_full(),
+            ReceiverFlavor::Tee(..) => false,
+            ReceiverFlavor::Map(chan) => chan.is_full(),
+            ReceiverFlavor::Filter(chan) => chan.source().is_full(),
+            ReceiverFlavor::At(chan) => chan.is_full(),
+            ReceiverFlavor::Tick(chan) => chan.is_full(),
+            ReceiverFlavor::Never(chan) => chan.is_full(),
+        }
+    }
+
+    /// Returns the number of messages in the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded();
+    /// assert_eq!(r.len(), 0);
+    ///
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// assert_eq!(r.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        match &self.flavor {
+            ReceiverFlavor::Array(chan) => chan.len(),
+            ReceiverFlavor::List(chan) => chan.len(),
+            ReceiverFlavor::Zero(chan) => chan.len(),
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.pending(cursor),
+            ReceiverFlavor::Priority(chan) => chan.len(),
+            ReceiverFlavor::Merge(chan) => chan.len(),
+            ReceiverFlavor::Tee(chan, index) => chan.len(*index),
+            ReceiverFlavor::Map(chan) => chan.len(),
+            ReceiverFlavor::Filter(chan) => chan.source().len(),
+            ReceiverFlavor::At(chan) => chan.len(),
+            ReceiverFlavor::Tick(chan) => chan.len(),
+            ReceiverFlavor::Never(chan) => chan.len(),
+        }
+    }
+
+    /// If the channel is bounded, returns its capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, unbounded};
+    ///
+    /// let (_, r) = unbounded::<i32>();
+    /// assert_eq!(r.capacity(), None);
+    ///
+    /// let (_, r) = bounded::<i32>(5);
+    /// assert_eq!(r.capacity(), Some(5));
+    ///
+    /// let (_, r) = bounded::<i32>(0);
+    /// assert_eq!(r.capacity(), Some(0));
+    /// ```
+    pub fn capacity(&self) -> Option<usize> {
+        match &self.flavor {
+            ReceiverFlavor::Array(chan) => chan.capacity(),
+            ReceiverFlavor::List(chan) => chan.capacity(),
+            ReceiverFlavor::Zero(chan) => chan.capacity(),
+            ReceiverFlavor::Broadcast(chan, _) => chan.capacity(),
+            ReceiverFlavor::Priority(chan) => chan.capacity(),
+            ReceiverFlavor::Merge(chan) => chan.capacity(),
+            ReceiverFlavor::Tee(..) => None,
+            ReceiverFlavor::Map(chan) => chan.capacity(),
+            ReceiverFlavor::Filter(chan) => chan.source().capacity(),
+            ReceiverFlavor::At(chan) => chan.capacity(),
+            ReceiverFlavor::Tick(chan) => chan.capacity(),
+            ReceiverFlavor::Never(chan) => chan.capacity(),
+        }
+    }
 
-impl<T> Receiver<T> {
-    /// Attempts to receive a message from the channel without blocking.
+    /// Returns the number of senders connected to the channel.
     ///
-    /// This method will either receive a message from the channel immediately or return an error
-    /// if the channel is empty.
-    ///
-    /// If called on a zero-capacity channel, this method will receive a message only if there
-    /// happens to be a send operation on the other side of the channel at the same time.
+    /// Channels created by [`after`], [`at`], [`tick`] and [`never`] have no senders, so this
+    /// method returns zero for them.
     ///
     /// # Examples
     ///
     /// ```
-    /// use crossbeam_channel::{unbounded, TryRecvError};
-    ///
-    /// let (s, r) = unbounded();
-    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    /// use crossbeam_channel::unbounded;
     ///
-    /// s.send(5).unwrap();
-    /// drop(s);
+    /// let (s1, r) = unbounded::<i32>();
+    /// let s2 = s1.clone();
+    /// assert_eq!(r.sender_count(), 2);
     ///
-    /// assert_eq!(r.try_recv(), Ok(5));
-    /// assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    /// drop(s1);
+    /// drop(s2);
+    /// assert_eq!(r.sender_count(), 0);
     /// ```
-    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+    pub fn sender_count(&self) -> usize {
         match &self.flavor {
-            ReceiverFlavor::Array(chan) => chan.try_recv(),
-            ReceiverFlavor::List(chan) => chan.try_recv(),
-            ReceiverFlavor::Zero(chan) => chan.try_recv(),
-            ReceiverFlavor::At(chan) => {
-                let msg = chan.try_recv();
-                unsafe {
-                    mem::transmute_copy::<Result<Instant, TryRecvError>, Result<T, TryRecvError>>(
-                        &msg,
-                    )
-                }
-            }
-            ReceiverFlavor::Tick(chan) => {
-                let msg = chan.try_recv();
-                unsafe {
-                    mem::transmute_copy::<Result<Instant, TryRecvError>, Result<T, TryRecvError>>(
-                        &msg,
-                    )
-                }
-            }
-            ReceiverFlavor::Never(chan) => chan.try_recv(),
+            ReceiverFlavor::Array(chan) => chan.sender_count(),
+            ReceiverFlavor::List(chan) => chan.sender_count(),
+            ReceiverFlavor::Zero(chan) => chan.sender_count(),
+            ReceiverFlavor::Broadcast(chan, _) => chan.sender_count(),
+            ReceiverFlavor::Priority(chan) => chan.sender_count(),
+            ReceiverFlavor::Merge(chan) => chan.sender_count(),
+            ReceiverFlavor::Tee(chan, _) => chan.sender_count(),
+            ReceiverFlavor::Map(chan) => chan.sender_count(),
+            ReceiverFlavor::Filter(chan) => chan.source().sender_count(),
+            ReceiverFlavor::At(_) | ReceiverFlavor::Tick(_) | ReceiverFlavor::Never(_) => 0,
         }
     }
 
-    /// Blocks the current thread until a message is received or the channel is empty and
-    /// disconnected.
+    /// Returns the number of receivers connected to the channel.
     ///
-    /// If the channel is empty and not disconnected, this call will block until the receive
-    /// operation can proceed. If the channel is empty and becomes disconnected, this call will
-    /// wake up and return an error.
+    /// Receivers of channels created by [`after`], [`at`], [`tick`] and [`never`] don't keep
+    /// count of their clones, so this method always returns one for them.
     ///
-    /// If called on a zero-capacity channel, this method will wait for a send operation to appear
-    /// on the other side of the channel.
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (_s, r1) = unbounded::<i32>();
+    /// assert_eq!(r1.receiver_count(), 1);
+    ///
+    /// let r2 = r1.clone();
+    /// assert_eq!(r1.receiver_count(), 2);
+    /// assert_eq!(r2.receiver_count(), 2);
+    /// ```
+    pub fn receiver_count(&self) -> usize {
+        match &self.flavor {
+            ReceiverFlavor::Array(chan) => chan.receiver_count(),
+            ReceiverFlavor::List(chan) => chan.receiver_count(),
+            ReceiverFlavor::Zero(chan) => chan.receiver_count(),
+            ReceiverFlavor::Broadcast(chan, _) => chan.receiver_count(),
+            ReceiverFlavor::Priority(chan) => chan.receiver_count(),
+            ReceiverFlavor::Merge(chan) => Arc::strong_count(chan),
+            ReceiverFlavor::Tee(chan, index) => chan.receiver_count(*index),
+            ReceiverFlavor::Map(chan) => Arc::strong_count(chan),
+            ReceiverFlavor::Filter(chan) => Arc::strong_count(chan),
+            ReceiverFlavor::At(_) | ReceiverFlavor::Tick(_) | ReceiverFlavor::Never(_) => 1,
+        }
+    }
+
+    /// Returns a snapshot of the statistics of the channel.
+    ///
+    /// See [`ChannelStats`] for what is counted. Receivers created by [`after`], [`at`], [`tick`]
+    /// and [`never`] keep no statistics and always return an empty snapshot. This method is only
+    /// available with the `metrics` feature enabled.
     ///
     /// # Examples
     ///
     /// ```
     /// use std::thread;
     /// use std::time::Duration;
-    /// use crossbeam_channel::{unbounded, RecvError};
+    /// use crossbeam_channel::bounded;
     ///
-    /// let (s, r) = unbounded();
+    /// let (s, r) = bounded(0);
     ///
     /// thread::spawn(move || {
-    ///     thread::sleep(Duration::from_secs(1));
-    ///     s.send(5).unwrap();
-    ///     drop(s);
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     s.send(1).unwrap();
     /// });
     ///
-    /// assert_eq!(r.recv(), Ok(5));
-    /// assert_eq!(r.recv(), Err(RecvError));
+    /// assert_eq!(r.recv(), Ok(1));
+    ///
+    /// let stats = r.stats();
+    /// assert_eq!(stats.received(), 1);
+    /// assert_eq!(stats.recv_waits(), 1);
+    /// assert!(stats.recv_wait_time() > Duration::from_millis(50));
     /// ```
-    pub fn recv(&self) -> Result<T, RecvError> {
+    #[cfg(feature = "metrics")]
+    pub fn stats(&self) -> ChannelStats {
         match &self.flavor {
-            ReceiverFlavor::Array(chan) => chan.recv(None),
-            ReceiverFlavor::List(chan) => chan.recv(None),
-            ReceiverFlavor::Zero(chan) => chan.recv(None),
-            ReceiverFlavor::At(chan) => {
-                let msg = chan.recv(None);
-                unsafe {
-                    mem::transmute_copy::<
-                        Result<Instant, RecvTimeoutError>,
-                        Result<T, RecvTimeoutError>,
-                    >(&msg)
-                }
-            }
-            ReceiverFlavor::Tick(chan) => {
-                let msg = chan.recv(None);
-                unsafe {
-                    mem::transmute_copy::<
-                        Result<Instant, RecvTimeoutError>,
-                        Result<T, RecvTimeoutError>,
-                    >(&msg)
-                }
-            }
-            ReceiverFlavor::Never(chan) => chan.recv(None),
+            ReceiverFlavor::Array(chan) => chan.stats(),
+            ReceiverFlavor::List(chan) => chan.stats(),
+            ReceiverFlavor::Zero(chan) => chan.stats(),
+            ReceiverFlavor::Broadcast(chan, _) => chan.stats(),
+            ReceiverFlavor::Priority(chan) => chan.stats(),
+            ReceiverFlavor::Map(chan) => chan.stats(),
+            ReceiverFlavor::Filter(chan) => chan.source().stats(),
+            ReceiverFlavor::Merge(_)
+            | ReceiverFlavor::Tee(..)
+            | ReceiverFlavor::At(_)
+            | ReceiverFlavor::Tick(_)
+            | ReceiverFlavor::Never(_) => ChannelStats::default(),
         }
-        .map_err(|_| RecvError)
     }
 
-    /// Waits for a message to be received from the channel, but only for a limited time.
+    /// A blocking iterator over messages in the channel.
     ///
-    /// If the channel is empty and not disconnected, this call will block until the receive
-    /// operation can proceed or the operation times out. If the channel is empty and becomes
-    /// disconnected, this call will wake up and return an error.
+    /// Each call to [`next`] blocks waiting for the next message and then returns it. However, if
+    /// the channel becomes empty and disconnected, it returns [`None`] without blocking.
     ///
-    /// If called on a zero-capacity channel, this method will wait for a send operation to appear
-    /// on the other side of the channel.
+    /// [`next`]: Iterator::next
     ///
     /// # Examples
     ///
     /// ```
     /// use std::thread;
-    /// use std::time::Duration;
-    /// use crossbeam_channel::{unbounded, RecvTimeoutError};
+    /// use crossbeam_channel::unbounded;
     ///
     /// let (s, r) = unbounded();
     ///
     /// thread::spawn(move || {
-    ///     thread::sleep(Duration::from_secs(1));
-    ///     s.send(5).unwrap();
-    ///     drop(s);
+    ///     s.send(1).unwrap();
+    ///     s.send(2).unwrap();
+    ///     s.send(3).unwrap();
+    ///     drop(s); // Disconnect the channel.
     /// });
     ///
-    /// assert_eq!(
-    ///     r.recv_timeout(Duration::from_millis(500)),
-    ///     Err(RecvTimeoutError::Timeout),
-    /// );
-    /// assert_eq!(
-    ///     r.recv_timeout(Duration::from_secs(1)),
-    ///     Ok(5),
-    /// );
-    /// assert_eq!(
-    ///     r.recv_timeout(Duration::from_secs(1)),
-    ///     Err(RecvTimeoutError::Disconnected),
-    /// );
+    /// // Collect all messages from the channel.
+    /// // Note that the call to `collect` blocks until the sender is dropped.
+    /// let v: Vec<_> = r.iter().collect();
+    ///
+    /// assert_eq!(v, [1, 2, 3]);
     /// ```
-    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
-        self.recv_deadline(Instant::now() + timeout)
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { receiver: self }
     }
 
-    /// Waits for a message to be received from the channel, but only before a given deadline.
+    /// A non-blocking iterator over messages in the channel.
     ///
-    /// If the channel is empty and not disconnected, this call will block until the receive
-    /// operation can proceed or the operation times out. If the channel is empty and becomes
-    /// disconnected, this call will wake up and return an error.
+    /// Each call to [`next`] returns a message if there is one ready to be received. The iterator
+    /// never blocks waiting for the next message.
     ///
-    /// If called on a zero-capacity channel, this method will wait for a send operation to appear
-    /// on the other side of the channel.
+    /// [`next`]: Iterator::next
     ///
     /// # Examples
     ///
     /// ```
     /// use std::thread;
-    /// use std::time::{Instant, Duration};
-    /// use crossbeam_channel::{unbounded, RecvTimeoutError};
+    /// use std::time::Duration;
+    /// use crossbeam_channel::unbounded;
     ///
-    /// let (s, r) = unbounded();
+    /// let (s, r) = unbounded::<i32>();
     ///
     /// thread::spawn(move || {
+    ///     s.send(1).unwrap();
     ///     thread::sleep(Duration::from_secs(1));
-    ///     s.send(5).unwrap();
-    ///     drop(s);
+    ///     s.send(2).unwrap();
+    ///     thread::sleep(Duration::from_secs(2));
+    ///     s.send(3).unwrap();
     /// });
     ///
-    /// let now = Instant::now();
+    /// thread::sleep(Duration::from_secs(2));
     ///
-    /// assert_eq!(
-    ///     r.recv_deadline(now + Duration::from_millis(500)),
-    ///     Err(RecvTimeoutError::Timeout),
-    /// );
-    /// assert_eq!(
-    ///     r.recv_deadline(now + Duration::from_millis(1500)),
-    ///     Ok(5),
-    /// );
-    /// assert_eq!(
-    ///     r.recv_deadline(now + Duration::from_secs(5)),
-    ///     Err(RecvTimeoutError::Disconnected),
-    /// );
+    /// // Collect all messages from the channel without blocking.
+    /// // The third message hasn't been sent yet so we'll collect only the first two.
+    /// let v: Vec<_> = r.try_iter().collect();
+    ///
+    /// assert_eq!(v, [1, 2]);
     /// ```
-    pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
-        match &self.flavor {
-            ReceiverFlavor::Array(chan) => chan.recv(Some(deadline)),
-            ReceiverFlavor::List(chan) => chan.recv(Some(deadline)),
-            ReceiverFlavor::Zero(chan) => chan.recv(Some(deadline)),
-            ReceiverFlavor::At(chan) => {
-                let msg = chan.recv(Some(deadline));
-                unsafe {
-                    mem::transmute_copy::<
-                        Result<Instant, RecvTimeoutError>,
-                        Result<T, RecvTimeoutError>,
-                    >(&msg)
-                }
-            }
-            ReceiverFlavor::Tick(chan) => {
-                let msg = chan.recv(Some(deadline));
-                unsafe {
-                    mem::transmute_copy::<
-                        Result<Instant, RecvTimeoutError>,
-                        Result<T, RecvTimeoutError>,
-                    >(&msg)
-                }
-            }
-            ReceiverFlavor::Never(chan) => chan.recv(Some(deadline)),
-        }
+    pub fn try_iter(&self) -> TryIter<'_, T> {
+        TryIter { receiver: self }
     }
 
-    /// Returns `true` if the channel is empty.
+    /// Closes the channel without dropping any senders or receivers.
     ///
-    /// Note: Zero-capacity channels are always empty.
+    /// After the channel is closed, send operations fail and receive operations fail once the
+    /// channel becomes empty, just as if all senders were dropped. Messages that are already in
+    /// the channel can still be received. All blocked senders and receivers are woken up.
+    ///
+    /// Returns `true` if this call closed the channel, or `false` if it was already closed or
+    /// disconnected. Channels created by [`after`], [`at`], [`never`], and [`tick`] cannot be
+    /// closed, so this method always returns `false` for them.
     ///
     /// # Examples
     ///
     /// ```
-    /// use crossbeam_channel::unbounded;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{bounded, RecvError};
     ///
-    /// let (s, r) = unbounded();
+    /// let (s, r) = bounded::<i32>(0);
     ///
-    /// assert!(r.is_empty());
-    /// s.send(0).unwrap();
-    /// assert!(!r.is_empty());
+    /// let handle = thread::spawn(move || s.send(1));
+    ///
+    /// // Wake up the blocked sender.
+    /// thread::sleep(Duration::from_secs(1));
+    /// assert!(r.close());
+    ///
+    /// assert!(handle.join().unwrap().is_err());
+    /// assert_eq!(r.recv(), Err(RecvError));
     /// ```
-    pub fn is_empty(&self) -> bool {
-        match &self.flavor {
-            ReceiverFlavor::Array(chan) => chan.is_empty(),
-            ReceiverFlavor::List(chan) => chan.is_empty(),
-            ReceiverFlavor::Zero(chan) => chan.is_empty(),
-            ReceiverFlavor::At(chan) => chan.is_empty(),
-            ReceiverFlavor::Tick(chan) => chan.is_empty(),
-            ReceiverFlavor::Never(chan) => chan.is_empty(),
+    pub fn close(&self) -> bool {
+        let closed = match &self.flavor {
+            ReceiverFlavor::Array(chan) => chan.disconnect(),
+            ReceiverFlavor::List(chan) => chan.disconnect_senders(),
+            ReceiverFlavor::Zero(chan) => chan.disconnect(),
+            ReceiverFlavor::Broadcast(chan, _) => chan.disconnect(),
+            ReceiverFlavor::Priority(chan) => chan.disconnect(),
+            ReceiverFlavor::Merge(chan) => chan.close(),
+            ReceiverFlavor::Tee(chan, _) => chan.close(),
+            ReceiverFlavor::Map(chan) => chan.close(),
+            ReceiverFlavor::Filter(chan) => chan.source().close(),
+            ReceiverFlavor::At(_) => false,
+            ReceiverFlavor::Tick(_) => false,
+            ReceiverFlavor::Never(_) => false,
+        };
+
+        if closed {
+            match &self.flavor {
+                ReceiverFlavor::Array(chan) => chan.notify_closed(),
+                ReceiverFlavor::List(chan) => chan.notify_closed(),
+                ReceiverFlavor::Zero(chan) => chan.notify_closed(),
+                ReceiverFlavor::Broadcast(chan, _) => chan.notify_closed(),
+                ReceiverFlavor::Priority(chan) => chan.notify_closed(),
+                ReceiverFlavor::Merge(_)
+                | ReceiverFlavor::Tee(..)
+                | ReceiverFlavor::Map(_)
+                | ReceiverFlavor::Filter(_)
+                | ReceiverFlavor::At(_)
+                | ReceiverFlavor::Tick(_)
+                | ReceiverFlavor::Never(_) => {}
+            }
         }
+        closed
     }
 
-    /// Returns `true` if the channel is full.
+    /// Returns `true` if the receiver was disconnected from a broadcast channel for lagging behind.
     ///
-    /// Note: Zero-capacity channels are always full.
+    /// A receiver of a channel created by [`broadcast_with_lagging`] with [`Lagging::Disconnect`]
+    /// gets disconnected once messages it hasn't received yet are evicted. Its receive operations
+    /// fail with the same errors as for a disconnected channel, and this method tells the two
+    /// cases apart. It returns `false` for all other receivers.
     ///
     /// # Examples
     ///
     /// ```
-    /// use crossbeam_channel::bounded;
+    /// use crossbeam_channel::{broadcast_with_lagging, Lagging, TryRecvError};
     ///
-    /// let (s, r) = bounded(1);
+    /// let (s, r1) = broadcast_with_lagging(1, Lagging::Disconnect);
+    /// let r2 = r1.clone();
     ///
-    /// assert!(!r.is_full());
-    /// s.send(0).unwrap();
-    /// assert!(r.is_full());
+    /// s.send(1).unwrap();
+    /// assert_eq!(r2.recv(), Ok(1));
+    ///
+    /// // `r1` misses the first message.
+    /// s.send(2).unwrap();
+    /// assert!(r1.is_lagged());
+    /// assert_eq!(r1.try_recv(), Err(TryRecvError::Disconnected));
+    ///
+    /// // `r2` gets disconnected because the sender is dropped.
+    /// drop(s);
+    /// assert_eq!(r2.recv(), Ok(2));
+    /// assert_eq!(r2.try_recv(), Err(TryRecvError::Disconnected));
+    /// assert!(!r2.is_lagged());
     /// ```
-    pub fn is_full(&self) -> bool {
+    pub fn is_lagged(&self) -> bool {
         match &self.flavor {
-            ReceiverFlavor::Array(chan) => chan.is_full(),
-            ReceiverFlavor::List(chan) => chan.is_full(),
-            ReceiverFlavor::Zero(chan) => chan.is_full(),
-            ReceiverFlavor::At(chan) => chan.is_full(),
-            ReceiverFlavor::Tick(chan) => chan.is_full(),
-            ReceiverFlavor::Never(chan) => chan.is_full(),
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.is_lagged(cursor),
+            ReceiverFlavor::Array(_)
+            | ReceiverFlavor::List(_)
+            | ReceiverFlavor::Zero(_)
+            | ReceiverFlavor::Priority(_)
+            | ReceiverFlavor::Merge(_)
+            | ReceiverFlavor::Tee(..)
+            | ReceiverFlavor::Map(_)
+            | ReceiverFlavor::Filter(_)
+            | ReceiverFlavor::At(_)
+            | ReceiverFlavor::Tick(_)
+            | ReceiverFlavor::Never(_) => false,
         }
     }
 
-    /// Returns the number of messages in the channel.
+    /// Returns `true` if the channel is closed or disconnected.
     ///
     /// # Examples
     ///
     /// ```
     /// use crossbeam_channel::unbounded;
     ///
-    /// let (s, r) = unbounded();
-    /// assert_eq!(r.len(), 0);
+    /// let (s, r) = unbounded::<i32>();
+    /// assert!(!r.is_closed());
     ///
-    /// s.send(1).unwrap();
-    /// s.send(2).unwrap();
-    /// assert_eq!(r.len(), 2);
+    /// drop(s);
+    /// assert!(r.is_closed());
     /// ```
-    pub fn len(&self) -> usize {
+    pub fn is_closed(&self) -> bool {
         match &self.flavor {
-            ReceiverFlavor::Array(chan) => chan.len(),
-            ReceiverFlavor::List(chan) => chan.len(),
-            ReceiverFlavor::Zero(chan) => chan.len(),
-            ReceiverFlavor::At(chan) => chan.len(),
-            ReceiverFlavor::Tick(chan) => chan.len(),
-            ReceiverFlavor::Never(chan) => chan.len(),
+            ReceiverFlavor::Array(chan) => chan.is_disconnected(),
+            ReceiverFlavor::List(chan) => chan.is_disconnected(),
+            ReceiverFlavor::Zero(chan) => chan.is_disconnected(),
+            ReceiverFlavor::Broadcast(chan, _) => chan.is_disconnected(),
+            ReceiverFlavor::Priority(chan) => chan.is_disconnected(),
+            ReceiverFlavor::Merge(chan) => chan.is_disconnected(),
+            ReceiverFlavor::Tee(chan, _) => chan.is_disconnected(),
+            ReceiverFlavor::Map(chan) => chan.is_disconnected(),
+            ReceiverFlavor::Filter(chan) => chan.source().is_closed(),
+            ReceiverFlavor::At(_) => false,
+            ReceiverFlavor::Tick(_) => false,
+            ReceiverFlavor::Never(_) => false,
         }
     }
 
-    /// If the channel is bounded, returns its capacity.
+    /// Splits the receiver into `n` receivers that each get a copy of every message.
+    ///
+    /// Messages aren't forwarded by a thread in the background. Whichever of the returned
+    /// receivers takes a message out of this one first leaves clones of it for all the others.
+    /// Receivers that fall behind buffer the clones without bound, so receivers that aren't going
+    /// to be used should be dropped, which stops them from getting clones.
+    ///
+    /// Clones of a returned receiver share the same messages, just like clones of any other
+    /// receiver. The returned receivers are disconnected once this one is, and closing any of them
+    /// closes this one.
+    ///
+    /// Clones are made in the receiving thread, for the other receivers in order. If cloning
+    /// panics, the panic propagates to the receiving operation and the message is lost for that
+    /// receiver. Receivers that already got a clone keep it, and the rest skip the message.
     ///
     /// # Examples
     ///
     /// ```
-    /// use crossbeam_channel::{bounded, unbounded};
+    /// use crossbeam_channel::{unbounded, RecvError};
     ///
-    /// let (_, r) = unbounded::<i32>();
-    /// assert_eq!(r.capacity(), None);
+    /// let (s, r) = unbounded();
+    /// let rs = r.tee(2);
     ///
-    /// let (_, r) = bounded::<i32>(5);
-    /// assert_eq!(r.capacity(), Some(5));
+    /// s.send(1).unwrap();
+    /// s.send(2).unwrap();
+    /// drop(s);
     ///
-    /// let (_, r) = bounded::<i32>(0);
-    /// assert_eq!(r.capacity(), Some(0));
+    /// for r in &rs {
+    ///     assert_eq!(r.recv(), Ok(1));
+    ///     assert_eq!(r.recv(), Ok(2));
+    ///     assert_eq!(r.recv(), Err(RecvError));
+    /// }
     /// ```
-    pub fn capacity(&self) -> Option<usize> {
-        match &self.flavor {
-            ReceiverFlavor::Array(chan) => chan.capacity(),
-            ReceiverFlavor::List(chan) => chan.capacity(),
-            ReceiverFlavor::Zero(chan) => chan.capacity(),
-            ReceiverFlavor::At(chan) => chan.capacity(),
-            ReceiverFlavor::Tick(chan) => chan.capacity(),
-            ReceiverFlavor::Never(chan) => chan.capacity(),
-        }
+    pub fn tee(self, n: usize) -> Vec<Receiver<T>>
+    where
+        T: Clone,
+    {
+        let chan = Arc::new(flavors::tee::Channel::new(self, n, T::clone));
+        (0..n)
+            .map(|index| Receiver::new(ReceiverFlavor::Tee(chan.clone(), index)))
+            .collect()
     }
 
-    /// A blocking iterator over messages in the channel.
+    /// Turns the receiver into one that transforms every message with `f`.
     ///
-    /// Each call to [`next`] blocks waiting for the next message and then returns it. However, if
-    /// the channel becomes empty and disconnected, it returns [`None`] without blocking.
+    /// The transform runs on the receiving thread as each message is received, with no thread in
+    /// between. The returned receiver works everywhere a receiver does, so it can be used in
+    /// [`Select`] and [`select!`], in which case the transform runs when the operation is selected.
     ///
-    /// [`next`]: Iterator::next
+    /// [`try_recv_if`] and [`try_peek_with`] transform the next message in order to inspect it. If
+    /// it isn't received, the transformed message is kept in front of this receiver and isn't
+    /// transformed again.
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::thread;
-    /// use crossbeam_channel::unbounded;
+    /// use crossbeam_channel::{select, unbounded};
     ///
-    /// let (s, r) = unbounded();
-    ///
-    /// thread::spawn(move || {
-    ///     s.send(1).unwrap();
-    ///     s.send(2).unwrap();
-    ///     s.send(3).unwrap();
-    ///     drop(s); // Disconnect the channel.
-    /// });
+    /// let (s, r) = unbounded::<&str>();
+    /// let r = r.map(|msg| msg.len());
     ///
-    /// // Collect all messages from the channel.
-    /// // Note that the call to `collect` blocks until the sender is dropped.
-    /// let v: Vec<_> = r.iter().collect();
-    ///
-    /// assert_eq!(v, [1, 2, 3]);
+    /// s.send("hello").unwrap();
+    /// select! {
+    ///     recv(r) -> msg => assert_eq!(msg, Ok(5)),
+    /// }
     /// ```
-    pub fn iter(&self) -> Iter<'_, T> {
-        Iter { receiver: self }
+    ///
+    /// [`select!`]: crate::select
+    /// [`try_recv_if`]: Receiver::try_recv_if
+    /// [`try_peek_with`]: Receiver::try_peek_with
+    pub fn map<U, F>(self, f: F) -> MappedReceiver<U>
+    where
+        T: Send + 'static,
+        U: Send + 'static,
+        F: Fn(T) -> U + Send + Sync + 'static,
+    {
+        Receiver::new(ReceiverFlavor::Map(Arc::new(flavors::map::Channel::new(
+            self,
+            Box::new(f),
+        ))))
     }
 
-    /// A non-blocking iterator over messages in the channel.
-    ///
-    /// Each call to [`next`] returns a message if there is one ready to be received. The iterator
-    /// never blocks waiting for the next message.
+    /// Turns the receiver into one that drops messages for which `pred` returns `false`.
     ///
-    /// [`next`]: Iterator::next
+    /// The predicate runs on the receiving thread, with no thread or queue in between. Dropped
+    /// messages are received from this receiver, so they still count towards its length until
+    /// then. The returned receiver works everywhere a receiver does, including [`Select`] and
+    /// [`select!`].
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::thread;
     /// use std::time::Duration;
-    /// use crossbeam_channel::unbounded;
+    /// use crossbeam_channel::{unbounded, RecvTimeoutError};
     ///
-    /// let (s, r) = unbounded::<i32>();
+    /// let (s, r) = unbounded();
+    /// let r = r.filter(|msg| msg % 2 == 0);
     ///
-    /// thread::spawn(move || {
-    ///     s.send(1).unwrap();
-    ///     thread::sleep(Duration::from_secs(1));
-    ///     s.send(2).unwrap();
-    ///     thread::sleep(Duration::from_secs(2));
-    ///     s.send(3).unwrap();
-    /// });
+    /// for i in 1..=4 {
+    ///     s.send(i).unwrap();
+    /// }
     ///
-    /// thread::sleep(Duration::from_secs(2));
+    /// assert_eq!(r.recv(), Ok(2));
+    /// assert_eq!(r.recv(), Ok(4));
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_millis(100)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    /// ```
     ///
-    /// // Collect all messages from the channel without blocking.
-    /// // The third message hasn't been sent yet so we'll collect only the first two.
-    /// let v: Vec<_> = r.try_iter().collect();
+    /// [`select!`]: crate::select
+    pub fn filter<P>(self, pred: P) -> MappedReceiver<T>
+    where
+        P: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        Receiver::new(ReceiverFlavor::Filter(Arc::new(
+            flavors::filter::Channel::new(self, Box::new(pred)),
+        )))
+    }
+
+    /// Creates a weak handle to the receiving side of the channel.
+    ///
+    /// A [`WeakReceiver`] doesn't count as a receiver, so it doesn't keep the channel connected. It
+    /// can be turned back into a receiver with [`WeakReceiver::upgrade`] for as long as the channel
+    /// is not disconnected.
+    ///
+    /// # Examples
     ///
-    /// assert_eq!(v, [1, 2]);
     /// ```
-    pub fn try_iter(&self) -> TryIter<'_, T> {
-        TryIter { receiver: self }
+    /// use crossbeam_channel::{unbounded, SendError};
+    ///
+    /// let (s, r) = unbounded();
+    /// let w = r.downgrade();
+    ///
+    /// s.send(1).unwrap();
+    /// assert_eq!(w.upgrade().unwrap().recv(), Ok(1));
+    ///
+    /// // The weak receiver doesn't keep the channel connected.
+    /// drop(r);
+    /// assert_eq!(s.send(2), Err(SendError(2)));
+    /// assert!(w.upgrade().is_none());
+    /// ```
+    pub fn downgrade(&self) -> WeakReceiver<T> {
+        let flavor = match &self.flavor {
+            ReceiverFlavor::Array(chan) => WeakReceiverFlavor::Array(chan.downgrade()),
+            ReceiverFlavor::List(chan) => WeakReceiverFlavor::List(chan.downgrade()),
+            ReceiverFlavor::Zero(chan) => WeakReceiverFlavor::Zero(chan.downgrade()),
+            ReceiverFlavor::Broadcast(chan, _) => WeakReceiverFlavor::Broadcast(chan.downgrade()),
+            ReceiverFlavor::Priority(chan) => WeakReceiverFlavor::Priority(chan.downgrade()),
+            ReceiverFlavor::Merge(chan) => WeakReceiverFlavor::Merge(Arc::downgrade(chan)),
+            ReceiverFlavor::Tee(chan, index) => {
+                WeakReceiverFlavor::Tee(Arc::downgrade(chan), *index)
+            }
+            ReceiverFlavor::Map(chan) => WeakReceiverFlavor::Map(Arc::downgrade(chan)),
+            ReceiverFlavor::Filter(chan) => WeakReceiverFlavor::Filter(Arc::downgrade(chan)),
+            ReceiverFlavor::At(chan) => WeakReceiverFlavor::At(chan.clone()),
+            ReceiverFlavor::Tick(chan) => WeakReceiverFlavor::Tick(chan.clone()),
+            ReceiverFlavor::Never(_) => WeakReceiverFlavor::Never,
+        };
+
+        WeakReceiver { flavor }
     }
 
     /// Returns `true` if receivers belong to the same channel.
@@ -1122,6 +3868,15 @@ impl<T> Receiver<T> {
             (ReceiverFlavor::Array(a), ReceiverFlavor::Array(b)) => a == b,
             (ReceiverFlavor::List(a), ReceiverFlavor::List(b)) => a == b,
             (ReceiverFlavor::Zero(a), ReceiverFlavor::Zero(b)) => a == b,
+            (ReceiverFlavor::Broadcast(a, _), ReceiverFlavor::Broadcast(b, _)) => a == b,
+            (ReceiverFlavor::Priority(a), ReceiverFlavor::Priority(b)) => a == b,
+            (ReceiverFlavor::Merge(a), ReceiverFlavor::Merge(b)) => Arc::ptr_eq(a, b),
+            (ReceiverFlavor::Tee(a, i), ReceiverFlavor::Tee(b, j)) => Arc::ptr_eq(a, b) && i == j,
+            (ReceiverFlavor::Map(a), ReceiverFlavor::Map(b)) => {
+                // Compare data pointers only, as vtable pointers of the same type can differ.
+                &**a as *const _ as *const u8 == &**b as *const _ as *const u8
+            }
+            (ReceiverFlavor::Filter(a), ReceiverFlavor::Filter(b)) => Arc::ptr_eq(a, b),
             (ReceiverFlavor::At(a), ReceiverFlavor::At(b)) => Arc::ptr_eq(a, b),
             (ReceiverFlavor::Tick(a), ReceiverFlavor::Tick(b)) => Arc::ptr_eq(a, b),
             (ReceiverFlavor::Never(_), ReceiverFlavor::Never(_)) => true,
@@ -1132,11 +3887,20 @@ impl<T> Receiver<T> {
 
 impl<T> Drop for Receiver<T> {
     fn drop(&mut self) {
+        #[cfg(feature = "futures")]
+        self.flavor.cancel_recv(&mut self.stream.waiter);
+
         unsafe {
             match &self.flavor {
                 ReceiverFlavor::Array(chan) => chan.release(|c| c.disconnect()),
                 ReceiverFlavor::List(chan) => chan.release(|c| c.disconnect_receivers()),
                 ReceiverFlavor::Zero(chan) => chan.release(|c| c.disconnect()),
+                ReceiverFlavor::Broadcast(chan, _) => chan.release(|c| c.disconnect()),
+                ReceiverFlavor::Priority(chan) => chan.release(|c| c.disconnect()),
+                ReceiverFlavor::Merge(_) => {}
+                ReceiverFlavor::Tee(chan, index) => chan.release(*index),
+                ReceiverFlavor::Map(_) => {}
+                ReceiverFlavor::Filter(_) => {}
                 ReceiverFlavor::At(_) => {}
                 ReceiverFlavor::Tick(_) => {}
                 ReceiverFlavor::Never(_) => {}
@@ -1151,12 +3915,25 @@ impl<T> Clone for Receiver<T> {
             ReceiverFlavor::Array(chan) => ReceiverFlavor::Array(chan.acquire()),
             ReceiverFlavor::List(chan) => ReceiverFlavor::List(chan.acquire()),
             ReceiverFlavor::Zero(chan) => ReceiverFlavor::Zero(chan.acquire()),
+            ReceiverFlavor::Broadcast(chan, cursor) => {
+                ReceiverFlavor::Broadcast(chan.acquire(), chan.fork(cursor))
+            }
+            ReceiverFlavor::Priority(chan) => ReceiverFlavor::Priority(chan.acquire()),
+            ReceiverFlavor::Merge(chan) => ReceiverFlavor::Merge(chan.clone()),
+            ReceiverFlavor::Tee(chan, index) => {
+                // This receiver holds a handle to the output, so it can't be released yet.
+                let acquired = chan.acquire(*index);
+                debug_assert!(acquired);
+                ReceiverFlavor::Tee(chan.clone(), *index)
+            }
+            ReceiverFlavor::Map(chan) => ReceiverFlavor::Map(chan.clone()),
+            ReceiverFlavor::Filter(chan) => ReceiverFlavor::Filter(chan.clone()),
             ReceiverFlavor::At(chan) => ReceiverFlavor::At(chan.clone()),
             ReceiverFlavor::Tick(chan) => ReceiverFlavor::Tick(chan.clone()),
             ReceiverFlavor::Never(_) => ReceiverFlavor::Never(flavors::never::Channel::new()),
         };
 
-        Receiver { flavor }
+        Receiver::new(flavor)
     }
 }
 
@@ -1184,6 +3961,185 @@ impl<T> IntoIterator for Receiver<T> {
     }
 }
 
+/// A weak handle to the receiving side of a channel.
+///
+/// Unlike a [`Receiver`], a weak receiver doesn't keep the channel connected: once all senders or
+/// all receivers are dropped, the channel gets disconnected even if weak receivers still exist.
+/// Weak receivers are created by [`Receiver::downgrade`] and can't receive messages until they are
+/// upgraded.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::unbounded;
+///
+/// let (s, r) = unbounded();
+/// let w = r.downgrade();
+///
+/// s.send(1).unwrap();
+/// if let Some(r) = w.upgrade() {
+///     assert_eq!(r.recv(), Ok(1));
+/// }
+///
+/// drop(s);
+/// assert!(w.upgrade().is_none());
+/// ```
+pub struct WeakReceiver<T> {
+    flavor: WeakReceiverFlavor<T>,
+}
+
+/// Weak receiver flavors.
+enum WeakReceiverFlavor<T> {
+    /// Bounded channel based on a preallocated array.
+    Array(counter::WeakReceiver<flavors::array::Channel<T>>),
+
+    /// Unbounded channel implemented as a linked list.
+    List(counter::WeakReceiver<flavors::list::Channel<T>>),
+
+    /// Zero-capacity channel.
+    Zero(counter::WeakReceiver<flavors::zero::Channel<T>>),
+
+    /// Broadcast channel.
+    Broadcast(counter::WeakReceiver<flavors::broadcast::Channel<T>>),
+
+    /// Channel ordered by message priority.
+    Priority(counter::WeakReceiver<flavors::priority::Channel<T>>),
+
+    /// Receivers merged into one.
+    Merge(Weak<flavors::merge::Channel<T>>),
+
+    /// An output of a tee, along with its index.
+    Tee(Weak<flavors::tee::Channel<T>>, usize),
+
+    /// Receiver with transformed messages.
+    Map(Weak<dyn flavors::map::Mapped<T>>),
+
+    /// Receiver with filtered messages.
+    Filter(Weak<flavors::filter::Channel<T>>),
+
+    /// The after flavor.
+    At(Arc<flavors::at::Channel>),
+
+    /// The tick flavor.
+    Tick(Arc<flavors::tick::Channel>),
+
+    /// The never flavor.
+    Never,
+}
+
+unsafe impl<T: Send> Send for WeakReceiver<T> {}
+unsafe impl<T: Send> Sync for WeakReceiver<T> {}
+
+impl<T> UnwindSafe for WeakReceiver<T> {}
+impl<T> RefUnwindSafe for WeakReceiver<T> {}
+
+impl<T> WeakReceiver<T> {
+    /// Attempts to turn the weak handle back into a receiver.
+    ///
+    /// Returns `None` if the channel is disconnected, which happens once all senders or all
+    /// receivers are dropped, or once the channel is closed. Receivers of [`after`], [`at`],
+    /// [`tick`], and [`never`] channels are never disconnected, so upgrading them always succeeds.
+    ///
+    /// A receiver upgraded on a [`broadcast`] channel only receives messages sent after the
+    /// upgrade.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::unbounded;
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// let w = r.downgrade();
+    /// assert!(w.upgrade().is_some());
+    ///
+    /// s.close();
+    /// assert!(w.upgrade().is_none());
+    /// ```
+    pub fn upgrade(&self) -> Option<Receiver<T>> {
+        let flavor = match &self.flavor {
+            WeakReceiverFlavor::Array(chan) => ReceiverFlavor::Array(chan.upgrade()?),
+            WeakReceiverFlavor::List(chan) => ReceiverFlavor::List(chan.upgrade()?),
+            WeakReceiverFlavor::Zero(chan) => ReceiverFlavor::Zero(chan.upgrade()?),
+            WeakReceiverFlavor::Broadcast(chan) => {
+                let chan = chan.upgrade()?;
+                let cursor = chan.subscribe();
+                ReceiverFlavor::Broadcast(chan, cursor)
+            }
+            WeakReceiverFlavor::Priority(chan) => ReceiverFlavor::Priority(chan.upgrade()?),
+            WeakReceiverFlavor::Merge(chan) => ReceiverFlavor::Merge(chan.upgrade()?),
+            WeakReceiverFlavor::Tee(chan, index) => {
+                let chan = chan.upgrade()?;
+                if !chan.acquire(*index) {
+                    return None;
+                }
+                ReceiverFlavor::Tee(chan, *index)
+            }
+            WeakReceiverFlavor::Map(chan) => ReceiverFlavor::Map(chan.upgrade()?),
+            WeakReceiverFlavor::Filter(chan) => ReceiverFlavor::Filter(chan.upgrade()?),
+            WeakReceiverFlavor::At(chan) => ReceiverFlavor::At(chan.clone()),
+            WeakReceiverFlavor::Tick(chan) => ReceiverFlavor::Tick(chan.clone()),
+            WeakReceiverFlavor::Never => ReceiverFlavor::Never(flavors::never::Channel::new()),
+        };
+
+        // Receivers are still alive, but the channel might have been disconnected from the other
+        // side.
+        let r = Receiver::new(flavor);
+        if r.is_closed() {
+            None
+        } else {
+            Some(r)
+        }
+    }
+}
+
+impl<T> Drop for WeakReceiver<T> {
+    fn drop(&mut self) {
+        unsafe {
+            match &self.flavor {
+                WeakReceiverFlavor::Array(chan) => chan.release(),
+                WeakReceiverFlavor::List(chan) => chan.release(),
+                WeakReceiverFlavor::Zero(chan) => chan.release(),
+                WeakReceiverFlavor::Broadcast(chan) => chan.release(),
+                WeakReceiverFlavor::Priority(chan) => chan.release(),
+                WeakReceiverFlavor::Merge(_) => {}
+                WeakReceiverFlavor::Tee(..) => {}
+                WeakReceiverFlavor::Map(_) => {}
+                WeakReceiverFlavor::Filter(_) => {}
+                WeakReceiverFlavor::At(_) => {}
+                WeakReceiverFlavor::Tick(_) => {}
+                WeakReceiverFlavor::Never => {}
+            }
+        }
+    }
+}
+
+impl<T> Clone for WeakReceiver<T> {
+    fn clone(&self) -> Self {
+        let flavor = match &self.flavor {
+            WeakReceiverFlavor::Array(chan) => WeakReceiverFlavor::Array(chan.acquire()),
+            WeakReceiverFlavor::List(chan) => WeakReceiverFlavor::List(chan.acquire()),
+            WeakReceiverFlavor::Zero(chan) => WeakReceiverFlavor::Zero(chan.acquire()),
+            WeakReceiverFlavor::Broadcast(chan) => WeakReceiverFlavor::Broadcast(chan.acquire()),
+            WeakReceiverFlavor::Priority(chan) => WeakReceiverFlavor::Priority(chan.acquire()),
+            WeakReceiverFlavor::Merge(chan) => WeakReceiverFlavor::Merge(chan.clone()),
+            WeakReceiverFlavor::Tee(chan, index) => WeakReceiverFlavor::Tee(chan.clone(), *index),
+            WeakReceiverFlavor::Map(chan) => WeakReceiverFlavor::Map(chan.clone()),
+            WeakReceiverFlavor::Filter(chan) => WeakReceiverFlavor::Filter(chan.clone()),
+            WeakReceiverFlavor::At(chan) => WeakReceiverFlavor::At(chan.clone()),
+            WeakReceiverFlavor::Tick(chan) => WeakReceiverFlavor::Tick(chan.clone()),
+            WeakReceiverFlavor::Never => WeakReceiverFlavor::Never,
+        };
+
+        WeakReceiver { flavor }
+    }
+}
+
+impl<T> fmt::Debug for WeakReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("WeakReceiver { .. }")
+    }
+}
+
 /// A blocking iterator over messages in a channel.
 ///
 /// Each call to [`next`] blocks waiting for the next message and then returns it. However, if the
@@ -1330,12 +4286,225 @@ impl<T> fmt::Debug for IntoIter<T> {
     }
 }
 
+/// A future that sends a message into a channel.
+///
+/// This future is created by [`Sender::send_async`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendFuture<'a, T> {
+    sender: &'a Sender<T>,
+    msg: Option<T>,
+    waiter: Waiter,
+}
+
+unsafe impl<T: Send> Send for SendFuture<'_, T> {}
+unsafe impl<T: Send> Sync for SendFuture<'_, T> {}
+
+impl<T> Unpin for SendFuture<'_, T> {}
+
+impl<T> Future for SendFuture<'_, T> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        assert!(
+            this.msg.is_some() || this.waiter.packet != 0,
+            "`SendFuture` polled after completion"
+        );
+
+        let res = this
+            .sender
+            .flavor
+            .poll_send(&mut this.msg, &mut this.waiter, cx.waker());
+        if let Poll::Ready(Ok(())) = res {
+            this.sender.record_sent(1);
+        }
+        res
+    }
+}
+
+impl<T> Drop for SendFuture<'_, T> {
+    fn drop(&mut self) {
+        self.sender.flavor.cancel_send(&mut self.waiter);
+    }
+}
+
+impl<T> fmt::Debug for SendFuture<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("SendFuture { .. }")
+    }
+}
+
+/// A future that receives a message from a channel.
+///
+/// This future is created by [`Receiver::recv_async`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvFuture<'a, T> {
+    receiver: &'a Receiver<T>,
+    waiter: Waiter,
+}
+
+unsafe impl<T: Send> Send for RecvFuture<'_, T> {}
+unsafe impl<T: Send> Sync for RecvFuture<'_, T> {}
+
+impl<T> Unpin for RecvFuture<'_, T> {}
+
+impl<T> Future for RecvFuture<'_, T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let res = this.receiver.flavor.poll_recv(&mut this.waiter, cx.waker());
+        if let Poll::Ready(Ok(_)) = res {
+            this.receiver.record_received(1);
+        }
+        res
+    }
+}
+
+impl<T> Drop for RecvFuture<'_, T> {
+    fn drop(&mut self) {
+        self.receiver.flavor.cancel_recv(&mut self.waiter);
+    }
+}
+
+impl<T> fmt::Debug for RecvFuture<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("RecvFuture { .. }")
+    }
+}
+
+/// The state of a sender used as a `Sink`.
+#[cfg(feature = "futures")]
+struct SinkState<T> {
+    /// The message passed to `start_send` that hasn't been sent yet.
+    msg: Option<T>,
+
+    /// The send operation registered by the task flushing the sink.
+    waiter: Waiter,
+}
+
+#[cfg(feature = "futures")]
+impl<T> SinkState<T> {
+    /// Creates the state of a sender that hasn't been used as a `Sink` yet.
+    fn new() -> Self {
+        SinkState {
+            msg: None,
+            waiter: Waiter::new(),
+        }
+    }
+}
+
+/// The state of a receiver used as a `Stream`.
+#[cfg(feature = "futures")]
+struct StreamState {
+    /// The receive operation registered by the task polling the stream.
+    waiter: Waiter,
+}
+
+#[cfg(feature = "futures")]
+impl StreamState {
+    /// Creates the state of a receiver that hasn't been used as a `Stream` yet.
+    fn new() -> Self {
+        StreamState {
+            waiter: Waiter::new(),
+        }
+    }
+}
+
+/// Receives messages until the channel becomes empty and disconnected.
+///
+/// Like [`Receiver::recv_async`], a task waiting for the next message registers its waker with
+/// the channel and gets woken up by the sender, so no helper threads are involved.
+///
+/// Receivers created by [`after`] and [`tick`] are the exception: nothing sends their messages,
+/// so a task waiting on them is woken up at the delivery time by a single background thread
+/// shared by the whole process instead, as described in [`Receiver::recv_async`].
+///
+/// This implementation is only available with the `futures` feature enabled.
+#[cfg(feature = "futures")]
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        let res = this.flavor.poll_recv(&mut this.stream.waiter, cx.waker());
+        if let Poll::Ready(Ok(_)) = res {
+            this.record_received(1);
+        }
+        res.map(Result::ok)
+    }
+}
+
+/// Sends messages into the channel.
+///
+/// The sender holds on to one message passed to `start_send` until the channel has room for it.
+/// Flushing the sink waits until that message is sent, and closing the sink only flushes it,
+/// leaving the channel connected as long as other senders exist.
+///
+/// Like [`Sender::send_async`], a task waiting for room in the channel registers its waker with
+/// the channel and gets woken up by the receiver, so no helper threads are involved.
+///
+/// This implementation is only available with the `futures` feature enabled.
+#[cfg(feature = "futures")]
+impl<T> Sink<T> for Sender<T> {
+    type Error = SendError<T>;
+
+    fn poll_ready(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Result<(), SendError<T>>> {
+        self.poll_flush(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, msg: T) -> Result<(), SendError<T>> {
+        let sink = &mut self.get_mut().sink;
+
+        assert!(
+            sink.msg.is_none() && sink.waiter.packet == 0,
+            "`start_send` called before `poll_ready` returned `Ready`"
+        );
+
+        sink.msg = Some(msg);
+        Ok(())
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Result<(), SendError<T>>> {
+        let this = self.get_mut();
+        let sink = &mut this.sink;
+
+        if sink.msg.is_none() && sink.waiter.packet == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let res = this
+            .flavor
+            .poll_send(&mut sink.msg, &mut sink.waiter, cx.waker());
+        if let Poll::Ready(Ok(())) = res {
+            this.record_sent(1);
+        }
+        res
+    }
+
+    fn poll_close(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Result<(), SendError<T>>> {
+        self.poll_flush(cx)
+    }
+}
+
 impl<T> SelectHandle for Sender<T> {
     fn try_select(&self, token: &mut Token) -> bool {
         match &self.flavor {
             SenderFlavor::Array(chan) => chan.sender().try_select(token),
             SenderFlavor::List(chan) => chan.sender().try_select(token),
             SenderFlavor::Zero(chan) => chan.sender().try_select(token),
+            SenderFlavor::Broadcast(chan) => chan.sender().try_select(token),
+            SenderFlavor::Priority(chan) => chan.sender().try_select(token),
         }
     }
 
@@ -1348,6 +4517,8 @@ impl<T> SelectHandle for Sender<T> {
             SenderFlavor::Array(chan) => chan.sender().register(oper, cx),
             SenderFlavor::List(chan) => chan.sender().register(oper, cx),
             SenderFlavor::Zero(chan) => chan.sender().register(oper, cx),
+            SenderFlavor::Broadcast(chan) => chan.sender().register(oper, cx),
+            SenderFlavor::Priority(chan) => chan.sender().register(oper, cx),
         }
     }
 
@@ -1356,6 +4527,8 @@ impl<T> SelectHandle for Sender<T> {
             SenderFlavor::Array(chan) => chan.sender().unregister(oper),
             SenderFlavor::List(chan) => chan.sender().unregister(oper),
             SenderFlavor::Zero(chan) => chan.sender().unregister(oper),
+            SenderFlavor::Broadcast(chan) => chan.sender().unregister(oper),
+            SenderFlavor::Priority(chan) => chan.sender().unregister(oper),
         }
     }
 
@@ -1364,6 +4537,8 @@ impl<T> SelectHandle for Sender<T> {
             SenderFlavor::Array(chan) => chan.sender().accept(token, cx),
             SenderFlavor::List(chan) => chan.sender().accept(token, cx),
             SenderFlavor::Zero(chan) => chan.sender().accept(token, cx),
+            SenderFlavor::Broadcast(chan) => chan.sender().accept(token, cx),
+            SenderFlavor::Priority(chan) => chan.sender().accept(token, cx),
         }
     }
 
@@ -1372,6 +4547,8 @@ impl<T> SelectHandle for Sender<T> {
             SenderFlavor::Array(chan) => chan.sender().is_ready(),
             SenderFlavor::List(chan) => chan.sender().is_ready(),
             SenderFlavor::Zero(chan) => chan.sender().is_ready(),
+            SenderFlavor::Broadcast(chan) => chan.sender().is_ready(),
+            SenderFlavor::Priority(chan) => chan.sender().is_ready(),
         }
     }
 
@@ -1380,6 +4557,8 @@ impl<T> SelectHandle for Sender<T> {
             SenderFlavor::Array(chan) => chan.sender().watch(oper, cx),
             SenderFlavor::List(chan) => chan.sender().watch(oper, cx),
             SenderFlavor::Zero(chan) => chan.sender().watch(oper, cx),
+            SenderFlavor::Broadcast(chan) => chan.sender().watch(oper, cx),
+            SenderFlavor::Priority(chan) => chan.sender().watch(oper, cx),
         }
     }
 
@@ -1388,6 +4567,8 @@ impl<T> SelectHandle for Sender<T> {
             SenderFlavor::Array(chan) => chan.sender().unwatch(oper),
             SenderFlavor::List(chan) => chan.sender().unwatch(oper),
             SenderFlavor::Zero(chan) => chan.sender().unwatch(oper),
+            SenderFlavor::Broadcast(chan) => chan.sender().unwatch(oper),
+            SenderFlavor::Priority(chan) => chan.sender().unwatch(oper),
         }
     }
 }
@@ -1398,8 +4579,14 @@ impl<T> SelectHandle for Receiver<T> {
             ReceiverFlavor::Array(chan) => chan.receiver().try_select(token),
             ReceiverFlavor::List(chan) => chan.receiver().try_select(token),
             ReceiverFlavor::Zero(chan) => chan.receiver().try_select(token),
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.receiver(cursor).try_select(token),
+            ReceiverFlavor::Priority(chan) => chan.receiver().try_select(token),
             ReceiverFlavor::At(chan) => chan.try_select(token),
             ReceiverFlavor::Tick(chan) => chan.try_select(token),
+            ReceiverFlavor::Merge(chan) => chan.try_select(token),
+            ReceiverFlavor::Tee(chan, index) => chan.receiver(*index).try_select(token),
+            ReceiverFlavor::Map(chan) => chan.try_select(token),
+            ReceiverFlavor::Filter(chan) => chan.try_select(token),
             ReceiverFlavor::Never(chan) => chan.try_select(token),
         }
     }
@@ -1409,8 +4596,14 @@ impl<T> SelectHandle for Receiver<T> {
             ReceiverFlavor::Array(_) => None,
             ReceiverFlavor::List(_) => None,
             ReceiverFlavor::Zero(_) => None,
+            ReceiverFlavor::Broadcast(..) => None,
+            ReceiverFlavor::Priority(_) => None,
             ReceiverFlavor::At(chan) => chan.deadline(),
             ReceiverFlavor::Tick(chan) => chan.deadline(),
+            ReceiverFlavor::Merge(chan) => chan.deadline(),
+            ReceiverFlavor::Tee(chan, index) => chan.receiver(*index).deadline(),
+            ReceiverFlavor::Map(chan) => chan.deadline(),
+            ReceiverFlavor::Filter(chan) => chan.deadline(),
             ReceiverFlavor::Never(chan) => chan.deadline(),
         }
     }
@@ -1420,8 +4613,14 @@ impl<T> SelectHandle for Receiver<T> {
             ReceiverFlavor::Array(chan) => chan.receiver().register(oper, cx),
             ReceiverFlavor::List(chan) => chan.receiver().register(oper, cx),
             ReceiverFlavor::Zero(chan) => chan.receiver().register(oper, cx),
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.receiver(cursor).register(oper, cx),
+            ReceiverFlavor::Priority(chan) => chan.receiver().register(oper, cx),
             ReceiverFlavor::At(chan) => chan.register(oper, cx),
             ReceiverFlavor::Tick(chan) => chan.register(oper, cx),
+            ReceiverFlavor::Merge(chan) => chan.register(oper, cx),
+            ReceiverFlavor::Tee(chan, index) => chan.receiver(*index).register(oper, cx),
+            ReceiverFlavor::Map(chan) => chan.register(oper, cx),
+            ReceiverFlavor::Filter(chan) => chan.register(oper, cx),
             ReceiverFlavor::Never(chan) => chan.register(oper, cx),
         }
     }
@@ -1431,8 +4630,14 @@ impl<T> SelectHandle for Receiver<T> {
             ReceiverFlavor::Array(chan) => chan.receiver().unregister(oper),
             ReceiverFlavor::List(chan) => chan.receiver().unregister(oper),
             ReceiverFlavor::Zero(chan) => chan.receiver().unregister(oper),
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.receiver(cursor).unregister(oper),
+            ReceiverFlavor::Priority(chan) => chan.receiver().unregister(oper),
             ReceiverFlavor::At(chan) => chan.unregister(oper),
             ReceiverFlavor::Tick(chan) => chan.unregister(oper),
+            ReceiverFlavor::Merge(chan) => chan.unregister(oper),
+            ReceiverFlavor::Tee(chan, index) => chan.receiver(*index).unregister(oper),
+            ReceiverFlavor::Map(chan) => chan.unregister(oper),
+            ReceiverFlavor::Filter(chan) => chan.unregister(oper),
             ReceiverFlavor::Never(chan) => chan.unregister(oper),
         }
     }
@@ -1442,8 +4647,14 @@ impl<T> SelectHandle for Receiver<T> {
             ReceiverFlavor::Array(chan) => chan.receiver().accept(token, cx),
             ReceiverFlavor::List(chan) => chan.receiver().accept(token, cx),
             ReceiverFlavor::Zero(chan) => chan.receiver().accept(token, cx),
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.receiver(cursor).accept(token, cx),
+            ReceiverFlavor::Priority(chan) => chan.receiver().accept(token, cx),
             ReceiverFlavor::At(chan) => chan.accept(token, cx),
             ReceiverFlavor::Tick(chan) => chan.accept(token, cx),
+            ReceiverFlavor::Merge(chan) => chan.accept(token, cx),
+            ReceiverFlavor::Tee(chan, index) => chan.receiver(*index).accept(token, cx),
+            ReceiverFlavor::Map(chan) => chan.accept(token, cx),
+            ReceiverFlavor::Filter(chan) => chan.accept(token, cx),
             ReceiverFlavor::Never(chan) => chan.accept(token, cx),
         }
     }
@@ -1453,8 +4664,14 @@ impl<T> SelectHandle for Receiver<T> {
             ReceiverFlavor::Array(chan) => chan.receiver().is_ready(),
             ReceiverFlavor::List(chan) => chan.receiver().is_ready(),
             ReceiverFlavor::Zero(chan) => chan.receiver().is_ready(),
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.receiver(cursor).is_ready(),
+            ReceiverFlavor::Priority(chan) => chan.receiver().is_ready(),
             ReceiverFlavor::At(chan) => chan.is_ready(),
             ReceiverFlavor::Tick(chan) => chan.is_ready(),
+            ReceiverFlavor::Merge(chan) => chan.is_ready(),
+            ReceiverFlavor::Tee(chan, index) => chan.receiver(*index).is_ready(),
+            ReceiverFlavor::Map(chan) => chan.is_ready(),
+            ReceiverFlavor::Filter(chan) => chan.is_ready(),
             ReceiverFlavor::Never(chan) => chan.is_ready(),
         }
     }
@@ -1464,8 +4681,14 @@ impl<T> SelectHandle for Receiver<T> {
             ReceiverFlavor::Array(chan) => chan.receiver().watch(oper, cx),
             ReceiverFlavor::List(chan) => chan.receiver().watch(oper, cx),
             ReceiverFlavor::Zero(chan) => chan.receiver().watch(oper, cx),
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.receiver(cursor).watch(oper, cx),
+            ReceiverFlavor::Priority(chan) => chan.receiver().watch(oper, cx),
             ReceiverFlavor::At(chan) => chan.watch(oper, cx),
             ReceiverFlavor::Tick(chan) => chan.watch(oper, cx),
+            ReceiverFlavor::Merge(chan) => chan.watch(oper, cx),
+            ReceiverFlavor::Tee(chan, index) => chan.receiver(*index).watch(oper, cx),
+            ReceiverFlavor::Map(chan) => chan.watch(oper, cx),
+            ReceiverFlavor::Filter(chan) => chan.watch(oper, cx),
             ReceiverFlavor::Never(chan) => chan.watch(oper, cx),
         }
     }
@@ -1475,8 +4698,14 @@ impl<T> SelectHandle for Receiver<T> {
             ReceiverFlavor::Array(chan) => chan.receiver().unwatch(oper),
             ReceiverFlavor::List(chan) => chan.receiver().unwatch(oper),
             ReceiverFlavor::Zero(chan) => chan.receiver().unwatch(oper),
+            ReceiverFlavor::Broadcast(chan, cursor) => chan.receiver(cursor).unwatch(oper),
+            ReceiverFlavor::Priority(chan) => chan.receiver().unwatch(oper),
             ReceiverFlavor::At(chan) => chan.unwatch(oper),
             ReceiverFlavor::Tick(chan) => chan.unwatch(oper),
+            ReceiverFlavor::Merge(chan) => chan.unwatch(oper),
+            ReceiverFlavor::Tee(chan, index) => chan.receiver(*index).unwatch(oper),
+            ReceiverFlavor::Map(chan) => chan.unwatch(oper),
+            ReceiverFlavor::Filter(chan) => chan.unwatch(oper),
             ReceiverFlavor::Never(chan) => chan.unwatch(oper),
         }
     }
@@ -1488,21 +4717,35 @@ pub(crate) unsafe fn write<T>(s: &Sender<T>, token: &mut Token, msg: T) -> Resul
         SenderFlavor::Array(chan) => chan.write(token, msg),
         SenderFlavor::List(chan) => chan.write(token, msg),
         SenderFlavor::Zero(chan) => chan.write(token, msg),
-    }
+        SenderFlavor::Broadcast(chan) => chan.write(token, msg),
+        SenderFlavor::Priority(chan) => chan.write(token, msg),
+    }?;
+    s.record_sent(1);
+    Ok(())
 }
 
 /// Reads a message from the channel.
 pub(crate) unsafe fn read<T>(r: &Receiver<T>, token: &mut Token) -> Result<T, ()> {
-    match &r.flavor {
+    let res = match &r.flavor {
         ReceiverFlavor::Array(chan) => chan.read(token),
         ReceiverFlavor::List(chan) => chan.read(token),
         ReceiverFlavor::Zero(chan) => chan.read(token),
+        ReceiverFlavor::Broadcast(chan, cursor) => chan.read(cursor, token),
+        ReceiverFlavor::Priority(chan) => chan.read(token),
         ReceiverFlavor::At(chan) => {
             mem::transmute_copy::<Result<Instant, ()>, Result<T, ()>>(&chan.read(token))
         }
         ReceiverFlavor::Tick(chan) => {
             mem::transmute_copy::<Result<Instant, ()>, Result<T, ()>>(&chan.read(token))
         }
+        ReceiverFlavor::Merge(chan) => chan.read(token),
+        ReceiverFlavor::Tee(chan, index) => chan.read(*index, token),
+        ReceiverFlavor::Map(chan) => chan.read(token),
+        ReceiverFlavor::Filter(chan) => chan.read(token),
         ReceiverFlavor::Never(chan) => chan.read(token),
+    };
+    if res.is_ok() {
+        r.record_received(1);
     }
+    res
 }
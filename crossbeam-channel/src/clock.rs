@@ -0,0 +1,245 @@
+//! The clock used for timeouts and timer channels.
+//!
+//! All time-driven behavior in this crate, including [`after`], [`at`], [`tick`], operations with
+//! timeouts or deadlines like [`Receiver::recv_timeout`] and [`Sender::send_deadline`], and
+//! [`Select::select_deadline`], reads the current time from this clock.
+//!
+//! By default the clock follows [`Instant::now`]. With the `test-util` feature enabled, tests can
+//! install a `MockClock` instead, which stops time from passing on its own and lets the test move
+//! it forward with `MockClock::advance`. This makes time-driven logic deterministic.
+//!
+//! [`after`]: crate::after
+//! [`at`]: crate::at
+//! [`tick`]: crate::tick
+//! [`Receiver::recv_timeout`]: crate::Receiver::recv_timeout
+//! [`Sender::send_deadline`]: crate::Sender::send_deadline
+//! [`Select::select_deadline`]: crate::Select::select_deadline
+
+#[cfg(feature = "test-util")]
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use crate::utils::Lazy;
+
+/// Set to `true` while a mock clock is installed.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Returns `true` if a mock clock may be installed.
+///
+/// This is checked before touching the mock state so that the real clock stays cheap. Without the
+/// `test-util` feature, no mock clock can be installed and the check compiles down to nothing.
+fn is_mocked() -> bool {
+    cfg!(feature = "test-util") && INSTALLED.load(Ordering::Acquire)
+}
+
+/// State of the mock clock.
+struct State {
+    /// Whether a mock clock is installed.
+    installed: bool,
+
+    /// The instant at which the mock clock was installed.
+    base: Instant,
+
+    /// How far the mock clock has been advanced since it was installed.
+    elapsed: Duration,
+
+    /// Threads waiting for the mock clock to advance.
+    waiters: Vec<Thread>,
+}
+
+impl State {
+    /// Returns the current mock time.
+    fn now(&self) -> Instant {
+        self.base + self.elapsed
+    }
+
+    /// Wakes up all threads waiting for the mock clock to advance.
+    #[cfg(feature = "test-util")]
+    fn wake_all(&mut self) {
+        for thread in self.waiters.drain(..) {
+            thread.unpark();
+        }
+    }
+}
+
+/// Returns the state of the mock clock.
+fn state() -> &'static Mutex<State> {
+    static STATE: Lazy<Mutex<State>> = Lazy::new();
+
+    STATE.get_or_init(|| {
+        Mutex::new(State {
+            installed: false,
+            base: Instant::now(),
+            elapsed: Duration::from_secs(0),
+            waiters: Vec::new(),
+        })
+    })
+}
+
+/// Returns the current time according to the clock.
+///
+/// This is [`Instant::now`], unless a `MockClock` is installed.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Instant;
+/// use crossbeam_channel::clock;
+///
+/// let before = Instant::now();
+/// let now = clock::now();
+/// assert!(before <= now && now <= Instant::now());
+/// ```
+pub fn now() -> Instant {
+    if is_mocked() {
+        let state = state().lock().unwrap();
+        if state.installed {
+            return state.now();
+        }
+    }
+    Instant::now()
+}
+
+/// Parks the current thread until the deadline, or until it gets unparked.
+///
+/// Like `thread::park_timeout`, this may also return spuriously.
+pub(crate) fn park_until(deadline: Instant) {
+    if is_mocked() {
+        let mut guard = state().lock().unwrap();
+        if guard.installed {
+            if guard.now() < deadline {
+                // Wait for the clock to be advanced.
+                guard.waiters.push(thread::current());
+                drop(guard);
+                thread::park();
+
+                let id = thread::current().id();
+                state()
+                    .lock()
+                    .unwrap()
+                    .waiters
+                    .retain(|thread| thread.id() != id);
+            }
+            return;
+        }
+    }
+
+    let now = Instant::now();
+    if now < deadline {
+        thread::park_timeout(deadline - now);
+    }
+}
+
+/// Sleeps until the deadline.
+pub(crate) fn sleep_until(deadline: Instant) {
+    loop {
+        let now = now();
+        if now >= deadline {
+            break;
+        }
+
+        if is_mocked() {
+            park_until(deadline);
+        } else {
+            thread::sleep(deadline - now);
+        }
+    }
+}
+
+/// A clock that only moves forward when told to.
+///
+/// While a `MockClock` is installed, the whole process reads time from it instead of the system
+/// clock, and time stands still until [`advance`] is called. Threads blocked on timer channels or
+/// waiting for a deadline are woken up as soon as the clock is advanced past their deadline.
+///
+/// Only one mock clock can be installed at a time. Dropping it uninstalls it, after which time
+/// follows the system clock again.
+///
+/// Since the clock is process-wide, tests that install it should not run concurrently with
+/// other tests that rely on time passing.
+///
+/// This type is only available with the `test-util` feature enabled.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use crossbeam_channel::after;
+/// use crossbeam_channel::clock::MockClock;
+///
+/// let clock = MockClock::install();
+/// let timeout = after(Duration::from_secs(60));
+///
+/// // No time passes, no matter how long the test actually takes.
+/// assert!(timeout.try_recv().is_err());
+///
+/// clock.advance(Duration::from_secs(30));
+/// assert!(timeout.try_recv().is_err());
+///
+/// clock.advance(Duration::from_secs(30));
+/// assert!(timeout.try_recv().is_ok());
+/// ```
+///
+/// [`advance`]: MockClock::advance
+#[cfg(feature = "test-util")]
+pub struct MockClock {
+    /// Prevents construction outside of `install`.
+    _private: (),
+}
+
+#[cfg(feature = "test-util")]
+impl MockClock {
+    /// Installs a mock clock, starting at the current system time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a mock clock is already installed.
+    pub fn install() -> MockClock {
+        let mut state = state().lock().unwrap();
+        if state.installed {
+            drop(state);
+            panic!("a mock clock is already installed");
+        }
+
+        state.installed = true;
+        state.base = Instant::now();
+        state.elapsed = Duration::from_secs(0);
+        INSTALLED.store(true, Ordering::Release);
+
+        MockClock { _private: () }
+    }
+
+    /// Returns the current mock time.
+    pub fn now(&self) -> Instant {
+        state().lock().unwrap().now()
+    }
+
+    /// Moves the clock forward by `dur`.
+    ///
+    /// Threads waiting for a deadline are woken up to check whether it has been reached.
+    pub fn advance(&self, dur: Duration) {
+        let mut state = state().lock().unwrap();
+        state.elapsed += dur;
+        state.wake_all();
+    }
+}
+
+#[cfg(feature = "test-util")]
+impl Drop for MockClock {
+    fn drop(&mut self) {
+        let mut state = state().lock().unwrap();
+        state.installed = false;
+        INSTALLED.store(false, Ordering::Release);
+        state.wake_all();
+    }
+}
+
+#[cfg(feature = "test-util")]
+impl fmt::Debug for MockClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("MockClock { .. }")
+    }
+}
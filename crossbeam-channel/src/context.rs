@@ -3,12 +3,15 @@
 use std::cell::Cell;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::task;
 use std::thread::{self, Thread, ThreadId};
 use std::time::Instant;
 
 use crossbeam_utils::Backoff;
 
+use crate::clock;
 use crate::select::Selected;
+use crate::select_set::{Signal, SlotId};
 
 /// Thread-local context used in select.
 #[derive(Debug, Clone)]
@@ -25,11 +28,24 @@ struct Inner {
     /// A slot into which another thread may store a pointer to its `Packet`.
     packet: AtomicUsize,
 
-    /// Thread handle.
-    thread: Thread,
+    /// Handle used to wake up the owner of the context.
+    unparker: Unparker,
 
-    /// Thread id.
-    thread_id: ThreadId,
+    /// Thread id, or `None` if the context belongs to an asynchronous task or a `SelectSet`.
+    thread_id: Option<ThreadId>,
+}
+
+/// Handle used to wake up the owner of a context.
+#[derive(Debug)]
+enum Unparker {
+    /// A thread blocked in `wait_until`.
+    Thread(Thread),
+
+    /// An asynchronous task waiting to be polled again.
+    Task(task::Waker),
+
+    /// An operation in a `SelectSet`, identified by its slot.
+    Set(Arc<Signal>, SlotId),
 }
 
 impl Context {
@@ -70,12 +86,52 @@ impl Context {
             inner: Arc::new(Inner {
                 select: AtomicUsize::new(Selected::Waiting.into()),
                 packet: AtomicUsize::new(0),
-                thread: thread::current(),
-                thread_id: thread::current().id(),
+                unparker: Unparker::Thread(thread::current()),
+                thread_id: Some(thread::current().id()),
+            }),
+        }
+    }
+
+    /// Creates a new context that wakes up an asynchronous task instead of a thread.
+    ///
+    /// Tasks cannot block in `wait_until`, so a fresh context is created every time a task
+    /// registers an operation and the task itself checks `selected` when it gets polled again.
+    #[inline]
+    pub(crate) fn for_task(waker: &task::Waker) -> Context {
+        Context {
+            inner: Arc::new(Inner {
+                select: AtomicUsize::new(Selected::Waiting.into()),
+                packet: AtomicUsize::new(0),
+                unparker: Unparker::Task(waker.clone()),
+                thread_id: None,
+            }),
+        }
+    }
+
+    /// Creates a new context that notifies the operation in slot `id` of a `SelectSet`.
+    ///
+    /// Like task contexts, these contexts are only used for watching and a fresh one is created
+    /// every time the operation gets watched again.
+    #[inline]
+    pub(crate) fn for_set(signal: Arc<Signal>, id: SlotId) -> Context {
+        Context {
+            inner: Arc::new(Inner {
+                select: AtomicUsize::new(Selected::Waiting.into()),
+                packet: AtomicUsize::new(0),
+                unparker: Unparker::Set(signal, id),
+                thread_id: None,
             }),
         }
     }
 
+    /// Returns the address of the shared context state.
+    ///
+    /// The address is unique for as long as any clone of the context is alive.
+    #[inline]
+    pub(crate) fn addr(&self) -> usize {
+        &*self.inner as *const Inner as usize
+    }
+
     /// Resets `select` and `packet`.
     #[inline]
     fn reset(&self) {
@@ -160,10 +216,8 @@ impl Context {
 
             // If there's a deadline, park the current thread until the deadline is reached.
             if let Some(end) = deadline {
-                let now = Instant::now();
-
-                if now < end {
-                    thread::park_timeout(end - now);
+                if clock::now() < end {
+                    clock::park_until(end);
                 } else {
                     // The deadline has been reached. Try aborting select.
                     return match self.try_select(Selected::Aborted) {
@@ -177,15 +231,22 @@ impl Context {
         }
     }
 
-    /// Unparks the thread this context belongs to.
+    /// Unparks the thread or wakes up the task or set this context belongs to.
     #[inline]
     pub fn unpark(&self) {
-        self.inner.thread.unpark();
+        match &self.inner.unparker {
+            Unparker::Thread(thread) => thread.unpark(),
+            Unparker::Task(waker) => waker.wake_by_ref(),
+            Unparker::Set(signal, id) => signal.notify(*id),
+        }
     }
 
     /// Returns the id of the thread this context belongs to.
+    ///
+    /// Contexts belonging to asynchronous tasks or sets are not tied to any thread and return
+    /// `None`.
     #[inline]
-    pub fn thread_id(&self) -> ThreadId {
+    pub fn thread_id(&self) -> Option<ThreadId> {
         self.inner.thread_id
     }
 }
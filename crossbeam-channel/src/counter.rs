@@ -3,7 +3,13 @@
 use std::isize;
 use std::ops;
 use std::process;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::custom::{Registration, RegistrationId};
+use crate::metrics::Metrics;
+#[cfg(feature = "metrics")]
+use crate::ChannelStats;
 
 /// Reference counter internals.
 struct Counter<C> {
@@ -13,8 +19,17 @@ struct Counter<C> {
     /// The number of receivers associated with the channel.
     receivers: AtomicUsize,
 
-    /// Set to `true` if the last sender or the last receiver reference deallocates the channel.
-    destroy: AtomicBool,
+    /// The number of references keeping the allocation alive.
+    ///
+    /// All senders together hold one reference, all receivers together hold another, and every
+    /// weak handle holds one more. The last reference deallocates the channel.
+    allocations: AtomicUsize,
+
+    /// Metrics recorded by the channel.
+    metrics: Metrics,
+
+    /// Selects waiting for the channel to be closed.
+    closed: Mutex<Vec<Registration>>,
 
     /// The internal channel.
     chan: C,
@@ -25,7 +40,9 @@ pub(crate) fn new<C>(chan: C) -> (Sender<C>, Receiver<C>) {
     let counter = Box::into_raw(Box::new(Counter {
         senders: AtomicUsize::new(1),
         receivers: AtomicUsize::new(1),
-        destroy: AtomicBool::new(false),
+        allocations: AtomicUsize::new(2),
+        metrics: Metrics::new(),
+        closed: Mutex::new(Vec::new()),
         chan,
     }));
     let s = Sender { counter };
@@ -33,6 +50,71 @@ pub(crate) fn new<C>(chan: C) -> (Sender<C>, Receiver<C>) {
     (s, r)
 }
 
+impl<C> Counter<C> {
+    /// Registers a select waiting for the channel to be closed.
+    ///
+    /// Returns the result of `is_closed`, which is checked after registering.
+    fn watch_closed<F: FnOnce() -> bool>(&self, reg: &Registration, is_closed: F) -> bool {
+        let mut closed = self.closed.lock().unwrap();
+        closed.push(reg.clone());
+        is_closed()
+    }
+
+    /// Unregisters a select waiting for the channel to be closed.
+    fn unwatch_closed(&self, id: RegistrationId) {
+        self.closed.lock().unwrap().retain(|reg| reg.id() != id);
+    }
+
+    /// Wakes up all selects waiting for the channel to be closed.
+    fn notify_closed(&self) {
+        for reg in self.closed.lock().unwrap().drain(..) {
+            reg.wake();
+        }
+    }
+}
+
+/// Acquires another reference to the allocation of the channel.
+fn allocate<C>(counter: *mut Counter<C>) {
+    let count = unsafe { (*counter).allocations.fetch_add(1, Ordering::Relaxed) };
+
+    // Like with senders and receivers, we abort when the count becomes very large.
+    if count > isize::MAX as usize {
+        process::abort();
+    }
+}
+
+/// Releases a reference to the allocation of the channel, deallocating it if this was the last
+/// one.
+unsafe fn deallocate<C>(counter: *mut Counter<C>) {
+    if (*counter).allocations.fetch_sub(1, Ordering::AcqRel) == 1 {
+        drop(Box::from_raw(counter));
+    }
+}
+
+/// Acquires another strong reference out of `count`, unless it has already dropped to zero.
+fn upgrade(count: &AtomicUsize) -> bool {
+    let mut current = count.load(Ordering::Relaxed);
+
+    loop {
+        if current == 0 {
+            return false;
+        }
+        if current > isize::MAX as usize {
+            process::abort();
+        }
+
+        match count.compare_exchange_weak(
+            current,
+            current + 1,
+            Ordering::Acquire,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => return true,
+            Err(c) => current = c,
+        }
+    }
+}
+
 /// The sending side.
 pub(crate) struct Sender<C> {
     counter: *mut Counter<C>,
@@ -66,10 +148,59 @@ impl<C> Sender<C> {
     pub(crate) unsafe fn release<F: FnOnce(&C) -> bool>(&self, disconnect: F) {
         if self.counter().senders.fetch_sub(1, Ordering::AcqRel) == 1 {
             disconnect(&self.counter().chan);
+            deallocate(self.counter);
+        }
+    }
+
+    /// Returns the number of sender references.
+    pub(crate) fn sender_count(&self) -> usize {
+        self.counter().senders.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of receiver references.
+    pub(crate) fn receiver_count(&self) -> usize {
+        self.counter().receivers.load(Ordering::SeqCst)
+    }
+
+    /// Returns the metrics recorded by the channel.
+    pub(crate) fn metrics(&self) -> &Metrics {
+        &self.counter().metrics
+    }
+
+    /// Takes a snapshot of the metrics recorded by the channel.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn stats(&self) -> ChannelStats {
+        self.counter()
+            .metrics
+            .snapshot(self.sender_count(), self.receiver_count())
+    }
+
+    /// Registers a select waiting for the channel to be closed.
+    ///
+    /// Returns the result of `is_closed`, which is checked after registering.
+    pub(crate) fn watch_closed<F: FnOnce() -> bool>(
+        &self,
+        reg: &Registration,
+        is_closed: F,
+    ) -> bool {
+        self.counter().watch_closed(reg, is_closed)
+    }
+
+    /// Unregisters a select waiting for the channel to be closed.
+    pub(crate) fn unwatch_closed(&self, id: RegistrationId) {
+        self.counter().unwatch_closed(id);
+    }
 
-            if self.counter().destroy.swap(true, Ordering::AcqRel) {
-                drop(Box::from_raw(self.counter));
-            }
+    /// Wakes up all selects waiting for the channel to be closed.
+    pub(crate) fn notify_closed(&self) {
+        self.counter().notify_closed();
+    }
+
+    /// Creates a weak reference to the sending side.
+    pub(crate) fn downgrade(&self) -> WeakSender<C> {
+        allocate(self.counter);
+        WeakSender {
+            counter: self.counter,
         }
     }
 }
@@ -121,10 +252,44 @@ impl<C> Receiver<C> {
     pub(crate) unsafe fn release<F: FnOnce(&C) -> bool>(&self, disconnect: F) {
         if self.counter().receivers.fetch_sub(1, Ordering::AcqRel) == 1 {
             disconnect(&self.counter().chan);
+            self.counter().notify_closed();
+            deallocate(self.counter);
+        }
+    }
+
+    /// Returns the number of sender references.
+    pub(crate) fn sender_count(&self) -> usize {
+        self.counter().senders.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of receiver references.
+    pub(crate) fn receiver_count(&self) -> usize {
+        self.counter().receivers.load(Ordering::SeqCst)
+    }
+
+    /// Returns the metrics recorded by the channel.
+    pub(crate) fn metrics(&self) -> &Metrics {
+        &self.counter().metrics
+    }
 
-            if self.counter().destroy.swap(true, Ordering::AcqRel) {
-                drop(Box::from_raw(self.counter));
-            }
+    /// Takes a snapshot of the metrics recorded by the channel.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn stats(&self) -> ChannelStats {
+        self.counter()
+            .metrics
+            .snapshot(self.sender_count(), self.receiver_count())
+    }
+
+    /// Wakes up all selects waiting for the channel to be closed.
+    pub(crate) fn notify_closed(&self) {
+        self.counter().notify_closed();
+    }
+
+    /// Creates a weak reference to the receiving side.
+    pub(crate) fn downgrade(&self) -> WeakReceiver<C> {
+        allocate(self.counter);
+        WeakReceiver {
+            counter: self.counter,
         }
     }
 }
@@ -142,3 +307,73 @@ impl<C> PartialEq for Receiver<C> {
         self.counter == other.counter
     }
 }
+
+/// A weak reference to the sending side.
+///
+/// Weak references keep the channel allocated, but don't count as senders.
+pub(crate) struct WeakSender<C> {
+    counter: *mut Counter<C>,
+}
+
+impl<C> WeakSender<C> {
+    /// Acquires another weak reference.
+    pub(crate) fn acquire(&self) -> WeakSender<C> {
+        allocate(self.counter);
+        WeakSender {
+            counter: self.counter,
+        }
+    }
+
+    /// Attempts to acquire a sender reference.
+    ///
+    /// Returns `None` if all sender references have already been released.
+    pub(crate) fn upgrade(&self) -> Option<Sender<C>> {
+        if upgrade(unsafe { &(*self.counter).senders }) {
+            Some(Sender {
+                counter: self.counter,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Releases the weak reference.
+    pub(crate) unsafe fn release(&self) {
+        deallocate(self.counter);
+    }
+}
+
+/// A weak reference to the receiving side.
+///
+/// Weak references keep the channel allocated, but don't count as receivers.
+pub(crate) struct WeakReceiver<C> {
+    counter: *mut Counter<C>,
+}
+
+impl<C> WeakReceiver<C> {
+    /// Acquires another weak reference.
+    pub(crate) fn acquire(&self) -> WeakReceiver<C> {
+        allocate(self.counter);
+        WeakReceiver {
+            counter: self.counter,
+        }
+    }
+
+    /// Attempts to acquire a receiver reference.
+    ///
+    /// Returns `None` if all receiver references have already been released.
+    pub(crate) fn upgrade(&self) -> Option<Receiver<C>> {
+        if upgrade(unsafe { &(*self.counter).receivers }) {
+            Some(Receiver {
+                counter: self.counter,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Releases the weak reference.
+    pub(crate) unsafe fn release(&self) {
+        deallocate(self.counter);
+    }
+}
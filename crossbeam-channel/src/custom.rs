@@ -0,0 +1,375 @@
+//! Support for user-defined sources that participate in select.
+
+use std::any::Any;
+use std::fmt;
+use std::time::Instant;
+
+use crate::context::Context;
+use crate::select::{Operation, SelectHandle, Selected, Token};
+
+/// A user-defined source of events that can participate in [`Select`] and [`select!`].
+///
+/// Implementing this trait makes it possible to wait on things other than channels, like
+/// cancellation tokens or custom queues, alongside ordinary channel operations. A source is added
+/// to selection with [`Select::recv_custom`] or with a `recv_custom(source) -> res` case in
+/// [`select!`], and a selected operation is completed with [`SelectedOperation::recv_custom`].
+///
+/// # Protocol
+///
+/// Selection proceeds in the following steps:
+///
+/// 1. [`try_select`] is called on each source. If one of them returns `true`, its operation is
+///    selected.
+/// 2. Otherwise, the selecting thread calls [`register`] on each source and blocks. A source that
+///    returns `true` from [`register`] is ready right away, and the thread does not block.
+/// 3. When the state of a source changes so that [`try_select`] could succeed, the source must
+///    call [`Registration::wake`] on registrations it holds. The first registration woken for a
+///    given thread wins, and all others return `false`.
+/// 4. After waking up, the thread calls [`unregister`] on every source, and then [`accept`] on
+///    the source whose registration won. If [`accept`] returns `false`, selection starts over.
+/// 5. Finally, [`complete`] is called on the selected source to produce the result.
+///
+/// To avoid missed wakeups, a source must check whether it is ready *after* storing the
+/// registration in [`register`], while holding the same lock that is used to wake registrations.
+///
+/// A source that succeeds in [`try_select`] or [`accept`] may store data describing the selected
+/// operation in the [`Slot`], which is later handed to [`complete`].
+///
+/// # Examples
+///
+/// A cancellation token:
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use std::thread;
+/// use std::time::Duration;
+/// use crossbeam_channel::{select, unbounded};
+/// use crossbeam_channel::{Registration, RegistrationId, Selectable, Slot};
+///
+/// #[derive(Default)]
+/// struct Cancel {
+///     inner: Mutex<(bool, Vec<Registration>)>,
+/// }
+///
+/// impl Cancel {
+///     fn cancel(&self) {
+///         let mut inner = self.inner.lock().unwrap();
+///         inner.0 = true;
+///         for reg in inner.1.drain(..) {
+///             reg.wake();
+///         }
+///     }
+/// }
+///
+/// impl Selectable for Cancel {
+///     type Output = ();
+///
+///     fn try_select(&self, _slot: &mut Slot) -> bool {
+///         self.is_ready()
+///     }
+///
+///     fn register(&self, reg: &Registration) -> bool {
+///         let mut inner = self.inner.lock().unwrap();
+///         inner.1.push(reg.clone());
+///         inner.0
+///     }
+///
+///     fn unregister(&self, id: RegistrationId) {
+///         self.inner.lock().unwrap().1.retain(|reg| reg.id() != id);
+///     }
+///
+///     fn is_ready(&self) -> bool {
+///         self.inner.lock().unwrap().0
+///     }
+///
+///     fn complete(&self, _slot: &mut Slot) {}
+/// }
+///
+/// let cancel = Arc::new(Cancel::default());
+/// let (s, r) = unbounded::<i32>();
+///
+/// let c = cancel.clone();
+/// thread::spawn(move || {
+///     thread::sleep(Duration::from_millis(100));
+///     c.cancel();
+/// });
+///
+/// // Block until the token is cancelled.
+/// select! {
+///     recv(r) -> msg => panic!("unexpected message: {:?}", msg),
+///     recv_custom(&*cancel) -> () => println!("cancelled"),
+/// }
+/// # drop(s);
+/// ```
+///
+/// [`Select`]: crate::Select
+/// [`select!`]: crate::select
+/// [`Select::recv_custom`]: crate::Select::recv_custom
+/// [`SelectedOperation::recv_custom`]: crate::SelectedOperation::recv_custom
+/// [`try_select`]: Selectable::try_select
+/// [`register`]: Selectable::register
+/// [`unregister`]: Selectable::unregister
+/// [`accept`]: Selectable::accept
+/// [`complete`]: Selectable::complete
+pub trait Selectable {
+    /// The result of completing a selected operation.
+    type Output;
+
+    /// Attempts to select an operation and returns `true` on success.
+    ///
+    /// This method must not block. On failure, the slot must be left untouched.
+    fn try_select(&self, slot: &mut Slot) -> bool;
+
+    /// Registers a blocked select for wakeup and returns `true` if the source is now ready.
+    ///
+    /// The source should keep a clone of the registration until it is unregistered.
+    fn register(&self, reg: &Registration) -> bool;
+
+    /// Unregisters a registration previously passed to [`register`].
+    ///
+    /// [`register`]: Selectable::register
+    fn unregister(&self, id: RegistrationId);
+
+    /// Returns `true` if an operation can be selected without blocking.
+    fn is_ready(&self) -> bool;
+
+    /// Completes the selected operation and returns its result.
+    fn complete(&self, slot: &mut Slot) -> Self::Output;
+
+    /// Attempts to select an operation after a registration has been woken and returns `true` on
+    /// success.
+    ///
+    /// The default implementation calls [`try_select`].
+    ///
+    /// [`try_select`]: Selectable::try_select
+    fn accept(&self, slot: &mut Slot) -> bool {
+        self.try_select(slot)
+    }
+
+    /// Returns a deadline by which the source will become ready, if there is one.
+    ///
+    /// A blocked select wakes up at the deadline without the source having to wake it. The default
+    /// implementation returns `None`.
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Registers a blocked select for readiness notification and returns `true` if the source is
+    /// now ready.
+    ///
+    /// This is used by [`Select::ready`] instead of [`register`], which it calls by default.
+    ///
+    /// [`Select::ready`]: crate::Select::ready
+    /// [`register`]: Selectable::register
+    fn watch(&self, reg: &Registration) -> bool {
+        self.register(reg)
+    }
+
+    /// Unregisters a registration previously passed to [`watch`].
+    ///
+    /// The default implementation calls [`unregister`].
+    ///
+    /// [`watch`]: Selectable::watch
+    /// [`unregister`]: Selectable::unregister
+    fn unwatch(&self, id: RegistrationId) {
+        self.unregister(id)
+    }
+}
+
+impl<S: Selectable + ?Sized> Selectable for &S {
+    type Output = S::Output;
+
+    fn try_select(&self, slot: &mut Slot) -> bool {
+        (**self).try_select(slot)
+    }
+
+    fn register(&self, reg: &Registration) -> bool {
+        (**self).register(reg)
+    }
+
+    fn unregister(&self, id: RegistrationId) {
+        (**self).unregister(id)
+    }
+
+    fn is_ready(&self) -> bool {
+        (**self).is_ready()
+    }
+
+    fn complete(&self, slot: &mut Slot) -> S::Output {
+        (**self).complete(slot)
+    }
+
+    fn accept(&self, slot: &mut Slot) -> bool {
+        (**self).accept(slot)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        (**self).deadline()
+    }
+
+    fn watch(&self, reg: &Registration) -> bool {
+        (**self).watch(reg)
+    }
+
+    fn unwatch(&self, id: RegistrationId) {
+        (**self).unwatch(id)
+    }
+}
+
+/// Storage for data associated with a selected operation on a [`Selectable`] source.
+///
+/// Each select starts with an empty slot. A source that selects an operation may store a value
+/// in it, which can be taken out again when the operation is completed.
+#[derive(Default)]
+pub struct Slot {
+    value: Option<Box<dyn Any>>,
+}
+
+impl Slot {
+    /// Stores a value in the slot, replacing the previous one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Slot;
+    ///
+    /// let mut slot = Slot::default();
+    /// slot.put(7);
+    /// assert_eq!(slot.take::<i32>(), Some(7));
+    /// ```
+    pub fn put<T: 'static>(&mut self, value: T) {
+        self.value = Some(Box::new(value));
+    }
+
+    /// Takes the value out of the slot, if it holds a value of type `T`.
+    ///
+    /// If the slot is empty or holds a value of a different type, `None` is returned and the slot
+    /// is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::Slot;
+    ///
+    /// let mut slot = Slot::default();
+    /// assert_eq!(slot.take::<i32>(), None);
+    ///
+    /// slot.put("hello");
+    /// assert_eq!(slot.take::<i32>(), None);
+    /// assert_eq!(slot.take::<&str>(), Some("hello"));
+    /// assert_eq!(slot.take::<&str>(), None);
+    /// ```
+    pub fn take<T: 'static>(&mut self) -> Option<T> {
+        match self.value.take()?.downcast::<T>() {
+            Ok(value) => Some(*value),
+            Err(value) => {
+                self.value = Some(value);
+                None
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Slot { .. }")
+    }
+}
+
+/// A select blocked on a [`Selectable`] source, waiting to be woken up.
+///
+/// Registrations are handed to [`Selectable::register`] and can be cloned and sent to other
+/// threads.
+#[derive(Clone)]
+pub struct Registration {
+    /// The operation this registration stands for.
+    oper: Operation,
+
+    /// The context of the blocked select.
+    cx: Context,
+}
+
+impl Registration {
+    /// Returns the identifier of this registration.
+    ///
+    /// The identifier is passed to [`Selectable::unregister`] when the registration is no longer
+    /// needed.
+    pub fn id(&self) -> RegistrationId {
+        RegistrationId(self.oper)
+    }
+
+    /// Selects the operation of this registration and wakes up the blocked select.
+    ///
+    /// Returns `true` if the operation was selected, or `false` if the select has already chosen
+    /// another operation, aborted, or timed out. In the latter case the select will not call
+    /// [`Selectable::accept`] on this source, so a source that hands out items one at a time
+    /// should keep waking other registrations until one of them returns `true`.
+    pub fn wake(&self) -> bool {
+        if self.cx.try_select(Selected::Operation(self.oper)).is_ok() {
+            self.cx.unpark();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl fmt::Debug for Registration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Registration { .. }")
+    }
+}
+
+/// Identifier of a [`Registration`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegistrationId(Operation);
+
+/// Adapter that lets a [`Selectable`] source be used as a `SelectHandle`.
+#[repr(transparent)]
+struct Custom<S>(S);
+
+/// Turns a reference to a [`Selectable`] source into a handle for select.
+pub fn custom_handle<'a, S: Selectable + 'a>(source: &'a S) -> &'a (dyn SelectHandle + 'a) {
+    // `Custom<S>` is a transparent wrapper, so the reference can be cast.
+    unsafe { &*(source as *const S as *const Custom<S>) }
+}
+
+impl<S: Selectable> SelectHandle for Custom<S> {
+    fn try_select(&self, token: &mut Token) -> bool {
+        self.0.try_select(&mut token.custom)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        self.0.deadline()
+    }
+
+    fn register(&self, oper: Operation, cx: &Context) -> bool {
+        self.0.register(&Registration {
+            oper,
+            cx: cx.clone(),
+        })
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.0.unregister(RegistrationId(oper));
+    }
+
+    fn accept(&self, token: &mut Token, _cx: &Context) -> bool {
+        self.0.accept(&mut token.custom)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.0.is_ready()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) -> bool {
+        self.0.watch(&Registration {
+            oper,
+            cx: cx.clone(),
+        })
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.0.unwatch(RegistrationId(oper));
+    }
+}
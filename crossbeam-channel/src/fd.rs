@@ -0,0 +1,310 @@
+//! Selection over file descriptor readiness on Linux.
+//!
+//! Blocked selects cannot wait on a file descriptor directly, so a single background thread polls
+//! all registered file descriptors together with an eventfd. Registering a file descriptor adds
+//! it to the list and signals the eventfd so that the background thread picks it up. When a file
+//! descriptor becomes ready, the background thread wakes the registration through the same
+//! contract every [`Selectable`] source follows. The thread is started on first use and lives for
+//! the rest of the process.
+
+use std::io;
+use std::os::raw::c_short;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::{Mutex, Once};
+use std::thread;
+use std::time::Duration;
+
+use crate::custom::{Registration, RegistrationId, Selectable, Slot};
+use crate::utils::Lazy;
+
+/// A file descriptor that can be selected when it becomes readable.
+///
+/// A readable file descriptor can be added to selection with [`Select::readable`], or with a
+/// `recv_custom(Readable(fd.as_raw_fd())) -> _` case in [`select!`].
+///
+/// An operation is selected when reading won't block, which includes the cases when the other
+/// end has hung up or an error is pending. The read itself is left to the caller.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use std::os::unix::io::AsRawFd;
+/// use std::os::unix::net::UnixStream;
+/// use crossbeam_channel::{select, unbounded, Readable};
+///
+/// let (mut a, b) = UnixStream::pair().unwrap();
+/// let (s, r) = unbounded::<i32>();
+///
+/// a.write_all(b"hello").unwrap();
+///
+/// // The socket becomes readable before a message arrives.
+/// select! {
+///     recv(r) -> msg => panic!("unexpected message: {:?}", msg),
+///     recv_custom(Readable(b.as_raw_fd())) -> _ => println!("socket is readable"),
+/// }
+/// # drop(s);
+/// ```
+///
+/// [`Select::readable`]: crate::Select::readable
+/// [`select!`]: crate::select
+#[derive(Debug, Clone, Copy)]
+#[repr(transparent)]
+pub struct Readable<F>(pub F);
+
+/// A file descriptor that can be selected when it becomes writable.
+///
+/// A writable file descriptor can be added to selection with [`Select::writable`], or with a
+/// `recv_custom(Writable(fd.as_raw_fd())) -> _` case in [`select!`].
+///
+/// An operation is selected when writing won't block, which includes the cases when the other
+/// end has hung up or an error is pending. The write itself is left to the caller.
+///
+/// # Examples
+///
+/// ```
+/// use std::os::unix::io::AsRawFd;
+/// use std::os::unix::net::UnixStream;
+/// use crossbeam_channel::{select, Writable};
+///
+/// let (a, _b) = UnixStream::pair().unwrap();
+///
+/// // A fresh socket has room in its send buffer.
+/// select! {
+///     recv_custom(Writable(a.as_raw_fd())) -> _ => println!("socket is writable"),
+/// }
+/// ```
+///
+/// [`Select::writable`]: crate::Select::writable
+/// [`select!`]: crate::select
+#[derive(Debug, Clone, Copy)]
+#[repr(transparent)]
+pub struct Writable<F>(pub F);
+
+impl<F: AsRawFd> Selectable for Readable<F> {
+    type Output = ();
+
+    fn try_select(&self, _slot: &mut Slot) -> bool {
+        self.is_ready()
+    }
+
+    fn register(&self, reg: &Registration) -> bool {
+        reactor().register(self.0.as_raw_fd(), libc::POLLIN, reg);
+        self.is_ready()
+    }
+
+    fn unregister(&self, id: RegistrationId) {
+        reactor().unregister(id);
+    }
+
+    fn is_ready(&self) -> bool {
+        is_ready(self.0.as_raw_fd(), libc::POLLIN)
+    }
+
+    fn complete(&self, _slot: &mut Slot) {}
+}
+
+impl<F: AsRawFd> Selectable for Writable<F> {
+    type Output = ();
+
+    fn try_select(&self, _slot: &mut Slot) -> bool {
+        self.is_ready()
+    }
+
+    fn register(&self, reg: &Registration) -> bool {
+        reactor().register(self.0.as_raw_fd(), libc::POLLOUT, reg);
+        self.is_ready()
+    }
+
+    fn unregister(&self, id: RegistrationId) {
+        reactor().unregister(id);
+    }
+
+    fn is_ready(&self) -> bool {
+        is_ready(self.0.as_raw_fd(), libc::POLLOUT)
+    }
+
+    fn complete(&self, _slot: &mut Slot) {}
+}
+
+/// Casts a reference to a file descriptor into a reference to a readable source.
+pub(crate) fn readable<F>(f: &F) -> &Readable<F> {
+    // `Readable<F>` is a transparent wrapper, so the reference can be cast.
+    unsafe { &*(f as *const F as *const Readable<F>) }
+}
+
+/// Casts a reference to a file descriptor into a reference to a writable source.
+pub(crate) fn writable<F>(f: &F) -> &Writable<F> {
+    // `Writable<F>` is a transparent wrapper, so the reference can be cast.
+    unsafe { &*(f as *const F as *const Writable<F>) }
+}
+
+/// Returns `true` if any of the events or an error is pending on the file descriptor.
+fn is_ready(fd: RawFd, events: c_short) -> bool {
+    let mut pollfd = libc::pollfd {
+        fd,
+        events,
+        revents: 0,
+    };
+    unsafe { libc::poll(&mut pollfd, 1, 0) > 0 }
+}
+
+/// A file descriptor some blocked select is waiting on.
+struct Entry {
+    /// The file descriptor.
+    fd: RawFd,
+
+    /// The events the select is waiting for.
+    events: c_short,
+
+    /// The registration to wake when the file descriptor becomes ready.
+    reg: Registration,
+}
+
+/// Background thread that polls registered file descriptors.
+struct Reactor {
+    /// File descriptors waited on by blocked selects.
+    entries: Mutex<Vec<Entry>>,
+
+    /// An eventfd used to interrupt polling when the list of entries changes.
+    eventfd: RawFd,
+}
+
+/// Returns the reactor, starting its thread on first use.
+///
+/// The reactor and its thread are never shut down.
+fn reactor() -> &'static Reactor {
+    static REACTOR: Lazy<Reactor> = Lazy::new();
+    static START: Once = Once::new();
+
+    let reactor = REACTOR.get_or_init(Reactor::new);
+    START.call_once(|| {
+        thread::Builder::new()
+            .name("crossbeam-channel-fd".to_string())
+            .spawn(move || reactor.run())
+            .expect("failed to spawn the file descriptor polling thread");
+    });
+    reactor
+}
+
+impl Reactor {
+    /// Creates a new reactor.
+    fn new() -> Reactor {
+        let eventfd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if eventfd < 0 {
+            panic!(
+                "failed to create an eventfd: {}",
+                io::Error::last_os_error()
+            );
+        }
+
+        Reactor {
+            entries: Mutex::new(Vec::new()),
+            eventfd,
+        }
+    }
+
+    /// Adds an entry and interrupts polling so that it gets picked up.
+    fn register(&self, fd: RawFd, events: c_short, reg: &Registration) {
+        self.entries.lock().unwrap().push(Entry {
+            fd,
+            events,
+            reg: reg.clone(),
+        });
+        self.notify();
+    }
+
+    /// Removes an entry.
+    ///
+    /// The background thread may keep polling the file descriptor until it wakes up for another
+    /// reason, which is harmless because entries are looked up again before being woken.
+    fn unregister(&self, id: RegistrationId) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|entry| entry.reg.id() != id);
+    }
+
+    /// Interrupts polling.
+    fn notify(&self) {
+        let one: u64 = 1;
+        unsafe {
+            libc::write(
+                self.eventfd,
+                &one as *const u64 as *const libc::c_void,
+                std::mem::size_of::<u64>(),
+            );
+        }
+    }
+
+    /// Polls the registered file descriptors forever, waking registrations as they become ready.
+    fn run(&self) {
+        let mut pollfds = Vec::new();
+
+        loop {
+            // Collect the file descriptors to poll, with the eventfd in front.
+            pollfds.clear();
+            pollfds.push(libc::pollfd {
+                fd: self.eventfd,
+                events: libc::POLLIN,
+                revents: 0,
+            });
+            for entry in self.entries.lock().unwrap().iter() {
+                pollfds.push(libc::pollfd {
+                    fd: entry.fd,
+                    events: entry.events,
+                    revents: 0,
+                });
+            }
+
+            let res = unsafe { libc::poll(pollfds[..].as_mut_ptr(), pollfds.len() as _, -1) };
+            if res < 0 {
+                // Polling fails right away if it runs out of memory or there are more file
+                // descriptors than the process may open. Back off instead of spinning until the
+                // entries change, but keep the thread alive so that waiting selects aren't stuck.
+                if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+                    thread::sleep(Duration::from_millis(10));
+                }
+                continue;
+            }
+
+            // Reset the eventfd if it was signaled.
+            if pollfds[0].revents != 0 {
+                let mut buf: u64 = 0;
+                unsafe {
+                    libc::read(
+                        self.eventfd,
+                        &mut buf as *mut u64 as *mut libc::c_void,
+                        std::mem::size_of::<u64>(),
+                    );
+                }
+            }
+
+            // Wake and remove entries whose file descriptors are ready. Entries that were added
+            // after the list was collected get picked up in the next round.
+            let mut entries = self.entries.lock().unwrap();
+            for pollfd in &pollfds[1..] {
+                if pollfd.revents == 0 {
+                    continue;
+                }
+                entries.retain(|entry| {
+                    if entry.fd == pollfd.fd && entry.events == pollfd.events {
+                        entry.reg.wake();
+                        false
+                    } else {
+                        true
+                    }
+                });
+            }
+        }
+    }
+}
+
+impl Drop for Reactor {
+    fn drop(&mut self) {
+        // Only a reactor that lost the race to be initialized first gets dropped.
+        unsafe {
+            libc::close(self.eventfd);
+        }
+    }
+}
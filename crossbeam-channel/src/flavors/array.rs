@@ -9,26 +9,37 @@
 //!   - <https://docs.google.com/document/d/1yIAYmbvL3JxOKOjuCyon7JhW4cSv1wy5hC0ApeGMV9s/pub>
 
 use std::cell::UnsafeCell;
+use std::collections::VecDeque;
 use std::marker::PhantomData;
 use std::mem::{self, MaybeUninit};
 use std::ptr;
 use std::sync::atomic::{self, AtomicUsize, Ordering};
-use std::time::Instant;
+use std::task::{self, Poll};
+use std::time::{Duration, Instant};
 
 use crossbeam_utils::{Backoff, CachePadded};
 
+use crate::channel::{Expiry, Overflow};
+use crate::clock;
 use crate::context::Context;
 use crate::err::{RecvTimeoutError, SendTimeoutError, TryRecvError, TrySendError};
 use crate::select::{Operation, SelectHandle, Selected, Token};
-use crate::waker::SyncWaker;
+use crate::waker::{SyncWaker, Waiter};
 
 /// A slot in a channel.
 struct Slot<T> {
     /// The current stamp.
+    ///
+    /// The mark bit is set in the stamp of a slot reserved by a permit. While the message hasn't
+    /// been written, the stamp is the tail the slot was reserved at. If the permit is dropped
+    /// without sending, the stamp is one more than that, and receivers skip the slot.
     stamp: AtomicUsize,
 
     /// The message in this slot.
     msg: UnsafeCell<MaybeUninit<T>>,
+
+    /// The instant at which the message in this slot expires, if it does.
+    deadline: UnsafeCell<Option<Instant>>,
 }
 
 /// The token type for the array flavor.
@@ -39,6 +50,10 @@ pub struct ArrayToken {
 
     /// Stamp to store into the slot after reading or writing.
     stamp: usize,
+
+    /// Set to `true` if a message is evicted by the write: the old message in the slot, or the
+    /// new message if there is no slot.
+    evict: bool,
 }
 
 impl Default for ArrayToken {
@@ -47,6 +62,7 @@ impl Default for ArrayToken {
         ArrayToken {
             slot: ptr::null(),
             stamp: 0,
+            evict: false,
         }
     }
 }
@@ -57,7 +73,8 @@ pub(crate) struct Channel<T> {
     ///
     /// This value is a "stamp" consisting of an index into the buffer, a mark bit, and a lap, but
     /// packed into a single `usize`. The lower bits represent the index, while the upper bits
-    /// represent the lap. The mark bit in the head is always zero.
+    /// represent the lap. The mark bit indicates that a receiver is inspecting the message at the
+    /// head in place, and other receivers have to wait until it is cleared.
     ///
     /// Messages are popped from the head of the channel.
     head: CachePadded<AtomicUsize>,
@@ -80,9 +97,16 @@ pub(crate) struct Channel<T> {
     /// A stamp with the value of `{ lap: 1, mark: 0, index: 0 }`.
     one_lap: usize,
 
-    /// If this bit is set in the tail, that means the channel is disconnected.
+    /// If this bit is set in the tail, that means the channel is disconnected. If it is set in the
+    /// head, that means the head is locked by a peeking receiver.
     mark_bit: usize,
 
+    /// The policy for sending into a full channel, or `None` if senders wait for free slots.
+    overflow: Option<Overflow>,
+
+    /// The expiry policy for messages.
+    expiry: Expiry<T>,
+
     /// Senders waiting while the channel is full.
     senders: SyncWaker,
 
@@ -116,6 +140,7 @@ impl<T> Channel<T> {
                     Slot {
                         stamp: AtomicUsize::new(i),
                         msg: UnsafeCell::new(MaybeUninit::uninit()),
+                        deadline: UnsafeCell::new(None),
                     }
                 })
                 .collect();
@@ -129,6 +154,8 @@ impl<T> Channel<T> {
             cap,
             one_lap,
             mark_bit,
+            overflow: None,
+            expiry: Expiry::new(),
             head: CachePadded::new(AtomicUsize::new(head)),
             tail: CachePadded::new(AtomicUsize::new(tail)),
             senders: SyncWaker::new(),
@@ -137,6 +164,20 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Creates a bounded channel of capacity `cap` that never blocks senders.
+    pub(crate) fn with_overflow(cap: usize, overflow: Overflow) -> Self {
+        let mut chan = Channel::with_capacity(cap);
+        chan.overflow = Some(overflow);
+        chan
+    }
+
+    /// Creates a bounded channel of capacity `cap` whose messages expire according to `expiry`.
+    pub(crate) fn with_expiry(cap: usize, expiry: Expiry<T>) -> Self {
+        let mut chan = Channel::with_capacity(cap);
+        chan.expiry = expiry;
+        chan
+    }
+
     /// Returns a receiver handle to the channel.
     pub(crate) fn receiver(&self) -> Receiver<'_, T> {
         Receiver(self)
@@ -157,6 +198,7 @@ impl<T> Channel<T> {
             if tail & self.mark_bit != 0 {
                 token.array.slot = ptr::null();
                 token.array.stamp = 0;
+                token.array.evict = false;
                 return true;
             }
 
@@ -191,6 +233,7 @@ impl<T> Channel<T> {
                         // Prepare the token for the follow-up call to `write`.
                         token.array.slot = slot as *const Slot<T> as *const u8;
                         token.array.stamp = tail + 1;
+                        token.array.evict = false;
                         return true;
                     }
                     Err(t) => {
@@ -203,13 +246,76 @@ impl<T> Channel<T> {
                 let head = self.head.load(Ordering::Relaxed);
 
                 // If the head lags one lap behind the tail as well...
-                if head.wrapping_add(self.one_lap) == tail {
+                //
+                // The head is locked with the mark bit while a message is being inspected, which
+                // doesn't change how full the channel is.
+                if (head & !self.mark_bit).wrapping_add(self.one_lap) == tail {
                     // ...then the channel is full.
-                    return false;
+                    match self.overflow {
+                        None => return false,
+                        Some(Overflow::DropOldest) if head & self.mark_bit == 0 => {
+                            // The oldest message is in the slot at the tail. Try moving the head
+                            // past it to claim the slot for the new message.
+                            let new_tail = self.next_stamp(tail);
+                            let new_head = new_tail.wrapping_sub(self.one_lap);
+
+                            if self
+                                .head
+                                .compare_exchange_weak(
+                                    head,
+                                    new_head,
+                                    Ordering::SeqCst,
+                                    Ordering::Relaxed,
+                                )
+                                .is_ok()
+                            {
+                                // Now the head has moved but the tail hasn't, so other senders see
+                                // a full slot without a full channel and spin until the tail
+                                // catches up. The window is as short as the one between moving the
+                                // tail and writing the stamp in a regular send, where receivers
+                                // wait the same way, and it isn't held across anything but the CAS
+                                // below.
+                                //
+                                // Other senders can't move the tail until the slot is written, so
+                                // only a concurrent disconnection can set the mark bit here.
+                                let mut t = tail;
+                                while let Err(current) = self.tail.compare_exchange_weak(
+                                    t,
+                                    new_tail | (t & self.mark_bit),
+                                    Ordering::SeqCst,
+                                    Ordering::Relaxed,
+                                ) {
+                                    t = current;
+                                }
+
+                                // Replace the old message on write.
+                                token.array.slot = slot as *const Slot<T> as *const u8;
+                                token.array.stamp = tail + 1;
+                                token.array.evict = true;
+                                return true;
+                            }
+                        }
+                        Some(_) => {
+                            // Discard the new message on write. This is also done if the oldest
+                            // message is being inspected, since it can't be evicted then, and
+                            // waiting for the inspection to finish could wait forever if it's the
+                            // inspecting thread that is sending.
+                            token.array.slot = ptr::null();
+                            token.array.stamp = 0;
+                            token.array.evict = true;
+                            return true;
+                        }
+                    }
                 }
 
                 backoff.spin();
                 tail = self.tail.load(Ordering::Relaxed);
+            } else if stamp == tail.wrapping_sub(self.one_lap) | self.mark_bit
+                || stamp == (tail.wrapping_sub(self.one_lap) + 1) | self.mark_bit
+            {
+                // The slot is still reserved by a permit from the previous lap, or waiting to be
+                // skipped, so the head hasn't moved past it and the channel is full.
+                return false;
             } else {
                 // Snooze because we need to wait for the stamp to get updated.
                 backoff.snooze();
@@ -218,22 +324,112 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Attempts to reserve a slot for sending a message later through a permit.
+    fn start_reserve(&self, token: &mut Token) -> bool {
+        debug_assert!(self.overflow.is_none());
+        if !self.start_send(token) {
+            return false;
+        }
+
+        if !token.array.slot.is_null() {
+            // Mark the slot as reserved so that receivers block instead of spinning while the
+            // message is being prepared.
+            let slot = unsafe { &*(token.array.slot as *const Slot<T>) };
+            let tail = token.array.stamp - 1;
+            slot.stamp.store(tail | self.mark_bit, Ordering::Release);
+        }
+        true
+    }
+
+    /// Writes a message into a slot reserved by a permit.
+    pub(crate) unsafe fn write_reserved(&self, token: &mut Token, msg: T) -> Result<(), T> {
+        if !token.array.slot.is_null() && self.is_disconnected() {
+            self.cancel_reserved(token);
+            token.array.slot = ptr::null();
+        }
+        self.write(token, msg)
+    }
+
+    /// Releases a slot reserved by a permit without writing a message into it.
+    pub(crate) unsafe fn cancel_reserved(&self, token: &mut Token) {
+        if token.array.slot.is_null() {
+            return;
+        }
+
+        let slot: &Slot<T> = &*(token.array.slot as *const Slot<T>);
+        let tail = token.array.stamp - 1;
+
+        // If no slot was reserved after this one, move the tail back to give it up entirely.
+        slot.stamp.store(tail, Ordering::Release);
+        if self
+            .tail
+            .compare_exchange(
+                self.next_stamp(tail),
+                tail,
+                Ordering::SeqCst,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+        {
+            // Wake a sleeping sender.
+            self.senders.notify();
+        } else {
+            // Otherwise, leave the slot for receivers to skip over.
+            slot.stamp
+                .store((tail + 1) | self.mark_bit, Ordering::Release);
+            self.receivers.notify();
+        }
+    }
+
     /// Writes a message into the channel.
     pub(crate) unsafe fn write(&self, token: &mut Token, msg: T) -> Result<(), T> {
-        // If there is no slot, the channel is disconnected.
+        self.write_with_ttl(token, msg, None)
+    }
+
+    /// Writes a message with the given time-to-live into the channel.
+    ///
+    /// If `ttl` is `None`, the message gets the time-to-live of the channel's expiry policy.
+    unsafe fn write_with_ttl(
+        &self,
+        token: &mut Token,
+        msg: T,
+        ttl: Option<Duration>,
+    ) -> Result<(), T> {
+        self.write_evicting(token, msg, ttl).map(drop)
+    }
+
+    /// Writes a message into the channel, returning the message evicted by the write, if any.
+    unsafe fn write_evicting(
+        &self,
+        token: &mut Token,
+        msg: T,
+        ttl: Option<Duration>,
+    ) -> Result<Option<T>, T> {
+        // If there is no slot, either the new message is discarded or the channel is disconnected.
         if token.array.slot.is_null() {
-            return Err(msg);
+            return if token.array.evict {
+                Ok(Some(msg))
+            } else {
+                Err(msg)
+            };
         }
 
         let slot: &Slot<T> = &*(token.array.slot as *const Slot<T>);
 
-        // Write the message into the slot and update the stamp.
-        slot.msg.get().write(MaybeUninit::new(msg));
+        // Write the message into the slot, taking the oldest message out if it is evicted, and
+        // update the stamp.
+        let evicted = if token.array.evict {
+            Some(slot.msg.get().replace(MaybeUninit::new(msg)).assume_init())
+        } else {
+            slot.msg.get().write(MaybeUninit::new(msg));
+            None
+        };
+        slot.deadline.get().write(self.expiry.deadline(ttl));
         slot.stamp.store(token.array.stamp, Ordering::Release);
 
         // Wake a sleeping receiver.
         self.receivers.notify();
-        Ok(())
+        Ok(evicted)
     }
 
     /// Attempts to reserve a slot for receiving a message.
@@ -273,7 +469,14 @@ impl<T> Channel<T> {
                         // Prepare the token for the follow-up call to `read`.
                         token.array.slot = slot as *const Slot<T> as *const u8;
                         token.array.stamp = head.wrapping_add(self.one_lap);
-                        return true;
+                        if !self.is_expired(slot) {
+                            return true;
+                        }
+
+                        // The message has expired, so take it out and move on to the next one.
+                        let msg = unsafe { self.read(token) }.ok().unwrap();
+                        self.expiry.expire(msg);
+                        head = self.head.load(Ordering::Relaxed);
                     }
                     Err(h) => {
                         head = h;
@@ -300,14 +503,143 @@ impl<T> Channel<T> {
 
                 backoff.spin();
                 head = self.head.load(Ordering::Relaxed);
+            } else if stamp == head | self.mark_bit {
+                // The slot is reserved by a permit, so wait until its message is written.
+                return false;
+            } else if stamp == (head + 1) | self.mark_bit {
+                // The permit was dropped without sending.
+                head = self.skip_dropped(head, slot, &backoff);
+            } else {
+                // Snooze because we need to wait for the stamp to get updated, or for a peeking
+                // receiver to unlock the head.
+                backoff.snooze();
+                head = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Tries moving the head past a slot left behind by a dropped permit, and hands the slot over
+    /// to senders in the next lap.
+    ///
+    /// Returns the new value of the head.
+    fn skip_dropped(&self, head: usize, slot: &Slot<T>, backoff: &Backoff) -> usize {
+        match self.head.compare_exchange_weak(
+            head,
+            self.next_stamp(head),
+            Ordering::SeqCst,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => {
+                slot.stamp
+                    .store(head.wrapping_add(self.one_lap), Ordering::Release);
+                self.senders.notify();
+                self.next_stamp(head)
+            }
+            Err(h) => {
+                backoff.spin();
+                h
+            }
+        }
+    }
+
+    /// Locks the head of the channel while it points at a message.
+    ///
+    /// Returns the value of the head before locking. While the head is locked, other receivers
+    /// wait instead of claiming the slot, so the message can be inspected in place.
+    fn lock_head(&self) -> Result<usize, TryRecvError> {
+        let backoff = Backoff::new();
+        let mut head = self.head.load(Ordering::Relaxed);
+
+        loop {
+            // Inspect the slot at the head.
+            let slot = unsafe { &*self.buffer.add(head & (self.mark_bit - 1)) };
+            let stamp = slot.stamp.load(Ordering::Acquire);
+
+            if head + 1 == stamp {
+                // The slot holds a message, so try locking the head.
+                match self.head.compare_exchange_weak(
+                    head,
+                    head | self.mark_bit,
+                    Ordering::SeqCst,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        if !self.is_expired(slot) {
+                            return Ok(head);
+                        }
+
+                        // The message has expired, so unlock the head by moving it past the slot,
+                        // then take the message out.
+                        self.head.store(self.next_stamp(head), Ordering::SeqCst);
+                        let token = &mut Token::default();
+                        token.array.slot = slot as *const Slot<T> as *const u8;
+                        token.array.stamp = head.wrapping_add(self.one_lap);
+                        let msg = unsafe { self.read(token) }.ok().unwrap();
+                        self.expiry.expire(msg);
+                        head = self.head.load(Ordering::Relaxed);
+                    }
+                    Err(h) => {
+                        head = h;
+                        backoff.spin();
+                    }
+                }
+            } else if stamp == head {
+                atomic::fence(Ordering::SeqCst);
+                let tail = self.tail.load(Ordering::Relaxed);
+
+                // If the tail equals the head, that means the channel is empty.
+                if (tail & !self.mark_bit) == head {
+                    return if tail & self.mark_bit != 0 {
+                        Err(TryRecvError::Disconnected)
+                    } else {
+                        Err(TryRecvError::Empty)
+                    };
+                }
+
+                backoff.spin();
+                head = self.head.load(Ordering::Relaxed);
+            } else if stamp == head | self.mark_bit {
+                // The slot is reserved by a permit.
+                return Err(TryRecvError::Empty);
+            } else if stamp == (head + 1) | self.mark_bit {
+                // The permit was dropped without sending.
+                head = self.skip_dropped(head, slot, &backoff);
             } else {
-                // Snooze because we need to wait for the stamp to get updated.
                 backoff.snooze();
                 head = self.head.load(Ordering::Relaxed);
             }
         }
     }
 
+    /// Attempts to receive the message at the head of the channel if `pred` returns `true` for it.
+    ///
+    /// Returns `Ok(None)` if the message is left in the channel.
+    pub(crate) fn try_recv_if<F>(&self, pred: F) -> Result<Option<T>, TryRecvError>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        let head = self.lock_head()?;
+        let slot = unsafe { &*self.buffer.add(head & (self.mark_bit - 1)) };
+
+        // Unlock the head when done, even if `pred` panics.
+        let guard = HeadGuard {
+            head: &self.head,
+            value: head,
+        };
+        if !pred(unsafe { &*(*slot.msg.get()).as_ptr() }) {
+            return Ok(None);
+        }
+
+        // Unlock the head by moving it past the slot, then read the message.
+        mem::forget(guard);
+        self.head.store(self.next_stamp(head), Ordering::SeqCst);
+
+        let token = &mut Token::default();
+        token.array.slot = slot as *const Slot<T> as *const u8;
+        token.array.stamp = head.wrapping_add(self.one_lap);
+        Ok(unsafe { self.read(token) }.ok())
+    }
+
     /// Reads a message from the channel.
     pub(crate) unsafe fn read(&self, token: &mut Token) -> Result<T, ()> {
         if token.array.slot.is_null() {
@@ -326,20 +658,280 @@ impl<T> Channel<T> {
         Ok(msg)
     }
 
-    /// Attempts to send a message into the channel.
-    pub(crate) fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
+    /// Returns `true` if the message in `slot` has expired.
+    ///
+    /// The slot must hold a message.
+    fn is_expired(&self, slot: &Slot<T>) -> bool {
+        self.expiry.is_expired(unsafe { *slot.deadline.get() })
+    }
+
+    /// Returns the stamp of the slot following the one at `stamp`.
+    fn next_stamp(&self, stamp: usize) -> usize {
+        let index = stamp & (self.mark_bit - 1);
+        let lap = stamp & !(self.one_lap - 1);
+
+        if index + 1 < self.cap {
+            // Same lap, incremented index.
+            stamp + 1
+        } else {
+            // One lap forward, index wraps around to zero.
+            lap.wrapping_add(self.one_lap)
+        }
+    }
+
+    /// Attempts to receive up to `max` messages without blocking.
+    ///
+    /// The run of messages that are ready at the head of the channel is claimed with a single
+    /// update of the head. Returns the number of messages pushed into `buf`.
+    pub(crate) fn try_recv_many(
+        &self,
+        buf: &mut Vec<T>,
+        max: usize,
+    ) -> Result<usize, TryRecvError> {
+        let token = &mut Token::default();
+        let backoff = Backoff::new();
+        let mut head = self.head.load(Ordering::Relaxed);
+
+        loop {
+            // Count the slots starting from the head that are ready for reading.
+            let mut count = 0;
+            let mut new = head;
+            while count < max {
+                let slot = unsafe { &*self.buffer.add(new & (self.mark_bit - 1)) };
+                if slot.stamp.load(Ordering::Acquire) != new + 1 {
+                    break;
+                }
+                new = self.next_stamp(new);
+                count += 1;
+            }
+
+            // If no slot is ready, claim a single message the usual way, which also waits for
+            // slots being written into and detects empty and disconnected channels.
+            if count == 0 {
+                return if self.start_recv(token) {
+                    match unsafe { self.read(token) } {
+                        Ok(msg) => {
+                            buf.push(msg);
+                            Ok(1)
+                        }
+                        Err(()) => Err(TryRecvError::Disconnected),
+                    }
+                } else {
+                    Err(TryRecvError::Empty)
+                };
+            }
+
+            // Reserve space up front so that pushing into the buffer can't panic once the slots
+            // are claimed.
+            buf.reserve(count);
+
+            // Try moving the head past all ready slots at once.
+            match self
+                .head
+                .compare_exchange_weak(head, new, Ordering::SeqCst, Ordering::Relaxed)
+            {
+                Ok(_) => {
+                    // Read all claimed messages before handing expired ones over, so that a
+                    // panicking expiry handler can't leave claimed slots behind.
+                    let mut expired = Vec::new();
+                    for _ in 0..count {
+                        let slot = unsafe { &*self.buffer.add(head & (self.mark_bit - 1)) };
+                        let is_expired = self.is_expired(slot);
+                        token.array.slot = slot as *const Slot<T> as *const u8;
+                        token.array.stamp = head.wrapping_add(self.one_lap);
+                        let msg = unsafe { self.read(token) }.ok().unwrap();
+                        if is_expired {
+                            expired.push(msg);
+                        } else {
+                            buf.push(msg);
+                        }
+                        head = self.next_stamp(head);
+                    }
+
+                    let received = count - expired.len();
+                    for msg in expired {
+                        self.expiry.expire(msg);
+                    }
+
+                    // If all claimed messages have expired, try again.
+                    if received > 0 {
+                        return Ok(received);
+                    }
+                    head = self.head.load(Ordering::Relaxed);
+                }
+                Err(h) => {
+                    head = h;
+                    backoff.spin();
+                }
+            }
+        }
+    }
+
+    /// Attempts to send a message with the given time-to-live into the channel.
+    pub(crate) fn try_send(&self, msg: T, ttl: Option<Duration>) -> Result<(), TrySendError<T>> {
         let token = &mut Token::default();
         if self.start_send(token) {
-            unsafe { self.write(token, msg).map_err(TrySendError::Disconnected) }
+            unsafe {
+                self.write_with_ttl(token, msg, ttl)
+                    .map_err(TrySendError::Disconnected)
+            }
         } else {
             Err(TrySendError::Full(msg))
         }
     }
 
-    /// Sends a message into the channel.
+    /// Attempts to reserve a slot for a permit without blocking.
+    pub(crate) fn try_reserve(&self, token: &mut Token) -> Result<(), TrySendError<()>> {
+        if !self.start_reserve(token) {
+            Err(TrySendError::Full(()))
+        } else if token.array.slot.is_null() {
+            Err(TrySendError::Disconnected(()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reserves a slot for a permit.
+    pub(crate) fn reserve(
+        &self,
+        token: &mut Token,
+        deadline: Option<Instant>,
+    ) -> Result<(), SendTimeoutError<()>> {
+        loop {
+            // Try reserving a slot several times.
+            let backoff = Backoff::new();
+            loop {
+                if self.start_reserve(token) {
+                    return if token.array.slot.is_null() {
+                        Err(SendTimeoutError::Disconnected(()))
+                    } else {
+                        Ok(())
+                    };
+                }
+
+                if backoff.is_completed() {
+                    break;
+                } else {
+                    backoff.snooze();
+                }
+            }
+
+            if let Some(d) = deadline {
+                if clock::now() >= d {
+                    return Err(SendTimeoutError::Timeout(()));
+                }
+            }
+
+            Context::with(|cx| {
+                // Prepare for blocking until a receiver wakes us up.
+                let oper = Operation::hook(token);
+                self.senders.register(oper, cx);
+
+                // Has the channel become ready just now?
+                if !self.is_full() || self.is_disconnected() {
+                    let _ = cx.try_select(Selected::Aborted);
+                }
+
+                // Block the current thread.
+                let sel = cx.wait_until(deadline);
+
+                match sel {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected => {
+                        self.senders.unregister(oper).unwrap();
+                    }
+                    Selected::Operation(_) => {}
+                }
+            });
+        }
+    }
+
+    /// Attempts to send messages from the front of `msgs` into the channel without blocking.
+    ///
+    /// The run of slots that are free at the tail of the channel is reserved with a single update
+    /// of the tail, and blocked receivers are notified once all messages are written. Returns the
+    /// number of sent messages.
+    pub(crate) fn try_send_many(&self, msgs: &mut VecDeque<T>) -> Result<usize, TrySendError<()>> {
+        let token = &mut Token::default();
+        let backoff = Backoff::new();
+        let mut tail = self.tail.load(Ordering::Relaxed);
+
+        loop {
+            // Count the slots starting from the tail that are ready for writing.
+            let mut count = 0;
+            let mut new = tail;
+            if tail & self.mark_bit == 0 {
+                while count < msgs.len() {
+                    let slot = unsafe { &*self.buffer.add(new & (self.mark_bit - 1)) };
+                    if slot.stamp.load(Ordering::Acquire) != new {
+                        break;
+                    }
+                    new = self.next_stamp(new);
+                    count += 1;
+                }
+            }
+
+            // If no slot is ready, reserve a single slot the usual way, which also waits for slots
+            // being read from and detects full and disconnected channels.
+            if count == 0 {
+                return if !self.start_send(token) {
+                    Err(TrySendError::Full(()))
+                } else if token.array.slot.is_null() {
+                    Err(TrySendError::Disconnected(()))
+                } else {
+                    unsafe { self.write(token, msgs.pop_front().unwrap()).ok().unwrap() };
+                    Ok(1)
+                };
+            }
+
+            // Try moving the tail past all free slots at once.
+            match self
+                .tail
+                .compare_exchange_weak(tail, new, Ordering::SeqCst, Ordering::Relaxed)
+            {
+                Ok(_) => {
+                    let deadline = self.expiry.deadline(None);
+                    for _ in 0..count {
+                        // Write the message into the slot and update the stamp.
+                        let slot = unsafe { &*self.buffer.add(tail & (self.mark_bit - 1)) };
+                        unsafe {
+                            slot.msg
+                                .get()
+                                .write(MaybeUninit::new(msgs.pop_front().unwrap()));
+                            slot.deadline.get().write(deadline);
+                        }
+                        slot.stamp.store(tail + 1, Ordering::Release);
+                        tail = self.next_stamp(tail);
+                    }
+
+                    // Wake sleeping receivers.
+                    self.receivers.notify_many(count);
+                    return Ok(count);
+                }
+                Err(t) => {
+                    tail = t;
+                    backoff.spin();
+                }
+            }
+        }
+    }
+
+    /// Sends a message into a channel with an overflow policy.
+    ///
+    /// Never blocks. Returns the message evicted to make room for the new one, if any.
+    pub(crate) fn send_evicting(&self, msg: T) -> Result<Option<T>, T> {
+        debug_assert!(self.overflow.is_some());
+        let token = &mut Token::default();
+        let ready = self.start_send(token);
+        debug_assert!(ready);
+        unsafe { self.write_evicting(token, msg, None) }
+    }
+
+    /// Sends a message with the given time-to-live into the channel.
     pub(crate) fn send(
         &self,
         msg: T,
+        ttl: Option<Duration>,
         deadline: Option<Instant>,
     ) -> Result<(), SendTimeoutError<T>> {
         let token = &mut Token::default();
@@ -348,7 +940,7 @@ impl<T> Channel<T> {
             let backoff = Backoff::new();
             loop {
                 if self.start_send(token) {
-                    let res = unsafe { self.write(token, msg) };
+                    let res = unsafe { self.write_with_ttl(token, msg, ttl) };
                     return res.map_err(SendTimeoutError::Disconnected);
                 }
 
@@ -360,7 +952,7 @@ impl<T> Channel<T> {
             }
 
             if let Some(d) = deadline {
-                if Instant::now() >= d {
+                if clock::now() >= d {
                     return Err(SendTimeoutError::Timeout(msg));
                 }
             }
@@ -420,7 +1012,7 @@ impl<T> Channel<T> {
             }
 
             if let Some(d) = deadline {
-                if Instant::now() >= d {
+                if clock::now() >= d {
                     return Err(RecvTimeoutError::Timeout);
                 }
             }
@@ -451,6 +1043,86 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Polls a send operation on behalf of an asynchronous task.
+    ///
+    /// The message is taken out of `msg` only once the operation completes.
+    pub(crate) fn poll_send(
+        &self,
+        msg: &mut Option<T>,
+        waiter: &mut Waiter,
+        waker: &task::Waker,
+    ) -> Poll<Result<(), T>> {
+        let token = &mut Token::default();
+        loop {
+            // Withdraw the registration left over from the previous poll.
+            if let Some(oper) = waiter.finish() {
+                self.senders.unregister(oper);
+            }
+
+            if self.start_send(token) {
+                let msg = msg.take().unwrap();
+                return Poll::Ready(unsafe { self.write(token, msg) });
+            }
+
+            // Prepare for waiting until a receiver wakes the task up.
+            let (oper, cx) = waiter.start(waker);
+            self.senders.register(oper, &cx);
+
+            // Has the channel become ready just now?
+            if self.is_full() && !self.is_disconnected() {
+                return Poll::Pending;
+            }
+        }
+    }
+
+    /// Withdraws a send operation registered by an asynchronous task.
+    pub(crate) fn cancel_send(&self, waiter: &mut Waiter) {
+        if let Some(oper) = waiter.finish() {
+            // If a receiver has already woken the task up, pass the notification on.
+            if self.senders.unregister(oper).is_none() {
+                self.senders.notify();
+            }
+        }
+    }
+
+    /// Polls a receive operation on behalf of an asynchronous task.
+    pub(crate) fn poll_recv(
+        &self,
+        waiter: &mut Waiter,
+        waker: &task::Waker,
+    ) -> Poll<Result<T, ()>> {
+        let token = &mut Token::default();
+        loop {
+            // Withdraw the registration left over from the previous poll.
+            if let Some(oper) = waiter.finish() {
+                self.receivers.unregister(oper);
+            }
+
+            if self.start_recv(token) {
+                return Poll::Ready(unsafe { self.read(token) });
+            }
+
+            // Prepare for waiting until a sender wakes the task up.
+            let (oper, cx) = waiter.start(waker);
+            self.receivers.register(oper, &cx);
+
+            // Has the channel become ready just now?
+            if self.is_empty() && !self.is_disconnected() {
+                return Poll::Pending;
+            }
+        }
+    }
+
+    /// Withdraws a receive operation registered by an asynchronous task.
+    pub(crate) fn cancel_recv(&self, waiter: &mut Waiter) {
+        if let Some(oper) = waiter.finish() {
+            // If a sender has already woken the task up, pass the notification on.
+            if self.receivers.unregister(oper).is_none() {
+                self.receivers.notify();
+            }
+        }
+    }
+
     /// Returns the current number of messages inside the channel.
     pub(crate) fn len(&self) -> usize {
         loop {
@@ -476,6 +1148,11 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Returns `true` if the channel has an overflow policy.
+    pub(crate) fn has_overflow(&self) -> bool {
+        self.overflow.is_some()
+    }
+
     /// Returns the capacity of the channel.
     #[allow(clippy::unnecessary_wraps)] // This is intentional.
     pub(crate) fn capacity(&self) -> Option<usize> {
@@ -511,7 +1188,13 @@ impl<T> Channel<T> {
         //
         // Note: If the head changes just before we load the tail, that means there was a moment
         // when the channel was not empty, so it is safe to just return `false`.
-        (tail & !self.mark_bit) == head
+        if (tail & !self.mark_bit) == head {
+            return true;
+        }
+
+        // A slot reserved by a permit at the head blocks receivers, just like an empty channel.
+        let slot = unsafe { &*self.buffer.add(head & (self.mark_bit - 1)) };
+        slot.stamp.load(Ordering::SeqCst) == head | self.mark_bit
     }
 
     /// Returns `true` if the channel is full.
@@ -523,7 +1206,7 @@ impl<T> Channel<T> {
         //
         // Note: If the tail changes just before we load the head, that means there was a moment
         // when the channel was not full, so it is safe to just return `false`.
-        head.wrapping_add(self.one_lap) == tail & !self.mark_bit
+        (head & !self.mark_bit).wrapping_add(self.one_lap) == tail & !self.mark_bit
     }
 }
 
@@ -542,8 +1225,14 @@ impl<T> Drop for Channel<T> {
             };
 
             unsafe {
+                let slot = &mut *self.buffer.add(index);
+
+                // Skip slots left behind by dropped permits.
+                if *slot.stamp.get_mut() & self.mark_bit != 0 {
+                    continue;
+                }
+
                 let p = {
-                    let slot = &mut *self.buffer.add(index);
                     let msg = &mut *slot.msg.get();
                     msg.as_mut_ptr()
                 };
@@ -562,6 +1251,21 @@ impl<T> Drop for Channel<T> {
     }
 }
 
+/// Stores a value into the head of a channel when dropped.
+struct HeadGuard<'a> {
+    /// The head of the channel.
+    head: &'a AtomicUsize,
+
+    /// The value to store.
+    value: usize,
+}
+
+impl Drop for HeadGuard<'_> {
+    fn drop(&mut self) {
+        self.head.store(self.value, Ordering::SeqCst);
+    }
+}
+
 /// Receiver handle to a channel.
 pub(crate) struct Receiver<'a, T>(&'a Channel<T>);
 
@@ -627,7 +1331,7 @@ impl<T> SelectHandle for Sender<'_, T> {
     }
 
     fn is_ready(&self) -> bool {
-        !self.0.is_full() || self.0.is_disconnected()
+        self.0.overflow.is_some() || !self.0.is_full() || self.0.is_disconnected()
     }
 
     fn watch(&self, oper: Operation, cx: &Context) -> bool {
@@ -1,15 +1,17 @@
 //! Channel that delivers a message at a certain moment in time.
 //!
-//! Messages cannot be sent into this kind of channel; they are materialized on demand.
+//! Messages cannot be sent into this kind of channel; they are materialized on demand. The moment
+//! of delivery can be moved or cancelled through a `Timer`, which wakes up blocked receivers.
 
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::thread;
 use std::time::{Duration, Instant};
 
+use crossbeam_utils::atomic::AtomicCell;
+
+use crate::clock;
 use crate::context::Context;
 use crate::err::{RecvTimeoutError, TryRecvError};
-use crate::select::{Operation, SelectHandle, Token};
-use crate::utils;
+use crate::select::{Operation, SelectHandle, Selected, Token};
+use crate::waker::SyncWaker;
 
 /// Result of a receive operation.
 pub(crate) type AtToken = Option<Instant>;
@@ -17,10 +19,12 @@ pub(crate) type AtToken = Option<Instant>;
 /// Channel that delivers a message at a certain moment in time
 pub(crate) struct Channel {
     /// The instant at which the message will be delivered.
-    delivery_time: Instant,
+    ///
+    /// This is `None` if the message has already been received or the delivery was cancelled.
+    delivery_time: AtomicCell<Option<Instant>>,
 
-    /// `true` if the message has been received.
-    received: AtomicBool,
+    /// Receivers waiting for the delivery time to change.
+    receivers: SyncWaker,
 }
 
 impl Channel {
@@ -28,76 +32,126 @@ impl Channel {
     #[inline]
     pub(crate) fn new_deadline(when: Instant) -> Self {
         Channel {
-            delivery_time: when,
-            received: AtomicBool::new(false),
+            delivery_time: AtomicCell::new(Some(when)),
+            receivers: SyncWaker::new(),
         }
     }
     /// Creates a channel that delivers a message after a certain duration of time.
     #[inline]
     pub(crate) fn new_timeout(dur: Duration) -> Self {
-        Self::new_deadline(Instant::now() + dur)
+        Self::new_deadline(clock::now() + dur)
+    }
+
+    /// Returns the instant at which the message will be delivered, if any.
+    #[inline]
+    pub(crate) fn delivery_time(&self) -> Option<Instant> {
+        self.delivery_time.load()
+    }
+
+    /// Sets the instant at which the message will be delivered, or cancels the delivery.
+    ///
+    /// Blocked receivers are woken up so that they wait for the new delivery time instead.
+    #[inline]
+    pub(crate) fn reset(&self, when: Option<Instant>) {
+        self.delivery_time.store(when);
+        self.receivers.notify_all();
     }
 
     /// Attempts to receive a message without blocking.
     #[inline]
     pub(crate) fn try_recv(&self) -> Result<Instant, TryRecvError> {
-        // We use relaxed ordering because this is just an optional optimistic check.
-        if self.received.load(Ordering::Relaxed) {
-            // The message has already been received.
-            return Err(TryRecvError::Empty);
+        loop {
+            let delivery_time = match self.delivery_time.load() {
+                // The message has already been received or was cancelled.
+                None => return Err(TryRecvError::Empty),
+                Some(t) => t,
+            };
+
+            if clock::now() < delivery_time {
+                // The message was not delivered yet.
+                return Err(TryRecvError::Empty);
+            }
+
+            // Try receiving the message if it is still available.
+            if self
+                .delivery_time
+                .compare_exchange(Some(delivery_time), None)
+                .is_ok()
+            {
+                // Success! Return delivery time as the message.
+                return Ok(delivery_time);
+            }
         }
+    }
 
-        if Instant::now() < self.delivery_time {
+    /// Attempts to receive the message without blocking if `pred` returns `true` for it.
+    ///
+    /// Returns `Ok(None)` if the message is left in the channel.
+    pub(crate) fn try_recv_if<F>(&self, pred: F) -> Result<Option<Instant>, TryRecvError>
+    where
+        F: FnOnce(&Instant) -> bool,
+    {
+        let delivery_time = match self.delivery_time.load() {
+            // The message has already been received or was cancelled.
+            None => return Err(TryRecvError::Empty),
+            Some(t) => t,
+        };
+
+        if clock::now() < delivery_time {
             // The message was not delivered yet.
             return Err(TryRecvError::Empty);
         }
 
-        // Try receiving the message if it is still available.
-        if !self.received.swap(true, Ordering::SeqCst) {
-            // Success! Return delivery time as the message.
-            Ok(self.delivery_time)
-        } else {
-            // The message was already received.
-            Err(TryRecvError::Empty)
+        if !pred(&delivery_time) {
+            return Ok(None);
+        }
+
+        // The message might have been received, cancelled, or rescheduled in the meantime.
+        match self
+            .delivery_time
+            .compare_exchange(Some(delivery_time), None)
+        {
+            Ok(_) => Ok(Some(delivery_time)),
+            Err(_) => Err(TryRecvError::Empty),
         }
     }
 
     /// Receives a message from the channel.
     #[inline]
     pub(crate) fn recv(&self, deadline: Option<Instant>) -> Result<Instant, RecvTimeoutError> {
-        // We use relaxed ordering because this is just an optional optimistic check.
-        if self.received.load(Ordering::Relaxed) {
-            // The message has already been received.
-            utils::sleep_until(deadline);
-            return Err(RecvTimeoutError::Timeout);
-        }
-
-        // Wait until the message is received or the deadline is reached.
+        let token = &mut Token::default();
         loop {
-            let now = Instant::now();
-
-            let deadline = match deadline {
-                // Check if we can receive the next message.
-                _ if now >= self.delivery_time => break,
-                // Check if the timeout deadline has been reached.
-                Some(d) if now >= d => return Err(RecvTimeoutError::Timeout),
+            if let Ok(msg) = self.try_recv() {
+                return Ok(msg);
+            }
 
-                // Sleep until one of the above happens
-                Some(d) if d < self.delivery_time => d,
-                _ => self.delivery_time,
-            };
+            if let Some(d) = deadline {
+                if clock::now() >= d {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+            }
 
-            thread::sleep(deadline - now);
-        }
-
-        // Try receiving the message if it is still available.
-        if !self.received.swap(true, Ordering::SeqCst) {
-            // Success! Return the message, which is the instant at which it was delivered.
-            Ok(self.delivery_time)
-        } else {
-            // The message was already received. Block forever.
-            utils::sleep_until(None);
-            unreachable!()
+            Context::with(|cx| {
+                // Prepare for blocking until the delivery time changes.
+                let oper = Operation::hook(token);
+                self.receivers.register(oper, cx);
+
+                // Block the current thread until the delivery time or the deadline, whichever
+                // comes first.
+                let until = match (self.delivery_time.load(), deadline) {
+                    (Some(t), Some(d)) => Some(t.min(d)),
+                    (t, d) => t.or(d),
+                };
+                let sel = cx.wait_until(until);
+
+                match sel {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected => {
+                        self.receivers.unregister(oper).unwrap();
+                    }
+                    Selected::Operation(_) => {}
+                }
+            });
         }
     }
 
@@ -110,19 +164,12 @@ impl Channel {
     /// Returns `true` if the channel is empty.
     #[inline]
     pub(crate) fn is_empty(&self) -> bool {
-        // We use relaxed ordering because this is just an optional optimistic check.
-        if self.received.load(Ordering::Relaxed) {
-            return true;
-        }
-
-        // If the delivery time hasn't been reached yet, the channel is empty.
-        if Instant::now() < self.delivery_time {
-            return true;
+        match self.delivery_time.load() {
+            // The message has already been received or was cancelled.
+            None => true,
+            // The channel is empty only if the delivery time hasn't been reached yet.
+            Some(t) => clock::now() < t,
         }
-
-        // The delivery time has been reached. The channel is empty only if the message has already
-        // been received.
-        self.received.load(Ordering::SeqCst)
     }
 
     /// Returns `true` if the channel is full.
@@ -167,21 +214,19 @@ impl SelectHandle for Channel {
 
     #[inline]
     fn deadline(&self) -> Option<Instant> {
-        // We use relaxed ordering because this is just an optional optimistic check.
-        if self.received.load(Ordering::Relaxed) {
-            None
-        } else {
-            Some(self.delivery_time)
-        }
+        self.delivery_time.load()
     }
 
     #[inline]
-    fn register(&self, _oper: Operation, _cx: &Context) -> bool {
+    fn register(&self, oper: Operation, cx: &Context) -> bool {
+        self.receivers.register(oper, cx);
         self.is_ready()
     }
 
     #[inline]
-    fn unregister(&self, _oper: Operation) {}
+    fn unregister(&self, oper: Operation) {
+        self.receivers.unregister(oper);
+    }
 
     #[inline]
     fn accept(&self, token: &mut Token, _cx: &Context) -> bool {
@@ -194,10 +239,13 @@ impl SelectHandle for Channel {
     }
 
     #[inline]
-    fn watch(&self, _oper: Operation, _cx: &Context) -> bool {
+    fn watch(&self, oper: Operation, cx: &Context) -> bool {
+        self.receivers.watch(oper, cx);
         self.is_ready()
     }
 
     #[inline]
-    fn unwatch(&self, _oper: Operation) {}
+    fn unwatch(&self, oper: Operation) {
+        self.receivers.unwatch(oper);
+    }
 }
@@ -0,0 +1,599 @@
+//! Broadcast channel.
+//!
+//! Every message sent into the channel is delivered to every receiver. The channel keeps a ring of
+//! the most recent messages, and each receiver keeps its own position in the ring. Senders never
+//! block: when the ring is full, the oldest message is evicted, and receivers that haven't read it
+//! yet are lagging behind.
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::task::{self, Poll};
+use std::time::Instant;
+
+use crate::channel::Lagging;
+use crate::clock;
+use crate::context::Context;
+use crate::err::{RecvTimeoutError, SendTimeoutError, TryRecvError, TrySendError};
+use crate::select::{Operation, SelectHandle, Selected, Token};
+use crate::utils::Spinlock;
+use crate::waker::{Waiter, Waker};
+
+/// Sequence number of the claimed message, or `None` if the receiver is disconnected.
+pub(crate) type BroadcastToken = Option<usize>;
+
+/// Position of a receiver in the channel.
+///
+/// Every receiver handle owns a cursor of its own. The fields are only modified while the channel
+/// is locked.
+pub(crate) struct Cursor {
+    /// Sequence number of the next message to be received.
+    pos: AtomicUsize,
+
+    /// Equals `true` once the receiver has been disconnected for lagging behind.
+    lagged: AtomicBool,
+}
+
+impl Cursor {
+    /// Creates a cursor pointing at the first message ever sent into the channel.
+    pub(crate) fn new() -> Cursor {
+        Cursor {
+            pos: AtomicUsize::new(0),
+            lagged: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Inner representation of a broadcast channel.
+struct Inner<T> {
+    /// The most recent messages.
+    buffer: VecDeque<T>,
+
+    /// Sequence number of the oldest message in the buffer.
+    head: usize,
+
+    /// Receivers waiting for the next message.
+    receivers: Waker,
+
+    /// Equals `true` when the channel is disconnected.
+    is_disconnected: bool,
+}
+
+impl<T> Inner<T> {
+    /// Returns the sequence number of the next message to be sent.
+    fn tail(&self) -> usize {
+        self.head.wrapping_add(self.buffer.len())
+    }
+}
+
+/// Broadcast channel.
+pub(crate) struct Channel<T> {
+    /// Inner representation of the channel.
+    inner: Spinlock<Inner<T>>,
+
+    /// Capacity of the channel.
+    cap: usize,
+
+    /// What happens to receivers that fall more than `cap` messages behind.
+    lagging: Lagging,
+
+    /// Clones a message for a receiver.
+    clone: fn(&T) -> T,
+}
+
+impl<T> Channel<T> {
+    /// Creates a broadcast channel of capacity `cap`.
+    pub(crate) fn with_capacity(cap: usize, lagging: Lagging) -> Self
+    where
+        T: Clone,
+    {
+        assert!(cap > 0, "capacity must be positive");
+
+        Channel {
+            inner: Spinlock::new(Inner {
+                buffer: VecDeque::with_capacity(cap),
+                head: 0,
+                receivers: Waker::new(),
+                is_disconnected: false,
+            }),
+            cap,
+            lagging,
+            clone: T::clone,
+        }
+    }
+
+    /// Returns a receiver handle to the channel.
+    pub(crate) fn receiver<'a>(&'a self, cursor: &'a Cursor) -> Receiver<'a, T> {
+        Receiver { chan: self, cursor }
+    }
+
+    /// Returns a sender handle to the channel.
+    pub(crate) fn sender(&self) -> Sender<'_, T> {
+        Sender(PhantomData)
+    }
+
+    /// Creates a cursor at the same position as `cursor`.
+    pub(crate) fn fork(&self, cursor: &Cursor) -> Cursor {
+        let _inner = self.inner.lock();
+        Cursor {
+            pos: AtomicUsize::new(cursor.pos.load(Ordering::Relaxed)),
+            lagged: AtomicBool::new(cursor.lagged.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Creates a cursor pointing at the next message to be sent.
+    pub(crate) fn subscribe(&self) -> Cursor {
+        let inner = self.inner.lock();
+        Cursor {
+            pos: AtomicUsize::new(inner.tail()),
+            lagged: AtomicBool::new(false),
+        }
+    }
+
+    /// Attempts to claim the next message for the receiver owning `cursor`.
+    ///
+    /// Returns `false` if the receiver has already received all sent messages.
+    fn claim(&self, inner: &Inner<T>, cursor: &Cursor, token: &mut Token) -> bool {
+        if cursor.lagged.load(Ordering::Relaxed) {
+            token.broadcast = None;
+            return true;
+        }
+
+        let mut pos = cursor.pos.load(Ordering::Relaxed);
+        let tail = inner.tail();
+
+        // Has the receiver missed messages that were evicted from the buffer?
+        if tail.wrapping_sub(pos) > inner.buffer.len() {
+            match self.lagging {
+                Lagging::SkipAhead => pos = inner.head,
+                Lagging::Disconnect => {
+                    cursor.lagged.store(true, Ordering::Relaxed);
+                    token.broadcast = None;
+                    return true;
+                }
+            }
+        }
+
+        if pos != tail {
+            cursor.pos.store(pos.wrapping_add(1), Ordering::Relaxed);
+            token.broadcast = Some(pos);
+            true
+        } else if inner.is_disconnected {
+            token.broadcast = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Reads the claimed message while the channel is locked.
+    fn read_locked(&self, inner: &Inner<T>, cursor: &Cursor, token: &mut Token) -> Result<T, ()> {
+        let seq = match token.broadcast {
+            None => return Err(()),
+            Some(seq) => seq,
+        };
+
+        match inner.buffer.get(seq.wrapping_sub(inner.head)) {
+            Some(msg) => Ok((self.clone)(msg)),
+            None => {
+                // The message was evicted after it had been claimed, so the receiver is lagging
+                // behind. Claim again starting from the evicted message.
+                cursor.pos.store(seq, Ordering::Relaxed);
+                let claimed = self.claim(inner, cursor, token);
+                debug_assert!(claimed);
+                self.read_locked(inner, cursor, token)
+            }
+        }
+    }
+
+    /// Attempts to claim the next message for the receiver owning `cursor`.
+    fn start_recv(&self, cursor: &Cursor, token: &mut Token) -> bool {
+        let inner = self.inner.lock();
+        self.claim(&inner, cursor, token)
+    }
+
+    /// Reads the message claimed for the receiver owning `cursor`.
+    pub(crate) unsafe fn read(&self, cursor: &Cursor, token: &mut Token) -> Result<T, ()> {
+        let inner = self.inner.lock();
+        self.read_locked(&inner, cursor, token)
+    }
+
+    /// Writes a message into the channel.
+    pub(crate) unsafe fn write(&self, _token: &mut Token, msg: T) -> Result<(), T> {
+        let mut inner = self.inner.lock();
+
+        if inner.is_disconnected {
+            return Err(msg);
+        }
+
+        // If the buffer is full, evict the oldest message.
+        let evicted = if inner.buffer.len() == self.cap {
+            inner.head = inner.head.wrapping_add(1);
+            inner.buffer.pop_front()
+        } else {
+            None
+        };
+
+        inner.buffer.push_back(msg);
+        inner.receivers.notify_all();
+        drop(inner);
+
+        // Drop the evicted message after unlocking the channel.
+        drop(evicted);
+        Ok(())
+    }
+
+    /// Attempts to send a message into the channel.
+    pub(crate) fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
+        let token = &mut Token::default();
+        unsafe { self.write(token, msg).map_err(TrySendError::Disconnected) }
+    }
+
+    /// Sends messages from the front of `msgs` into the channel.
+    ///
+    /// All messages are sent while the channel is locked once, and blocked receivers are woken up
+    /// after the last one. Returns the number of sent messages.
+    pub(crate) fn try_send_many(&self, msgs: &mut VecDeque<T>) -> Result<usize, TrySendError<()>> {
+        let mut inner = self.inner.lock();
+
+        if inner.is_disconnected {
+            return Err(TrySendError::Disconnected(()));
+        }
+
+        // Evict the oldest messages to make room for the new ones.
+        let count = msgs.len();
+        let room = self.cap - inner.buffer.len();
+        let evict = cmp::min(count.saturating_sub(room), inner.buffer.len());
+        let evicted = inner.buffer.drain(..evict).collect::<Vec<_>>();
+        inner.head = inner.head.wrapping_add(evict);
+
+        // If there are more messages than the capacity, the oldest of them are evicted right away.
+        let skip = count.saturating_sub(self.cap);
+        let skipped = msgs.drain(..skip).collect::<Vec<_>>();
+        inner.head = inner.head.wrapping_add(skip);
+
+        inner.buffer.extend(msgs.drain(..));
+        inner.receivers.notify_all();
+        drop(inner);
+
+        // Drop the evicted messages after unlocking the channel.
+        drop(evicted);
+        drop(skipped);
+        Ok(count)
+    }
+
+    /// Sends a message into the channel.
+    ///
+    /// Broadcast channels never block senders, so the deadline is ignored.
+    pub(crate) fn send(
+        &self,
+        msg: T,
+        _deadline: Option<Instant>,
+    ) -> Result<(), SendTimeoutError<T>> {
+        let token = &mut Token::default();
+        unsafe {
+            self.write(token, msg)
+                .map_err(SendTimeoutError::Disconnected)
+        }
+    }
+
+    /// Attempts to receive a message without blocking.
+    pub(crate) fn try_recv(&self, cursor: &Cursor) -> Result<T, TryRecvError> {
+        let token = &mut Token::default();
+        let inner = self.inner.lock();
+
+        if self.claim(&inner, cursor, token) {
+            self.read_locked(&inner, cursor, token)
+                .map_err(|_| TryRecvError::Disconnected)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    /// Attempts to receive the next message for the receiver owning `cursor` if `pred` returns
+    /// `true` for it.
+    ///
+    /// The message is inspected while the channel is locked. Returns `Ok(None)` if the message is
+    /// left in the channel.
+    pub(crate) fn try_recv_if<F>(&self, cursor: &Cursor, pred: F) -> Result<Option<T>, TryRecvError>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        let token = &mut Token::default();
+        let inner = self.inner.lock();
+
+        if !self.claim(&inner, cursor, token) {
+            return Err(TryRecvError::Empty);
+        }
+        let seq = match token.broadcast {
+            None => return Err(TryRecvError::Disconnected),
+            Some(seq) => seq,
+        };
+
+        // Keep the receiver at the message until it is accepted, even if `pred` panics.
+        cursor.pos.store(seq, Ordering::Relaxed);
+        let msg = &inner.buffer[seq.wrapping_sub(inner.head)];
+        if !pred(msg) {
+            return Ok(None);
+        }
+
+        cursor.pos.store(seq.wrapping_add(1), Ordering::Relaxed);
+        Ok(Some((self.clone)(msg)))
+    }
+
+    /// Attempts to receive up to `max` messages without blocking.
+    ///
+    /// All messages are received while the channel is locked once. Returns the number of messages
+    /// pushed into `buf`.
+    pub(crate) fn try_recv_many(
+        &self,
+        cursor: &Cursor,
+        buf: &mut Vec<T>,
+        max: usize,
+    ) -> Result<usize, TryRecvError> {
+        let token = &mut Token::default();
+        let inner = self.inner.lock();
+        let mut count = 0;
+
+        while count < max {
+            if !self.claim(&inner, cursor, token) {
+                break;
+            }
+
+            match self.read_locked(&inner, cursor, token) {
+                Ok(msg) => buf.push(msg),
+                Err(()) if count == 0 => return Err(TryRecvError::Disconnected),
+                Err(()) => break,
+            }
+            count += 1;
+        }
+
+        if count == 0 {
+            Err(TryRecvError::Empty)
+        } else {
+            Ok(count)
+        }
+    }
+
+    /// Receives a message from the channel.
+    pub(crate) fn recv(
+        &self,
+        cursor: &Cursor,
+        deadline: Option<Instant>,
+    ) -> Result<T, RecvTimeoutError> {
+        let token = &mut Token::default();
+        loop {
+            let mut inner = self.inner.lock();
+
+            if self.claim(&inner, cursor, token) {
+                return self
+                    .read_locked(&inner, cursor, token)
+                    .map_err(|_| RecvTimeoutError::Disconnected);
+            }
+
+            if let Some(d) = deadline {
+                if clock::now() >= d {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+            }
+
+            Context::with(|cx| {
+                // Prepare for blocking until a sender wakes us up.
+                let oper = Operation::hook(token);
+                inner.receivers.register(oper, cx);
+                drop(inner);
+
+                // Block the current thread.
+                let sel = cx.wait_until(deadline);
+
+                match sel {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected => {
+                        self.inner.lock().receivers.unregister(oper).unwrap();
+                    }
+                    Selected::Operation(_) => {}
+                }
+            });
+        }
+    }
+
+    /// Polls a receive operation on behalf of an asynchronous task.
+    pub(crate) fn poll_recv(
+        &self,
+        cursor: &Cursor,
+        waiter: &mut Waiter,
+        waker: &task::Waker,
+    ) -> Poll<Result<T, ()>> {
+        let token = &mut Token::default();
+        let mut inner = self.inner.lock();
+
+        // Withdraw the registration left over from the previous poll.
+        if let Some(oper) = waiter.finish() {
+            inner.receivers.unregister(oper);
+        }
+
+        if self.claim(&inner, cursor, token) {
+            return Poll::Ready(self.read_locked(&inner, cursor, token));
+        }
+
+        // Prepare for waiting until a sender wakes the task up.
+        let (oper, cx) = waiter.start(waker);
+        inner.receivers.register(oper, &cx);
+        Poll::Pending
+    }
+
+    /// Withdraws a receive operation registered by an asynchronous task.
+    ///
+    /// Every sent message wakes up all receivers, so there is no notification to pass on.
+    pub(crate) fn cancel_recv(&self, waiter: &mut Waiter) {
+        if let Some(oper) = waiter.finish() {
+            self.inner.lock().receivers.unregister(oper);
+        }
+    }
+
+    /// Disconnects the channel and wakes up all blocked receivers.
+    ///
+    /// Returns `true` if this call disconnected the channel.
+    pub(crate) fn disconnect(&self) -> bool {
+        let mut inner = self.inner.lock();
+
+        if !inner.is_disconnected {
+            inner.is_disconnected = true;
+            inner.receivers.disconnect();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns `true` if the channel is disconnected.
+    pub(crate) fn is_disconnected(&self) -> bool {
+        self.inner.lock().is_disconnected
+    }
+
+    /// Returns the number of messages retained in the channel.
+    pub(crate) fn len(&self) -> usize {
+        self.inner.lock().buffer.len()
+    }
+
+    /// Returns the number of messages the receiver owning `cursor` hasn't received yet.
+    pub(crate) fn pending(&self, cursor: &Cursor) -> usize {
+        let inner = self.inner.lock();
+
+        if cursor.lagged.load(Ordering::Relaxed) {
+            0
+        } else {
+            let pending = inner
+                .tail()
+                .wrapping_sub(cursor.pos.load(Ordering::Relaxed));
+            if pending > inner.buffer.len() {
+                match self.lagging {
+                    Lagging::SkipAhead => inner.buffer.len(),
+                    Lagging::Disconnect => 0,
+                }
+            } else {
+                pending
+            }
+        }
+    }
+
+    /// Returns the capacity of the channel.
+    #[allow(clippy::unnecessary_wraps)] // This is intentional.
+    pub(crate) fn capacity(&self) -> Option<usize> {
+        Some(self.cap)
+    }
+
+    /// Returns `true` if the channel is empty.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the channel is full.
+    pub(crate) fn is_full(&self) -> bool {
+        self.len() == self.cap
+    }
+
+    /// Returns `true` if the receiver owning `cursor` is disconnected for lagging behind.
+    pub(crate) fn is_lagged(&self, cursor: &Cursor) -> bool {
+        if cursor.lagged.load(Ordering::Relaxed) {
+            return true;
+        }
+
+        // The flag is only set by the next receive operation, so check for missed messages too.
+        let inner = self.inner.lock();
+        self.lagging == Lagging::Disconnect
+            && inner
+                .tail()
+                .wrapping_sub(cursor.pos.load(Ordering::Relaxed))
+                > inner.buffer.len()
+    }
+
+    /// Returns `true` if the receiver owning `cursor` is ready to receive or be disconnected.
+    fn is_ready(&self, cursor: &Cursor) -> bool {
+        let inner = self.inner.lock();
+        cursor.lagged.load(Ordering::Relaxed)
+            || cursor.pos.load(Ordering::Relaxed) != inner.tail()
+            || inner.is_disconnected
+    }
+}
+
+/// Receiver handle to a channel.
+pub(crate) struct Receiver<'a, T> {
+    /// The channel.
+    chan: &'a Channel<T>,
+
+    /// Position of the receiver.
+    cursor: &'a Cursor,
+}
+
+/// Sender handle to a channel.
+///
+/// Send operations never block, so the handle doesn't need to access the channel.
+pub(crate) struct Sender<'a, T>(PhantomData<&'a Channel<T>>);
+
+impl<T> SelectHandle for Receiver<'_, T> {
+    fn try_select(&self, token: &mut Token) -> bool {
+        self.chan.start_recv(self.cursor, token)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, oper: Operation, cx: &Context) -> bool {
+        self.chan.inner.lock().receivers.register(oper, cx);
+        self.is_ready()
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.chan.inner.lock().receivers.unregister(oper);
+    }
+
+    fn accept(&self, token: &mut Token, _cx: &Context) -> bool {
+        self.try_select(token)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.chan.is_ready(self.cursor)
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) -> bool {
+        self.chan.inner.lock().receivers.watch(oper, cx);
+        self.is_ready()
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.chan.inner.lock().receivers.unwatch(oper);
+    }
+}
+
+impl<T> SelectHandle for Sender<'_, T> {
+    fn try_select(&self, _token: &mut Token) -> bool {
+        true
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, _oper: Operation, _cx: &Context) -> bool {
+        self.is_ready()
+    }
+
+    fn unregister(&self, _oper: Operation) {}
+
+    fn accept(&self, token: &mut Token, _cx: &Context) -> bool {
+        self.try_select(token)
+    }
+
+    fn is_ready(&self) -> bool {
+        true
+    }
+
+    fn watch(&self, _oper: Operation, _cx: &Context) -> bool {
+        self.is_ready()
+    }
+
+    fn unwatch(&self, _oper: Operation) {}
+}
@@ -0,0 +1,143 @@
+//! Channel that drops messages of another receiver that don't match a predicate.
+//!
+//! The predicate has to run before an operation can be selected, so selected operations take the
+//! matching message out of the source and leave it for `read` to pick up.
+
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::channel::Receiver;
+use crate::context::Context;
+use crate::err::{RecvTimeoutError, TryRecvError};
+use crate::select::{Operation, SelectHandle, Token};
+use crate::utils::Spinlock;
+
+/// Channel that drops messages of another receiver that don't match a predicate.
+pub(crate) struct Channel<T> {
+    /// The receiver messages are taken from.
+    source: Receiver<T>,
+
+    /// The predicate messages have to match.
+    pred: Box<dyn Fn(&T) -> bool + Send + Sync>,
+
+    /// Messages taken out of the source by selected operations that haven't been read yet.
+    selected: Spinlock<VecDeque<T>>,
+}
+
+impl<T> Channel<T> {
+    /// Creates a channel that receives messages from `source` matching `pred`.
+    pub(crate) fn new(source: Receiver<T>, pred: Box<dyn Fn(&T) -> bool + Send + Sync>) -> Self {
+        Channel {
+            source,
+            pred,
+            selected: Spinlock::new(VecDeque::new()),
+        }
+    }
+
+    /// Attempts to receive a message without blocking.
+    ///
+    /// Messages that don't match the predicate are dropped along the way.
+    pub(crate) fn try_recv(&self) -> Result<T, TryRecvError> {
+        loop {
+            let msg = self.source.try_recv()?;
+            if (self.pred)(&msg) {
+                return Ok(msg);
+            }
+        }
+    }
+
+    /// Receives a message from the channel.
+    pub(crate) fn recv(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        loop {
+            let msg = match deadline {
+                None => self
+                    .source
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected)?,
+                Some(deadline) => self.source.recv_deadline(deadline)?,
+            };
+            if (self.pred)(&msg) {
+                return Ok(msg);
+            }
+        }
+    }
+
+    /// Attempts to receive a message without blocking if `pred` returns `true` for it.
+    ///
+    /// The predicate is passed as a trait object for the same reason as in merged channels: the
+    /// source can be a filtered receiver itself.
+    pub(crate) fn try_recv_if(
+        &self,
+        pred: &mut dyn FnMut(&T) -> bool,
+    ) -> Result<Option<T>, TryRecvError> {
+        loop {
+            // Messages that don't match the filter are received and dropped, while messages that
+            // do are left to `pred`.
+            let mut rejected = false;
+            let res = self.source.try_recv_if(|msg| {
+                if (self.pred)(msg) {
+                    pred(msg)
+                } else {
+                    rejected = true;
+                    true
+                }
+            })?;
+            if !rejected {
+                return Ok(res);
+            }
+        }
+    }
+
+    /// Reads a message taken out of the source by `try_select`.
+    pub(crate) unsafe fn read(&self, _token: &mut Token) -> Result<T, ()> {
+        self.selected.lock().pop_front().ok_or(())
+    }
+
+    /// Returns the source receiver.
+    pub(crate) fn source(&self) -> &Receiver<T> {
+        &self.source
+    }
+}
+
+impl<T> SelectHandle for Channel<T> {
+    fn try_select(&self, _token: &mut Token) -> bool {
+        match self.try_recv() {
+            Ok(msg) => {
+                self.selected.lock().push_back(msg);
+                true
+            }
+            Err(TryRecvError::Disconnected) => true,
+            Err(TryRecvError::Empty) => false,
+        }
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        SelectHandle::deadline(&self.source)
+    }
+
+    fn register(&self, oper: Operation, cx: &Context) -> bool {
+        // A message the source hands over might not match, so wait for the source to become ready
+        // and let `accept` check its messages.
+        self.watch(oper, cx)
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.unwatch(oper);
+    }
+
+    fn accept(&self, token: &mut Token, _cx: &Context) -> bool {
+        self.try_select(token)
+    }
+
+    fn is_ready(&self) -> bool {
+        SelectHandle::is_ready(&self.source)
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) -> bool {
+        SelectHandle::watch(&self.source, oper, cx)
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        SelectHandle::unwatch(&self.source, oper);
+    }
+}
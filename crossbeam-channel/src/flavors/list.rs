@@ -1,18 +1,23 @@
 //! Unbounded channel implemented as a linked list.
 
 use std::cell::UnsafeCell;
+use std::cmp;
+use std::collections::VecDeque;
 use std::marker::PhantomData;
-use std::mem::MaybeUninit;
+use std::mem::{self, MaybeUninit};
 use std::ptr;
 use std::sync::atomic::{self, AtomicPtr, AtomicUsize, Ordering};
-use std::time::Instant;
+use std::task::{self, Poll};
+use std::time::{Duration, Instant};
 
 use crossbeam_utils::{Backoff, CachePadded};
 
+use crate::channel::Expiry;
+use crate::clock;
 use crate::context::Context;
 use crate::err::{RecvTimeoutError, SendTimeoutError, TryRecvError, TrySendError};
 use crate::select::{Operation, SelectHandle, Selected, Token};
-use crate::waker::SyncWaker;
+use crate::waker::{SyncWaker, Waiter};
 
 // TODO(stjepang): Once we bump the minimum required Rust version to 1.28 or newer, re-apply the
 // following changes by @kleimkuhler:
@@ -33,17 +38,22 @@ const LAP: usize = 32;
 // The maximum number of messages a block can hold.
 const BLOCK_CAP: usize = LAP - 1;
 // How many lower bits are reserved for metadata.
-const SHIFT: usize = 1;
+const SHIFT: usize = 2;
 // Has two different purposes:
 // * If set in head, indicates that the block is not the last one.
 // * If set in tail, indicates that the channel is disconnected.
 const MARK_BIT: usize = 1;
+// If set in head, indicates that a receiver is inspecting the message at the head in place.
+const LOCK_BIT: usize = 2;
 
 /// A slot in a block.
 struct Slot<T> {
     /// The message.
     msg: UnsafeCell<MaybeUninit<T>>,
 
+    /// The instant at which the message expires, if it does.
+    deadline: UnsafeCell<MaybeUninit<Option<Instant>>>,
+
     /// The state of the slot.
     state: AtomicUsize,
 }
@@ -75,8 +85,8 @@ impl<T> Block<T> {
         // SAFETY: This is safe because:
         //  [1] `Block::next` (AtomicPtr) may be safely zero initialized.
         //  [2] `Block::slots` (Array) may be safely zero initialized because of [3, 4].
-        //  [3] `Slot::msg` (UnsafeCell) may be safely zero initialized because it
-        //       holds a MaybeUninit.
+        //  [3] `Slot::msg` and `Slot::deadline` (UnsafeCell) may be safely zero initialized
+        //       because they hold a MaybeUninit.
         //  [4] `Slot::state` (AtomicUsize) may be safely zero initialized.
         unsafe { MaybeUninit::zeroed().assume_init() }
     }
@@ -161,6 +171,9 @@ pub(crate) struct Channel<T> {
     /// Receivers waiting while the channel is empty and not disconnected.
     receivers: SyncWaker,
 
+    /// The expiry policy for messages.
+    expiry: Expiry<T>,
+
     /// Indicates that dropping a `Channel<T>` may drop messages of type `T`.
     _marker: PhantomData<T>,
 }
@@ -178,10 +191,18 @@ impl<T> Channel<T> {
                 index: AtomicUsize::new(0),
             }),
             receivers: SyncWaker::new(),
+            expiry: Expiry::new(),
             _marker: PhantomData,
         }
     }
 
+    /// Creates a new unbounded channel whose messages expire according to `expiry`.
+    pub(crate) fn with_expiry(expiry: Expiry<T>) -> Self {
+        let mut chan = Channel::new();
+        chan.expiry = expiry;
+        chan
+    }
+
     /// Returns a receiver handle to the channel.
     pub(crate) fn receiver(&self) -> Receiver<'_, T> {
         Receiver(self)
@@ -194,6 +215,17 @@ impl<T> Channel<T> {
 
     /// Attempts to reserve a slot for sending a message.
     fn start_send(&self, token: &mut Token) -> bool {
+        self.reserve(token, 1);
+        true
+    }
+
+    /// Reserves up to `max` consecutive slots in the tail block for sending messages.
+    ///
+    /// The first reserved slot is stored into the token. Returns the number of reserved slots, or
+    /// zero if the channel is disconnected.
+    fn reserve(&self, token: &mut Token, max: usize) -> usize {
+        debug_assert!(max > 0);
+
         let backoff = Backoff::new();
         let mut tail = self.tail.index.load(Ordering::Acquire);
         let mut block = self.tail.block.load(Ordering::Acquire);
@@ -203,7 +235,7 @@ impl<T> Channel<T> {
             // Check if the channel is disconnected.
             if tail & MARK_BIT != 0 {
                 token.list.block = ptr::null();
-                return true;
+                return 0;
             }
 
             // Calculate the offset of the index into the block.
@@ -217,9 +249,12 @@ impl<T> Channel<T> {
                 continue;
             }
 
+            // Slots can be reserved up to the end of the block.
+            let count = cmp::min(max, BLOCK_CAP - offset);
+
             // If we're going to have to install the next block, allocate it in advance in order to
             // make the wait for other threads as short as possible.
-            if offset + 1 == BLOCK_CAP && next_block.is_none() {
+            if offset + count == BLOCK_CAP && next_block.is_none() {
                 next_block = Some(Box::new(Block::<T>::new()));
             }
 
@@ -244,7 +279,7 @@ impl<T> Channel<T> {
                 }
             }
 
-            let new_tail = tail + (1 << SHIFT);
+            let new_tail = tail + (count << SHIFT);
 
             // Try advancing the tail forward.
             match self.tail.index.compare_exchange_weak(
@@ -255,7 +290,7 @@ impl<T> Channel<T> {
             ) {
                 Ok(_) => unsafe {
                     // If we've reached the end of the block, install the next one.
-                    if offset + 1 == BLOCK_CAP {
+                    if offset + count == BLOCK_CAP {
                         let next_block = Box::into_raw(next_block.unwrap());
                         self.tail.block.store(next_block, Ordering::Release);
                         self.tail.index.fetch_add(1 << SHIFT, Ordering::Release);
@@ -264,7 +299,7 @@ impl<T> Channel<T> {
 
                     token.list.block = block as *const u8;
                     token.list.offset = offset;
-                    return true;
+                    return count;
                 },
                 Err(t) => {
                     tail = t;
@@ -277,6 +312,18 @@ impl<T> Channel<T> {
 
     /// Writes a message into the channel.
     pub(crate) unsafe fn write(&self, token: &mut Token, msg: T) -> Result<(), T> {
+        self.write_with_ttl(token, msg, None)
+    }
+
+    /// Writes a message with the given time-to-live into the channel.
+    ///
+    /// If `ttl` is `None`, the message gets the time-to-live of the channel's expiry policy.
+    unsafe fn write_with_ttl(
+        &self,
+        token: &mut Token,
+        msg: T,
+        ttl: Option<Duration>,
+    ) -> Result<(), T> {
         // If there is no slot, the channel is disconnected.
         if token.list.block.is_null() {
             return Err(msg);
@@ -287,6 +334,9 @@ impl<T> Channel<T> {
         let offset = token.list.offset;
         let slot = (*block).slots.get_unchecked(offset);
         slot.msg.get().write(MaybeUninit::new(msg));
+        slot.deadline
+            .get()
+            .write(MaybeUninit::new(self.expiry.deadline(ttl)));
         slot.state.fetch_or(WRITE, Ordering::Release);
 
         // Wake a sleeping receiver.
@@ -304,8 +354,9 @@ impl<T> Channel<T> {
             // Calculate the offset of the index into the block.
             let offset = (head >> SHIFT) % LAP;
 
-            // If we reached the end of the block, wait until the next one is installed.
-            if offset == BLOCK_CAP {
+            // If we reached the end of the block, wait until the next one is installed. If the head
+            // is locked by a peeking receiver, wait until it gets unlocked.
+            if offset == BLOCK_CAP || head & LOCK_BIT != 0 {
                 backoff.snooze();
                 head = self.head.index.load(Ordering::Acquire);
                 block = self.head.block.load(Ordering::Acquire);
@@ -368,7 +419,15 @@ impl<T> Channel<T> {
 
                     token.list.block = block as *const u8;
                     token.list.offset = offset;
-                    return true;
+                    if !self.is_expired(block, offset) {
+                        return true;
+                    }
+
+                    // The message has expired, so take it out and move on to the next one.
+                    let msg = self.read(token).ok().unwrap();
+                    self.expiry.expire(msg);
+                    head = self.head.index.load(Ordering::Acquire);
+                    block = self.head.block.load(Ordering::Acquire);
                 },
                 Err(h) => {
                     head = h;
@@ -404,24 +463,354 @@ impl<T> Channel<T> {
         Ok(msg)
     }
 
-    /// Attempts to send a message into the channel.
-    pub(crate) fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
-        self.send(msg, None).map_err(|err| match err {
+    /// Locks the head of the channel while it points at a message.
+    ///
+    /// Returns the value of the head index before locking and the head block. While the head is
+    /// locked, other receivers wait instead of claiming the slot, so the message can be inspected
+    /// in place.
+    fn lock_head(&self) -> Result<(usize, *mut Block<T>), TryRecvError> {
+        let backoff = Backoff::new();
+        let mut head = self.head.index.load(Ordering::Acquire);
+        let mut block = self.head.block.load(Ordering::Acquire);
+
+        loop {
+            let offset = (head >> SHIFT) % LAP;
+
+            if offset == BLOCK_CAP || head & LOCK_BIT != 0 {
+                backoff.snooze();
+                head = self.head.index.load(Ordering::Acquire);
+                block = self.head.block.load(Ordering::Acquire);
+                continue;
+            }
+
+            if head & MARK_BIT == 0 {
+                atomic::fence(Ordering::SeqCst);
+                let tail = self.tail.index.load(Ordering::Relaxed);
+
+                // If the tail equals the head, that means the channel is empty.
+                if head >> SHIFT == tail >> SHIFT {
+                    return if tail & MARK_BIT != 0 {
+                        Err(TryRecvError::Disconnected)
+                    } else {
+                        Err(TryRecvError::Empty)
+                    };
+                }
+            }
+
+            // Wait until the first block gets initialized.
+            if block.is_null() {
+                backoff.snooze();
+                head = self.head.index.load(Ordering::Acquire);
+                block = self.head.block.load(Ordering::Acquire);
+                continue;
+            }
+
+            // Try locking the head.
+            match self.head.index.compare_exchange_weak(
+                head,
+                head | LOCK_BIT,
+                Ordering::SeqCst,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => unsafe {
+                    let offset = (head >> SHIFT) % LAP;
+                    if !self.is_expired(block, offset) {
+                        return Ok((head, block));
+                    }
+
+                    // The message has expired, so unlock the head by moving it past the slot,
+                    // then take the message out.
+                    self.unlock_past(head, block);
+                    let token = &mut Token::default();
+                    token.list.block = block as *const u8;
+                    token.list.offset = offset;
+                    let msg = self.read(token).ok().unwrap();
+                    self.expiry.expire(msg);
+                    head = self.head.index.load(Ordering::Acquire);
+                    block = self.head.block.load(Ordering::Acquire);
+                },
+                Err(h) => {
+                    head = h;
+                    block = self.head.block.load(Ordering::Acquire);
+                    backoff.spin();
+                }
+            }
+        }
+    }
+
+    /// Attempts to receive the message at the head of the channel if `pred` returns `true` for it.
+    ///
+    /// Returns `Ok(None)` if the message is left in the channel.
+    pub(crate) fn try_recv_if<F>(&self, pred: F) -> Result<Option<T>, TryRecvError>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        let (head, block) = self.lock_head()?;
+        let offset = (head >> SHIFT) % LAP;
+
+        // Unlock the head when done, even if `pred` panics.
+        let guard = HeadGuard {
+            index: &self.head.index,
+            value: head,
+        };
+        let slot = unsafe { (*block).slots.get_unchecked(offset) };
+        slot.wait_write();
+        if !pred(unsafe { &*(*slot.msg.get()).as_ptr() }) {
+            return Ok(None);
+        }
+
+        // Unlock the head by moving it past the slot, then read the message.
+        mem::forget(guard);
+        unsafe {
+            self.unlock_past(head, block);
+            let token = &mut Token::default();
+            token.list.block = block as *const u8;
+            token.list.offset = offset;
+            Ok(self.read(token).ok())
+        }
+    }
+
+    /// Unlocks the head locked at `head` by moving it past the slot, the same way `start_recv`
+    /// does.
+    unsafe fn unlock_past(&self, head: usize, block: *mut Block<T>) {
+        let offset = (head >> SHIFT) % LAP;
+        let mut new_head = head + (1 << SHIFT);
+        if new_head & MARK_BIT == 0 {
+            atomic::fence(Ordering::SeqCst);
+            let tail = self.tail.index.load(Ordering::Relaxed);
+
+            // If head and tail are not in the same block, set `MARK_BIT` in head.
+            if (head >> SHIFT) / LAP != (tail >> SHIFT) / LAP {
+                new_head |= MARK_BIT;
+            }
+        }
+
+        // If we've reached the end of the block, move to the next one.
+        if offset + 1 == BLOCK_CAP {
+            let next = (*block).wait_next();
+            let mut next_index = (new_head & !MARK_BIT).wrapping_add(1 << SHIFT);
+            if !(*next).next.load(Ordering::Relaxed).is_null() {
+                next_index |= MARK_BIT;
+            }
+
+            self.head.block.store(next, Ordering::Release);
+            self.head.index.store(next_index, Ordering::Release);
+        } else {
+            self.head.index.store(new_head, Ordering::Release);
+        }
+    }
+
+    /// Returns `true` if the message in the slot at `offset` in `block` has expired.
+    ///
+    /// Waits until the message is written into the slot.
+    unsafe fn is_expired(&self, block: *mut Block<T>, offset: usize) -> bool {
+        let slot = (*block).slots.get_unchecked(offset);
+        slot.wait_write();
+        self.expiry
+            .is_expired(slot.deadline.get().read().assume_init())
+    }
+
+    /// Attempts to receive up to `max` messages without blocking.
+    ///
+    /// Returns the number of messages pushed into `buf`.
+    pub(crate) fn try_recv_many(
+        &self,
+        buf: &mut Vec<T>,
+        max: usize,
+    ) -> Result<usize, TryRecvError> {
+        let mut count = self.recv_block(buf, max)?;
+
+        // A single claim never crosses a block boundary, so continue with the next block.
+        while count < max {
+            match self.recv_block(buf, max - count) {
+                Ok(n) => count += n,
+                Err(_) => break,
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Attempts to receive up to `max` messages from the head block without blocking.
+    ///
+    /// The messages are claimed with a single update of the head. Returns the number of messages
+    /// pushed into `buf`.
+    fn recv_block(&self, buf: &mut Vec<T>, max: usize) -> Result<usize, TryRecvError> {
+        let token = &mut Token::default();
+        let backoff = Backoff::new();
+        let mut head = self.head.index.load(Ordering::Acquire);
+        let mut block = self.head.block.load(Ordering::Acquire);
+
+        loop {
+            // Calculate the offset of the index into the block.
+            let offset = (head >> SHIFT) % LAP;
+
+            // If we reached the end of the block, wait until the next one is installed. If the head
+            // is locked by a peeking receiver, wait until it gets unlocked.
+            if offset == BLOCK_CAP || head & LOCK_BIT != 0 {
+                backoff.snooze();
+                head = self.head.index.load(Ordering::Acquire);
+                block = self.head.block.load(Ordering::Acquire);
+                continue;
+            }
+
+            // Messages can be claimed up to the end of the block.
+            let mut count = cmp::min(max, BLOCK_CAP - offset);
+            let mut new_head = head;
+
+            if head & MARK_BIT == 0 {
+                atomic::fence(Ordering::SeqCst);
+                let tail = self.tail.index.load(Ordering::Relaxed);
+
+                // If the tail equals the head, that means the channel is empty.
+                if head >> SHIFT == tail >> SHIFT {
+                    if tail & MARK_BIT != 0 {
+                        return Err(TryRecvError::Disconnected);
+                    } else {
+                        return Err(TryRecvError::Empty);
+                    }
+                }
+
+                // If head and tail are not in the same block, set `MARK_BIT` in head. Otherwise,
+                // claim only the messages before the tail.
+                if (head >> SHIFT) / LAP != (tail >> SHIFT) / LAP {
+                    new_head |= MARK_BIT;
+                } else {
+                    count = cmp::min(count, (tail >> SHIFT) - (head >> SHIFT));
+                }
+            }
+
+            new_head += count << SHIFT;
+
+            // The block can be null here only if the first message is being sent into the channel.
+            // In that case, just wait until it gets initialized.
+            if block.is_null() {
+                backoff.snooze();
+                head = self.head.index.load(Ordering::Acquire);
+                block = self.head.block.load(Ordering::Acquire);
+                continue;
+            }
+
+            // Reserve space up front so that pushing into the buffer can't panic once the slots
+            // are claimed.
+            buf.reserve(count);
+
+            // Try moving the head index forward past all claimed messages.
+            match self.head.index.compare_exchange_weak(
+                head,
+                new_head,
+                Ordering::SeqCst,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => unsafe {
+                    // If we've reached the end of the block, move to the next one.
+                    if offset + count == BLOCK_CAP {
+                        let next = (*block).wait_next();
+                        let mut next_index = (new_head & !MARK_BIT).wrapping_add(1 << SHIFT);
+                        if !(*next).next.load(Ordering::Relaxed).is_null() {
+                            next_index |= MARK_BIT;
+                        }
+
+                        self.head.block.store(next, Ordering::Release);
+                        self.head.index.store(next_index, Ordering::Release);
+                    }
+
+                    // Read the messages one by one. Each read may continue destruction of the
+                    // block, but never before the slots following it are read. Expired messages
+                    // are handed over only once all claimed messages are read, so that a panicking
+                    // expiry handler can't leave claimed slots behind.
+                    let mut expired = Vec::new();
+                    token.list.block = block as *const u8;
+                    for i in 0..count {
+                        token.list.offset = offset + i;
+                        let is_expired = self.is_expired(block, offset + i);
+                        let msg = self.read(token).ok().unwrap();
+                        if is_expired {
+                            expired.push(msg);
+                        } else {
+                            buf.push(msg);
+                        }
+                    }
+
+                    let received = count - expired.len();
+                    for msg in expired {
+                        self.expiry.expire(msg);
+                    }
+
+                    // If all claimed messages have expired, try again.
+                    if received > 0 {
+                        return Ok(received);
+                    }
+                    head = self.head.index.load(Ordering::Acquire);
+                    block = self.head.block.load(Ordering::Acquire);
+                },
+                Err(h) => {
+                    head = h;
+                    block = self.head.block.load(Ordering::Acquire);
+                    backoff.spin();
+                }
+            }
+        }
+    }
+
+    /// Attempts to send a message with the given time-to-live into the channel.
+    pub(crate) fn try_send(&self, msg: T, ttl: Option<Duration>) -> Result<(), TrySendError<T>> {
+        self.send(msg, ttl, None).map_err(|err| match err {
             SendTimeoutError::Disconnected(msg) => TrySendError::Disconnected(msg),
             SendTimeoutError::Timeout(_) => unreachable!(),
         })
     }
 
-    /// Sends a message into the channel.
+    /// Sends messages from the front of `msgs` into the channel.
+    ///
+    /// Slots in the tail block are reserved for as many messages as possible at once, and blocked
+    /// receivers are notified once all messages are written. Returns the number of sent messages.
+    pub(crate) fn try_send_many(&self, msgs: &mut VecDeque<T>) -> Result<usize, TrySendError<()>> {
+        let token = &mut Token::default();
+        let mut count = 0;
+
+        while !msgs.is_empty() {
+            let reserved = self.reserve(token, msgs.len());
+            if reserved == 0 {
+                // The channel is disconnected.
+                if count == 0 {
+                    return Err(TrySendError::Disconnected(()));
+                }
+                break;
+            }
+
+            // Write the messages into the slots.
+            let block = token.list.block as *mut Block<T>;
+            let deadline = self.expiry.deadline(None);
+            for i in 0..reserved {
+                unsafe {
+                    let slot = (*block).slots.get_unchecked(token.list.offset + i);
+                    slot.msg
+                        .get()
+                        .write(MaybeUninit::new(msgs.pop_front().unwrap()));
+                    slot.deadline.get().write(MaybeUninit::new(deadline));
+                    slot.state.fetch_or(WRITE, Ordering::Release);
+                }
+            }
+            count += reserved;
+        }
+
+        // Wake sleeping receivers.
+        self.receivers.notify_many(count);
+        Ok(count)
+    }
+
+    /// Sends a message with the given time-to-live into the channel.
     pub(crate) fn send(
         &self,
         msg: T,
+        ttl: Option<Duration>,
         _deadline: Option<Instant>,
     ) -> Result<(), SendTimeoutError<T>> {
         let token = &mut Token::default();
         assert!(self.start_send(token));
         unsafe {
-            self.write(token, msg)
+            self.write_with_ttl(token, msg, ttl)
                 .map_err(SendTimeoutError::Disconnected)
         }
     }
@@ -458,7 +847,7 @@ impl<T> Channel<T> {
             }
 
             if let Some(d) = deadline {
-                if Instant::now() >= d {
+                if clock::now() >= d {
                     return Err(RecvTimeoutError::Timeout);
                 }
             }
@@ -489,6 +878,44 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Polls a receive operation on behalf of an asynchronous task.
+    pub(crate) fn poll_recv(
+        &self,
+        waiter: &mut Waiter,
+        waker: &task::Waker,
+    ) -> Poll<Result<T, ()>> {
+        let token = &mut Token::default();
+        loop {
+            // Withdraw the registration left over from the previous poll.
+            if let Some(oper) = waiter.finish() {
+                self.receivers.unregister(oper);
+            }
+
+            if self.start_recv(token) {
+                return Poll::Ready(unsafe { self.read(token) });
+            }
+
+            // Prepare for waiting until a sender wakes the task up.
+            let (oper, cx) = waiter.start(waker);
+            self.receivers.register(oper, &cx);
+
+            // Has the channel become ready just now?
+            if self.is_empty() && !self.is_disconnected() {
+                return Poll::Pending;
+            }
+        }
+    }
+
+    /// Withdraws a receive operation registered by an asynchronous task.
+    pub(crate) fn cancel_recv(&self, waiter: &mut Waiter) {
+        if let Some(oper) = waiter.finish() {
+            // If a sender has already woken the task up, pass the notification on.
+            if self.receivers.unregister(oper).is_none() {
+                self.receivers.notify();
+            }
+        }
+    }
+
     /// Returns the current number of messages inside the channel.
     pub(crate) fn len(&self) -> usize {
         loop {
@@ -670,6 +1097,21 @@ impl<T> Drop for Channel<T> {
     }
 }
 
+/// Stores a value into the head index of a channel when dropped.
+struct HeadGuard<'a> {
+    /// The head index of the channel.
+    index: &'a AtomicUsize,
+
+    /// The value to store.
+    value: usize,
+}
+
+impl Drop for HeadGuard<'_> {
+    fn drop(&mut self) {
+        self.index.store(self.value, Ordering::Release);
+    }
+}
+
 /// Receiver handle to a channel.
 pub(crate) struct Receiver<'a, T>(&'a Channel<T>);
 
@@ -0,0 +1,196 @@
+//! Channel that transforms messages of another receiver.
+//!
+//! The transform runs on each message as it's taken out of the source receiver. Messages that were
+//! transformed in order to be inspected but weren't received are buffered in front of the source.
+//!
+//! Like in filtered channels, selected operations take the message out of the channel and leave it
+//! for `read` to pick up.
+
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::channel::Receiver;
+use crate::context::Context;
+use crate::err::TryRecvError;
+use crate::select::{Operation, SelectHandle, Token};
+use crate::utils::Spinlock;
+use crate::waker::SyncWaker;
+#[cfg(feature = "metrics")]
+use crate::ChannelStats;
+
+/// Channel that transforms messages of type `T` into messages of type `U`.
+pub(crate) struct Channel<T, U> {
+    /// The receiver messages are taken from.
+    source: Receiver<T>,
+
+    /// The transform.
+    f: Box<dyn Fn(T) -> U + Send + Sync>,
+
+    /// Transformed messages that were inspected but not received, in front of the source.
+    peeked: Spinlock<VecDeque<U>>,
+
+    /// Messages taken out of the channel by selected operations that haven't been read yet.
+    selected: Spinlock<VecDeque<U>>,
+
+    /// Operations waiting for an inspected message to be put back.
+    waiters: SyncWaker,
+}
+
+impl<T, U> Channel<T, U> {
+    /// Creates a channel that transforms messages from `source` with `f`.
+    pub(crate) fn new(source: Receiver<T>, f: Box<dyn Fn(T) -> U + Send + Sync>) -> Self {
+        Channel {
+            source,
+            f,
+            peeked: Spinlock::new(VecDeque::new()),
+            selected: Spinlock::new(VecDeque::new()),
+            waiters: SyncWaker::new(),
+        }
+    }
+}
+
+/// Operations on a channel that transforms messages into type `U`.
+///
+/// The type of the source messages is erased so that the channel fits into a `Receiver<U>`.
+pub(crate) trait Mapped<U>: SelectHandle + Send + Sync {
+    /// Attempts to receive a message without blocking.
+    fn try_recv(&self) -> Result<U, TryRecvError>;
+
+    /// Attempts to receive a message without blocking if `pred` returns `true` for it.
+    ///
+    /// The message is transformed and taken out of the channel while `pred` runs, and put back in
+    /// front of the source if `pred` rejects it. The predicate is passed as a trait object because
+    /// the source type is erased.
+    fn try_recv_if(&self, pred: &mut dyn FnMut(&U) -> bool) -> Result<Option<U>, TryRecvError>;
+
+    /// Reads a message taken out of the channel by `try_select`.
+    unsafe fn read(&self, token: &mut Token) -> Result<U, ()>;
+
+    /// Returns the current number of messages inside the channel.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the channel is empty.
+    fn is_empty(&self) -> bool;
+
+    /// Returns `true` if the source is full.
+    fn is_full(&self) -> bool;
+
+    /// Returns the capacity of the source.
+    fn capacity(&self) -> Option<usize>;
+
+    /// Returns the number of senders connected to the source.
+    fn sender_count(&self) -> usize;
+
+    /// Closes the source.
+    fn close(&self) -> bool;
+
+    /// Returns `true` if the source is disconnected.
+    fn is_disconnected(&self) -> bool;
+
+    /// Returns a snapshot of the statistics of the source.
+    #[cfg(feature = "metrics")]
+    fn stats(&self) -> ChannelStats;
+}
+
+impl<T: Send, U: Send> Mapped<U> for Channel<T, U> {
+    fn try_recv(&self) -> Result<U, TryRecvError> {
+        if let Some(msg) = self.peeked.lock().pop_front() {
+            return Ok(msg);
+        }
+        self.source.try_recv().map(&self.f)
+    }
+
+    fn try_recv_if(&self, pred: &mut dyn FnMut(&U) -> bool) -> Result<Option<U>, TryRecvError> {
+        let msg = self.try_recv()?;
+        if pred(&msg) {
+            return Ok(Some(msg));
+        }
+
+        self.peeked.lock().push_front(msg);
+        self.waiters.notify();
+        Ok(None)
+    }
+
+    unsafe fn read(&self, _token: &mut Token) -> Result<U, ()> {
+        self.selected.lock().pop_front().ok_or(())
+    }
+
+    fn len(&self) -> usize {
+        self.peeked.lock().len() + self.source.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.peeked.lock().is_empty() && self.source.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.source.is_full()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.source.capacity()
+    }
+
+    fn sender_count(&self) -> usize {
+        self.source.sender_count()
+    }
+
+    fn close(&self) -> bool {
+        self.source.close()
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.source.is_closed()
+    }
+
+    #[cfg(feature = "metrics")]
+    fn stats(&self) -> ChannelStats {
+        self.source.stats()
+    }
+}
+
+impl<T: Send, U: Send> SelectHandle for Channel<T, U> {
+    fn try_select(&self, _token: &mut Token) -> bool {
+        match Mapped::try_recv(self) {
+            Ok(msg) => {
+                self.selected.lock().push_back(msg);
+                true
+            }
+            Err(TryRecvError::Disconnected) => true,
+            Err(TryRecvError::Empty) => false,
+        }
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        SelectHandle::deadline(&self.source)
+    }
+
+    fn register(&self, oper: Operation, cx: &Context) -> bool {
+        // The source doesn't know about inspected messages, so wait for either of them to become
+        // ready and let `accept` take the message.
+        self.watch(oper, cx)
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.unwatch(oper);
+    }
+
+    fn accept(&self, token: &mut Token, _cx: &Context) -> bool {
+        self.try_select(token)
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.peeked.lock().is_empty() || SelectHandle::is_ready(&self.source)
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) -> bool {
+        self.waiters.watch(oper, cx);
+        SelectHandle::watch(&self.source, oper, cx);
+        self.is_ready()
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.waiters.unwatch(oper);
+        SelectHandle::unwatch(&self.source, oper);
+    }
+}
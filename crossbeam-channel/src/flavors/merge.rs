@@ -0,0 +1,204 @@
+//! Channel that merges messages from several receivers.
+//!
+//! This flavor has no buffer of its own. Receive operations take messages directly from the
+//! inputs, and blocking operations wait on all inputs at once the same way [`Select`] does.
+//!
+//! [`Select`]: crate::Select
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use crate::channel::Receiver;
+use crate::context::Context;
+use crate::err::TryRecvError;
+use crate::select::{Operation, SelectHandle, Token};
+use crate::utils::Spinlock;
+
+/// Channel that merges messages from several receivers.
+pub(crate) struct Channel<T> {
+    /// The merged receivers.
+    inputs: Vec<Receiver<T>>,
+
+    /// The input to try first in the next receive operation.
+    ///
+    /// Rotating the first input prevents busy inputs from starving the others.
+    next: AtomicUsize,
+
+    /// Messages taken out of the inputs by selected operations that haven't been read yet.
+    ///
+    /// Every successful `try_select` that takes a message pushes it here and is followed by a
+    /// `read` that pops one, so a `read` following a `try_select` that took a message never finds
+    /// this queue empty.
+    selected: Spinlock<VecDeque<T>>,
+}
+
+impl<T> Channel<T> {
+    /// Creates a channel that merges messages from `inputs`.
+    pub(crate) fn new(inputs: Vec<Receiver<T>>) -> Self {
+        Channel {
+            inputs,
+            next: AtomicUsize::new(0),
+            selected: Spinlock::new(VecDeque::new()),
+        }
+    }
+
+    /// Attempts to receive a message without blocking.
+    ///
+    /// Returns `Disconnected` only if all inputs are disconnected and empty.
+    pub(crate) fn try_recv(&self) -> Result<T, TryRecvError> {
+        let len = self.inputs.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut disconnected = 0;
+
+        for i in 0..len {
+            match self.inputs[(start + i) % len].try_recv() {
+                Ok(msg) => return Ok(msg),
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => disconnected += 1,
+            }
+        }
+
+        if disconnected == len {
+            Err(TryRecvError::Disconnected)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    /// Attempts to receive a message without blocking if `pred` returns `true` for it.
+    ///
+    /// The predicate is called at most once. It is passed as a trait object because inputs can be
+    /// merged receivers themselves, and a generic predicate would be wrapped in a new closure type
+    /// at each level.
+    pub(crate) fn try_recv_if(
+        &self,
+        pred: &mut dyn FnMut(&T) -> bool,
+    ) -> Result<Option<T>, TryRecvError> {
+        let len = self.inputs.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut disconnected = 0;
+        for i in 0..len {
+            // Only the first input that has a message ready calls the predicate.
+            match self.inputs[(start + i) % len].try_recv_if(&mut *pred) {
+                Ok(msg) => return Ok(msg),
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => disconnected += 1,
+            }
+        }
+
+        if disconnected == len {
+            Err(TryRecvError::Disconnected)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    /// Reads a message taken out of the inputs by `try_select`.
+    pub(crate) unsafe fn read(&self, _token: &mut Token) -> Result<T, ()> {
+        self.selected.lock().pop_front().ok_or(())
+    }
+
+    /// Returns the current number of messages inside the inputs.
+    pub(crate) fn len(&self) -> usize {
+        self.inputs.iter().map(|r| r.len()).sum()
+    }
+
+    /// Returns the combined capacity of the inputs, or `None` if any of them is unbounded.
+    pub(crate) fn capacity(&self) -> Option<usize> {
+        self.inputs.iter().map(|r| r.capacity()).sum()
+    }
+
+    /// Returns `true` if all inputs are empty.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.inputs.iter().all(|r| r.is_empty())
+    }
+
+    /// Returns `true` if all inputs are full.
+    pub(crate) fn is_full(&self) -> bool {
+        self.inputs.iter().all(|r| r.is_full())
+    }
+
+    /// Returns the total number of senders connected to the inputs.
+    pub(crate) fn sender_count(&self) -> usize {
+        self.inputs.iter().map(|r| r.sender_count()).sum()
+    }
+
+    /// Returns `true` if all inputs are disconnected.
+    pub(crate) fn is_disconnected(&self) -> bool {
+        self.inputs.iter().all(|r| r.is_closed())
+    }
+
+    /// Closes all inputs.
+    ///
+    /// Returns `true` if this call closed any of them.
+    pub(crate) fn close(&self) -> bool {
+        let mut closed = false;
+        for r in &self.inputs {
+            if r.close() {
+                closed = true;
+            }
+        }
+        closed
+    }
+}
+
+impl<T> SelectHandle for Channel<T> {
+    fn try_select(&self, _token: &mut Token) -> bool {
+        match self.try_recv() {
+            Ok(msg) => {
+                self.selected.lock().push_back(msg);
+                true
+            }
+            Err(TryRecvError::Disconnected) => true,
+            Err(TryRecvError::Empty) => false,
+        }
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        self.inputs.iter().filter_map(SelectHandle::deadline).min()
+    }
+
+    fn register(&self, oper: Operation, cx: &Context) -> bool {
+        // Wait for any input to become ready instead of pairing up with a particular one, and let
+        // `accept` take whichever message is ready.
+        self.watch(oper, cx)
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.unwatch(oper);
+    }
+
+    fn accept(&self, token: &mut Token, _cx: &Context) -> bool {
+        self.try_select(token)
+    }
+
+    fn is_ready(&self) -> bool {
+        // A disconnected input is always ready, but only makes the merged channel ready once all
+        // inputs are disconnected.
+        let mut disconnected = 0;
+        for r in &self.inputs {
+            if SelectHandle::is_ready(r) {
+                if r.is_closed() && r.is_empty() {
+                    disconnected += 1;
+                } else {
+                    return true;
+                }
+            }
+        }
+        disconnected == self.inputs.len()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) -> bool {
+        for r in &self.inputs {
+            SelectHandle::watch(r, oper, cx);
+        }
+        self.is_ready()
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        for r in &self.inputs {
+            SelectHandle::unwatch(r, oper);
+        }
+    }
+}
@@ -1,17 +1,29 @@
 //! Channel flavors.
 //!
-//! There are six flavors:
+//! There are twelve flavors:
 //!
 //! 1. `at` - Channel that delivers a message after a certain amount of time.
 //! 2. `array` - Bounded channel based on a preallocated array.
-//! 3. `list` - Unbounded channel implemented as a linked list.
-//! 4. `never` - Channel that never delivers messages.
-//! 5. `tick` - Channel that delivers messages periodically.
-//! 6. `zero` - Zero-capacity channel.
+//! 3. `broadcast` - Bounded channel that delivers every message to every receiver.
+//! 4. `filter` - Channel that drops messages of another receiver that don't match a predicate.
+//! 5. `list` - Unbounded channel implemented as a linked list.
+//! 6. `map` - Channel that transforms messages of another receiver.
+//! 7. `merge` - Channel that merges messages from several receivers.
+//! 8. `never` - Channel that never delivers messages.
+//! 9. `priority` - Channel that delivers messages in order of priority.
+//! 10. `tee` - Channel that copies every message from a receiver to several outputs.
+//! 11. `tick` - Channel that delivers messages periodically.
+//! 12. `zero` - Zero-capacity channel.
 
 pub(crate) mod array;
 pub(crate) mod at;
+pub(crate) mod broadcast;
+pub(crate) mod filter;
 pub(crate) mod list;
+pub(crate) mod map;
+pub(crate) mod merge;
 pub(crate) mod never;
+pub(crate) mod priority;
+pub(crate) mod tee;
 pub(crate) mod tick;
 pub(crate) mod zero;
@@ -0,0 +1,762 @@
+//! Priority channel.
+//!
+//! Messages are kept in a binary heap, so receivers always get the greatest message in the
+//! channel. Messages that compare equal are received in the order they were sent.
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::task::{self, Poll};
+use std::time::Instant;
+
+use crossbeam_utils::Backoff;
+
+use crate::clock;
+use crate::context::Context;
+use crate::err::{RecvTimeoutError, SendTimeoutError, TryRecvError, TrySendError};
+use crate::select::{Operation, SelectHandle, Selected, Token};
+use crate::utils::Spinlock;
+use crate::waker::{SyncWaker, Waiter};
+
+/// Equals `true` if a message or a slot has been claimed, or `false` if the channel is
+/// disconnected.
+pub(crate) type PriorityToken = bool;
+
+/// A message in the heap.
+struct Entry<T> {
+    /// The message.
+    msg: T,
+
+    /// Sequence number of the message, used to break ties between equal messages.
+    seq: u64,
+}
+
+/// Inner representation of a priority channel.
+struct Inner<T> {
+    /// Binary max-heap of messages.
+    heap: Vec<Entry<T>>,
+
+    /// Sequence number of the next message to be sent.
+    next_seq: u64,
+
+    /// The number of messages claimed by receivers but not yet read.
+    claimed: usize,
+
+    /// The number of slots reserved by senders but not yet written to.
+    reserved: usize,
+
+    /// Equals `true` when the channel is disconnected.
+    is_disconnected: bool,
+}
+
+/// Priority channel.
+pub(crate) struct Channel<T> {
+    /// Inner representation of the channel.
+    inner: Spinlock<Inner<T>>,
+
+    /// Capacity of the channel, or `None` if it is unbounded.
+    cap: Option<usize>,
+
+    /// Compares the priorities of two messages.
+    cmp: fn(&T, &T) -> cmp::Ordering,
+
+    /// Senders waiting while the channel is full.
+    senders: SyncWaker,
+
+    /// Receivers waiting while the channel is empty and not disconnected.
+    receivers: SyncWaker,
+}
+
+impl<T> Channel<T> {
+    /// Creates a priority channel of capacity `cap`, or unbounded if `cap` is `None`.
+    pub(crate) fn with_capacity(cap: Option<usize>) -> Self
+    where
+        T: Ord,
+    {
+        if let Some(cap) = cap {
+            assert!(cap > 0, "capacity must be positive");
+        }
+
+        Channel {
+            inner: Spinlock::new(Inner {
+                heap: Vec::new(),
+                next_seq: 0,
+                claimed: 0,
+                reserved: 0,
+                is_disconnected: false,
+            }),
+            cap,
+            cmp: T::cmp,
+            senders: SyncWaker::new(),
+            receivers: SyncWaker::new(),
+        }
+    }
+
+    /// Returns a receiver handle to the channel.
+    pub(crate) fn receiver(&self) -> Receiver<'_, T> {
+        Receiver(self)
+    }
+
+    /// Returns a sender handle to the channel.
+    pub(crate) fn sender(&self) -> Sender<'_, T> {
+        Sender(self)
+    }
+
+    /// Returns `true` if entry `a` should be received before entry `b`.
+    fn precedes(&self, a: &Entry<T>, b: &Entry<T>) -> bool {
+        match (self.cmp)(&a.msg, &b.msg) {
+            cmp::Ordering::Greater => true,
+            cmp::Ordering::Less => false,
+            cmp::Ordering::Equal => a.seq < b.seq,
+        }
+    }
+
+    /// Pushes a message into the heap.
+    fn push(&self, inner: &mut Inner<T>, msg: T) {
+        let seq = inner.next_seq;
+        inner.next_seq += 1;
+        inner.heap.push(Entry { msg, seq });
+
+        // Sift the new entry up.
+        let mut i = inner.heap.len() - 1;
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if !self.precedes(&inner.heap[i], &inner.heap[parent]) {
+                break;
+            }
+            inner.heap.swap(i, parent);
+            i = parent;
+        }
+    }
+
+    /// Pops the message that should be received first from the heap.
+    fn pop(&self, inner: &mut Inner<T>) -> Option<T> {
+        let len = inner.heap.len();
+        if len == 0 {
+            return None;
+        }
+        inner.heap.swap(0, len - 1);
+        let entry = inner.heap.pop().unwrap();
+
+        // Sift the moved entry down.
+        let len = inner.heap.len();
+        let mut i = 0;
+        loop {
+            let mut first = i;
+            for &child in &[2 * i + 1, 2 * i + 2] {
+                if child < len && self.precedes(&inner.heap[child], &inner.heap[first]) {
+                    first = child;
+                }
+            }
+            if first == i {
+                break;
+            }
+            inner.heap.swap(i, first);
+            i = first;
+        }
+
+        Some(entry.msg)
+    }
+
+    /// Returns `true` if a sender can reserve a slot.
+    fn has_room(&self, inner: &Inner<T>) -> bool {
+        match self.cap {
+            None => true,
+            Some(cap) => inner.heap.len() + inner.reserved < cap,
+        }
+    }
+
+    /// Attempts to reserve a slot for sending a message.
+    fn start_send(&self, token: &mut Token) -> bool {
+        let mut inner = self.inner.lock();
+
+        if inner.is_disconnected {
+            token.priority = false;
+            true
+        } else if self.has_room(&inner) {
+            inner.reserved += 1;
+            token.priority = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Writes a message into the channel.
+    pub(crate) unsafe fn write(&self, token: &mut Token, msg: T) -> Result<(), T> {
+        // If there is no slot, the channel is disconnected.
+        if !token.priority {
+            return Err(msg);
+        }
+
+        let mut inner = self.inner.lock();
+        inner.reserved -= 1;
+        self.push(&mut inner, msg);
+        drop(inner);
+
+        // Wake a sleeping receiver.
+        self.receivers.notify();
+        Ok(())
+    }
+
+    /// Writes a message into a slot reserved by a permit.
+    pub(crate) unsafe fn write_reserved(&self, token: &mut Token, msg: T) -> Result<(), T> {
+        if token.priority && self.inner.lock().is_disconnected {
+            self.cancel_reserved(token);
+        }
+        self.write(token, msg)
+    }
+
+    /// Releases a slot reserved by a permit without writing a message into it.
+    pub(crate) unsafe fn cancel_reserved(&self, token: &mut Token) {
+        if !token.priority {
+            return;
+        }
+        token.priority = false;
+
+        self.inner.lock().reserved -= 1;
+
+        // Wake a sleeping sender.
+        self.senders.notify();
+    }
+
+    /// Attempts to claim a message for receiving.
+    fn start_recv(&self, token: &mut Token) -> bool {
+        let mut inner = self.inner.lock();
+
+        if inner.heap.len() > inner.claimed {
+            inner.claimed += 1;
+            token.priority = true;
+            true
+        } else if inner.is_disconnected {
+            token.priority = false;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Reads a message from the channel.
+    ///
+    /// The message that should be received first at the time of reading is taken, which might
+    /// have been sent after the claim.
+    pub(crate) unsafe fn read(&self, token: &mut Token) -> Result<T, ()> {
+        // If there is no message, the channel is disconnected.
+        if !token.priority {
+            return Err(());
+        }
+
+        let mut inner = self.inner.lock();
+        inner.claimed -= 1;
+        let msg = self.pop(&mut inner).unwrap();
+        drop(inner);
+
+        // Wake a sleeping sender.
+        self.senders.notify();
+        Ok(msg)
+    }
+
+    /// Attempts to send a message into the channel.
+    pub(crate) fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
+        let token = &mut Token::default();
+        if self.start_send(token) {
+            unsafe { self.write(token, msg).map_err(TrySendError::Disconnected) }
+        } else {
+            Err(TrySendError::Full(msg))
+        }
+    }
+
+    /// Sends messages from the front of `msgs` into the channel.
+    ///
+    /// All messages that fit are sent while the channel is locked once, and blocked receivers are
+    /// notified once all messages are written. Returns the number of sent messages.
+    pub(crate) fn try_send_many(&self, msgs: &mut VecDeque<T>) -> Result<usize, TrySendError<()>> {
+        let mut inner = self.inner.lock();
+
+        if inner.is_disconnected {
+            return Err(TrySendError::Disconnected(()));
+        }
+
+        let mut count = 0;
+        while self.has_room(&inner) {
+            match msgs.pop_front() {
+                Some(msg) => self.push(&mut inner, msg),
+                None => break,
+            }
+            count += 1;
+        }
+        drop(inner);
+
+        if count == 0 {
+            return Err(TrySendError::Full(()));
+        }
+
+        // Wake sleeping receivers.
+        self.receivers.notify_many(count);
+        Ok(count)
+    }
+
+    /// Sends a message into the channel.
+    pub(crate) fn send(
+        &self,
+        msg: T,
+        deadline: Option<Instant>,
+    ) -> Result<(), SendTimeoutError<T>> {
+        let token = &mut Token::default();
+        loop {
+            // Try sending a message several times.
+            let backoff = Backoff::new();
+            loop {
+                if self.start_send(token) {
+                    let res = unsafe { self.write(token, msg) };
+                    return res.map_err(SendTimeoutError::Disconnected);
+                }
+
+                if backoff.is_completed() {
+                    break;
+                } else {
+                    backoff.snooze();
+                }
+            }
+
+            if let Some(d) = deadline {
+                if clock::now() >= d {
+                    return Err(SendTimeoutError::Timeout(msg));
+                }
+            }
+
+            Context::with(|cx| {
+                // Prepare for blocking until a receiver wakes us up.
+                let oper = Operation::hook(token);
+                self.senders.register(oper, cx);
+
+                // Has the channel become ready just now?
+                if self.can_send() {
+                    let _ = cx.try_select(Selected::Aborted);
+                }
+
+                // Block the current thread.
+                let sel = cx.wait_until(deadline);
+
+                match sel {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected => {
+                        self.senders.unregister(oper).unwrap();
+                    }
+                    Selected::Operation(_) => {}
+                }
+            });
+        }
+    }
+
+    /// Attempts to reserve a slot for a permit without blocking.
+    pub(crate) fn try_reserve(&self, token: &mut Token) -> Result<(), TrySendError<()>> {
+        if !self.start_send(token) {
+            Err(TrySendError::Full(()))
+        } else if !token.priority {
+            Err(TrySendError::Disconnected(()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reserves a slot for a permit.
+    pub(crate) fn reserve(
+        &self,
+        token: &mut Token,
+        deadline: Option<Instant>,
+    ) -> Result<(), SendTimeoutError<()>> {
+        loop {
+            // Try reserving a slot several times.
+            let backoff = Backoff::new();
+            loop {
+                if self.start_send(token) {
+                    return if token.priority {
+                        Ok(())
+                    } else {
+                        Err(SendTimeoutError::Disconnected(()))
+                    };
+                }
+
+                if backoff.is_completed() {
+                    break;
+                } else {
+                    backoff.snooze();
+                }
+            }
+
+            if let Some(d) = deadline {
+                if clock::now() >= d {
+                    return Err(SendTimeoutError::Timeout(()));
+                }
+            }
+
+            Context::with(|cx| {
+                // Prepare for blocking until a receiver wakes us up.
+                let oper = Operation::hook(token);
+                self.senders.register(oper, cx);
+
+                // Has the channel become ready just now?
+                if self.can_send() {
+                    let _ = cx.try_select(Selected::Aborted);
+                }
+
+                // Block the current thread.
+                let sel = cx.wait_until(deadline);
+
+                match sel {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected => {
+                        self.senders.unregister(oper).unwrap();
+                    }
+                    Selected::Operation(_) => {}
+                }
+            });
+        }
+    }
+
+    /// Attempts to receive a message without blocking.
+    pub(crate) fn try_recv(&self) -> Result<T, TryRecvError> {
+        let token = &mut Token::default();
+
+        if self.start_recv(token) {
+            unsafe { self.read(token).map_err(|_| TryRecvError::Disconnected) }
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    /// Attempts to receive the message that should be received first if `pred` returns `true`
+    /// for it.
+    ///
+    /// The message is inspected while the channel is locked. Returns `Ok(None)` if the message is
+    /// left in the channel.
+    pub(crate) fn try_recv_if<F>(&self, pred: F) -> Result<Option<T>, TryRecvError>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        let backoff = Backoff::new();
+        let mut inner = self.inner.lock();
+
+        // Claimed messages are about to be taken from the top of the heap, so wait until they
+        // are read.
+        while inner.claimed > 0 {
+            drop(inner);
+            backoff.snooze();
+            inner = self.inner.lock();
+        }
+
+        if inner.heap.is_empty() {
+            return if inner.is_disconnected {
+                Err(TryRecvError::Disconnected)
+            } else {
+                Err(TryRecvError::Empty)
+            };
+        }
+
+        if !pred(&inner.heap[0].msg) {
+            return Ok(None);
+        }
+
+        let msg = self.pop(&mut inner).unwrap();
+        drop(inner);
+
+        // Wake a sleeping sender.
+        self.senders.notify();
+        Ok(Some(msg))
+    }
+
+    /// Attempts to receive up to `max` messages without blocking.
+    ///
+    /// All messages are received while the channel is locked once. Returns the number of messages
+    /// pushed into `buf`.
+    pub(crate) fn try_recv_many(
+        &self,
+        buf: &mut Vec<T>,
+        max: usize,
+    ) -> Result<usize, TryRecvError> {
+        let mut inner = self.inner.lock();
+
+        let count = cmp::min(max, inner.heap.len() - inner.claimed);
+        if count == 0 {
+            return if inner.is_disconnected {
+                Err(TryRecvError::Disconnected)
+            } else {
+                Err(TryRecvError::Empty)
+            };
+        }
+
+        buf.reserve(count);
+        for _ in 0..count {
+            buf.push(self.pop(&mut inner).unwrap());
+        }
+        drop(inner);
+
+        // Wake sleeping senders.
+        self.senders.notify_many(count);
+        Ok(count)
+    }
+
+    /// Receives a message from the channel.
+    pub(crate) fn recv(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        let token = &mut Token::default();
+        loop {
+            // Try receiving a message several times.
+            let backoff = Backoff::new();
+            loop {
+                if self.start_recv(token) {
+                    let res = unsafe { self.read(token) };
+                    return res.map_err(|_| RecvTimeoutError::Disconnected);
+                }
+
+                if backoff.is_completed() {
+                    break;
+                } else {
+                    backoff.snooze();
+                }
+            }
+
+            if let Some(d) = deadline {
+                if clock::now() >= d {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+            }
+
+            Context::with(|cx| {
+                // Prepare for blocking until a sender wakes us up.
+                let oper = Operation::hook(token);
+                self.receivers.register(oper, cx);
+
+                // Has the channel become ready just now?
+                if self.can_recv() {
+                    let _ = cx.try_select(Selected::Aborted);
+                }
+
+                // Block the current thread.
+                let sel = cx.wait_until(deadline);
+
+                match sel {
+                    Selected::Waiting => unreachable!(),
+                    Selected::Aborted | Selected::Disconnected => {
+                        self.receivers.unregister(oper).unwrap();
+                        // If the channel was disconnected, we still have to check for remaining
+                        // messages.
+                    }
+                    Selected::Operation(_) => {}
+                }
+            });
+        }
+    }
+
+    /// Polls a send operation on behalf of an asynchronous task.
+    ///
+    /// The message is taken out of `msg` only once the operation completes.
+    pub(crate) fn poll_send(
+        &self,
+        msg: &mut Option<T>,
+        waiter: &mut Waiter,
+        waker: &task::Waker,
+    ) -> Poll<Result<(), T>> {
+        let token = &mut Token::default();
+        loop {
+            // Withdraw the registration left over from the previous poll.
+            if let Some(oper) = waiter.finish() {
+                self.senders.unregister(oper);
+            }
+
+            if self.start_send(token) {
+                let msg = msg.take().unwrap();
+                return Poll::Ready(unsafe { self.write(token, msg) });
+            }
+
+            // Prepare for waiting until a receiver wakes the task up.
+            let (oper, cx) = waiter.start(waker);
+            self.senders.register(oper, &cx);
+
+            // Has the channel become ready just now?
+            if !self.can_send() {
+                return Poll::Pending;
+            }
+        }
+    }
+
+    /// Withdraws a send operation registered by an asynchronous task.
+    pub(crate) fn cancel_send(&self, waiter: &mut Waiter) {
+        if let Some(oper) = waiter.finish() {
+            // If a receiver has already woken the task up, pass the notification on.
+            if self.senders.unregister(oper).is_none() {
+                self.senders.notify();
+            }
+        }
+    }
+
+    /// Polls a receive operation on behalf of an asynchronous task.
+    pub(crate) fn poll_recv(
+        &self,
+        waiter: &mut Waiter,
+        waker: &task::Waker,
+    ) -> Poll<Result<T, ()>> {
+        let token = &mut Token::default();
+        loop {
+            // Withdraw the registration left over from the previous poll.
+            if let Some(oper) = waiter.finish() {
+                self.receivers.unregister(oper);
+            }
+
+            if self.start_recv(token) {
+                return Poll::Ready(unsafe { self.read(token) });
+            }
+
+            // Prepare for waiting until a sender wakes the task up.
+            let (oper, cx) = waiter.start(waker);
+            self.receivers.register(oper, &cx);
+
+            // Has the channel become ready just now?
+            if !self.can_recv() {
+                return Poll::Pending;
+            }
+        }
+    }
+
+    /// Withdraws a receive operation registered by an asynchronous task.
+    pub(crate) fn cancel_recv(&self, waiter: &mut Waiter) {
+        if let Some(oper) = waiter.finish() {
+            // If a sender has already woken the task up, pass the notification on.
+            if self.receivers.unregister(oper).is_none() {
+                self.receivers.notify();
+            }
+        }
+    }
+
+    /// Disconnects the channel and wakes up all blocked senders and receivers.
+    ///
+    /// Returns `true` if this call disconnected the channel.
+    pub(crate) fn disconnect(&self) -> bool {
+        let mut inner = self.inner.lock();
+
+        if !inner.is_disconnected {
+            inner.is_disconnected = true;
+            drop(inner);
+
+            self.senders.disconnect();
+            self.receivers.disconnect();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns `true` if the channel is disconnected.
+    pub(crate) fn is_disconnected(&self) -> bool {
+        self.inner.lock().is_disconnected
+    }
+
+    /// Returns `true` if a send operation can proceed or the channel is disconnected.
+    fn can_send(&self) -> bool {
+        let inner = self.inner.lock();
+        inner.is_disconnected || self.has_room(&inner)
+    }
+
+    /// Returns `true` if a receive operation can proceed or the channel is disconnected.
+    fn can_recv(&self) -> bool {
+        let inner = self.inner.lock();
+        inner.heap.len() > inner.claimed || inner.is_disconnected
+    }
+
+    /// Returns the current number of messages inside the channel.
+    pub(crate) fn len(&self) -> usize {
+        self.inner.lock().heap.len()
+    }
+
+    /// Returns the capacity of the channel.
+    pub(crate) fn capacity(&self) -> Option<usize> {
+        self.cap
+    }
+
+    /// Returns `true` if the channel is empty.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the channel is full.
+    pub(crate) fn is_full(&self) -> bool {
+        self.cap == Some(self.len())
+    }
+}
+
+/// Receiver handle to a channel.
+pub(crate) struct Receiver<'a, T>(&'a Channel<T>);
+
+/// Sender handle to a channel.
+pub(crate) struct Sender<'a, T>(&'a Channel<T>);
+
+impl<T> SelectHandle for Receiver<'_, T> {
+    fn try_select(&self, token: &mut Token) -> bool {
+        self.0.start_recv(token)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, oper: Operation, cx: &Context) -> bool {
+        self.0.receivers.register(oper, cx);
+        self.is_ready()
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.0.receivers.unregister(oper);
+    }
+
+    fn accept(&self, token: &mut Token, _cx: &Context) -> bool {
+        self.try_select(token)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.0.can_recv()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) -> bool {
+        self.0.receivers.watch(oper, cx);
+        self.is_ready()
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.0.receivers.unwatch(oper);
+    }
+}
+
+impl<T> SelectHandle for Sender<'_, T> {
+    fn try_select(&self, token: &mut Token) -> bool {
+        self.0.start_send(token)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn register(&self, oper: Operation, cx: &Context) -> bool {
+        self.0.senders.register(oper, cx);
+        self.is_ready()
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.0.senders.unregister(oper);
+    }
+
+    fn accept(&self, token: &mut Token, _cx: &Context) -> bool {
+        self.try_select(token)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.0.can_send()
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) -> bool {
+        self.0.senders.watch(oper, cx);
+        self.is_ready()
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.0.senders.unwatch(oper);
+    }
+}
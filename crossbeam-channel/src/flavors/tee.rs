@@ -0,0 +1,323 @@
+//! Channel that copies every message from a receiver to several outputs.
+//!
+//! Outputs don't have a thread of their own that forwards messages. Whichever output receives a
+//! message from the source first leaves a copy of it in the queues of all other outputs. Outputs
+//! that fall behind keep buffering copies until they catch up or get dropped.
+//!
+//! Copies are made outside the lock on the outputs, so a slow or panicking `Clone` doesn't block
+//! the other outputs. While a copy is being made, its place in the queue is taken by a pending
+//! entry, which keeps copies in the order they were received from the source.
+
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use crate::channel::Receiver as Source;
+use crate::context::Context;
+use crate::err::TryRecvError;
+use crate::select::{Operation, SelectHandle, Token};
+use crate::utils::{Spinlock, SpinlockGuard};
+use crate::waker::SyncWaker;
+
+/// An entry in the queue of an output.
+enum Entry<T> {
+    /// The copy is ready to be received.
+    Ready(T),
+
+    /// The message with this sequence number is being cloned for the output.
+    Pending(usize),
+}
+
+/// State of one output.
+struct Output<T> {
+    /// Copies of messages received from the source by other outputs.
+    queue: VecDeque<Entry<T>>,
+
+    /// Messages taken by selected operations that haven't been read yet.
+    selected: VecDeque<T>,
+
+    /// Number of receiver handles of this output.
+    ///
+    /// Once it drops to zero, the output stops getting copies of messages.
+    receivers: usize,
+}
+
+/// Channel that copies every message from a receiver to several outputs.
+pub(crate) struct Channel<T> {
+    /// The receiver messages are copied from.
+    source: Source<T>,
+
+    /// Clones a message.
+    clone: fn(&T) -> T,
+
+    /// The outputs.
+    ///
+    /// The lock is held while receiving from the source and reserving a place for the message in
+    /// the queues of other outputs, so that all outputs get copies in the same order. Messages are
+    /// cloned into their places after the lock is released.
+    outputs: Spinlock<Vec<Output<T>>>,
+
+    /// The sequence number of the next message received from the source.
+    seq: AtomicUsize,
+
+    /// Operations waiting for a message to be copied into the queue of their output.
+    waiters: SyncWaker,
+}
+
+impl<T> Channel<T> {
+    /// Creates a channel with `n` outputs copying messages from `source`.
+    pub(crate) fn new(source: Source<T>, n: usize, clone: fn(&T) -> T) -> Self {
+        Channel {
+            source,
+            clone,
+            outputs: Spinlock::new(
+                (0..n)
+                    .map(|_| Output {
+                        queue: VecDeque::new(),
+                        selected: VecDeque::new(),
+                        receivers: 1,
+                    })
+                    .collect(),
+            ),
+            seq: AtomicUsize::new(0),
+            waiters: SyncWaker::new(),
+        }
+    }
+
+    /// Returns a receiver handle to the output at `index`.
+    pub(crate) fn receiver(&self, index: usize) -> Receiver<'_, T> {
+        Receiver { chan: self, index }
+    }
+
+    /// Attempts to receive a message for the output at `index` without blocking.
+    pub(crate) fn try_recv(&self, index: usize) -> Result<T, TryRecvError> {
+        let mut outputs = self.outputs.lock();
+        match outputs[index].queue.pop_front() {
+            Some(Entry::Ready(msg)) => return Ok(msg),
+            Some(pending) => {
+                // The next copy is still being cloned.
+                outputs[index].queue.push_front(pending);
+                return Err(TryRecvError::Empty);
+            }
+            None => {}
+        }
+        self.recv_source(outputs, index)
+    }
+
+    /// Attempts to receive a message for the output at `index` without blocking if `pred` returns
+    /// `true` for it.
+    ///
+    /// The message is taken out of the channel while `pred` runs, and put back at the front of the
+    /// queue of the output if `pred` rejects it.
+    pub(crate) fn try_recv_if<F>(&self, index: usize, pred: F) -> Result<Option<T>, TryRecvError>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        let msg = self.try_recv(index)?;
+        if pred(&msg) {
+            return Ok(Some(msg));
+        }
+
+        // Other outputs get a copy of the message even if the predicate rejects it.
+        self.outputs.lock()[index]
+            .queue
+            .push_front(Entry::Ready(msg));
+        Ok(None)
+    }
+
+    /// Receives a message from the source and copies it into the queues of all outputs except the
+    /// one at `index`.
+    ///
+    /// Places for the copies are reserved while `outputs` is locked, and then the lock is released
+    /// while the message is cloned. If cloning panics, the message is lost for the output at
+    /// `index`, outputs that already got their copy keep it, and the rest skip the message.
+    fn recv_source(
+        &self,
+        mut outputs: SpinlockGuard<'_, Vec<Output<T>>>,
+        index: usize,
+    ) -> Result<T, TryRecvError> {
+        let msg = self.source.try_recv()?;
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+
+        let mut targets = Vec::new();
+        for (i, output) in outputs.iter_mut().enumerate() {
+            if i != index && output.receivers > 0 {
+                output.queue.push_back(Entry::Pending(seq));
+                targets.push(i);
+            }
+        }
+        drop(outputs);
+
+        // Clones are made for outputs in order, popping targets from the back.
+        targets.reverse();
+
+        // Removes the places that weren't filled and wakes up waiting receivers, even if cloning
+        // panics.
+        let mut guard = FillGuard {
+            chan: self,
+            seq,
+            targets,
+        };
+        while let Some(&i) = guard.targets.last() {
+            let copy = (self.clone)(&msg);
+            let mut outputs = self.outputs.lock();
+            let place = outputs[i].queue.iter_mut().rev().find(|entry| match entry {
+                Entry::Pending(s) => *s == seq,
+                Entry::Ready(_) => false,
+            });
+
+            // The place is gone if the last handle of the output was dropped in the meantime.
+            if let Some(place) = place {
+                *place = Entry::Ready(copy);
+            }
+            drop(outputs);
+            guard.targets.pop();
+        }
+        Ok(msg)
+    }
+
+    /// Reads a message taken by `try_select`.
+    pub(crate) unsafe fn read(&self, index: usize, _token: &mut Token) -> Result<T, ()> {
+        self.outputs.lock()[index].selected.pop_front().ok_or(())
+    }
+
+    /// Returns the current number of messages waiting for the output at `index`.
+    pub(crate) fn len(&self, index: usize) -> usize {
+        self.outputs.lock()[index].queue.len() + self.source.len()
+    }
+
+    /// Returns `true` if no messages are waiting for the output at `index`.
+    pub(crate) fn is_empty(&self, index: usize) -> bool {
+        self.outputs.lock()[index].queue.is_empty() && self.source.is_empty()
+    }
+
+    /// Returns the number of senders connected to the source.
+    pub(crate) fn sender_count(&self) -> usize {
+        self.source.sender_count()
+    }
+
+    /// Returns the number of receiver handles of the output at `index`.
+    pub(crate) fn receiver_count(&self, index: usize) -> usize {
+        self.outputs.lock()[index].receivers
+    }
+
+    /// Closes the source.
+    pub(crate) fn close(&self) -> bool {
+        self.source.close()
+    }
+
+    /// Returns `true` if the source is disconnected.
+    pub(crate) fn is_disconnected(&self) -> bool {
+        self.source.is_closed()
+    }
+
+    /// Adds a receiver handle to the output at `index` unless all of its handles are gone.
+    pub(crate) fn acquire(&self, index: usize) -> bool {
+        let mut outputs = self.outputs.lock();
+        let output = &mut outputs[index];
+        if output.receivers == 0 {
+            false
+        } else {
+            output.receivers += 1;
+            true
+        }
+    }
+
+    /// Drops a receiver handle of the output at `index`.
+    ///
+    /// Once the last handle is gone, buffered copies are dropped.
+    pub(crate) fn release(&self, index: usize) {
+        let mut outputs = self.outputs.lock();
+        let output = &mut outputs[index];
+        output.receivers -= 1;
+        if output.receivers == 0 {
+            let queue = mem::replace(&mut output.queue, VecDeque::new());
+            drop(outputs);
+            drop(queue);
+        }
+    }
+}
+
+/// Finishes copying a message into the queues of outputs.
+struct FillGuard<'a, T> {
+    /// The channel.
+    chan: &'a Channel<T>,
+
+    /// The sequence number of the message.
+    seq: usize,
+
+    /// Indices of outputs that haven't gotten their copy yet.
+    targets: Vec<usize>,
+}
+
+impl<T> Drop for FillGuard<'_, T> {
+    fn drop(&mut self) {
+        let seq = self.seq;
+        for output in self.chan.outputs.lock().iter_mut() {
+            output.queue.retain(|copy| match copy {
+                Entry::Pending(s) => *s != seq,
+                Entry::Ready(_) => true,
+            });
+        }
+        self.chan.waiters.notify();
+    }
+}
+
+/// Receiver handle to an output of a channel.
+pub(crate) struct Receiver<'a, T> {
+    /// The channel.
+    chan: &'a Channel<T>,
+
+    /// Index of the output.
+    index: usize,
+}
+
+impl<T> SelectHandle for Receiver<'_, T> {
+    fn try_select(&self, _token: &mut Token) -> bool {
+        match self.chan.try_recv(self.index) {
+            Ok(msg) => {
+                self.chan.outputs.lock()[self.index].selected.push_back(msg);
+                true
+            }
+            Err(TryRecvError::Disconnected) => true,
+            Err(TryRecvError::Empty) => false,
+        }
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        SelectHandle::deadline(&self.chan.source)
+    }
+
+    fn register(&self, oper: Operation, cx: &Context) -> bool {
+        self.watch(oper, cx)
+    }
+
+    fn unregister(&self, oper: Operation) {
+        self.unwatch(oper);
+    }
+
+    fn accept(&self, token: &mut Token, _cx: &Context) -> bool {
+        self.try_select(token)
+    }
+
+    fn is_ready(&self) -> bool {
+        match self.chan.outputs.lock()[self.index].queue.front() {
+            Some(Entry::Ready(_)) => true,
+            // The next copy is still being cloned.
+            Some(Entry::Pending(_)) => false,
+            None => SelectHandle::is_ready(&self.chan.source),
+        }
+    }
+
+    fn watch(&self, oper: Operation, cx: &Context) -> bool {
+        self.chan.waiters.watch(oper, cx);
+        SelectHandle::watch(&self.chan.source, oper, cx);
+        self.is_ready()
+    }
+
+    fn unwatch(&self, oper: Operation) {
+        self.chan.waiters.unwatch(oper);
+        SelectHandle::unwatch(&self.chan.source, oper);
+    }
+}
@@ -2,11 +2,12 @@
 //!
 //! Messages cannot be sent into this kind of channel; they are materialized on demand.
 
-use std::thread;
 use std::time::{Duration, Instant};
 
 use crossbeam_utils::atomic::AtomicCell;
 
+use crate::channel::MissedTick;
+use crate::clock;
 use crate::context::Context;
 use crate::err::{RecvTimeoutError, TryRecvError};
 use crate::select::{Operation, SelectHandle, Token};
@@ -21,15 +22,45 @@ pub(crate) struct Channel {
 
     /// The time interval in which messages get delivered.
     duration: Duration,
+
+    /// What to do when the receiver falls behind.
+    missed: MissedTick,
 }
 
 impl Channel {
     /// Creates a channel that delivers messages periodically.
     #[inline]
-    pub(crate) fn new(dur: Duration) -> Self {
+    pub(crate) fn new(dur: Duration, missed: MissedTick) -> Self {
+        Self::new_at(clock::now() + dur, dur, missed)
+    }
+
+    /// Creates a channel that delivers messages periodically, starting at a certain instant.
+    #[inline]
+    pub(crate) fn new_at(start: Instant, dur: Duration, missed: MissedTick) -> Self {
         Channel {
-            delivery_time: AtomicCell::new(Instant::now() + dur),
+            delivery_time: AtomicCell::new(start),
             duration: dur,
+            missed,
+        }
+    }
+
+    /// Returns the delivery time following the one at `delivery_time`, if a message is received
+    /// at `now`.
+    #[inline]
+    fn next_delivery_time(&self, delivery_time: Instant, now: Instant) -> Instant {
+        match self.missed {
+            MissedTick::Burst => delivery_time + self.duration,
+            MissedTick::Delay => delivery_time.max(now) + self.duration,
+            MissedTick::Skip => {
+                let period = self.duration.as_nanos();
+                if now < delivery_time || period == 0 {
+                    return delivery_time.max(now) + self.duration;
+                }
+
+                // Skip over all ticks that were missed, staying aligned to the schedule.
+                let ticks = (now - delivery_time).as_nanos() / period + 1;
+                delivery_time + Duration::from_nanos((ticks * period) as u64)
+            }
         }
     }
 
@@ -37,7 +68,7 @@ impl Channel {
     #[inline]
     pub(crate) fn try_recv(&self) -> Result<Instant, TryRecvError> {
         loop {
-            let now = Instant::now();
+            let now = clock::now();
             let delivery_time = self.delivery_time.load();
 
             if now < delivery_time {
@@ -46,7 +77,7 @@ impl Channel {
 
             if self
                 .delivery_time
-                .compare_exchange(delivery_time, now + self.duration)
+                .compare_exchange(delivery_time, self.next_delivery_time(delivery_time, now))
                 .is_ok()
             {
                 return Ok(delivery_time);
@@ -54,17 +85,45 @@ impl Channel {
         }
     }
 
+    /// Attempts to receive a message without blocking if `pred` returns `true` for it.
+    ///
+    /// Returns `Ok(None)` if the message is left in the channel.
+    pub(crate) fn try_recv_if<F>(&self, pred: F) -> Result<Option<Instant>, TryRecvError>
+    where
+        F: FnOnce(&Instant) -> bool,
+    {
+        let now = clock::now();
+        let delivery_time = self.delivery_time.load();
+
+        if now < delivery_time {
+            return Err(TryRecvError::Empty);
+        }
+
+        if !pred(&delivery_time) {
+            return Ok(None);
+        }
+
+        // Another receiver might have received the message in the meantime.
+        match self
+            .delivery_time
+            .compare_exchange(delivery_time, self.next_delivery_time(delivery_time, now))
+        {
+            Ok(_) => Ok(Some(delivery_time)),
+            Err(_) => Err(TryRecvError::Empty),
+        }
+    }
+
     /// Receives a message from the channel.
     #[inline]
     pub(crate) fn recv(&self, deadline: Option<Instant>) -> Result<Instant, RecvTimeoutError> {
         loop {
             let delivery_time = self.delivery_time.load();
-            let now = Instant::now();
+            let now = clock::now();
 
             if let Some(d) = deadline {
                 if d < delivery_time {
                     if now < d {
-                        thread::sleep(d - now);
+                        clock::sleep_until(d);
                     }
                     return Err(RecvTimeoutError::Timeout);
                 }
@@ -72,11 +131,11 @@ impl Channel {
 
             if self
                 .delivery_time
-                .compare_exchange(delivery_time, delivery_time.max(now) + self.duration)
+                .compare_exchange(delivery_time, self.next_delivery_time(delivery_time, now))
                 .is_ok()
             {
                 if now < delivery_time {
-                    thread::sleep(delivery_time - now);
+                    clock::sleep_until(delivery_time);
                 }
                 return Ok(delivery_time);
             }
@@ -92,7 +151,7 @@ impl Channel {
     /// Returns `true` if the channel is empty.
     #[inline]
     pub(crate) fn is_empty(&self) -> bool {
-        Instant::now() < self.delivery_time.load()
+        clock::now() < self.delivery_time.load()
     }
 
     /// Returns `true` if the channel is full.
@@ -4,7 +4,9 @@
 
 use std::cell::UnsafeCell;
 use std::marker::PhantomData;
+use std::mem;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{self, Poll};
 use std::time::Instant;
 
 use crossbeam_utils::Backoff;
@@ -13,7 +15,7 @@ use crate::context::Context;
 use crate::err::{RecvTimeoutError, SendTimeoutError, TryRecvError, TrySendError};
 use crate::select::{Operation, SelectHandle, Selected, Token};
 use crate::utils::Spinlock;
-use crate::waker::Waker;
+use crate::waker::{Waiter, Waker};
 
 /// A pointer to a packet.
 pub(crate) type ZeroToken = usize;
@@ -326,6 +328,139 @@ impl<T> Channel<T> {
         })
     }
 
+    /// Polls a send operation on behalf of an asynchronous task.
+    ///
+    /// While the task is waiting, the message is kept inside a packet owned by the future.
+    pub(crate) fn poll_send(
+        &self,
+        msg: &mut Option<T>,
+        waiter: &mut Waiter,
+        waker: &task::Waker,
+    ) -> Poll<Result<(), T>> {
+        let token = &mut Token::default();
+        let mut inner = self.inner.lock();
+
+        // Withdraw the registration left over from the previous poll.
+        if let Some(oper) = waiter.finish() {
+            let packet = unsafe { Box::from_raw(waiter.packet as *mut Packet<T>) };
+            waiter.packet = 0;
+
+            if inner.senders.unregister(oper).is_none() {
+                // A receiver has paired up with the task. Wait until it takes the message, then
+                // drop the packet.
+                drop(inner);
+                packet.wait_ready();
+                return Poll::Ready(Ok(()));
+            }
+
+            // Nobody has paired up with the task, so take the message back.
+            *msg = unsafe { packet.msg.get().replace(None) };
+        }
+
+        // If there's a waiting receiver, pair up with it.
+        if let Some(operation) = inner.receivers.try_select() {
+            token.zero = operation.packet;
+            drop(inner);
+            unsafe {
+                self.write(token, msg.take().unwrap()).ok().unwrap();
+            }
+            return Poll::Ready(Ok(()));
+        }
+
+        if inner.is_disconnected {
+            return Poll::Ready(Err(msg.take().unwrap()));
+        }
+
+        // Prepare for waiting until a receiver wakes the task up. The packet is owned by the
+        // future, so it is marked as being on the stack to prevent the receiver from destroying it.
+        let (oper, cx) = waiter.start(waker);
+        let packet = Box::new(Packet::<T>::message_on_stack(msg.take().unwrap()));
+        waiter.packet = Box::into_raw(packet) as usize;
+        inner.senders.register_with_packet(oper, waiter.packet, &cx);
+        inner.receivers.notify();
+        Poll::Pending
+    }
+
+    /// Withdraws a send operation registered by an asynchronous task.
+    pub(crate) fn cancel_send(&self, waiter: &mut Waiter) {
+        if let Some(oper) = waiter.finish() {
+            let entry = self.inner.lock().senders.unregister(oper);
+            let packet = unsafe { Box::from_raw(waiter.packet as *mut Packet<T>) };
+            waiter.packet = 0;
+
+            if entry.is_none() {
+                // A receiver has paired up with the task and is taking the message.
+                packet.wait_ready();
+            }
+        }
+    }
+
+    /// Polls a receive operation on behalf of an asynchronous task.
+    pub(crate) fn poll_recv(
+        &self,
+        waiter: &mut Waiter,
+        waker: &task::Waker,
+    ) -> Poll<Result<T, ()>> {
+        let token = &mut Token::default();
+        let mut inner = self.inner.lock();
+
+        // Withdraw the registration left over from the previous poll.
+        if let Some(oper) = waiter.finish() {
+            token.zero = mem::replace(&mut waiter.packet, 0);
+
+            if inner.receivers.unregister(oper).is_none() {
+                // A sender has paired up with the task, so read the message from the packet.
+                drop(inner);
+                return Poll::Ready(unsafe { self.read(token) });
+            }
+
+            // Nobody has paired up with the task, so destroy the packet.
+            unsafe {
+                drop(Box::from_raw(token.zero as *mut Packet<T>));
+            }
+        }
+
+        // If there's a waiting sender, pair up with it.
+        if let Some(operation) = inner.senders.try_select() {
+            token.zero = operation.packet;
+            drop(inner);
+            return Poll::Ready(unsafe { self.read(token) });
+        }
+
+        if inner.is_disconnected {
+            return Poll::Ready(Err(()));
+        }
+
+        // Prepare for waiting until a sender wakes the task up.
+        let (oper, cx) = waiter.start(waker);
+        waiter.packet = Box::into_raw(Packet::<T>::empty_on_heap()) as usize;
+        inner
+            .receivers
+            .register_with_packet(oper, waiter.packet, &cx);
+        inner.senders.notify();
+        Poll::Pending
+    }
+
+    /// Withdraws a receive operation registered by an asynchronous task.
+    ///
+    /// If a sender has already paired up with the task, its message is dropped.
+    pub(crate) fn cancel_recv(&self, waiter: &mut Waiter) {
+        if let Some(oper) = waiter.finish() {
+            let entry = self.inner.lock().receivers.unregister(oper);
+            let token = &mut Token::default();
+            token.zero = mem::replace(&mut waiter.packet, 0);
+
+            if entry.is_none() {
+                // Wait for the message and drop it together with the packet.
+                drop(unsafe { self.read(token) });
+            } else {
+                unsafe {
+                    drop(Box::from_raw(token.zero as *mut Packet<T>));
+                }
+            }
+        }
+    }
+
     /// Disconnects the channel and wakes up all blocked senders and receivers.
     ///
     /// Returns `true` if this call disconnected the channel.
@@ -342,6 +477,11 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Returns `true` if the channel is disconnected.
+    pub(crate) fn is_disconnected(&self) -> bool {
+        self.inner.lock().is_disconnected
+    }
+
     /// Returns the current number of messages inside the channel.
     pub(crate) fn len(&self) -> usize {
         0
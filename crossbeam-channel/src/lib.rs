@@ -78,6 +78,29 @@
 //! assert_eq!(r.recv(), Ok("Hi!"));
 //! ```
 //!
+//! Channels created with [`broadcast`] deliver every message to every receiver instead of handing
+//! each message to a single one:
+//!
+//! ```
+//! use crossbeam_channel::broadcast;
+//!
+//! // Create a broadcast channel that keeps the 10 most recent messages.
+//! let (s, r1) = broadcast(10);
+//! let r2 = r1.clone();
+//!
+//! s.send("Hi!").unwrap();
+//!
+//! // Both receivers get the message.
+//! assert_eq!(r1.recv(), Ok("Hi!"));
+//! assert_eq!(r2.recv(), Ok("Hi!"));
+//! ```
+//!
+//! For passing a single message, like the response to a request, [`oneshot`] creates a lighter
+//! channel whose handles are consumed or dropped after use.
+//!
+//! For state where only the latest value matters, like configuration, [`watch`] creates a channel
+//! that holds a single value and lets receivers wait until it changes.
+//!
 //! # Sharing channels
 //!
 //! Senders and receivers can be cloned and sent to other threads:
@@ -285,7 +308,13 @@
 //! ```
 //!
 //! If you need to select over a dynamically created list of channel operations, use [`Select`]
-//! instead. The [`select!`] macro is just a convenience wrapper around [`Select`].
+//! instead. The [`select!`] macro is just a convenience wrapper around [`Select`]. Long-lived
+//! loops over many channels that come and go can keep them in a [`SelectSet`], which owns its
+//! senders and receivers and stays registered with them between selections.
+//!
+//! Sources of events other than channels can take part in selection by implementing the
+//! [`Selectable`] trait. On Linux, file descriptors can be waited on with [`Select::readable`] and
+//! [`Select::writable`].
 //!
 //! # Extra channels
 //!
@@ -297,6 +326,7 @@
 //! * [`never`](never()) creates a channel that never delivers messages.
 //!
 //! These channels are very efficient because messages get lazily generated on receive operations.
+//! In tests, they can be driven by a mock clock instead of real time; see the [`clock`] module.
 //!
 //! An example that prints elapsed time every 50 milliseconds for the duration of 1 second:
 //!
@@ -340,29 +370,53 @@ use cfg_if::cfg_if;
 
 cfg_if! {
     if #[cfg(feature = "std")] {
+        mod alarm;
         mod channel;
+        pub mod clock;
         mod context;
         mod counter;
+        mod custom;
         mod err;
+        #[cfg(target_os = "linux")]
+        mod fd;
         mod flavors;
+        mod metrics;
+        mod oneshot;
         mod select;
         mod select_macro;
+        mod select_set;
         mod utils;
         mod waker;
+        mod watch;
 
         /// Crate internals used by the `select!` macro.
         #[doc(hidden)]
         pub mod internal {
+            pub use crate::custom::custom_handle;
             pub use crate::select::SelectHandle;
             pub use crate::select::{select, select_timeout, try_select};
         }
 
-        pub use crate::channel::{after, at, never, tick};
-        pub use crate::channel::{bounded, unbounded};
+        pub use crate::channel::{after, at, never, tick, tick_at, tick_with, MissedTick, Timer};
+        pub use crate::channel::{bounded, bounded_with_policy, unbounded, Overflow};
+        pub use crate::channel::{bounded_with_expiry, unbounded_with_expiry, Expiry};
+        pub use crate::channel::{broadcast, broadcast_with_lagging, Lagging};
+        pub use crate::channel::{priority_bounded, priority_unbounded};
+        pub use crate::channel::{merge, MappedReceiver};
         pub use crate::channel::{IntoIter, Iter, TryIter};
-        pub use crate::channel::{Receiver, Sender};
+        pub use crate::channel::{RecvFuture, SendFuture};
+        pub use crate::channel::{Permit, Receiver, ReceiversClosed, Sender};
+        pub use crate::channel::{WeakReceiver, WeakSender};
+        #[cfg(feature = "metrics")]
+        pub use crate::metrics::ChannelStats;
 
+        pub use crate::custom::{Registration, RegistrationId, Selectable, Slot};
+        #[cfg(target_os = "linux")]
+        pub use crate::fd::{Readable, Writable};
+        pub use crate::oneshot::{oneshot, OneshotReceiver, OneshotSender};
         pub use crate::select::{Select, SelectedOperation};
+        pub use crate::select_set::SelectSet;
+        pub use crate::watch::{watch, WatchReceiver, WatchRef, WatchSender};
 
         pub use crate::err::{ReadyTimeoutError, SelectTimeoutError, TryReadyError, TrySelectError};
         pub use crate::err::{RecvError, RecvTimeoutError, TryRecvError};
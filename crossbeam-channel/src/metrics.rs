@@ -0,0 +1,318 @@
+//! Runtime metrics kept by channels.
+//!
+//! With the `metrics` feature disabled, all types in this module are empty and recording compiles
+//! down to nothing.
+
+use cfg_if::cfg_if;
+
+/// Whether channels keep metrics.
+pub(crate) const ENABLED: bool = cfg!(feature = "metrics");
+
+cfg_if! {
+    if #[cfg(feature = "metrics")] {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::{Duration, Instant};
+
+        use crossbeam_utils::atomic::AtomicCell;
+
+        /// Counters recorded by a channel.
+        pub(crate) struct Metrics {
+            /// The number of sent messages.
+            sent: AtomicUsize,
+
+            /// The number of received messages.
+            received: AtomicUsize,
+
+            /// The number of non-blocking send operations that failed.
+            try_send_failures: AtomicUsize,
+
+            /// The number of non-blocking receive operations that failed.
+            try_recv_failures: AtomicUsize,
+
+            /// The number of send operations that had to block.
+            send_waits: AtomicUsize,
+
+            /// The number of receive operations that had to block.
+            recv_waits: AtomicUsize,
+
+            /// The total time send operations spent blocked, in nanoseconds.
+            send_wait_nanos: AtomicCell<u64>,
+
+            /// The total time receive operations spent blocked, in nanoseconds.
+            recv_wait_nanos: AtomicCell<u64>,
+
+            /// The largest number of messages observed in the channel after a send.
+            peak_len: AtomicUsize,
+        }
+
+        impl Metrics {
+            /// Creates a set of zeroed counters.
+            pub(crate) fn new() -> Metrics {
+                Metrics {
+                    sent: AtomicUsize::new(0),
+                    received: AtomicUsize::new(0),
+                    try_send_failures: AtomicUsize::new(0),
+                    try_recv_failures: AtomicUsize::new(0),
+                    send_waits: AtomicUsize::new(0),
+                    recv_waits: AtomicUsize::new(0),
+                    send_wait_nanos: AtomicCell::new(0),
+                    recv_wait_nanos: AtomicCell::new(0),
+                    peak_len: AtomicUsize::new(0),
+                }
+            }
+
+            /// Records `count` sent messages, after which the channel holds `len()` messages.
+            pub(crate) fn sent<F: FnOnce() -> usize>(&self, count: usize, len: F) {
+                self.sent.fetch_add(count, Ordering::Relaxed);
+
+                let len = len();
+                let mut peak = self.peak_len.load(Ordering::Relaxed);
+                while len > peak {
+                    match self.peak_len.compare_exchange_weak(
+                        peak,
+                        len,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    ) {
+                        Ok(_) => break,
+                        Err(p) => peak = p,
+                    }
+                }
+            }
+
+            /// Records `count` received messages.
+            pub(crate) fn received(&self, count: usize) {
+                self.received.fetch_add(count, Ordering::Relaxed);
+            }
+
+            /// Records a failed non-blocking send operation.
+            pub(crate) fn try_send_failed(&self) {
+                self.try_send_failures.fetch_add(1, Ordering::Relaxed);
+            }
+
+            /// Records a failed non-blocking receive operation.
+            pub(crate) fn try_recv_failed(&self) {
+                self.try_recv_failures.fetch_add(1, Ordering::Relaxed);
+            }
+
+            /// Records a send operation that blocked since `wait` was started.
+            pub(crate) fn send_waited(&self, wait: Wait) {
+                self.send_waits.fetch_add(1, Ordering::Relaxed);
+                self.send_wait_nanos.fetch_add(wait.elapsed_nanos());
+            }
+
+            /// Records a receive operation that blocked since `wait` was started.
+            pub(crate) fn recv_waited(&self, wait: Wait) {
+                self.recv_waits.fetch_add(1, Ordering::Relaxed);
+                self.recv_wait_nanos.fetch_add(wait.elapsed_nanos());
+            }
+
+            /// Takes a snapshot of the counters, together with the current number of senders and
+            /// receivers.
+            pub(crate) fn snapshot(&self, senders: usize, receivers: usize) -> ChannelStats {
+                ChannelStats {
+                    sent: self.sent.load(Ordering::Relaxed),
+                    received: self.received.load(Ordering::Relaxed),
+                    try_send_failures: self.try_send_failures.load(Ordering::Relaxed),
+                    try_recv_failures: self.try_recv_failures.load(Ordering::Relaxed),
+                    send_waits: self.send_waits.load(Ordering::Relaxed),
+                    recv_waits: self.recv_waits.load(Ordering::Relaxed),
+                    send_wait_time: Duration::from_nanos(self.send_wait_nanos.load()),
+                    recv_wait_time: Duration::from_nanos(self.recv_wait_nanos.load()),
+                    peak_len: self.peak_len.load(Ordering::Relaxed),
+                    senders,
+                    receivers,
+                }
+            }
+        }
+
+        /// Measures how long an operation blocks.
+        pub(crate) struct Wait {
+            start: Instant,
+        }
+
+        impl Wait {
+            /// Starts measuring.
+            ///
+            /// Blocked time is measured with the system clock even while a mock clock is
+            /// installed, because it reports how long threads were actually stalled.
+            pub(crate) fn start() -> Wait {
+                Wait {
+                    start: Instant::now(),
+                }
+            }
+
+            /// Returns the time elapsed since the start, in nanoseconds.
+            fn elapsed_nanos(&self) -> u64 {
+                let elapsed = self.start.elapsed();
+                elapsed.as_secs() * 1_000_000_000 + u64::from(elapsed.subsec_nanos())
+            }
+        }
+
+        /// A snapshot of the statistics of a channel.
+        ///
+        /// Snapshots are returned by [`Sender::stats`] and [`Receiver::stats`]. The counters cover
+        /// the whole channel, no matter which sender or receiver they were taken from, and are
+        /// updated independently of each other, so a snapshot taken while the channel is in use
+        /// may be slightly inconsistent.
+        ///
+        /// Messages evicted by an [`Overflow`] policy or expired according to an [`Expiry`] policy
+        /// count as sent, but never as received.
+        /// Receivers created by [`after`], [`at`], [`tick`] and [`never`] keep no statistics and
+        /// always return an empty snapshot.
+        ///
+        /// This type is only available with the `metrics` feature enabled.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use crossbeam_channel::bounded;
+        ///
+        /// let (s, r) = bounded(2);
+        /// s.send(1).unwrap();
+        /// s.send(2).unwrap();
+        /// assert!(s.try_send(3).is_err());
+        /// assert_eq!(r.recv(), Ok(1));
+        ///
+        /// let stats = r.stats();
+        /// assert_eq!(stats.sent(), 2);
+        /// assert_eq!(stats.received(), 1);
+        /// assert_eq!(stats.try_send_failures(), 1);
+        /// assert_eq!(stats.peak_len(), 2);
+        /// ```
+        ///
+        /// [`Sender::stats`]: crate::Sender::stats
+        /// [`Receiver::stats`]: crate::Receiver::stats
+        /// [`Overflow`]: crate::Overflow
+        /// [`Expiry`]: crate::Expiry
+        /// [`after`]: crate::after
+        /// [`at`]: crate::at
+        /// [`tick`]: crate::tick
+        /// [`never`]: crate::never
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+        pub struct ChannelStats {
+            sent: usize,
+            received: usize,
+            try_send_failures: usize,
+            try_recv_failures: usize,
+            send_waits: usize,
+            recv_waits: usize,
+            send_wait_time: Duration,
+            recv_wait_time: Duration,
+            peak_len: usize,
+            senders: usize,
+            receivers: usize,
+        }
+
+        impl ChannelStats {
+            /// Returns the number of messages sent into the channel.
+            pub fn sent(&self) -> usize {
+                self.sent
+            }
+
+            /// Returns the number of messages received from the channel.
+            pub fn received(&self) -> usize {
+                self.received
+            }
+
+            /// Returns the number of non-blocking send operations that failed because the channel
+            /// was full or disconnected.
+            ///
+            /// This counts failed calls to methods like [`Sender::try_send`] and
+            /// [`Sender::try_reserve`].
+            ///
+            /// [`Sender::try_send`]: crate::Sender::try_send
+            /// [`Sender::try_reserve`]: crate::Sender::try_reserve
+            pub fn try_send_failures(&self) -> usize {
+                self.try_send_failures
+            }
+
+            /// Returns the number of non-blocking receive operations that failed because the
+            /// channel was empty or disconnected.
+            ///
+            /// This counts failed calls to methods like [`Receiver::try_recv`] and
+            /// [`Receiver::try_recv_if`].
+            ///
+            /// [`Receiver::try_recv`]: crate::Receiver::try_recv
+            /// [`Receiver::try_recv_if`]: crate::Receiver::try_recv_if
+            pub fn try_recv_failures(&self) -> usize {
+                self.try_recv_failures
+            }
+
+            /// Returns the number of blocking send operations that couldn't complete right away and
+            /// had to wait.
+            pub fn send_waits(&self) -> usize {
+                self.send_waits
+            }
+
+            /// Returns the number of blocking receive operations that couldn't complete right away
+            /// and had to wait.
+            pub fn recv_waits(&self) -> usize {
+                self.recv_waits
+            }
+
+            /// Returns the total time send operations spent waiting.
+            pub fn send_wait_time(&self) -> Duration {
+                self.send_wait_time
+            }
+
+            /// Returns the total time receive operations spent waiting.
+            pub fn recv_wait_time(&self) -> Duration {
+                self.recv_wait_time
+            }
+
+            /// Returns the largest number of messages the channel has held.
+            pub fn peak_len(&self) -> usize {
+                self.peak_len
+            }
+
+            /// Returns the number of senders connected to the channel.
+            pub fn senders(&self) -> usize {
+                self.senders
+            }
+
+            /// Returns the number of receivers connected to the channel.
+            pub fn receivers(&self) -> usize {
+                self.receivers
+            }
+        }
+    } else {
+        /// Counters recorded by a channel.
+        pub(crate) struct Metrics {}
+
+        impl Metrics {
+            #[inline]
+            pub(crate) fn new() -> Metrics {
+                Metrics {}
+            }
+
+            #[inline]
+            pub(crate) fn sent<F: FnOnce() -> usize>(&self, _count: usize, _len: F) {}
+
+            #[inline]
+            pub(crate) fn received(&self, _count: usize) {}
+
+            #[inline]
+            pub(crate) fn try_send_failed(&self) {}
+
+            #[inline]
+            pub(crate) fn try_recv_failed(&self) {}
+
+            #[inline]
+            pub(crate) fn send_waited(&self, _wait: Wait) {}
+
+            #[inline]
+            pub(crate) fn recv_waited(&self, _wait: Wait) {}
+        }
+
+        /// Measures how long an operation blocks.
+        pub(crate) struct Wait {}
+
+        impl Wait {
+            #[inline]
+            pub(crate) fn start() -> Wait {
+                Wait {}
+            }
+        }
+    }
+}
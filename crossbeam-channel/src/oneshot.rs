@@ -0,0 +1,408 @@
+//! Channel for sending a single message.
+//!
+//! The whole channel is one slot for the message and an atomic state describing it. The sender
+//! writes the message into the slot and then publishes it by changing the state, after which the
+//! receiver takes it out. Receivers wait through the [`Selectable`] contract, which is also how
+//! they take part in selection.
+
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::clock;
+use crate::custom::{Registration, RegistrationId, Selectable, Slot};
+use crate::err::{RecvError, RecvTimeoutError, SendError, TryRecvError};
+use crate::select::Select;
+use crate::utils::Spinlock;
+
+/// The message hasn't been sent yet.
+const EMPTY: usize = 0;
+
+/// The message has been sent and is waiting in the slot.
+const SENT: usize = 1;
+
+/// The message has been received, or the sender was dropped without sending it.
+const DISCONNECTED: usize = 2;
+
+/// The receiver was dropped before the message was sent.
+const RECEIVER_DROPPED: usize = 3;
+
+/// State shared between the sender and the receiver.
+struct Inner<T> {
+    /// One of `EMPTY`, `SENT`, `DISCONNECTED`, or `RECEIVER_DROPPED`.
+    state: AtomicUsize,
+
+    /// The message, initialized while the state is `SENT`.
+    msg: UnsafeCell<MaybeUninit<T>>,
+
+    /// Selects waiting for the message.
+    registrations: Spinlock<Vec<Registration>>,
+}
+
+unsafe impl<T: Send> Send for Inner<T> {}
+unsafe impl<T: Send> Sync for Inner<T> {}
+
+impl<T> Inner<T> {
+    /// Wakes up all selects waiting for the message.
+    fn notify(&self) {
+        for reg in self.registrations.lock().drain(..) {
+            reg.wake();
+        }
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == SENT {
+            unsafe {
+                ptr::drop_in_place((*self.msg.get()).as_mut_ptr());
+            }
+        }
+    }
+}
+
+/// Creates a channel for sending a single message.
+///
+/// This is lighter than [`bounded(1)`] for request/response patterns: the channel is a single
+/// slot and an atomic state, and both handles are consumed or dropped after use rather than
+/// cloned.
+///
+/// The receiver gets disconnected if the sender is dropped without sending, and sending fails if
+/// the receiver has already been dropped.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::oneshot;
+///
+/// let (s, r) = oneshot();
+///
+/// thread::spawn(move || {
+///     s.send(42).unwrap();
+/// });
+///
+/// assert_eq!(r.recv(), Ok(42));
+/// ```
+///
+/// Replying to a request:
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::{oneshot, unbounded, OneshotSender};
+///
+/// let (requests_s, requests_r) = unbounded::<(i32, OneshotSender<i32>)>();
+///
+/// thread::spawn(move || {
+///     for (n, reply) in requests_r {
+///         let _ = reply.send(n * 2);
+///     }
+/// });
+///
+/// let (reply_s, reply_r) = oneshot();
+/// requests_s.send((21, reply_s)).unwrap();
+/// assert_eq!(reply_r.recv(), Ok(42));
+/// ```
+///
+/// [`bounded(1)`]: crate::bounded
+pub fn oneshot<T>() -> (OneshotSender<T>, OneshotReceiver<T>) {
+    let inner = Arc::new(Inner {
+        state: AtomicUsize::new(EMPTY),
+        msg: UnsafeCell::new(MaybeUninit::uninit()),
+        registrations: Spinlock::new(Vec::new()),
+    });
+
+    let s = OneshotSender {
+        inner: inner.clone(),
+    };
+    let r = OneshotReceiver { inner };
+    (s, r)
+}
+
+/// The sending side of a oneshot channel.
+///
+/// Sending a message consumes the sender. Dropping it without sending disconnects the receiver.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::{oneshot, RecvError};
+///
+/// let (s, r) = oneshot::<i32>();
+/// drop(s);
+///
+/// assert_eq!(r.recv(), Err(RecvError));
+/// ```
+pub struct OneshotSender<T> {
+    /// State shared with the receiver.
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> OneshotSender<T> {
+    /// Sends the message.
+    ///
+    /// If the receiver has already been dropped, the message is returned in an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{oneshot, SendError};
+    ///
+    /// let (s, r) = oneshot();
+    /// assert_eq!(s.send(1), Ok(()));
+    /// assert_eq!(r.recv(), Ok(1));
+    ///
+    /// let (s, r) = oneshot();
+    /// drop(r);
+    /// assert_eq!(s.send(2), Err(SendError(2)));
+    /// ```
+    pub fn send(self, msg: T) -> Result<(), SendError<T>> {
+        // The receiver never touches the slot before the state becomes `SENT`.
+        unsafe {
+            (*self.inner.msg.get()).as_mut_ptr().write(msg);
+        }
+
+        match self
+            .inner
+            .state
+            .compare_exchange(EMPTY, SENT, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => {
+                self.inner.notify();
+                Ok(())
+            }
+            Err(_) => {
+                // The receiver was dropped, so take the message back.
+                let msg = unsafe { (*self.inner.msg.get()).as_ptr().read() };
+                Err(SendError(msg))
+            }
+        }
+    }
+}
+
+impl<T> Drop for OneshotSender<T> {
+    fn drop(&mut self) {
+        // This fails if the message was sent or the receiver is gone, and then there's nobody to
+        // notify.
+        if self
+            .inner
+            .state
+            .compare_exchange(EMPTY, DISCONNECTED, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+        {
+            self.inner.notify();
+        }
+    }
+}
+
+impl<T> fmt::Debug for OneshotSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("OneshotSender { .. }")
+    }
+}
+
+/// The receiving side of a oneshot channel.
+///
+/// Once the message has been received, the channel is disconnected and further receive
+/// operations fail.
+///
+/// The receiver implements [`Selectable`], so it can be waited on alongside channel operations in
+/// [`Select`] and [`select!`]. The result of such an operation is the message, or an error if the
+/// sender was dropped without sending it.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use crossbeam_channel::{oneshot, select};
+///
+/// let (s, r) = oneshot();
+/// s.send("done").unwrap();
+///
+/// select! {
+///     recv_custom(r) -> msg => assert_eq!(msg, Ok("done")),
+///     default(Duration::from_secs(1)) => println!("timed out"),
+/// }
+/// ```
+///
+/// [`select!`]: crate::select
+pub struct OneshotReceiver<T> {
+    /// State shared with the sender.
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> OneshotReceiver<T> {
+    /// Attempts to receive the message without blocking.
+    ///
+    /// If the message hasn't been sent yet, an error is returned. If the sender was dropped
+    /// without sending, or the message has already been received, the channel is disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{oneshot, TryRecvError};
+    ///
+    /// let (s, r) = oneshot();
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    ///
+    /// s.send(5).unwrap();
+    /// assert_eq!(r.try_recv(), Ok(5));
+    /// assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    /// ```
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        match self.inner.state.compare_exchange(
+            SENT,
+            DISCONNECTED,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            // The message is ours now.
+            Ok(_) => Ok(unsafe { (*self.inner.msg.get()).as_ptr().read() }),
+            Err(EMPTY) => Err(TryRecvError::Empty),
+            Err(_) => Err(TryRecvError::Disconnected),
+        }
+    }
+
+    /// Blocks the current thread until the message is received.
+    ///
+    /// If the sender is dropped without sending, or the message has already been received, an
+    /// error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{oneshot, RecvError};
+    ///
+    /// let (s, r) = oneshot();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_secs(1));
+    ///     s.send(5).unwrap();
+    /// });
+    ///
+    /// assert_eq!(r.recv(), Ok(5));
+    /// assert_eq!(r.recv(), Err(RecvError));
+    /// ```
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.recv_until(None).map_err(|_| RecvError)
+    }
+
+    /// Waits for the message for a limited time.
+    ///
+    /// If the sender is dropped without sending, or the message has already been received, an
+    /// error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{oneshot, RecvTimeoutError};
+    ///
+    /// let (s, r) = oneshot::<i32>();
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_millis(100)),
+    ///     Err(RecvTimeoutError::Timeout),
+    /// );
+    ///
+    /// drop(s);
+    /// assert_eq!(
+    ///     r.recv_timeout(Duration::from_millis(100)),
+    ///     Err(RecvTimeoutError::Disconnected),
+    /// );
+    /// ```
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_deadline(clock::now() + timeout)
+    }
+
+    /// Waits for the message until a given deadline.
+    ///
+    /// If the sender is dropped without sending, or the message has already been received, an
+    /// error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use crossbeam_channel::{oneshot, RecvTimeoutError};
+    ///
+    /// let (s, r) = oneshot();
+    /// s.send(1).unwrap();
+    ///
+    /// let deadline = Instant::now() + Duration::from_millis(100);
+    /// assert_eq!(r.recv_deadline(deadline), Ok(1));
+    /// assert_eq!(r.recv_deadline(deadline), Err(RecvTimeoutError::Disconnected));
+    /// ```
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Some(deadline))
+    }
+
+    /// Blocks until the message is received or the deadline is reached.
+    fn recv_until(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        match self.try_recv() {
+            Ok(msg) => return Ok(msg),
+            Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+            Err(TryRecvError::Empty) => {}
+        }
+
+        let mut sel = Select::new();
+        sel.recv_custom(self);
+        let oper = match deadline {
+            None => sel.select(),
+            Some(d) => sel
+                .select_deadline(d)
+                .map_err(|_| RecvTimeoutError::Timeout)?,
+        };
+        oper.recv_custom(self)
+            .map_err(|_| RecvTimeoutError::Disconnected)
+    }
+}
+
+impl<T> Selectable for OneshotReceiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn try_select(&self, _slot: &mut Slot) -> bool {
+        self.is_ready()
+    }
+
+    fn register(&self, reg: &Registration) -> bool {
+        let mut registrations = self.inner.registrations.lock();
+        registrations.push(reg.clone());
+        self.is_ready()
+    }
+
+    fn unregister(&self, id: RegistrationId) {
+        self.inner.registrations.lock().retain(|reg| reg.id() != id);
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.state.load(Ordering::Acquire) != EMPTY
+    }
+
+    fn complete(&self, _slot: &mut Slot) -> Result<T, RecvError> {
+        self.try_recv().map_err(|_| RecvError)
+    }
+}
+
+impl<T> Drop for OneshotReceiver<T> {
+    fn drop(&mut self) {
+        // If the message was already sent, it gets dropped together with the channel.
+        let _ = self.inner.state.compare_exchange(
+            EMPTY,
+            RECEIVER_DROPPED,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        );
+    }
+}
+
+impl<T> fmt::Debug for OneshotReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("OneshotReceiver { .. }")
+    }
+}
@@ -3,28 +3,38 @@
 use std::fmt;
 use std::marker::PhantomData;
 use std::mem;
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
 use std::time::{Duration, Instant};
 
 use crossbeam_utils::Backoff;
 
 use crate::channel::{self, Receiver, Sender};
+use crate::clock;
 use crate::context::Context;
+use crate::custom::{self, Selectable, Slot};
 use crate::err::{ReadyTimeoutError, TryReadyError};
 use crate::err::{RecvError, SendError};
 use crate::err::{SelectTimeoutError, TrySelectError};
+#[cfg(target_os = "linux")]
+use crate::fd;
 use crate::flavors;
 use crate::utils;
 
 /// Temporary data that gets initialized during select or a blocking operation, and is consumed by
 /// `read` or `write`.
 ///
-/// Each field contains data associated with a specific channel flavor.
+/// Each field contains data associated with a specific channel flavor, except for `custom`, which
+/// holds data for user-defined [`Selectable`] sources.
 #[derive(Debug, Default)]
 pub struct Token {
     pub at: flavors::at::AtToken,
     pub array: flavors::array::ArrayToken,
+    pub broadcast: flavors::broadcast::BroadcastToken,
+    pub custom: Slot,
     pub list: flavors::list::ListToken,
     pub never: flavors::never::NeverToken,
+    pub priority: flavors::priority::PriorityToken,
     pub tick: flavors::tick::TickToken,
     pub zero: flavors::zero::ZeroToken,
 }
@@ -47,6 +57,16 @@ impl Operation {
         assert!(val > 2);
         Operation(val)
     }
+
+    /// Creates an operation identifier for a context belonging to an asynchronous task or a
+    /// `SelectSet`.
+    ///
+    /// Such contexts are allocated anew for every registration, so the address of the context is
+    /// unique for as long as the registration is alive.
+    #[inline]
+    pub(crate) fn task(cx: &Context) -> Operation {
+        Operation(cx.addr())
+    }
 }
 
 /// Current state of a select or a blocking operation.
@@ -173,6 +193,7 @@ enum Timeout {
 fn run_select(
     handles: &mut [(&dyn SelectHandle, usize, *const u8)],
     timeout: Timeout,
+    is_biased: bool,
 ) -> Option<(Token, usize, *const u8)> {
     if handles.is_empty() {
         // Wait until the timeout and return.
@@ -189,8 +210,10 @@ fn run_select(
         }
     }
 
-    // Shuffle the operations for fairness.
-    utils::shuffle(handles);
+    // Shuffle the operations for fairness, unless they are to be tried in order.
+    if !is_biased {
+        utils::shuffle(handles);
+    }
 
     // Create a token, which serves as a temporary variable that gets initialized in this function
     // and is later used by a call to `channel::read()` or `channel::write()` that completes the
@@ -309,7 +332,7 @@ fn run_select(
             Timeout::Now => return None,
             Timeout::Never => {}
             Timeout::At(when) => {
-                if Instant::now() >= when {
+                if clock::now() >= when {
                     return None;
                 }
             }
@@ -321,6 +344,7 @@ fn run_select(
 fn run_ready(
     handles: &mut [(&dyn SelectHandle, usize, *const u8)],
     timeout: Timeout,
+    is_biased: bool,
 ) -> Option<usize> {
     if handles.is_empty() {
         // Wait until the timeout and return.
@@ -337,8 +361,10 @@ fn run_ready(
         }
     }
 
-    // Shuffle the operations for fairness.
-    utils::shuffle(handles);
+    // Shuffle the operations for fairness, unless they are to be tried in order.
+    if !is_biased {
+        utils::shuffle(handles);
+    }
 
     loop {
         let backoff = Backoff::new();
@@ -362,7 +388,7 @@ fn run_ready(
             Timeout::Now => return None,
             Timeout::Never => {}
             Timeout::At(when) => {
-                if Instant::now() >= when {
+                if clock::now() >= when {
                     return None;
                 }
             }
@@ -445,8 +471,9 @@ fn run_ready(
 #[inline]
 pub fn try_select<'a>(
     handles: &mut [(&'a dyn SelectHandle, usize, *const u8)],
+    is_biased: bool,
 ) -> Result<SelectedOperation<'a>, TrySelectError> {
-    match run_select(handles, Timeout::Now) {
+    match run_select(handles, Timeout::Now, is_biased) {
         None => Err(TrySelectError),
         Some((token, index, ptr)) => Ok(SelectedOperation {
             token,
@@ -461,12 +488,13 @@ pub fn try_select<'a>(
 #[inline]
 pub fn select<'a>(
     handles: &mut [(&'a dyn SelectHandle, usize, *const u8)],
+    is_biased: bool,
 ) -> SelectedOperation<'a> {
     if handles.is_empty() {
         panic!("no operations have been added to `Select`");
     }
 
-    let (token, index, ptr) = run_select(handles, Timeout::Never).unwrap();
+    let (token, index, ptr) = run_select(handles, Timeout::Never, is_biased).unwrap();
     SelectedOperation {
         token,
         index,
@@ -480,8 +508,9 @@ pub fn select<'a>(
 pub fn select_timeout<'a>(
     handles: &mut [(&'a dyn SelectHandle, usize, *const u8)],
     timeout: Duration,
+    is_biased: bool,
 ) -> Result<SelectedOperation<'a>, SelectTimeoutError> {
-    select_deadline(handles, Instant::now() + timeout)
+    select_deadline(handles, clock::now() + timeout, is_biased)
 }
 
 /// Blocks until a given deadline, or until one of the operations becomes ready and selects it.
@@ -489,8 +518,9 @@ pub fn select_timeout<'a>(
 pub(crate) fn select_deadline<'a>(
     handles: &mut [(&'a dyn SelectHandle, usize, *const u8)],
     deadline: Instant,
+    is_biased: bool,
 ) -> Result<SelectedOperation<'a>, SelectTimeoutError> {
-    match run_select(handles, Timeout::At(deadline)) {
+    match run_select(handles, Timeout::At(deadline), is_biased) {
         None => Err(SelectTimeoutError),
         Some((token, index, ptr)) => Ok(SelectedOperation {
             token,
@@ -505,7 +535,7 @@ pub(crate) fn select_deadline<'a>(
 ///
 /// `Select` allows you to define a set of channel operations, wait until any one of them becomes
 /// ready, and finally execute it. If multiple operations are ready at the same time, a random one
-/// among them is selected.
+/// among them is selected, unless the `Select` was created with [`new_biased`].
 ///
 /// An operation is considered to be ready if it doesn't have to block. Note that it is ready even
 /// when it will simply return an error because the channel is disconnected.
@@ -577,6 +607,7 @@ pub(crate) fn select_deadline<'a>(
 /// }
 /// ```
 ///
+/// [`new_biased`]: Select::new_biased
 /// [`try_select`]: Select::try_select
 /// [`select`]: Select::select
 /// [`select_timeout`]: Select::select_timeout
@@ -589,6 +620,9 @@ pub struct Select<'a> {
 
     /// The next index to assign to an operation.
     next_index: usize,
+
+    /// Whether to try operations in the order they were added instead of a random one.
+    is_biased: bool,
 }
 
 unsafe impl Send for Select<'_> {}
@@ -611,6 +645,44 @@ impl<'a> Select<'a> {
         Select {
             handles: Vec::with_capacity(4),
             next_index: 0,
+            is_biased: false,
+        }
+    }
+
+    /// Creates an empty list of channel operations for biased selection.
+    ///
+    /// When multiple operations are ready at the same time, the one that was added first is
+    /// selected, rather than a random one. This makes it possible to give some operations
+    /// priority over others, at the cost of fairness: an operation that is always ready can
+    /// starve the operations added after it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (s1, r1) = unbounded();
+    /// let (s2, r2) = unbounded();
+    /// s1.send("shutdown").unwrap();
+    /// s2.send("data").unwrap();
+    ///
+    /// let mut sel = Select::new_biased();
+    /// let shutdown = sel.recv(&r1);
+    /// let data = sel.recv(&r2);
+    ///
+    /// // Both operations are ready, so the first one is always selected.
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), shutdown);
+    /// assert_eq!(oper.recv(&r1), Ok("shutdown"));
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), data);
+    /// assert_eq!(oper.recv(&r2), Ok("data"));
+    /// ```
+    pub fn new_biased() -> Select<'a> {
+        Select {
+            is_biased: true,
+            ..Select::new()
         }
     }
 
@@ -658,6 +730,108 @@ impl<'a> Select<'a> {
         i
     }
 
+    /// Adds an operation on a user-defined source.
+    ///
+    /// Returns the index of the added operation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Registration, RegistrationId, Select, Selectable, Slot};
+    ///
+    /// // A source that is always ready and yields `42`.
+    /// struct Always;
+    ///
+    /// impl Selectable for Always {
+    ///     type Output = i32;
+    ///
+    ///     fn try_select(&self, _slot: &mut Slot) -> bool { true }
+    ///     fn register(&self, _reg: &Registration) -> bool { true }
+    ///     fn unregister(&self, _id: RegistrationId) {}
+    ///     fn is_ready(&self) -> bool { true }
+    ///     fn complete(&self, _slot: &mut Slot) -> i32 { 42 }
+    /// }
+    ///
+    /// let mut sel = Select::new();
+    /// let index = sel.recv_custom(&Always);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), index);
+    /// assert_eq!(oper.recv_custom(&Always), 42);
+    /// ```
+    pub fn recv_custom<S: Selectable>(&mut self, source: &'a S) -> usize {
+        let i = self.next_index;
+        let ptr = source as *const S as *const u8;
+        self.handles.push((custom::custom_handle(source), i, ptr));
+        self.next_index += 1;
+        i
+    }
+
+    /// Adds an operation that waits until a file descriptor becomes readable.
+    ///
+    /// Returns the index of the added operation. See [`Readable`] for when the operation is
+    /// considered ready.
+    ///
+    /// [`Readable`]: crate::Readable
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use std::os::unix::net::UnixStream;
+    /// use std::thread;
+    /// use crossbeam_channel::{unbounded, Select};
+    ///
+    /// let (mut a, mut b) = UnixStream::pair().unwrap();
+    /// let (s, r) = unbounded::<i32>();
+    ///
+    /// thread::spawn(move || a.write_all(b"hello").unwrap());
+    ///
+    /// let mut sel = Select::new();
+    /// sel.recv(&r);
+    /// let index = sel.readable(&b);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), index);
+    /// oper.readable(&b);
+    ///
+    /// let mut buf = [0; 5];
+    /// b.read_exact(&mut buf).unwrap();
+    /// assert_eq!(&buf, b"hello");
+    /// # drop(s);
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn readable<F: AsRawFd>(&mut self, f: &'a F) -> usize {
+        self.recv_custom(fd::readable(f))
+    }
+
+    /// Adds an operation that waits until a file descriptor becomes writable.
+    ///
+    /// Returns the index of the added operation. See [`Writable`] for when the operation is
+    /// considered ready.
+    ///
+    /// [`Writable`]: crate::Writable
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::os::unix::net::UnixStream;
+    /// use crossbeam_channel::Select;
+    ///
+    /// let (a, _b) = UnixStream::pair().unwrap();
+    ///
+    /// let mut sel = Select::new();
+    /// let index = sel.writable(&a);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), index);
+    /// oper.writable(&a);
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn writable<F: AsRawFd>(&mut self, f: &'a F) -> usize {
+        self.recv_custom(fd::writable(f))
+    }
+
     /// Removes a previously added operation.
     ///
     /// This is useful when an operation is selected because the channel got disconnected and we
@@ -710,7 +884,11 @@ impl<'a> Select<'a> {
             .expect("no operation with this index")
             .0;
 
-        self.handles.swap_remove(i);
+        if self.is_biased {
+            self.handles.remove(i);
+        } else {
+            self.handles.swap_remove(i);
+        }
     }
 
     /// Attempts to select one of the operations without blocking.
@@ -752,7 +930,7 @@ impl<'a> Select<'a> {
     /// }
     /// ```
     pub fn try_select(&mut self) -> Result<SelectedOperation<'a>, TrySelectError> {
-        try_select(&mut self.handles)
+        try_select(&mut self.handles, self.is_biased)
     }
 
     /// Blocks until one of the operations becomes ready and selects it.
@@ -799,7 +977,7 @@ impl<'a> Select<'a> {
     /// }
     /// ```
     pub fn select(&mut self) -> SelectedOperation<'a> {
-        select(&mut self.handles)
+        select(&mut self.handles, self.is_biased)
     }
 
     /// Blocks for a limited time until one of the operations becomes ready and selects it.
@@ -849,7 +1027,7 @@ impl<'a> Select<'a> {
         &mut self,
         timeout: Duration,
     ) -> Result<SelectedOperation<'a>, SelectTimeoutError> {
-        select_timeout(&mut self.handles, timeout)
+        select_timeout(&mut self.handles, timeout, self.is_biased)
     }
 
     /// Blocks until a given deadline, or until one of the operations becomes ready and selects it.
@@ -901,7 +1079,7 @@ impl<'a> Select<'a> {
         &mut self,
         deadline: Instant,
     ) -> Result<SelectedOperation<'a>, SelectTimeoutError> {
-        select_deadline(&mut self.handles, deadline)
+        select_deadline(&mut self.handles, deadline, self.is_biased)
     }
 
     /// Attempts to find a ready operation without blocking.
@@ -940,7 +1118,7 @@ impl<'a> Select<'a> {
     /// }
     /// ```
     pub fn try_ready(&mut self) -> Result<usize, TryReadyError> {
-        match run_ready(&mut self.handles, Timeout::Now) {
+        match run_ready(&mut self.handles, Timeout::Now, self.is_biased) {
             None => Err(TryReadyError),
             Some(index) => Ok(index),
         }
@@ -993,7 +1171,7 @@ impl<'a> Select<'a> {
             panic!("no operations have been added to `Select`");
         }
 
-        run_ready(&mut self.handles, Timeout::Never).unwrap()
+        run_ready(&mut self.handles, Timeout::Never, self.is_biased).unwrap()
     }
 
     /// Blocks for a limited time until one of the operations becomes ready.
@@ -1037,7 +1215,7 @@ impl<'a> Select<'a> {
     /// }
     /// ```
     pub fn ready_timeout(&mut self, timeout: Duration) -> Result<usize, ReadyTimeoutError> {
-        self.ready_deadline(Instant::now() + timeout)
+        self.ready_deadline(clock::now() + timeout)
     }
 
     /// Blocks until a given deadline, or until one of the operations becomes ready.
@@ -1083,7 +1261,7 @@ impl<'a> Select<'a> {
     /// }
     /// ```
     pub fn ready_deadline(&mut self, deadline: Instant) -> Result<usize, ReadyTimeoutError> {
-        match run_ready(&mut self.handles, Timeout::At(deadline)) {
+        match run_ready(&mut self.handles, Timeout::At(deadline), self.is_biased) {
             None => Err(ReadyTimeoutError),
             Some(index) => Ok(index),
         }
@@ -1095,6 +1273,7 @@ impl<'a> Clone for Select<'a> {
         Select {
             handles: self.handles.clone(),
             next_index: self.next_index,
+            is_biased: self.is_biased,
         }
     }
 }
@@ -1113,7 +1292,7 @@ impl fmt::Debug for Select<'_> {
 
 /// A selected operation that needs to be completed.
 ///
-/// To complete the operation, call [`send`] or [`recv`].
+/// To complete the operation, call [`send`], [`recv`], or [`recv_custom`].
 ///
 /// # Panics
 ///
@@ -1122,6 +1301,7 @@ impl fmt::Debug for Select<'_> {
 ///
 /// [`send`]: SelectedOperation::send
 /// [`recv`]: SelectedOperation::recv
+/// [`recv_custom`]: SelectedOperation::recv_custom
 #[must_use]
 pub struct SelectedOperation<'a> {
     /// Token needed to complete the operation.
@@ -1130,7 +1310,7 @@ pub struct SelectedOperation<'a> {
     /// The index of the selected operation.
     index: usize,
 
-    /// The address of the selected `Sender` or `Receiver`.
+    /// The address of the selected `Sender`, `Receiver`, or custom source.
     ptr: *const u8,
 
     /// Indicates that `Sender`s and `Receiver`s are borrowed.
@@ -1233,6 +1413,82 @@ impl SelectedOperation<'_> {
         mem::forget(self);
         res.map_err(|_| RecvError)
     }
+
+    /// Completes the operation on a user-defined source.
+    ///
+    /// The passed source reference must be the same one that was used in [`Select::recv_custom`]
+    /// when the operation was added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an incorrect source reference is passed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{Registration, RegistrationId, Select, Selectable, Slot};
+    ///
+    /// // A source that hands out the value stored in the slot by `try_select`.
+    /// struct Value(i32);
+    ///
+    /// impl Selectable for Value {
+    ///     type Output = i32;
+    ///
+    ///     fn try_select(&self, slot: &mut Slot) -> bool {
+    ///         slot.put(self.0);
+    ///         true
+    ///     }
+    ///     fn register(&self, _reg: &Registration) -> bool { true }
+    ///     fn unregister(&self, _id: RegistrationId) {}
+    ///     fn is_ready(&self) -> bool { true }
+    ///     fn complete(&self, slot: &mut Slot) -> i32 {
+    ///         slot.take().unwrap()
+    ///     }
+    /// }
+    ///
+    /// let v = Value(7);
+    /// let mut sel = Select::new();
+    /// let oper1 = sel.recv_custom(&v);
+    ///
+    /// let oper = sel.select();
+    /// assert_eq!(oper.index(), oper1);
+    /// assert_eq!(oper.recv_custom(&v), 7);
+    /// ```
+    pub fn recv_custom<S: Selectable>(mut self, source: &S) -> S::Output {
+        assert!(
+            source as *const S as *const u8 == self.ptr,
+            "passed a source that wasn't selected",
+        );
+        let res = source.complete(&mut self.token.custom);
+        mem::forget(self);
+        res
+    }
+
+    /// Completes the operation that waited for a file descriptor to become readable.
+    ///
+    /// The passed reference must be the same one that was used in [`Select::readable`] when the
+    /// operation was added. Reading from the file descriptor is left to the caller.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an incorrect reference is passed.
+    #[cfg(target_os = "linux")]
+    pub fn readable<F: AsRawFd>(self, f: &F) {
+        self.recv_custom(fd::readable(f))
+    }
+
+    /// Completes the operation that waited for a file descriptor to become writable.
+    ///
+    /// The passed reference must be the same one that was used in [`Select::writable`] when the
+    /// operation was added. Writing to the file descriptor is left to the caller.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an incorrect reference is passed.
+    #[cfg(target_os = "linux")]
+    pub fn writable<F: AsRawFd>(self, f: &F) {
+        self.recv_custom(fd::writable(f))
+    }
 }
 
 impl fmt::Debug for SelectedOperation<'_> {
@@ -14,8 +14,11 @@
 /// The codegen stage consists of these subparts:
 /// 1. `@init`: Attempts to optimize `select!` away and initializes the list of handles.
 /// 1. `@count`: Counts the listed cases.
-/// 3. `@add`: Adds send/receive operations to the list of handles and starts selection.
-/// 4. `@complete`: Completes the selected send/receive operation.
+/// 3. `@add`: Adds send/receive/custom operations to the list of handles and starts selection.
+/// 4. `@complete`: Completes the selected send/receive/custom operation.
+///
+/// The entry points take a leading `@fair` or `@biased` marker, which is turned into a `false` or
+/// `true` token and carried through every stage so that `@add` knows how to run selection.
 ///
 /// If the parsing stage encounters a syntax error or the codegen stage ends up with too many
 /// cases to process, the macro fails with a compile-time error.
@@ -24,11 +27,13 @@
 macro_rules! crossbeam_channel_internal {
     // The list is empty. Now check the arguments of each processed case.
     (@list
+        $bias:tt
         ()
         ($($head:tt)*)
     ) => {
         $crate::crossbeam_channel_internal!(
             @case
+            $bias
             ($($head)*)
             ()
             ()
@@ -36,17 +41,20 @@ macro_rules! crossbeam_channel_internal {
     };
     // If necessary, insert an empty argument list after `default`.
     (@list
+        $bias:tt
         (default => $($tail:tt)*)
         ($($head:tt)*)
     ) => {
         $crate::crossbeam_channel_internal!(
             @list
+            $bias
             (default() => $($tail)*)
             ($($head)*)
         )
     };
     // But print an error if `default` is followed by a `->`.
     (@list
+        $bias:tt
         (default -> $($tail:tt)*)
         ($($head:tt)*)
     ) => {
@@ -56,6 +64,7 @@ macro_rules! crossbeam_channel_internal {
     };
     // Print an error if there's an `->` after the argument list in the default case.
     (@list
+        $bias:tt
         (default $args:tt -> $($tail:tt)*)
         ($($head:tt)*)
     ) => {
@@ -65,6 +74,7 @@ macro_rules! crossbeam_channel_internal {
     };
     // Print an error if there is a missing result in a recv case.
     (@list
+        $bias:tt
         (recv($($args:tt)*) => $($tail:tt)*)
         ($($head:tt)*)
     ) => {
@@ -72,8 +82,19 @@ macro_rules! crossbeam_channel_internal {
             "expected `->` after `recv` case, found `=>`"
         )
     };
+    // Print an error if there is a missing result in a custom receive case.
+    (@list
+        $bias:tt
+        (recv_custom($($args:tt)*) => $($tail:tt)*)
+        ($($head:tt)*)
+    ) => {
+        compile_error!(
+            "expected `->` after `recv_custom` case, found `=>`"
+        )
+    };
     // Print an error if there is a missing result in a send case.
     (@list
+        $bias:tt
         (send($($args:tt)*) => $($tail:tt)*)
         ($($head:tt)*)
     ) => {
@@ -83,6 +104,7 @@ macro_rules! crossbeam_channel_internal {
     };
     // Make sure the arrow and the result are not repeated.
     (@list
+        $bias:tt
         ($case:ident $args:tt -> $res:tt -> $($tail:tt)*)
         ($($head:tt)*)
     ) => {
@@ -90,6 +112,7 @@ macro_rules! crossbeam_channel_internal {
     };
     // Print an error if there is a semicolon after the block.
     (@list
+        $bias:tt
         ($case:ident $args:tt $(-> $res:pat)* => $body:block; $($tail:tt)*)
         ($($head:tt)*)
     ) => {
@@ -99,50 +122,59 @@ macro_rules! crossbeam_channel_internal {
     };
     // The first case is separated by a comma.
     (@list
+        $bias:tt
         ($case:ident ($($args:tt)*) $(-> $res:pat)* => $body:expr, $($tail:tt)*)
         ($($head:tt)*)
     ) => {
         $crate::crossbeam_channel_internal!(
             @list
+            $bias
             ($($tail)*)
             ($($head)* $case ($($args)*) $(-> $res)* => { $body },)
         )
     };
     // Don't require a comma after the case if it has a proper block.
     (@list
+        $bias:tt
         ($case:ident ($($args:tt)*) $(-> $res:pat)* => $body:block $($tail:tt)*)
         ($($head:tt)*)
     ) => {
         $crate::crossbeam_channel_internal!(
             @list
+            $bias
             ($($tail)*)
             ($($head)* $case ($($args)*) $(-> $res)* => { $body },)
         )
     };
     // Only one case remains.
     (@list
+        $bias:tt
         ($case:ident ($($args:tt)*) $(-> $res:pat)* => $body:expr)
         ($($head:tt)*)
     ) => {
         $crate::crossbeam_channel_internal!(
             @list
+            $bias
             ()
             ($($head)* $case ($($args)*) $(-> $res)* => { $body },)
         )
     };
     // Accept a trailing comma at the end of the list.
     (@list
+        $bias:tt
         ($case:ident ($($args:tt)*) $(-> $res:pat)* => $body:expr,)
         ($($head:tt)*)
     ) => {
         $crate::crossbeam_channel_internal!(
             @list
+            $bias
             ()
             ($($head)* $case ($($args)*) $(-> $res)* => { $body },)
         )
     };
     // Diagnose and print an error.
     (@list
+        $bias:tt
         ($($tail:tt)*)
         ($($head:tt)*)
     ) => {
@@ -152,6 +184,9 @@ macro_rules! crossbeam_channel_internal {
     (@list_error1 recv $($tail:tt)*) => {
         $crate::crossbeam_channel_internal!(@list_error2 recv $($tail)*)
     };
+    (@list_error1 recv_custom $($tail:tt)*) => {
+        $crate::crossbeam_channel_internal!(@list_error2 recv_custom $($tail)*)
+    };
     (@list_error1 send $($tail:tt)*) => {
         $crate::crossbeam_channel_internal!(@list_error2 send $($tail)*)
     };
@@ -161,7 +196,7 @@ macro_rules! crossbeam_channel_internal {
     (@list_error1 $t:tt $($tail:tt)*) => {
         compile_error!(
             concat!(
-                "expected one of `recv`, `send`, or `default`, found `",
+                "expected one of `recv`, `recv_custom`, `send`, or `default`, found `",
                 stringify!($t),
                 "`",
             )
@@ -221,6 +256,11 @@ macro_rules! crossbeam_channel_internal {
             "expected an expression after `=>`"
         )
     };
+    (@list_error3 $case:ident($($args:tt)*) $(-> $r:pat)* => recv_custom($($a:tt)*) $($tail:tt)*) => {
+        compile_error!(
+            "expected an expression after `=>`"
+        )
+    };
     (@list_error3 $case:ident($($args:tt)*) $(-> $r:pat)* => send($($a:tt)*) $($tail:tt)*) => {
         compile_error!(
             "expected an expression after `=>`"
@@ -314,6 +354,15 @@ macro_rules! crossbeam_channel_internal {
             )
         )
     };
+    (@list_error3 recv_custom($($args:tt)*) $t:tt $($tail:tt)*) => {
+        compile_error!(
+            concat!(
+                "expected `->`, found `",
+                stringify!($t),
+                "`",
+            )
+        )
+    };
     (@list_error3 send($($args:tt)*) $t:tt $($tail:tt)*) => {
         compile_error!(
             concat!(
@@ -332,6 +381,15 @@ macro_rules! crossbeam_channel_internal {
             )
         )
     };
+    (@list_error3 recv_custom $args:tt $($tail:tt)*) => {
+        compile_error!(
+            concat!(
+                "expected an argument list after `recv_custom`, found `",
+                stringify!($args),
+                "`",
+            )
+        )
+    };
     (@list_error3 send $args:tt $($tail:tt)*) => {
         compile_error!(
             concat!(
@@ -360,12 +418,14 @@ macro_rules! crossbeam_channel_internal {
 
     // Success! All cases were parsed.
     (@case
+        $bias:tt
         ()
         $cases:tt
         $default:tt
     ) => {
         $crate::crossbeam_channel_internal!(
             @init
+            $bias
             $cases
             $default
         )
@@ -373,12 +433,14 @@ macro_rules! crossbeam_channel_internal {
 
     // Check the format of a recv case.
     (@case
+        $bias:tt
         (recv($r:expr) -> $res:pat => $body:tt, $($tail:tt)*)
         ($($cases:tt)*)
         $default:tt
     ) => {
         $crate::crossbeam_channel_internal!(
             @case
+            $bias
             ($($tail)*)
             ($($cases)* recv($r) -> $res => $body,)
             $default
@@ -386,12 +448,14 @@ macro_rules! crossbeam_channel_internal {
     };
     // Allow trailing comma...
     (@case
+        $bias:tt
         (recv($r:expr,) -> $res:pat => $body:tt, $($tail:tt)*)
         ($($cases:tt)*)
         $default:tt
     ) => {
         $crate::crossbeam_channel_internal!(
             @case
+            $bias
             ($($tail)*)
             ($($cases)* recv($r) -> $res => $body,)
             $default
@@ -399,6 +463,7 @@ macro_rules! crossbeam_channel_internal {
     };
     // Print an error if the argument list is invalid.
     (@case
+        $bias:tt
         (recv($($args:tt)*) -> $res:pat => $body:tt, $($tail:tt)*)
         ($($cases:tt)*)
         $default:tt
@@ -413,6 +478,7 @@ macro_rules! crossbeam_channel_internal {
     };
     // Print an error if there is no argument list.
     (@case
+        $bias:tt
         (recv $t:tt $($tail:tt)*)
         ($($cases:tt)*)
         $default:tt
@@ -426,14 +492,77 @@ macro_rules! crossbeam_channel_internal {
         )
     };
 
+    // Check the format of a custom receive case.
+    (@case
+        $bias:tt
+        (recv_custom($r:expr) -> $res:pat => $body:tt, $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        $crate::crossbeam_channel_internal!(
+            @case
+            $bias
+            ($($tail)*)
+            ($($cases)* recv_custom($r) -> $res => $body,)
+            $default
+        )
+    };
+    // Allow trailing comma...
+    (@case
+        $bias:tt
+        (recv_custom($r:expr,) -> $res:pat => $body:tt, $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        $crate::crossbeam_channel_internal!(
+            @case
+            $bias
+            ($($tail)*)
+            ($($cases)* recv_custom($r) -> $res => $body,)
+            $default
+        )
+    };
+    // Print an error if the argument list is invalid.
+    (@case
+        $bias:tt
+        (recv_custom($($args:tt)*) -> $res:pat => $body:tt, $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        compile_error!(
+            concat!(
+                "invalid argument list in `recv_custom(",
+                stringify!($($args)*),
+                ")`",
+            )
+        )
+    };
+    // Print an error if there is no argument list.
+    (@case
+        $bias:tt
+        (recv_custom $t:tt $($tail:tt)*)
+        ($($cases:tt)*)
+        $default:tt
+    ) => {
+        compile_error!(
+            concat!(
+                "expected an argument list after `recv_custom`, found `",
+                stringify!($t),
+                "`",
+            )
+        )
+    };
+
     // Check the format of a send case.
     (@case
+        $bias:tt
         (send($s:expr, $m:expr) -> $res:pat => $body:tt, $($tail:tt)*)
         ($($cases:tt)*)
         $default:tt
     ) => {
         $crate::crossbeam_channel_internal!(
             @case
+            $bias
             ($($tail)*)
             ($($cases)* send($s, $m) -> $res => $body,)
             $default
@@ -441,12 +570,14 @@ macro_rules! crossbeam_channel_internal {
     };
     // Allow trailing comma...
     (@case
+        $bias:tt
         (send($s:expr, $m:expr,) -> $res:pat => $body:tt, $($tail:tt)*)
         ($($cases:tt)*)
         $default:tt
     ) => {
         $crate::crossbeam_channel_internal!(
             @case
+            $bias
             ($($tail)*)
             ($($cases)* send($s, $m) -> $res => $body,)
             $default
@@ -454,6 +585,7 @@ macro_rules! crossbeam_channel_internal {
     };
     // Print an error if the argument list is invalid.
     (@case
+        $bias:tt
         (send($($args:tt)*) -> $res:pat => $body:tt, $($tail:tt)*)
         ($($cases:tt)*)
         $default:tt
@@ -468,6 +600,7 @@ macro_rules! crossbeam_channel_internal {
     };
     // Print an error if there is no argument list.
     (@case
+        $bias:tt
         (send $t:tt $($tail:tt)*)
         ($($cases:tt)*)
         $default:tt
@@ -483,12 +616,14 @@ macro_rules! crossbeam_channel_internal {
 
     // Check the format of a default case.
     (@case
+        $bias:tt
         (default() => $body:tt, $($tail:tt)*)
         $cases:tt
         ()
     ) => {
         $crate::crossbeam_channel_internal!(
             @case
+            $bias
             ($($tail)*)
             $cases
             (default() => $body,)
@@ -496,12 +631,14 @@ macro_rules! crossbeam_channel_internal {
     };
     // Check the format of a default case with timeout.
     (@case
+        $bias:tt
         (default($timeout:expr) => $body:tt, $($tail:tt)*)
         $cases:tt
         ()
     ) => {
         $crate::crossbeam_channel_internal!(
             @case
+            $bias
             ($($tail)*)
             $cases
             (default($timeout) => $body,)
@@ -509,12 +646,14 @@ macro_rules! crossbeam_channel_internal {
     };
     // Allow trailing comma...
     (@case
+        $bias:tt
         (default($timeout:expr,) => $body:tt, $($tail:tt)*)
         $cases:tt
         ()
     ) => {
         $crate::crossbeam_channel_internal!(
             @case
+            $bias
             ($($tail)*)
             $cases
             (default($timeout) => $body,)
@@ -522,6 +661,7 @@ macro_rules! crossbeam_channel_internal {
     };
     // Check for duplicate default cases...
     (@case
+        $bias:tt
         (default $($tail:tt)*)
         $cases:tt
         ($($def:tt)+)
@@ -532,6 +672,7 @@ macro_rules! crossbeam_channel_internal {
     };
     // Print an error if the argument list is invalid.
     (@case
+        $bias:tt
         (default($($args:tt)*) => $body:tt, $($tail:tt)*)
         $cases:tt
         $default:tt
@@ -546,6 +687,7 @@ macro_rules! crossbeam_channel_internal {
     };
     // Print an error if there is an unexpected token after `default`.
     (@case
+        $bias:tt
         (default $t:tt $($tail:tt)*)
         $cases:tt
         $default:tt
@@ -561,13 +703,14 @@ macro_rules! crossbeam_channel_internal {
 
     // The case was not consumed, therefore it must be invalid.
     (@case
+        $bias:tt
         ($case:ident $($tail:tt)*)
         $cases:tt
         $default:tt
     ) => {
         compile_error!(
             concat!(
-                "expected one of `recv`, `send`, or `default`, found `",
+                "expected one of `recv`, `recv_custom`, `send`, or `default`, found `",
                 stringify!($case),
                 "`",
             )
@@ -576,6 +719,7 @@ macro_rules! crossbeam_channel_internal {
 
     // Optimize `select!` into `try_recv()`.
     (@init
+        $bias:tt
         (recv($r:expr) -> $res:pat => $recv_body:tt,)
         (default() => $default_body:tt,)
     ) => {{
@@ -597,6 +741,7 @@ macro_rules! crossbeam_channel_internal {
     }};
     // Optimize `select!` into `recv()`.
     (@init
+        $bias:tt
         (recv($r:expr) -> $res:pat => $body:tt,)
         ()
     ) => {{
@@ -611,6 +756,7 @@ macro_rules! crossbeam_channel_internal {
     }};
     // Optimize `select!` into `recv_timeout()`.
     (@init
+        $bias:tt
         (recv($r:expr) -> $res:pat => $recv_body:tt,)
         (default($timeout:expr) => $default_body:tt,)
     ) => {{
@@ -731,6 +877,7 @@ macro_rules! crossbeam_channel_internal {
 
     // Create the list of handles and add operations to it.
     (@init
+        $bias:tt
         ($($cases:tt)*)
         $default:tt
     ) => {{
@@ -742,6 +889,7 @@ macro_rules! crossbeam_channel_internal {
 
         $crate::crossbeam_channel_internal!(
             @add
+            $bias
             _sel
             ($($cases)*)
             $default
@@ -793,6 +941,7 @@ macro_rules! crossbeam_channel_internal {
 
     // Run blocking selection.
     (@add
+        $bias:tt
         $sel:ident
         ()
         ()
@@ -800,7 +949,7 @@ macro_rules! crossbeam_channel_internal {
         $cases:tt
     ) => {{
         let _oper: $crate::SelectedOperation<'_> = {
-            let _oper = $crate::internal::select(&mut $sel);
+            let _oper = $crate::internal::select(&mut $sel, $bias);
 
             // Erase the lifetime so that `sel` can be dropped early even without NLL.
             unsafe { ::std::mem::transmute(_oper) }
@@ -815,6 +964,7 @@ macro_rules! crossbeam_channel_internal {
     }};
     // Run non-blocking selection.
     (@add
+        $bias:tt
         $sel:ident
         ()
         (default() => $body:tt,)
@@ -822,7 +972,7 @@ macro_rules! crossbeam_channel_internal {
         $cases:tt
     ) => {{
         let _oper: ::std::option::Option<$crate::SelectedOperation<'_>> = {
-            let _oper = $crate::internal::try_select(&mut $sel);
+            let _oper = $crate::internal::try_select(&mut $sel, $bias);
 
             // Erase the lifetime so that `sel` can be dropped early even without NLL.
             unsafe { ::std::mem::transmute(_oper) }
@@ -845,6 +995,7 @@ macro_rules! crossbeam_channel_internal {
     }};
     // Run selection with a timeout.
     (@add
+        $bias:tt
         $sel:ident
         ()
         (default($timeout:expr) => $body:tt,)
@@ -852,7 +1003,7 @@ macro_rules! crossbeam_channel_internal {
         $cases:tt
     ) => {{
         let _oper: ::std::option::Option<$crate::SelectedOperation<'_>> = {
-            let _oper = $crate::internal::select_timeout(&mut $sel, $timeout);
+            let _oper = $crate::internal::select_timeout(&mut $sel, $timeout, $bias);
 
             // Erase the lifetime so that `sel` can be dropped early even without NLL.
             unsafe { ::std::mem::transmute(_oper) }
@@ -875,6 +1026,7 @@ macro_rules! crossbeam_channel_internal {
     }};
     // Have we used up all labels?
     (@add
+        $bias:tt
         $sel:ident
         $input:tt
         $default:tt
@@ -885,6 +1037,7 @@ macro_rules! crossbeam_channel_internal {
     };
     // Add a receive operation to `sel`.
     (@add
+        $bias:tt
         $sel:ident
         (recv($r:expr) -> $res:pat => $body:tt, $($tail:tt)*)
         $default:tt
@@ -906,6 +1059,7 @@ macro_rules! crossbeam_channel_internal {
 
                 $crate::crossbeam_channel_internal!(
                     @add
+                    $bias
                     $sel
                     ($($tail)*)
                     $default
@@ -915,8 +1069,45 @@ macro_rules! crossbeam_channel_internal {
             }
         }
     }};
+    // Add an operation on a custom source to `sel`.
+    (@add
+        $bias:tt
+        $sel:ident
+        (recv_custom($r:expr) -> $res:pat => $body:tt, $($tail:tt)*)
+        $default:tt
+        (($i:tt $var:ident) $($labels:tt)*)
+        ($($cases:tt)*)
+    ) => {{
+        match $r {
+            ref _r => {
+                let $var = unsafe {
+                    // Erase the lifetime so that `sel` can be dropped early even without NLL.
+                    unsafe fn unbind<'a, T>(x: &T) -> &'a T {
+                        ::std::mem::transmute(x)
+                    }
+                    unbind(_r)
+                };
+                $sel[$i] = (
+                    $crate::internal::custom_handle($var),
+                    $i,
+                    $var as *const _ as *const u8,
+                );
+
+                $crate::crossbeam_channel_internal!(
+                    @add
+                    $bias
+                    $sel
+                    ($($tail)*)
+                    $default
+                    ($($labels)*)
+                    ($($cases)* [$i] recv_custom($var) -> $res => $body,)
+                )
+            }
+        }
+    }};
     // Add a send operation to `sel`.
     (@add
+        $bias:tt
         $sel:ident
         (send($s:expr, $m:expr) -> $res:pat => $body:tt, $($tail:tt)*)
         $default:tt
@@ -938,6 +1129,7 @@ macro_rules! crossbeam_channel_internal {
 
                 $crate::crossbeam_channel_internal!(
                     @add
+                    $bias
                     $sel
                     ($($tail)*)
                     $default
@@ -969,6 +1161,27 @@ macro_rules! crossbeam_channel_internal {
             }
         }
     }};
+    // Complete an operation on a custom source.
+    (@complete
+        $sel:ident
+        $oper:ident
+        ([$i:tt] recv_custom($r:ident) -> $res:pat => $body:tt, $($tail:tt)*)
+    ) => {{
+        if $oper.index() == $i {
+            let _res = $oper.recv_custom($r);
+            { $sel };
+
+            let $res = _res;
+            $body
+        } else {
+            $crate::crossbeam_channel_internal! {
+                @complete
+                $sel
+                $oper
+                ($($tail)*)
+            }
+        }
+    }};
     // Complete a send operation.
     (@complete
         $sel:ident
@@ -1001,41 +1214,70 @@ macro_rules! crossbeam_channel_internal {
         )
     }};
 
-    // Catches a bug within this macro (should not happen).
-    (@$($tokens:tt)*) => {
-        compile_error!(
-            concat!(
-                "internal error in crossbeam-channel: ",
-                stringify!(@$($tokens)*),
-            )
-        )
-    };
-
-    // The entry points.
-    () => {
+    // The entry points. The leading marker says whether selection is biased.
+    (@fair) => {
         compile_error!("empty `select!` block")
     };
-    ($($case:ident $(($($args:tt)*))* => $body:expr $(,)*)*) => {
+    (@biased) => {
+        compile_error!("empty `select_biased!` block")
+    };
+    (@fair $($case:ident $(($($args:tt)*))* => $body:expr $(,)*)*) => {
         $crate::crossbeam_channel_internal!(
             @list
+            false
             ($($case $(($($args)*))* => { $body },)*)
             ()
         )
     };
-    ($($tokens:tt)*) => {
+    (@biased $($case:ident $(($($args:tt)*))* => $body:expr $(,)*)*) => {
+        $crate::crossbeam_channel_internal!(
+            @list
+            true
+            ($($case $(($($args)*))* => { $body },)*)
+            ()
+        )
+    };
+    (@fair $($tokens:tt)*) => {
         $crate::crossbeam_channel_internal!(
             @list
+            false
             ($($tokens)*)
             ()
         )
     };
+    (@biased $($tokens:tt)*) => {
+        $crate::crossbeam_channel_internal!(
+            @list
+            true
+            ($($tokens)*)
+            ()
+        )
+    };
+
+    // Catches a bug within this macro (should not happen).
+    (@$($tokens:tt)*) => {
+        compile_error!(
+            concat!(
+                "internal error in crossbeam-channel: ",
+                stringify!(@$($tokens)*),
+            )
+        )
+    };
+
+    // Without a marker, selection is fair.
+    ($($tokens:tt)*) => {
+        $crate::crossbeam_channel_internal!(
+            @fair
+            $($tokens)*
+        )
+    };
 }
 
 /// Selects from a set of channel operations.
 ///
 /// This macro allows you to define a set of channel operations, wait until any one of them becomes
 /// ready, and finally execute it. If multiple operations are ready at the same time, a random one
-/// among them is selected.
+/// among them is selected. To prefer the operations listed first instead, use [`select_biased!`].
 ///
 /// It is also possible to define a `default` case that gets executed if none of the operations are
 /// ready, either right away or for a certain duration of time.
@@ -1046,7 +1288,12 @@ macro_rules! crossbeam_channel_internal {
 /// The `select` macro is a convenience wrapper around [`Select`]. However, it cannot select over a
 /// dynamically created list of channel operations.
 ///
+/// Besides `recv` and `send`, a `recv_custom(source) -> res` case waits on a user-defined source
+/// implementing [`Selectable`], where `res` is bound to the source's output.
+///
 /// [`Select`]: super::Select
+/// [`Selectable`]: super::Selectable
+/// [`select_biased!`]: crate::select_biased
 ///
 /// # Examples
 ///
@@ -1160,6 +1407,45 @@ macro_rules! crossbeam_channel_internal {
 macro_rules! select {
     ($($tokens:tt)*) => {
         $crate::crossbeam_channel_internal!(
+            @fair
+            $($tokens)*
+        )
+    };
+}
+
+/// Selects from a set of channel operations, preferring the ones listed first.
+///
+/// This macro works just like [`select!`], except that if multiple operations are ready at the
+/// same time, the one listed first is selected rather than a random one. Note that this makes the
+/// selection unfair: an operation that is always ready can starve the operations listed after it.
+///
+/// [`select!`]: crate::select
+///
+/// # Examples
+///
+/// Give a shutdown signal priority over incoming data:
+///
+/// ```
+/// use crossbeam_channel::{select_biased, unbounded};
+///
+/// let (shutdown_s, shutdown_r) = unbounded::<()>();
+/// let (data_s, data_r) = unbounded();
+///
+/// data_s.send(1).unwrap();
+/// data_s.send(2).unwrap();
+/// drop(shutdown_s);
+///
+/// // Both operations are ready, but shutdown always wins.
+/// select_biased! {
+///     recv(shutdown_r) -> _ => println!("shutting down"),
+///     recv(data_r) -> msg => panic!("unexpected message: {:?}", msg),
+/// }
+/// ```
+#[macro_export]
+macro_rules! select_biased {
+    ($($tokens:tt)*) => {
+        $crate::crossbeam_channel_internal!(
+            @biased
             $($tokens)*
         )
     };
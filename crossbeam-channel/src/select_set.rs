@@ -0,0 +1,662 @@
+//! A set of channel operations that stays registered across selections.
+//!
+//! Every operation in the set is watched with a context of its own that, instead of waking up a
+//! blocked thread directly, records the operation as notified and then wakes up the thread waiting
+//! on the set. Only notified operations are checked and watched again, so waiting on a set doesn't
+//! cost more with every operation added to it.
+
+use std::any::Any;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use crate::channel::{Receiver, Sender};
+use crate::clock;
+use crate::context::Context;
+use crate::err::{ReadyTimeoutError, TryReadyError};
+use crate::select::{Operation, SelectHandle};
+
+/// Identifies an operation in a set.
+///
+/// Slots are reused once their operation is removed, so the slot index alone could also refer to
+/// an operation inserted later. Every operation put into a slot gets a new generation, which tells
+/// stale indices apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct SlotId {
+    /// The index of the slot.
+    index: usize,
+
+    /// The generation of the operation in the slot.
+    generation: usize,
+}
+
+/// Operations of a set that have been notified, and the thread waiting for them.
+pub(crate) struct Signal {
+    inner: Mutex<SignalInner>,
+}
+
+/// Inner representation of `Signal`.
+struct SignalInner {
+    /// Notified operations.
+    notified: Vec<SlotId>,
+
+    /// The thread blocked until an operation gets notified.
+    thread: Option<Thread>,
+}
+
+impl Signal {
+    /// Creates a new `Signal`.
+    fn new() -> Signal {
+        Signal {
+            inner: Mutex::new(SignalInner {
+                notified: Vec::new(),
+                thread: None,
+            }),
+        }
+    }
+
+    /// Records the operation `id` as notified and wakes up the waiting thread.
+    pub(crate) fn notify(&self, id: SlotId) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.notified.push(id);
+        if let Some(thread) = inner.thread.take() {
+            thread.unpark();
+        }
+    }
+
+    /// Moves the notified operations into `out`.
+    fn drain_into(&self, out: &mut Vec<SlotId>) {
+        out.append(&mut self.inner.lock().unwrap().notified);
+    }
+
+    /// Blocks the current thread until an operation gets notified or the deadline is reached.
+    ///
+    /// This may also return spuriously.
+    fn wait_until(&self, deadline: Option<Instant>) {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            if !inner.notified.is_empty() {
+                return;
+            }
+            inner.thread = Some(thread::current());
+        }
+
+        match deadline {
+            None => thread::park(),
+            Some(deadline) => clock::park_until(deadline),
+        }
+
+        self.inner.lock().unwrap().thread = None;
+    }
+}
+
+impl fmt::Debug for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Signal { .. }")
+    }
+}
+
+/// A sender or receiver owned by a set.
+trait Handle: SelectHandle + Send + Sync {
+    /// Returns the handle as `Any` so that it can be downcast to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Send + 'static> Handle for Receiver<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl<T: Send + 'static> Handle for Sender<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// An operation in a set.
+struct Entry<K> {
+    /// The key the operation was inserted with.
+    key: K,
+
+    /// The sender or receiver.
+    handle: Box<dyn Handle>,
+
+    /// The context the operation is currently watched with.
+    cx: Context,
+
+    /// The slot of the operation.
+    id: SlotId,
+
+    /// Whether the operation is queued to be checked for readiness.
+    queued: bool,
+}
+
+impl<K> Entry<K> {
+    /// Creates an entry and starts watching its operation.
+    fn new(key: K, handle: Box<dyn Handle>, signal: &Arc<Signal>, id: SlotId) -> (Entry<K>, bool) {
+        let cx = Context::for_set(signal.clone(), id);
+        let is_ready = handle.watch(Operation::task(&cx), &cx);
+        let entry = Entry {
+            key,
+            handle,
+            cx,
+            id,
+            queued: false,
+        };
+        (entry, is_ready)
+    }
+
+    /// Watches the operation with a fresh context and returns `true` if it is ready.
+    ///
+    /// A context can be selected only once, so every notification has to be followed by a new
+    /// registration in order not to miss the next one.
+    fn rewatch(&mut self, signal: &Arc<Signal>) -> bool {
+        self.handle.unwatch(Operation::task(&self.cx));
+        self.cx = Context::for_set(signal.clone(), self.id);
+        self.handle.watch(Operation::task(&self.cx), &self.cx) || self.handle.is_ready()
+    }
+
+    /// Stops watching the operation.
+    fn unwatch(&self) {
+        self.handle.unwatch(Operation::task(&self.cx));
+    }
+}
+
+/// Timeout for a waiting operation.
+#[derive(Clone, Copy)]
+enum Timeout {
+    /// Try once without blocking.
+    Now,
+
+    /// Block forever.
+    Never,
+
+    /// Block until the given instant.
+    At(Instant),
+}
+
+/// A set of channel operations for long-lived select loops.
+///
+/// Unlike [`Select`], which borrows its senders and receivers for the duration of a single
+/// selection, a `SelectSet` owns them and identifies each operation by a key of type `K`.
+/// Operations can be inserted and removed in constant time while the set is in use, which makes it
+/// a good fit for event loops over large and changing groups of channels.
+///
+/// Operations stay registered with their channels between calls to [`ready`] and friends. Waiting
+/// on the set only checks operations whose channels have signaled a change since the last call,
+/// instead of registering and unregistering every operation each time.
+///
+/// Like [`Select::ready`], the set only tells which operation is ready, and returns its key. The
+/// operation itself is then executed on the sender or receiver, which can be obtained with
+/// [`receiver`] or [`sender`]. An operation that is ready keeps being reported until it can no
+/// longer proceed, and operations that are ready at the same time are reported in turns.
+///
+/// An operation is considered to be ready if it doesn't have to block. Note that it is ready even
+/// when it will simply return an error because the channel is disconnected. Readiness can also be
+/// reported spuriously, so it's a good idea to use non-blocking operations like [`try_recv`] and
+/// [`try_send`] on the returned operation.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::{unbounded, Receiver, SelectSet, TryRecvError};
+///
+/// let mut set = SelectSet::new();
+/// let mut senders = Vec::new();
+/// for id in 0..3 {
+///     let (s, r) = unbounded::<i32>();
+///     set.insert_recv(id, r);
+///     senders.push(s);
+/// }
+///
+/// for (id, s) in senders.into_iter().enumerate() {
+///     thread::spawn(move || s.send(id as i32 * 10).unwrap());
+/// }
+///
+/// let mut sum = 0;
+/// while !set.is_empty() {
+///     let id = set.ready();
+///     let r: &Receiver<i32> = set.receiver(&id).unwrap();
+///     match r.try_recv() {
+///         Ok(msg) => sum += msg,
+///         Err(TryRecvError::Empty) => {}
+///         Err(TryRecvError::Disconnected) => {
+///             set.remove(&id);
+///         }
+///     }
+/// }
+/// assert_eq!(sum, 30);
+/// ```
+///
+/// [`Select`]: crate::Select
+/// [`Select::ready`]: crate::Select::ready
+/// [`ready`]: SelectSet::ready
+/// [`receiver`]: SelectSet::receiver
+/// [`sender`]: SelectSet::sender
+/// [`try_recv`]: Receiver::try_recv
+/// [`try_send`]: Sender::try_send
+pub struct SelectSet<K> {
+    /// Slots holding the operations, indexed by the index of their entry.
+    entries: Vec<Option<Entry<K>>>,
+
+    /// Indices of empty slots.
+    free: Vec<usize>,
+
+    /// Indices of operations, by key.
+    indices: HashMap<K, usize>,
+
+    /// Indices of operations that have to be checked for readiness on every call because they
+    /// are driven by time rather than by notifications.
+    timers: HashSet<usize>,
+
+    /// The generation of the next operation put into a slot.
+    generation: usize,
+
+    /// Operations queued to be checked for readiness.
+    queue: VecDeque<SlotId>,
+
+    /// Scratch space for notified operations.
+    notified: Vec<SlotId>,
+
+    /// Notifications from the channels.
+    signal: Arc<Signal>,
+}
+
+impl<K: Eq + Hash + Clone> SelectSet<K> {
+    /// Creates an empty set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::SelectSet;
+    ///
+    /// let mut set = SelectSet::<&str>::new();
+    ///
+    /// // The set is empty, which means no operation can be ready.
+    /// assert!(set.try_ready().is_err());
+    /// ```
+    pub fn new() -> SelectSet<K> {
+        SelectSet {
+            entries: Vec::new(),
+            free: Vec::new(),
+            indices: HashMap::new(),
+            timers: HashSet::new(),
+            generation: 0,
+            queue: VecDeque::new(),
+            notified: Vec::new(),
+            signal: Arc::new(Signal::new()),
+        }
+    }
+
+    /// Adds a receive operation with the given key.
+    ///
+    /// If the set already contains an operation with the same key, it is replaced. Returns `true`
+    /// if the key was not in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectSet};
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    ///
+    /// let mut set = SelectSet::new();
+    /// assert!(set.insert_recv("input", r));
+    /// ```
+    pub fn insert_recv<T: Send + 'static>(&mut self, key: K, r: Receiver<T>) -> bool {
+        self.insert(key, Box::new(r))
+    }
+
+    /// Adds a send operation with the given key.
+    ///
+    /// If the set already contains an operation with the same key, it is replaced. Returns `true`
+    /// if the key was not in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, SelectSet};
+    ///
+    /// let (s, r) = bounded::<i32>(1);
+    ///
+    /// let mut set = SelectSet::new();
+    /// assert!(set.insert_send("output", s));
+    /// ```
+    pub fn insert_send<T: Send + 'static>(&mut self, key: K, s: Sender<T>) -> bool {
+        self.insert(key, Box::new(s))
+    }
+
+    /// Adds an operation on `handle` with the given key.
+    fn insert(&mut self, key: K, handle: Box<dyn Handle>) -> bool {
+        let is_new = !self.remove(&key);
+
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.entries.push(None);
+                self.entries.len() - 1
+            }
+        };
+
+        let id = SlotId {
+            index,
+            generation: self.generation,
+        };
+        self.generation = self.generation.wrapping_add(1);
+
+        let is_timer = handle.deadline().is_some();
+        let (mut entry, is_ready) = Entry::new(key.clone(), handle, &self.signal, id);
+        if is_ready {
+            entry.queued = true;
+            self.queue.push_back(id);
+        }
+        if is_timer {
+            self.timers.insert(index);
+        }
+        self.entries[index] = Some(entry);
+        self.indices.insert(key, index);
+        is_new
+    }
+
+    /// Removes the operation with the given key.
+    ///
+    /// The sender or receiver of the operation is dropped. Returns `true` if the key was in the
+    /// set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectSet};
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    ///
+    /// let mut set = SelectSet::new();
+    /// set.insert_recv(1, r);
+    ///
+    /// assert!(set.remove(&1));
+    /// assert!(!set.remove(&1));
+    ///
+    /// // The receiver was dropped together with the operation.
+    /// assert!(s.send(0).is_err());
+    /// ```
+    pub fn remove(&mut self, key: &K) -> bool {
+        let index = match self.indices.remove(key) {
+            None => return false,
+            Some(index) => index,
+        };
+
+        // The operation may still be queued or notified. Its id is skipped from then on, since the
+        // next operation put into this slot gets a new generation.
+        let entry = self.entries[index].take().unwrap();
+        entry.unwatch();
+        self.timers.remove(&index);
+        self.free.push(index);
+        true
+    }
+
+    /// Returns `true` if the set contains an operation with the given key.
+    pub fn contains(&self, key: &K) -> bool {
+        self.indices.contains_key(key)
+    }
+
+    /// Returns the number of operations in the set.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Returns `true` if the set contains no operations.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Returns the receiver of the receive operation with the given key.
+    ///
+    /// Returns `None` if there is no such operation, or if it doesn't receive messages of type
+    /// `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectSet};
+    ///
+    /// let (s, r) = unbounded();
+    /// s.send(1).unwrap();
+    ///
+    /// let mut set = SelectSet::new();
+    /// set.insert_recv("input", r);
+    ///
+    /// let key = set.ready();
+    /// assert_eq!(set.receiver(&key).unwrap().try_recv(), Ok(1));
+    /// assert!(set.receiver::<String>(&key).is_none());
+    /// ```
+    pub fn receiver<T: 'static>(&self, key: &K) -> Option<&Receiver<T>> {
+        self.handle(key)?.as_any().downcast_ref()
+    }
+
+    /// Returns the sender of the send operation with the given key.
+    ///
+    /// Returns `None` if there is no such operation, or if it doesn't send messages of type `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{bounded, SelectSet};
+    ///
+    /// let (s, r) = bounded(1);
+    ///
+    /// let mut set = SelectSet::new();
+    /// set.insert_send("output", s);
+    ///
+    /// let key = set.ready();
+    /// assert_eq!(set.sender(&key).unwrap().try_send(1), Ok(()));
+    /// assert_eq!(r.try_recv(), Ok(1));
+    /// ```
+    pub fn sender<T: 'static>(&self, key: &K) -> Option<&Sender<T>> {
+        self.handle(key)?.as_any().downcast_ref()
+    }
+
+    /// Returns the handle of the operation with the given key.
+    fn handle(&self, key: &K) -> Option<&dyn Handle> {
+        let index = *self.indices.get(key)?;
+        self.entries[index].as_ref().map(|entry| &*entry.handle)
+    }
+
+    /// Attempts to find a ready operation without blocking.
+    ///
+    /// If an operation is ready, its key is returned. If multiple operations are ready at the same
+    /// time, they are returned in turns on subsequent calls. If none of the operations are ready,
+    /// an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{unbounded, SelectSet};
+    ///
+    /// let (s1, r1) = unbounded::<&str>();
+    /// let (s2, r2) = unbounded::<&str>();
+    ///
+    /// let mut set = SelectSet::new();
+    /// set.insert_recv(1, r1);
+    /// set.insert_recv(2, r2);
+    /// assert!(set.try_ready().is_err());
+    ///
+    /// s2.send("hello").unwrap();
+    /// assert_eq!(set.try_ready(), Ok(2));
+    /// # drop(s1);
+    /// ```
+    pub fn try_ready(&mut self) -> Result<K, TryReadyError> {
+        self.run(Timeout::Now).ok_or(TryReadyError)
+    }
+
+    /// Blocks until one of the operations becomes ready and returns its key.
+    ///
+    /// If multiple operations are ready at the same time, they are returned in turns on
+    /// subsequent calls.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, SelectSet};
+    ///
+    /// let (s1, r1) = unbounded::<i32>();
+    /// let (s2, r2) = unbounded::<i32>();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     s2.send(20).unwrap();
+    /// });
+    ///
+    /// let mut set = SelectSet::new();
+    /// set.insert_recv("first", r1);
+    /// set.insert_recv("second", r2);
+    ///
+    /// assert_eq!(set.ready(), "second");
+    /// assert_eq!(set.receiver(&"second").unwrap().try_recv(), Ok(20));
+    /// # drop(s1);
+    /// ```
+    pub fn ready(&mut self) -> K {
+        if self.is_empty() {
+            panic!("no operations have been added to `SelectSet`");
+        }
+
+        self.run(Timeout::Never).unwrap()
+    }
+
+    /// Blocks for a limited time until one of the operations becomes ready and returns its key.
+    ///
+    /// If none of the operations become ready for the specified duration, an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{unbounded, SelectSet};
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    ///
+    /// let mut set = SelectSet::new();
+    /// set.insert_recv(0, r);
+    ///
+    /// assert!(set.ready_timeout(Duration::from_millis(100)).is_err());
+    /// # drop(s);
+    /// ```
+    pub fn ready_timeout(&mut self, timeout: Duration) -> Result<K, ReadyTimeoutError> {
+        self.ready_deadline(clock::now() + timeout)
+    }
+
+    /// Blocks until a given deadline, or until one of the operations becomes ready and returns its
+    /// key.
+    ///
+    /// If none of the operations become ready before the deadline, an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use crossbeam_channel::{unbounded, SelectSet};
+    ///
+    /// let (s, r) = unbounded::<i32>();
+    /// let deadline = Instant::now() + Duration::from_millis(100);
+    ///
+    /// let mut set = SelectSet::new();
+    /// set.insert_recv(0, r);
+    ///
+    /// assert!(set.ready_deadline(deadline).is_err());
+    /// # drop(s);
+    /// ```
+    pub fn ready_deadline(&mut self, deadline: Instant) -> Result<K, ReadyTimeoutError> {
+        self.run(Timeout::At(deadline)).ok_or(ReadyTimeoutError)
+    }
+
+    /// Runs until one of the operations becomes ready, potentially blocking the current thread.
+    fn run(&mut self, timeout: Timeout) -> Option<K> {
+        loop {
+            // Queue operations that have been notified or are driven by time.
+            self.signal.drain_into(&mut self.notified);
+            for id in self.notified.drain(..) {
+                if let Some(entry) = &mut self.entries[id.index] {
+                    if entry.id == id && !entry.queued {
+                        entry.queued = true;
+                        self.queue.push_back(id);
+                    }
+                }
+            }
+            for &index in &self.timers {
+                let entry = self.entries[index].as_mut().unwrap();
+                if !entry.queued && entry.handle.is_ready() {
+                    entry.queued = true;
+                    self.queue.push_back(entry.id);
+                }
+            }
+
+            // Check queued operations for readiness, skipping removed ones.
+            while let Some(id) = self.queue.pop_front() {
+                let entry = match &mut self.entries[id.index] {
+                    Some(entry) if entry.id == id => entry,
+                    _ => continue,
+                };
+                entry.queued = false;
+
+                if entry.rewatch(&self.signal) {
+                    // Check the operation again next time in case it's still ready, but only
+                    // after the other queued operations.
+                    entry.queued = true;
+                    self.queue.push_back(id);
+                    return Some(entry.key.clone());
+                }
+            }
+
+            // Check for timeout, and compute the earliest deadline.
+            let mut deadline = match timeout {
+                Timeout::Now => return None,
+                Timeout::Never => None,
+                Timeout::At(when) => {
+                    if clock::now() >= when {
+                        return None;
+                    }
+                    Some(when)
+                }
+            };
+            for &index in &self.timers {
+                let entry = self.entries[index].as_ref().unwrap();
+                if let Some(x) = entry.handle.deadline() {
+                    deadline = deadline.map(|y| x.min(y)).or(Some(x));
+                }
+            }
+
+            // Block until an operation gets notified.
+            self.signal.wait_until(deadline);
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone> Default for SelectSet<K> {
+    fn default() -> SelectSet<K> {
+        SelectSet::new()
+    }
+}
+
+impl<K> Drop for SelectSet<K> {
+    fn drop(&mut self) {
+        for entry in self.entries.iter().flatten() {
+            entry.unwatch();
+        }
+    }
+}
+
+impl<K> fmt::Debug for SelectSet<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("SelectSet { .. }")
+    }
+}
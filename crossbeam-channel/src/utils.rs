@@ -1,14 +1,18 @@
 //! Miscellaneous utilities.
 
 use std::cell::{Cell, UnsafeCell};
+use std::marker::PhantomData;
 use std::num::Wrapping;
 use std::ops::{Deref, DerefMut};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
 use std::thread;
 use std::time::{Duration, Instant};
 
 use crossbeam_utils::Backoff;
 
+use crate::clock;
+
 /// Randomly shuffles a slice.
 pub(crate) fn shuffle<T>(v: &mut [T]) {
     let len = v.len();
@@ -51,11 +55,8 @@ pub(crate) fn sleep_until(deadline: Option<Instant>) {
         match deadline {
             None => thread::sleep(Duration::from_secs(1000)),
             Some(d) => {
-                let now = Instant::now();
-                if now >= d {
-                    break;
-                }
-                thread::sleep(d - now);
+                clock::sleep_until(d);
+                break;
             }
         }
     }
@@ -67,6 +68,9 @@ pub(crate) struct Spinlock<T> {
     value: UnsafeCell<T>,
 }
 
+unsafe impl<T: Send> Send for Spinlock<T> {}
+unsafe impl<T: Send> Sync for Spinlock<T> {}
+
 impl<T> Spinlock<T> {
     /// Returns a new spinlock initialized with `value`.
     pub(crate) fn new(value: T) -> Spinlock<T> {
@@ -110,3 +114,47 @@ impl<T> DerefMut for SpinlockGuard<'_, T> {
         unsafe { &mut *self.parent.value.get() }
     }
 }
+
+/// A value that is initialized on first access and lives for the rest of the process.
+///
+/// This is meant for `static` items, which can't hold values that need to be allocated.
+pub(crate) struct Lazy<T> {
+    ptr: AtomicPtr<T>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Lazy<T> {
+    /// Returns a new, uninitialized value.
+    pub(crate) const fn new() -> Lazy<T> {
+        Lazy {
+            ptr: AtomicPtr::new(ptr::null_mut()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the value, initializing it with `init` if this is the first access.
+    ///
+    /// If multiple threads race to initialize the value, one of them wins and the values created
+    /// by the others are dropped.
+    pub(crate) fn get_or_init<F>(&'static self, init: F) -> &'static T
+    where
+        F: FnOnce() -> T,
+    {
+        let ptr = self.ptr.load(Ordering::Acquire);
+        if !ptr.is_null() {
+            return unsafe { &*ptr };
+        }
+
+        let new = Box::into_raw(Box::new(init()));
+        match self
+            .ptr
+            .compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => unsafe { &*new },
+            Err(current) => {
+                drop(unsafe { Box::from_raw(new) });
+                unsafe { &*current }
+            }
+        }
+    }
+}
@@ -1,8 +1,10 @@
 //! Waking mechanism for threads blocked on channel operations.
 
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::task;
 use std::thread::{self, ThreadId};
 
+use crate::alarm::AlarmId;
 use crate::context::Context;
 use crate::select::{Operation, Selected};
 use crate::utils::Spinlock;
@@ -80,7 +82,7 @@ impl Waker {
             .iter()
             .position(|selector| {
                 // Does the entry belong to a different thread?
-                selector.cx.thread_id() != current_thread_id()
+                selector.cx.thread_id() != Some(current_thread_id())
                     && selector // Try selecting this operation.
                         .cx
                         .try_select(Selected::Operation(selector.oper))
@@ -107,7 +109,7 @@ impl Waker {
             let thread_id = current_thread_id();
 
             self.selectors.iter().any(|entry| {
-                entry.cx.thread_id() != thread_id && entry.cx.selected() == Selected::Waiting
+                entry.cx.thread_id() != Some(thread_id) && entry.cx.selected() == Selected::Waiting
             })
         }
     }
@@ -138,6 +140,22 @@ impl Waker {
         }
     }
 
+    /// Selects all registered operations, wakes up their threads, and notifies all operations
+    /// waiting to be ready.
+    #[inline]
+    pub(crate) fn notify_all(&mut self) {
+        self.selectors.retain(|entry| {
+            if entry.cx.try_select(Selected::Operation(entry.oper)).is_ok() {
+                entry.cx.unpark();
+                false
+            } else {
+                true
+            }
+        });
+
+        self.notify();
+    }
+
     /// Notifies all registered operations that the channel is disconnected.
     #[inline]
     pub(crate) fn disconnect(&mut self) {
@@ -224,6 +242,41 @@ impl SyncWaker {
         }
     }
 
+    /// Attempts to find up to `count` threads (not the current one), select their operations, and
+    /// wake them up.
+    #[inline]
+    pub(crate) fn notify_many(&self, count: usize) {
+        if !self.is_empty.load(Ordering::SeqCst) {
+            let mut inner = self.inner.lock();
+            if !self.is_empty.load(Ordering::SeqCst) {
+                for _ in 0..count {
+                    if inner.try_select().is_none() {
+                        break;
+                    }
+                }
+                inner.notify();
+                self.is_empty.store(
+                    inner.selectors.is_empty() && inner.observers.is_empty(),
+                    Ordering::SeqCst,
+                );
+            }
+        }
+    }
+
+    /// Selects all registered operations, wakes up their threads, and notifies all operations
+    /// waiting to be ready.
+    #[inline]
+    pub(crate) fn notify_all(&self) {
+        if !self.is_empty.load(Ordering::SeqCst) {
+            let mut inner = self.inner.lock();
+            inner.notify_all();
+            self.is_empty.store(
+                inner.selectors.is_empty() && inner.observers.is_empty(),
+                Ordering::SeqCst,
+            );
+        }
+    }
+
     /// Registers an operation waiting to be ready.
     #[inline]
     pub(crate) fn watch(&self, oper: Operation, cx: &Context) {
@@ -265,6 +318,72 @@ impl Drop for SyncWaker {
     }
 }
 
+/// An operation registered by an asynchronous task.
+///
+/// Threads block inside channel methods and keep their registrations on the stack. Tasks return
+/// `Poll::Pending` instead, so the registration is kept inside the future until it gets polled
+/// again or dropped.
+pub(crate) struct Waiter {
+    /// The registered operation and the context it was registered with.
+    entry: Option<(Operation, Context)>,
+
+    /// The context of the last finished registration.
+    ///
+    /// Operations of tasks are identified by the address of their context, so the context is
+    /// kept alive until its operation gets unregistered. Otherwise, another task could allocate a
+    /// context at the same address and have its registration removed instead.
+    finished: Option<Context>,
+
+    /// Optional packet.
+    pub(crate) packet: usize,
+
+    /// The waker handed to the alarm thread, if the task waits for a deadline.
+    pub(crate) alarm: Option<AlarmId>,
+}
+
+impl Waiter {
+    /// Creates a new `Waiter` with no registered operation.
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Waiter {
+            entry: None,
+            finished: None,
+            packet: 0,
+            alarm: None,
+        }
+    }
+
+    /// Creates a context for the task and returns the operation it should be registered with.
+    #[inline]
+    pub(crate) fn start(&mut self, waker: &task::Waker) -> (Operation, Context) {
+        debug_assert!(self.entry.is_none());
+        self.finished = None;
+        let cx = Context::for_task(waker);
+        let oper = Operation::task(&cx);
+        self.entry = Some((oper, cx.clone()));
+        (oper, cx)
+    }
+
+    /// Forgets the registered operation and returns it, if there was one.
+    ///
+    /// The caller is responsible for unregistering the returned operation.
+    #[inline]
+    pub(crate) fn finish(&mut self) -> Option<Operation> {
+        let (oper, cx) = self.entry.take()?;
+        self.finished = Some(cx);
+        Some(oper)
+    }
+}
+
+impl Drop for Waiter {
+    #[inline]
+    fn drop(&mut self) {
+        debug_assert!(self.entry.is_none());
+        debug_assert_eq!(self.packet, 0);
+        debug_assert!(self.alarm.is_none());
+    }
+}
+
 /// Returns the id of the current thread.
 #[inline]
 fn current_thread_id() -> ThreadId {
@@ -0,0 +1,385 @@
+//! Channel that holds a single value and notifies receivers when it changes.
+//!
+//! Unlike other channels, a watch channel is not a queue: every send replaces the current value,
+//! and each receiver only keeps track of whether it has seen the latest one. Receivers wait for
+//! changes through the [`Selectable`] contract, which is also how they take part in selection.
+
+use std::fmt;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock, RwLockReadGuard};
+
+use crate::custom::{Registration, RegistrationId, Selectable, Slot};
+use crate::err::{RecvError, SendError};
+use crate::select::Select;
+
+/// State shared between the sender and receivers.
+struct Shared<T> {
+    /// The current value.
+    value: RwLock<T>,
+
+    /// Incremented every time the value changes.
+    version: AtomicUsize,
+
+    /// Set when the sender is dropped.
+    disconnected: AtomicBool,
+
+    /// The number of receivers.
+    receivers: AtomicUsize,
+
+    /// Selects waiting for the value to change.
+    registrations: Mutex<Vec<Registration>>,
+}
+
+impl<T> Shared<T> {
+    /// Wakes up all selects waiting for the value to change.
+    fn notify(&self) {
+        for reg in self
+            .registrations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .drain(..)
+        {
+            reg.wake();
+        }
+    }
+}
+
+/// Creates a watch channel holding `initial`.
+///
+/// A watch channel always holds exactly one value, which the [`WatchSender`] replaces and
+/// receivers can look at with [`borrow`]. Receivers don't consume values. Instead, each receiver
+/// tracks whether it has seen the latest value, and [`changed`] blocks until a new one is sent.
+/// This fits state like configuration or health, where only the latest value matters.
+///
+/// A receiver implements [`Selectable`], so it can wait for changes alongside channel operations
+/// in [`Select`] and [`select!`].
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use crossbeam_channel::watch;
+///
+/// let (s, r) = watch("starting");
+///
+/// let handle = thread::spawn(move || {
+///     // Wait until the state is no longer "starting".
+///     r.changed().unwrap();
+///     *r.borrow()
+/// });
+///
+/// s.send("ready").unwrap();
+/// assert_eq!(handle.join().unwrap(), "ready");
+/// ```
+///
+/// [`borrow`]: WatchReceiver::borrow
+/// [`changed`]: WatchReceiver::changed
+/// [`select!`]: crate::select
+pub fn watch<T>(initial: T) -> (WatchSender<T>, WatchReceiver<T>) {
+    let shared = Arc::new(Shared {
+        value: RwLock::new(initial),
+        version: AtomicUsize::new(0),
+        disconnected: AtomicBool::new(false),
+        receivers: AtomicUsize::new(1),
+        registrations: Mutex::new(Vec::new()),
+    });
+
+    let s = WatchSender {
+        shared: shared.clone(),
+    };
+    let r = WatchReceiver {
+        shared,
+        seen: AtomicUsize::new(0),
+    };
+    (s, r)
+}
+
+/// The sending side of a watch channel.
+///
+/// Receivers get disconnected when the sender is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_channel::watch;
+///
+/// let (s, r) = watch(0);
+///
+/// s.send(1).unwrap();
+/// s.send_modify(|v| *v += 1);
+///
+/// assert!(r.has_changed());
+/// assert_eq!(*r.borrow(), 2);
+/// ```
+pub struct WatchSender<T> {
+    /// State shared with the receivers.
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> WatchSender<T> {
+    /// Replaces the value and notifies receivers.
+    ///
+    /// If all receivers have been dropped, the value is not stored and an error is returned
+    /// containing it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::{watch, SendError};
+    ///
+    /// let (s, r) = watch(1);
+    /// assert_eq!(s.send(2), Ok(()));
+    /// assert_eq!(*r.borrow(), 2);
+    ///
+    /// drop(r);
+    /// assert_eq!(s.send(3), Err(SendError(3)));
+    /// ```
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        if self.shared.receivers.load(Ordering::SeqCst) == 0 {
+            return Err(SendError(value));
+        }
+
+        self.send_modify(|v| *v = value);
+        Ok(())
+    }
+
+    /// Modifies the value in place and notifies receivers.
+    ///
+    /// Unlike [`send`], this works even when all receivers have been dropped.
+    ///
+    /// If `modify` panics, the panic propagates and receivers aren't notified, but the value keeps
+    /// any changes made before the panic. The channel stays usable afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::watch;
+    ///
+    /// let (s, r) = watch(vec![1]);
+    /// s.send_modify(|v| v.push(2));
+    ///
+    /// assert_eq!(*r.borrow(), [1, 2]);
+    /// ```
+    ///
+    /// [`send`]: WatchSender::send
+    pub fn send_modify<F>(&self, modify: F)
+    where
+        F: FnOnce(&mut T),
+    {
+        {
+            let mut value = self
+                .shared
+                .value
+                .write()
+                .unwrap_or_else(PoisonError::into_inner);
+            modify(&mut value);
+            self.shared.version.fetch_add(1, Ordering::SeqCst);
+        }
+        self.shared.notify();
+    }
+}
+
+impl<T> Drop for WatchSender<T> {
+    fn drop(&mut self) {
+        self.shared.disconnected.store(true, Ordering::SeqCst);
+        self.shared.notify();
+    }
+}
+
+impl<T> fmt::Debug for WatchSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("WatchSender { .. }")
+    }
+}
+
+/// The receiving side of a watch channel.
+///
+/// Receivers can be cloned. A clone starts out having seen the same values as the original, and
+/// from then on keeps track of changes on its own.
+///
+/// # Examples
+///
+/// Waiting for a change alongside a channel:
+///
+/// ```
+/// use crossbeam_channel::{select, unbounded, watch};
+///
+/// let (config_s, config_r) = watch("v1");
+/// let (jobs_s, jobs_r) = unbounded::<i32>();
+///
+/// config_s.send("v2").unwrap();
+///
+/// select! {
+///     recv(jobs_r) -> job => println!("job: {:?}", job),
+///     recv_custom(config_r) -> res => {
+///         assert!(res.is_ok());
+///         assert_eq!(*config_r.borrow(), "v2");
+///     }
+/// }
+/// # drop(jobs_s);
+/// ```
+pub struct WatchReceiver<T> {
+    /// State shared with the sender and other receivers.
+    shared: Arc<Shared<T>>,
+
+    /// The version of the value this receiver has seen.
+    seen: AtomicUsize,
+}
+
+impl<T> WatchReceiver<T> {
+    /// Returns a reference to the current value.
+    ///
+    /// This does not mark the value as seen. The sender is blocked from changing the value while
+    /// the returned reference is alive, so it should be dropped quickly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::watch;
+    ///
+    /// let (s, r) = watch(String::from("hello"));
+    /// assert_eq!(r.borrow().len(), 5);
+    /// ```
+    pub fn borrow(&self) -> WatchRef<'_, T> {
+        WatchRef {
+            guard: self
+                .shared
+                .value
+                .read()
+                .unwrap_or_else(PoisonError::into_inner),
+        }
+    }
+
+    /// Returns `true` if the value has changed since this receiver last observed a change.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_channel::watch;
+    ///
+    /// let (s, r) = watch(1);
+    /// assert!(!r.has_changed());
+    ///
+    /// s.send(2).unwrap();
+    /// assert!(r.has_changed());
+    ///
+    /// r.changed().unwrap();
+    /// assert!(!r.has_changed());
+    /// ```
+    pub fn has_changed(&self) -> bool {
+        self.shared.version.load(Ordering::SeqCst) != self.seen.load(Ordering::SeqCst)
+    }
+
+    /// Blocks the current thread until the value changes, and marks it as seen.
+    ///
+    /// Returns right away if the value has changed since this receiver last observed a change.
+    /// If the sender has been dropped and there are no unseen changes, an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use crossbeam_channel::{watch, RecvError};
+    ///
+    /// let (s, r) = watch(0);
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     s.send(1).unwrap();
+    /// });
+    ///
+    /// assert_eq!(r.changed(), Ok(()));
+    /// assert_eq!(*r.borrow(), 1);
+    ///
+    /// // The sender was dropped.
+    /// assert_eq!(r.changed(), Err(RecvError));
+    /// ```
+    pub fn changed(&self) -> Result<(), RecvError> {
+        let mut sel = Select::new();
+        sel.recv_custom(self);
+        sel.select().recv_custom(self)
+    }
+}
+
+impl<T> Selectable for WatchReceiver<T> {
+    type Output = Result<(), RecvError>;
+
+    fn try_select(&self, _slot: &mut Slot) -> bool {
+        self.is_ready()
+    }
+
+    fn register(&self, reg: &Registration) -> bool {
+        let mut registrations = self
+            .shared
+            .registrations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        registrations.push(reg.clone());
+        self.is_ready()
+    }
+
+    fn unregister(&self, id: RegistrationId) {
+        self.shared
+            .registrations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|reg| reg.id() != id);
+    }
+
+    fn is_ready(&self) -> bool {
+        self.has_changed() || self.shared.disconnected.load(Ordering::SeqCst)
+    }
+
+    fn complete(&self, _slot: &mut Slot) -> Result<(), RecvError> {
+        let version = self.shared.version.load(Ordering::SeqCst);
+        if self.seen.swap(version, Ordering::SeqCst) != version {
+            Ok(())
+        } else {
+            Err(RecvError)
+        }
+    }
+}
+
+impl<T> Clone for WatchReceiver<T> {
+    fn clone(&self) -> Self {
+        self.shared.receivers.fetch_add(1, Ordering::SeqCst);
+        WatchReceiver {
+            shared: self.shared.clone(),
+            seen: AtomicUsize::new(self.seen.load(Ordering::SeqCst)),
+        }
+    }
+}
+
+impl<T> Drop for WatchReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.receivers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl<T> fmt::Debug for WatchReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("WatchReceiver { .. }")
+    }
+}
+
+/// A reference to the value in a watch channel, returned by [`WatchReceiver::borrow`].
+pub struct WatchRef<'a, T> {
+    /// Keeps the value from changing.
+    guard: RwLockReadGuard<'a, T>,
+}
+
+impl<T> Deref for WatchRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for WatchRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
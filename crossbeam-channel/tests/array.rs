@@ -1,6 +1,7 @@
 //! Tests for the array channel flavor.
 
 use std::any::Any;
+use std::collections::VecDeque;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::thread;
@@ -250,6 +251,184 @@ fn recv_after_disconnect() {
     assert_eq!(r.recv(), Err(RecvError));
 }
 
+#[test]
+fn recv_many() {
+    let (s, r) = bounded::<usize>(7);
+    let mut buf = Vec::new();
+
+    assert_eq!(r.try_recv_many(&mut buf, 10), Err(TryRecvError::Empty));
+    assert_eq!(r.try_recv_many(&mut buf, 0), Ok(0));
+
+    // Go around the buffer many times so that claimed runs wrap around its end.
+    let mut next = 0;
+    for i in 0..100 {
+        let n = i % 3 + 1;
+        for _ in 0..n {
+            s.send(next).unwrap();
+            next += 1;
+        }
+        assert_eq!(r.try_recv_many(&mut buf, 2), Ok(n.min(2)));
+        if n == 3 {
+            assert_eq!(r.recv_many(&mut buf, 2), Ok(1));
+        }
+    }
+    assert_eq!(buf, (0..next).collect::<Vec<_>>());
+
+    s.send(next).unwrap();
+    drop(s);
+    assert_eq!(r.recv_many(&mut buf, 10), Ok(1));
+    assert_eq!(r.recv_many(&mut buf, 10), Err(RecvError));
+    assert_eq!(
+        r.try_recv_many(&mut buf, 10),
+        Err(TryRecvError::Disconnected)
+    );
+}
+
+#[test]
+fn recv_many_blocks() {
+    let (s, r) = bounded(7);
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            let mut buf = Vec::new();
+            assert_eq!(r.recv_many(&mut buf, 10), Ok(1));
+            assert_eq!(buf, [7]);
+            assert_eq!(r.recv_many(&mut buf, 10), Err(RecvError));
+        });
+        scope.spawn(move |_| {
+            thread::sleep(ms(1000));
+            s.send(7).unwrap();
+            thread::sleep(ms(1000));
+            drop(s);
+        });
+    })
+    .unwrap();
+}
+
+#[test]
+fn mpmc_recv_many() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = bounded::<usize>(7);
+    let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+    let received = AtomicUsize::new(0);
+
+    scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                let mut buf = Vec::new();
+                while let Ok(n) = r.recv_many(&mut buf, 5) {
+                    for i in buf.drain(..) {
+                        v[i].fetch_add(1, Ordering::SeqCst);
+                    }
+                    // The last receiver to finish wakes up the others.
+                    if received.fetch_add(n, Ordering::SeqCst) + n == COUNT * THREADS {
+                        r.close();
+                    }
+                }
+            });
+        }
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                for i in 0..COUNT {
+                    s.send(i).unwrap();
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    for c in v {
+        assert_eq!(c.load(Ordering::SeqCst), THREADS);
+    }
+}
+
+#[test]
+fn send_many() {
+    let (s, r) = bounded::<usize>(7);
+    let mut msgs = VecDeque::new();
+    assert_eq!(s.try_send_many(&mut msgs), Ok(0));
+
+    // Go around the buffer many times so that reserved runs wrap around its end.
+    let mut next = 0;
+    for i in 0..100 {
+        let n = i % 3 + 1;
+        msgs.extend(next..next + n);
+        next += n;
+        assert_eq!(s.try_send_many(&mut msgs), Ok(n));
+        assert!(msgs.is_empty());
+        for j in next - n..next {
+            assert_eq!(r.try_recv(), Ok(j));
+        }
+    }
+
+    // Messages that don't fit are left in the queue.
+    msgs.extend(0..10);
+    assert_eq!(s.try_send_many(&mut msgs), Ok(7));
+    assert_eq!(msgs, [7, 8, 9]);
+    assert_eq!(s.try_send_many(&mut msgs), Err(TrySendError::Full(())));
+    assert_eq!(msgs, [7, 8, 9]);
+
+    r.recv().unwrap();
+    assert_eq!(s.try_send_many(&mut msgs), Ok(1));
+    assert_eq!(msgs, [8, 9]);
+
+    drop(r);
+    assert_eq!(
+        s.try_send_many(&mut msgs),
+        Err(TrySendError::Disconnected(()))
+    );
+    assert_eq!(msgs, [8, 9]);
+    assert_eq!(s.send_iter(msgs), Err(SendError(8)));
+}
+
+#[test]
+fn send_iter_blocks() {
+    let (s, r) = bounded(7);
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            assert_eq!(s.send_iter(0..100), Ok(()));
+        });
+        scope.spawn(move |_| {
+            thread::sleep(ms(1000));
+            assert_eq!(r.iter().collect::<Vec<_>>(), (0..100).collect::<Vec<_>>());
+        });
+    })
+    .unwrap();
+}
+
+#[test]
+fn mpmc_send_many() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = bounded::<usize>(7);
+    let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+    scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                for _ in 0..COUNT {
+                    let n = r.recv().unwrap();
+                    v[n].fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                s.send_iter(0..COUNT).unwrap();
+            });
+        }
+    })
+    .unwrap();
+
+    for c in v {
+        assert_eq!(c.load(Ordering::SeqCst), THREADS);
+    }
+}
+
 #[test]
 fn len() {
     const COUNT: usize = 25_000;
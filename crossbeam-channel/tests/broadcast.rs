@@ -0,0 +1,396 @@
+//! Tests for the broadcast channel flavor.
+
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{broadcast, broadcast_with_lagging, select, Lagging, Select};
+use crossbeam_channel::{RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = broadcast(1);
+    s.send(7).unwrap();
+    assert_eq!(r.try_recv(), Ok(7));
+
+    s.send(8).unwrap();
+    assert_eq!(r.recv(), Ok(8));
+
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(r.recv_timeout(ms(1000)), Err(RecvTimeoutError::Timeout));
+}
+
+#[test]
+#[should_panic(expected = "capacity must be positive")]
+fn zero_capacity() {
+    let _ = broadcast::<()>(0);
+}
+
+#[test]
+fn capacity() {
+    for i in 1..10 {
+        let (s, r) = broadcast::<()>(i);
+        assert_eq!(s.capacity(), Some(i));
+        assert_eq!(r.capacity(), Some(i));
+    }
+}
+
+#[test]
+fn every_receiver_gets_every_message() {
+    let (s, r1) = broadcast(10);
+    let r2 = r1.clone();
+
+    for i in 0..5 {
+        s.send(i).unwrap();
+    }
+
+    for i in 0..5 {
+        assert_eq!(r1.recv(), Ok(i));
+    }
+    assert_eq!(r1.try_recv(), Err(TryRecvError::Empty));
+
+    for i in 0..5 {
+        assert_eq!(r2.recv(), Ok(i));
+    }
+    assert_eq!(r2.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn clone_starts_at_same_position() {
+    let (s, r1) = broadcast(10);
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    assert_eq!(r1.recv(), Ok(1));
+    let r2 = r1.clone();
+
+    assert_eq!(r2.recv(), Ok(2));
+    assert_eq!(r1.recv(), Ok(2));
+}
+
+#[test]
+fn len_empty_full() {
+    let (s, r1) = broadcast(2);
+    let r2 = r1.clone();
+
+    assert_eq!(s.len(), 0);
+    assert!(s.is_empty());
+    assert!(!s.is_full());
+    assert_eq!(r1.len(), 0);
+    assert!(r1.is_empty());
+    assert!(!r1.is_full());
+
+    s.send(()).unwrap();
+    s.send(()).unwrap();
+
+    assert_eq!(s.len(), 2);
+    assert!(s.is_full());
+    assert_eq!(r1.len(), 2);
+    assert!(r1.is_full());
+
+    r1.recv().unwrap();
+
+    // Messages are kept in the channel for other receivers.
+    assert_eq!(s.len(), 2);
+    assert_eq!(r1.len(), 1);
+    assert!(!r1.is_full());
+    assert_eq!(r2.len(), 2);
+    assert!(r2.is_full());
+
+    // Sending never blocks, so the oldest message gets evicted.
+    s.send(()).unwrap();
+
+    assert_eq!(s.len(), 2);
+    assert_eq!(r1.len(), 2);
+    assert_eq!(r2.len(), 2);
+}
+
+#[test]
+fn skip_ahead() {
+    let (s, r1) = broadcast(3);
+    let r2 = r1.clone();
+
+    for i in 0..10 {
+        s.send(i).unwrap();
+        assert_eq!(r1.recv(), Ok(i));
+    }
+
+    assert_eq!(r2.recv(), Ok(7));
+    assert_eq!(r2.recv(), Ok(8));
+    assert_eq!(r2.recv(), Ok(9));
+    assert_eq!(r2.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn lagging_disconnect() {
+    let (s, r1) = broadcast_with_lagging(3, Lagging::Disconnect);
+    let r2 = r1.clone();
+
+    for i in 0..3 {
+        s.send(i).unwrap();
+    }
+    assert_eq!(r2.recv(), Ok(0));
+
+    s.send(3).unwrap();
+    assert!(r1.is_lagged());
+    assert!(!r2.is_lagged());
+    assert_eq!(r1.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(r1.recv(), Err(RecvError));
+    assert!(r1.is_empty());
+    assert!(r1.is_lagged());
+
+    // Catching up doesn't reconnect the receiver.
+    assert_eq!(r2.recv(), Ok(1));
+    s.send(4).unwrap();
+    assert_eq!(r1.try_recv(), Err(TryRecvError::Disconnected));
+
+    // Clones of a lagged receiver are disconnected too.
+    assert_eq!(r1.clone().try_recv(), Err(TryRecvError::Disconnected));
+    assert!(r1.clone().is_lagged());
+
+    // A receiver that kept up is not lagging once the channel gets disconnected.
+    drop(s);
+    for i in 2..5 {
+        assert_eq!(r2.recv(), Ok(i));
+    }
+    assert_eq!(r2.recv(), Err(RecvError));
+    assert!(!r2.is_lagged());
+}
+
+#[test]
+fn recv_many() {
+    let (s, r1) = broadcast(3);
+    let r2 = r1.clone();
+    let mut buf = Vec::new();
+
+    assert_eq!(r1.try_recv_many(&mut buf, 10), Err(TryRecvError::Empty));
+
+    for i in 0..5 {
+        s.send(i).unwrap();
+    }
+    assert_eq!(r1.recv_many(&mut buf, 2), Ok(2));
+    assert_eq!(buf, [2, 3]);
+    assert_eq!(r2.recv_many(&mut buf, 10), Ok(3));
+    assert_eq!(buf, [2, 3, 2, 3, 4]);
+
+    drop(s);
+    assert_eq!(r1.try_recv_many(&mut buf, 10), Ok(1));
+    assert_eq!(
+        r1.try_recv_many(&mut buf, 10),
+        Err(TryRecvError::Disconnected)
+    );
+    assert_eq!(r2.recv_many(&mut buf, 10), Err(RecvError));
+}
+
+#[test]
+fn send_many() {
+    let (s, r1) = broadcast(3);
+    let r2 = r1.clone();
+    let mut msgs = VecDeque::new();
+
+    assert_eq!(s.try_send_many(&mut msgs), Ok(0));
+
+    // Sending never blocks, so only the newest messages are kept.
+    msgs.extend(0..2);
+    assert_eq!(s.try_send_many(&mut msgs), Ok(2));
+    assert!(msgs.is_empty());
+    assert_eq!(r1.recv(), Ok(0));
+
+    msgs.extend(2..7);
+    assert_eq!(s.try_send_many(&mut msgs), Ok(5));
+    for i in 4..7 {
+        assert_eq!(r1.recv(), Ok(i));
+        assert_eq!(r2.recv(), Ok(i));
+    }
+
+    assert_eq!(s.send_iter(7..9), Ok(()));
+    assert_eq!(r1.try_recv(), Ok(7));
+    assert_eq!(r2.try_recv(), Ok(7));
+
+    drop(r1);
+    drop(r2);
+    msgs.extend(0..3);
+    assert_eq!(
+        s.try_send_many(&mut msgs),
+        Err(TrySendError::Disconnected(()))
+    );
+    assert_eq!(msgs, [0, 1, 2]);
+    assert_eq!(s.send_iter(msgs), Err(SendError(0)));
+}
+
+#[test]
+fn send_after_disconnect() {
+    let (s, r) = broadcast(10);
+
+    s.send(1).unwrap();
+    drop(r);
+
+    assert_eq!(s.send(2), Err(SendError(2)));
+    assert!(s.try_send(3).is_err());
+}
+
+#[test]
+fn recv_after_disconnect() {
+    let (s, r1) = broadcast(10);
+    let r2 = r1.clone();
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    drop(s);
+
+    for r in &[r1, r2] {
+        assert_eq!(r.recv(), Ok(1));
+        assert_eq!(r.recv(), Ok(2));
+        assert_eq!(r.recv(), Err(RecvError));
+    }
+}
+
+#[test]
+fn disconnect_wakes_receiver() {
+    let (s, r) = broadcast::<()>(1);
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            assert_eq!(r.recv(), Err(RecvError));
+        });
+        scope.spawn(move |_| {
+            thread::sleep(ms(1000));
+            drop(s);
+        });
+    })
+    .unwrap();
+}
+
+#[test]
+fn send_wakes_all_receivers() {
+    const THREADS: usize = 4;
+
+    let (s, r) = broadcast(1);
+    let received = AtomicUsize::new(0);
+
+    scope(|scope| {
+        for _ in 0..THREADS {
+            let r = r.clone();
+            let received = &received;
+            scope.spawn(move |_| {
+                assert_eq!(r.recv(), Ok(7));
+                received.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+
+        thread::sleep(ms(500));
+        s.send(7).unwrap();
+    })
+    .unwrap();
+
+    assert_eq!(received.load(Ordering::SeqCst), THREADS);
+}
+
+#[test]
+fn spmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = broadcast(COUNT);
+    let receivers = (0..THREADS).map(|_| r.clone()).collect::<Vec<_>>();
+    drop(r);
+
+    scope(|scope| {
+        for r in receivers {
+            scope.spawn(move |_| {
+                for i in 0..COUNT {
+                    assert_eq!(r.recv(), Ok(i));
+                }
+                assert_eq!(r.recv(), Err(RecvError));
+            });
+        }
+
+        for i in 0..COUNT {
+            s.send(i).unwrap();
+        }
+        drop(s);
+    })
+    .unwrap();
+}
+
+#[test]
+fn mpmc_lagging() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = broadcast(16);
+    let receivers = (0..THREADS).map(|_| r.clone()).collect::<Vec<_>>();
+    drop(r);
+
+    scope(|scope| {
+        for r in receivers {
+            scope.spawn(move |_| {
+                // Messages from a single sender arrive in order even when some are skipped.
+                let mut last = [None; THREADS];
+                for (sender, i) in r.iter() {
+                    if let Some(l) = last[sender] {
+                        assert!(l < i);
+                    }
+                    last[sender] = Some(i);
+                }
+            });
+        }
+
+        for sender in 0..THREADS {
+            let s = s.clone();
+            scope.spawn(move |_| {
+                for i in 0..COUNT {
+                    s.send((sender, i)).unwrap();
+                }
+            });
+        }
+        drop(s);
+    })
+    .unwrap();
+}
+
+#[test]
+fn select() {
+    let (s1, r1) = broadcast(10);
+    let (s2, r2) = broadcast(10);
+    let r3 = r1.clone();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            s1.send(1).unwrap();
+            thread::sleep(ms(500));
+            s2.send(2).unwrap();
+        });
+
+        select! {
+            recv(r1) -> msg => assert_eq!(msg, Ok(1)),
+            recv(r2) -> _ => panic!(),
+        }
+        select! {
+            recv(r1) -> _ => panic!(),
+            recv(r2) -> msg => assert_eq!(msg, Ok(2)),
+        }
+    })
+    .unwrap();
+
+    // The other receiver still gets the message.
+    let mut sel = Select::new();
+    sel.recv(&r3);
+    let oper = sel.select_timeout(ms(1000)).unwrap();
+    assert_eq!(oper.recv(&r3), Ok(1));
+
+    // Sending is always ready.
+    select! {
+        send(s1, 3) -> res => assert_eq!(res, Ok(())),
+        default => panic!(),
+    }
+    assert_eq!(r1.recv(), Ok(3));
+}
@@ -0,0 +1,354 @@
+//! Tests for the mock clock.
+
+#![cfg(feature = "test-util")]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::clock::{self, MockClock};
+use crossbeam_channel::{after, at, bounded, never, tick, tick_at, tick_with, unbounded};
+use crossbeam_channel::{MissedTick, Select};
+use crossbeam_channel::{RecvTimeoutError, SendTimeoutError, TryRecvError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+/// Set while a test in this file is running.
+static LOCKED: AtomicBool = AtomicBool::new(false);
+
+/// Releases the lock when dropped.
+struct Lock;
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        LOCKED.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Serializes tests in this file, since the mock clock is process-wide.
+fn lock() -> Lock {
+    while LOCKED
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        thread::sleep(ms(1));
+    }
+    Lock
+}
+
+/// Keeps advancing the clock in small steps until `done` is set.
+fn advance_until(clock: &MockClock, done: &AtomicBool) {
+    while !done.load(Ordering::SeqCst) {
+        clock.advance(secs(1));
+        thread::sleep(ms(1));
+    }
+}
+
+#[test]
+fn now() {
+    let _lock = lock();
+    let clock = MockClock::install();
+
+    let start = clock::now();
+    assert_eq!(clock.now(), start);
+    thread::sleep(ms(50));
+    assert_eq!(clock::now(), start);
+
+    clock.advance(secs(100));
+    assert_eq!(clock::now(), start + secs(100));
+
+    drop(clock);
+    let now = clock::now();
+    assert!(now < start + secs(1));
+    assert!(now + secs(1) > Instant::now());
+}
+
+#[test]
+#[should_panic(expected = "a mock clock is already installed")]
+fn install_twice() {
+    let _lock = lock();
+    let _clock = MockClock::install();
+    let _ = MockClock::install();
+}
+
+#[test]
+fn after_and_at() {
+    let _lock = lock();
+    let clock = MockClock::install();
+    let start = clock.now();
+
+    let r1 = after(secs(60));
+    let r2 = at(start + secs(30));
+
+    clock.advance(secs(29));
+    assert_eq!(r1.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(r2.try_recv(), Err(TryRecvError::Empty));
+
+    clock.advance(secs(1));
+    assert_eq!(r1.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(r2.try_recv(), Ok(start + secs(30)));
+
+    clock.advance(secs(30));
+    assert_eq!(r1.try_recv(), Ok(start + secs(60)));
+    assert_eq!(r1.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn tick_messages() {
+    let _lock = lock();
+    let clock = MockClock::install();
+    let start = clock.now();
+
+    let r = tick(secs(10));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+
+    clock.advance(secs(10));
+    assert_eq!(r.try_recv(), Ok(start + secs(10)));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+
+    // Missed ticks are coalesced into one message.
+    clock.advance(secs(35));
+    assert_eq!(r.try_recv(), Ok(start + secs(20)));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+
+    clock.advance(secs(10));
+    assert_eq!(r.try_recv(), Ok(start + secs(55)));
+}
+
+#[test]
+fn missed_ticks() {
+    let _lock = lock();
+    let clock = MockClock::install();
+    let start = clock.now();
+
+    let burst = tick_with(secs(10), MissedTick::Burst);
+    let skip = tick_with(secs(10), MissedTick::Skip);
+    let delay = tick_with(secs(10), MissedTick::Delay);
+    let aligned = tick_at(start + secs(5), secs(10));
+
+    clock.advance(secs(35));
+    assert_eq!(burst.try_recv(), Ok(start + secs(10)));
+    assert_eq!(burst.try_recv(), Ok(start + secs(20)));
+    assert_eq!(burst.try_recv(), Ok(start + secs(30)));
+    assert_eq!(burst.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(skip.try_recv(), Ok(start + secs(10)));
+    assert_eq!(skip.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(delay.try_recv(), Ok(start + secs(10)));
+    assert_eq!(delay.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(aligned.try_recv(), Ok(start + secs(5)));
+    assert_eq!(aligned.try_recv(), Err(TryRecvError::Empty));
+
+    clock.advance(secs(5));
+    assert_eq!(burst.try_recv(), Ok(start + secs(40)));
+    assert_eq!(skip.try_recv(), Ok(start + secs(40)));
+    assert_eq!(delay.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(aligned.try_recv(), Err(TryRecvError::Empty));
+
+    clock.advance(secs(5));
+    assert_eq!(delay.try_recv(), Ok(start + secs(45)));
+    assert_eq!(aligned.try_recv(), Ok(start + secs(45)));
+}
+
+#[test]
+fn missed_burst() {
+    let _lock = lock();
+    let clock = MockClock::install();
+    let start = clock.now();
+    let done = AtomicBool::new(false);
+    let r = tick_with(secs(5), MissedTick::Burst);
+
+    clock.advance(secs(17));
+    assert_eq!(r.try_recv(), Ok(start + secs(5)));
+    assert_eq!(r.try_recv(), Ok(start + secs(10)));
+    assert_eq!(r.try_recv(), Ok(start + secs(15)));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+
+    // After catching up, the next message is sent on schedule.
+    scope(|scope| {
+        scope.spawn(|_| {
+            assert_eq!(r.recv(), Ok(start + secs(20)));
+            assert!(clock::now() >= start + secs(20));
+            done.store(true, Ordering::SeqCst);
+        });
+        advance_until(&clock, &done);
+    })
+    .unwrap();
+}
+
+#[test]
+fn missed_skip() {
+    let _lock = lock();
+    let clock = MockClock::install();
+    let start = clock.now();
+    let r = tick_with(secs(10), MissedTick::Skip);
+
+    clock.advance(secs(35));
+    assert_eq!(r.try_recv(), Ok(start + secs(10)));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+
+    // The next message stays on the original schedule.
+    clock.advance(secs(4));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    clock.advance(secs(1));
+    assert_eq!(r.try_recv(), Ok(start + secs(40)));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn phase_aligned() {
+    let _lock = lock();
+    let clock = MockClock::install();
+    let first = clock.now() + secs(10);
+    let r = tick_at(first, secs(5));
+
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    clock.advance(secs(10));
+    assert_eq!(r.try_recv(), Ok(first));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+
+    clock.advance(secs(6));
+    assert_eq!(r.try_recv(), Ok(first + secs(5)));
+
+    // Missed ticks are skipped, so messages stay aligned to the schedule.
+    clock.advance(secs(10));
+    assert_eq!(r.try_recv(), Ok(first + secs(10)));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    clock.advance(secs(4));
+    assert_eq!(r.try_recv(), Ok(first + secs(20)));
+
+    // A start in the past delivers the first message right away.
+    let past = clock.now() - secs(1);
+    let r = tick_at(past, secs(100));
+    assert_eq!(r.try_recv(), Ok(past));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn blocking_recv() {
+    let _lock = lock();
+    let clock = MockClock::install();
+    let start = clock.now();
+    let done = AtomicBool::new(false);
+    let timeout = after(secs(60));
+    let ticker = tick(secs(5));
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            assert_eq!(ticker.recv(), Ok(start + secs(5)));
+            assert_eq!(timeout.recv(), Ok(start + secs(60)));
+            done.store(true, Ordering::SeqCst);
+        });
+        advance_until(&clock, &done);
+    })
+    .unwrap();
+
+    assert!(clock.now() >= start + secs(60));
+}
+
+#[test]
+fn recv_timeout() {
+    let _lock = lock();
+    let clock = MockClock::install();
+    let start = clock.now();
+    let done = AtomicBool::new(false);
+    let (s, r) = unbounded::<i32>();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            assert_eq!(r.recv_timeout(secs(30)), Err(RecvTimeoutError::Timeout));
+            assert!(clock::now() >= start + secs(30));
+            done.store(true, Ordering::SeqCst);
+        });
+        advance_until(&clock, &done);
+    })
+    .unwrap();
+
+    // A deadline that was already reached in mock time expires right away.
+    s.send(1).unwrap();
+    assert_eq!(r.recv_deadline(start), Ok(1));
+    assert_eq!(r.recv_deadline(start), Err(RecvTimeoutError::Timeout));
+}
+
+#[test]
+fn send_deadline() {
+    let _lock = lock();
+    let clock = MockClock::install();
+    let start = clock.now();
+    let done = AtomicBool::new(false);
+    let (s, _r) = bounded(1);
+    s.send(1).unwrap();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            assert_eq!(
+                s.send_deadline(2, start + secs(20)),
+                Err(SendTimeoutError::Timeout(2))
+            );
+            assert_eq!(
+                s.send_timeout(3, secs(0)),
+                Err(SendTimeoutError::Timeout(3))
+            );
+            done.store(true, Ordering::SeqCst);
+        });
+        advance_until(&clock, &done);
+    })
+    .unwrap();
+
+    assert!(clock.now() >= start + secs(20));
+}
+
+#[test]
+fn select_deadline() {
+    let _lock = lock();
+    let clock = MockClock::install();
+    let start = clock.now();
+    let done = AtomicBool::new(false);
+    let r = never::<i32>();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            let mut sel = Select::new();
+            sel.recv(&r);
+            assert!(sel.select_deadline(start + secs(10)).is_err());
+            assert!(sel.ready_timeout(secs(10)).is_err());
+
+            let timeout = after(secs(10));
+            let mut sel = Select::new();
+            sel.recv(&r);
+            let index = sel.recv(&timeout);
+            let oper = sel.select();
+            assert_eq!(oper.index(), index);
+            assert!(oper.recv(&timeout).is_ok());
+
+            done.store(true, Ordering::SeqCst);
+        });
+        advance_until(&clock, &done);
+    })
+    .unwrap();
+
+    assert!(clock.now() >= start + secs(30));
+}
+
+#[test]
+fn uninstall_wakes_sleepers() {
+    let _lock = lock();
+    let clock = MockClock::install();
+    let (_s, r) = unbounded::<i32>();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            // Once the mock clock is gone, this times out in real time.
+            assert_eq!(r.recv_timeout(ms(500)), Err(RecvTimeoutError::Timeout));
+        });
+        thread::sleep(ms(100));
+        drop(clock);
+    })
+    .unwrap();
+}
@@ -0,0 +1,159 @@
+//! Tests for closing channels explicitly.
+
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{after, bounded, broadcast, never, select, tick, unbounded};
+use crossbeam_channel::{RecvError, SendError, TryRecvError, TrySendError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn close_sender() {
+    let (s, r) = unbounded::<i32>();
+    assert!(!s.is_closed());
+    assert!(!r.is_closed());
+
+    assert!(s.close());
+    assert!(s.is_closed());
+    assert!(r.is_closed());
+    assert!(!s.close());
+    assert!(!r.close());
+
+    let (s, r) = bounded::<i32>(1);
+    assert!(s.close());
+    assert!(r.is_closed());
+
+    let (s, r) = bounded::<i32>(0);
+    assert!(s.close());
+    assert!(r.is_closed());
+
+    let (s, r) = broadcast::<i32>(1);
+    assert!(s.close());
+    assert!(r.is_closed());
+}
+
+#[test]
+fn close_receiver() {
+    let (s, r) = unbounded::<i32>();
+    assert!(r.close());
+    assert!(s.is_closed());
+    assert!(!s.close());
+    assert_eq!(s.send(1), Err(SendError(1)));
+
+    let (s, r) = bounded::<i32>(1);
+    assert!(r.close());
+    assert_eq!(s.try_send(1), Err(TrySendError::Disconnected(1)));
+
+    let (s, r) = bounded::<i32>(0);
+    assert!(r.close());
+    assert_eq!(s.try_send(1), Err(TrySendError::Disconnected(1)));
+
+    let (s, r) = broadcast::<i32>(1);
+    assert!(r.close());
+    assert_eq!(s.try_send(1), Err(TrySendError::Disconnected(1)));
+}
+
+#[test]
+fn drop_is_closed() {
+    let (s, r) = unbounded::<i32>();
+    drop(s);
+    assert!(r.is_closed());
+    assert!(!r.close());
+
+    let (s, r) = bounded::<i32>(1);
+    drop(r);
+    assert!(s.is_closed());
+    assert!(!s.close());
+}
+
+#[test]
+fn drain_after_close() {
+    for cap in 1..3 {
+        let (s, r) = bounded(cap);
+        s.send(1).unwrap();
+        assert!(s.close());
+        assert_eq!(r.try_recv(), Ok(1));
+        assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    let (s, r) = unbounded();
+    for i in 0..100 {
+        s.send(i).unwrap();
+    }
+    assert!(r.close());
+    assert_eq!(s.send(100), Err(SendError(100)));
+    assert_eq!(r.iter().collect::<Vec<_>>(), (0..100).collect::<Vec<_>>());
+
+    let (s, r1) = broadcast(2);
+    let r2 = r1.clone();
+    s.send(1).unwrap();
+    assert!(s.close());
+    assert_eq!(r1.recv(), Ok(1));
+    assert_eq!(r1.recv(), Err(RecvError));
+    assert_eq!(r2.recv(), Ok(1));
+    assert_eq!(r2.recv(), Err(RecvError));
+}
+
+#[test]
+fn close_wakes_receivers() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = bounded::<i32>(1);
+    let (s3, r3) = bounded::<i32>(0);
+
+    scope(|scope| {
+        scope.spawn(|_| assert_eq!(r1.recv(), Err(RecvError)));
+        scope.spawn(|_| assert_eq!(r2.recv(), Err(RecvError)));
+        scope.spawn(|_| assert_eq!(r3.recv(), Err(RecvError)));
+        scope.spawn(|_| {
+            select! {
+                recv(r1) -> msg => assert_eq!(msg, Err(RecvError)),
+                recv(never::<i32>()) -> _ => panic!(),
+            }
+        });
+
+        thread::sleep(ms(500));
+        assert!(s1.close());
+        assert!(s2.close());
+        assert!(s3.close());
+    })
+    .unwrap();
+}
+
+#[test]
+fn close_wakes_senders() {
+    let (s1, r1) = bounded(1);
+    let (s2, r2) = bounded(0);
+    s1.send(0).unwrap();
+
+    scope(|scope| {
+        scope.spawn(|_| assert_eq!(s1.send(1), Err(SendError(1))));
+        scope.spawn(|_| assert_eq!(s2.send(2), Err(SendError(2))));
+
+        thread::sleep(ms(500));
+        assert!(r1.close());
+        assert!(r2.close());
+    })
+    .unwrap();
+
+    assert_eq!(r1.recv(), Ok(0));
+    assert_eq!(r1.recv(), Err(RecvError));
+}
+
+#[test]
+fn cannot_close_special_channels() {
+    let r = after(ms(1000));
+    assert!(!r.close());
+    assert!(!r.is_closed());
+
+    let r = tick(ms(1000));
+    assert!(!r.close());
+    assert!(!r.is_closed());
+
+    let r = never::<i32>();
+    assert!(!r.close());
+    assert!(!r.is_closed());
+}
@@ -0,0 +1,187 @@
+//! Tests for sender and receiver counts and waiting for receivers to close.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{after, bounded, broadcast, never, priority_unbounded, unbounded};
+use crossbeam_channel::{select, Select, Sender};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn counts() {
+    let (s1, r1) = bounded::<i32>(1);
+    assert_eq!(s1.sender_count(), 1);
+    assert_eq!(s1.receiver_count(), 1);
+
+    let s2 = s1.clone();
+    let r2 = r1.clone();
+    let r3 = r2.clone();
+    assert_eq!(r1.sender_count(), 2);
+    assert_eq!(r1.receiver_count(), 3);
+    assert_eq!(s2.receiver_count(), 3);
+
+    // Weak handles don't count.
+    let w = s1.downgrade();
+    assert_eq!(r1.sender_count(), 2);
+    let s3 = w.upgrade().unwrap();
+    assert_eq!(r1.sender_count(), 3);
+
+    drop(s1);
+    drop(s2);
+    drop(r1);
+    assert_eq!(s3.sender_count(), 1);
+    assert_eq!(s3.receiver_count(), 2);
+
+    // Closing the channel doesn't change the counts.
+    assert!(r2.close());
+    assert_eq!(s3.receiver_count(), 2);
+
+    drop(r2);
+    drop(r3);
+    assert_eq!(s3.receiver_count(), 0);
+}
+
+#[test]
+fn other_flavors() {
+    let (s, r) = unbounded::<i32>();
+    let _r2 = r.clone();
+    assert_eq!(s.receiver_count(), 2);
+
+    let (s, r) = bounded::<i32>(0);
+    let _s2 = s.clone();
+    assert_eq!(r.sender_count(), 2);
+
+    let (s, r) = broadcast::<i32>(1);
+    let _r2 = r.clone();
+    assert_eq!(s.receiver_count(), 2);
+
+    let (s, r) = priority_unbounded::<i32>();
+    drop(r);
+    assert_eq!(s.receiver_count(), 0);
+
+    let r = after(ms(100));
+    let _r2 = r.clone();
+    assert_eq!(r.sender_count(), 0);
+    assert_eq!(r.receiver_count(), 1);
+
+    let r = never::<i32>();
+    assert_eq!(r.sender_count(), 0);
+    assert_eq!(r.receiver_count(), 1);
+}
+
+fn wait(s: Sender<i32>, close: impl FnOnce() + Send) {
+    scope(|scope| {
+        scope.spawn(move |_| {
+            thread::sleep(ms(500));
+            close();
+        });
+
+        let start = Instant::now();
+        s.wait_for_receivers_closed();
+        assert!(Instant::now() - start >= ms(400));
+        assert!(s.is_closed());
+    })
+    .unwrap();
+}
+
+#[test]
+fn wait_for_receivers_closed() {
+    let (s, r) = bounded(1);
+    let r2 = r.clone();
+    wait(s, move || {
+        drop(r);
+        drop(r2);
+    });
+
+    let (s, r) = unbounded();
+    wait(s, move || drop(r));
+
+    let (s, r) = bounded(0);
+    wait(s, move || drop(r));
+
+    let (s, r) = broadcast(2);
+    wait(s, move || drop(r));
+
+    let (s, r) = priority_unbounded();
+    wait(s, move || drop(r));
+
+    // Closing the channel wakes up waiting senders too.
+    let (s, r) = bounded(1);
+    let s2 = s.clone();
+    wait(s, move || assert!(s2.close()));
+    drop(r);
+
+    let (s, r) = unbounded();
+    wait(s, move || assert!(r.close()));
+}
+
+#[test]
+fn already_closed() {
+    let (s, r) = unbounded::<i32>();
+    drop(r);
+    s.wait_for_receivers_closed();
+
+    let (s, _r) = bounded::<i32>(1);
+    s.close();
+    s.wait_for_receivers_closed();
+}
+
+#[test]
+fn select() {
+    let (s, r) = bounded::<i32>(1);
+    let (_s2, r2) = unbounded::<i32>();
+
+    select! {
+        recv(r2) -> _ => panic!(),
+        recv_custom(s.receivers_closed()) -> () => panic!(),
+        default(ms(100)) => {}
+    }
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            thread::sleep(ms(500));
+            drop(r);
+        });
+
+        let start = Instant::now();
+        select! {
+            recv(r2) -> _ => panic!(),
+            recv_custom(s.receivers_closed()) -> () => {}
+        }
+        assert!(Instant::now() - start >= ms(400));
+    })
+    .unwrap();
+
+    let (s, r) = unbounded::<i32>();
+    let closed = s.receivers_closed();
+    let mut sel = Select::new();
+    let index = sel.recv_custom(&closed);
+    assert!(sel.try_select().is_err());
+
+    drop(r);
+    let oper = sel.select_timeout(ms(1000)).unwrap();
+    assert_eq!(oper.index(), index);
+    oper.recv_custom(&closed);
+}
+
+#[test]
+fn stress() {
+    const COUNT: usize = 1000;
+
+    for _ in 0..COUNT {
+        let (s, r) = unbounded::<i32>();
+        let r2 = r.clone();
+
+        scope(|scope| {
+            scope.spawn(|_| s.wait_for_receivers_closed());
+            scope.spawn(|_| s.wait_for_receivers_closed());
+            scope.spawn(move |_| drop(r));
+            scope.spawn(move |_| drop(r2));
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,323 @@
+//! Tests for user-defined selectable sources.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{never, select, select_biased, unbounded, Select, TryReadyError};
+use crossbeam_channel::{Registration, RegistrationId, Selectable, Slot};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// A token that becomes ready once cancelled.
+#[derive(Default)]
+struct Cancel {
+    inner: Mutex<(bool, Vec<Registration>)>,
+}
+
+impl Cancel {
+    fn cancel(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.0 = true;
+        for reg in inner.1.drain(..) {
+            reg.wake();
+        }
+    }
+}
+
+impl Selectable for Cancel {
+    type Output = ();
+
+    fn try_select(&self, _slot: &mut Slot) -> bool {
+        self.is_ready()
+    }
+
+    fn register(&self, reg: &Registration) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        inner.1.push(reg.clone());
+        inner.0
+    }
+
+    fn unregister(&self, id: RegistrationId) {
+        self.inner.lock().unwrap().1.retain(|reg| reg.id() != id);
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.lock().unwrap().0
+    }
+
+    fn complete(&self, _slot: &mut Slot) {}
+}
+
+/// A queue that hands each item to exactly one select.
+#[derive(Default)]
+struct Queue {
+    inner: Mutex<(VecDeque<usize>, Vec<Registration>)>,
+}
+
+impl Queue {
+    fn push(&self, item: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.0.push_back(item);
+
+        // Wake registrations until one of them accepts the item.
+        while !inner.1.is_empty() {
+            if inner.1.remove(0).wake() {
+                break;
+            }
+        }
+    }
+
+    fn registrations(&self) -> usize {
+        self.inner.lock().unwrap().1.len()
+    }
+}
+
+impl Selectable for Queue {
+    type Output = usize;
+
+    fn try_select(&self, slot: &mut Slot) -> bool {
+        match self.inner.lock().unwrap().0.pop_front() {
+            None => false,
+            Some(item) => {
+                slot.put(item);
+                true
+            }
+        }
+    }
+
+    fn register(&self, reg: &Registration) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        inner.1.push(reg.clone());
+        !inner.0.is_empty()
+    }
+
+    fn unregister(&self, id: RegistrationId) {
+        self.inner.lock().unwrap().1.retain(|reg| reg.id() != id);
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.inner.lock().unwrap().0.is_empty()
+    }
+
+    fn complete(&self, slot: &mut Slot) -> usize {
+        slot.take().unwrap()
+    }
+}
+
+/// A source that becomes ready at a deadline without ever waking registrations.
+struct Deadline(Instant);
+
+impl Selectable for Deadline {
+    type Output = Instant;
+
+    fn try_select(&self, _slot: &mut Slot) -> bool {
+        self.is_ready()
+    }
+
+    fn register(&self, _reg: &Registration) -> bool {
+        self.is_ready()
+    }
+
+    fn unregister(&self, _id: RegistrationId) {}
+
+    fn is_ready(&self) -> bool {
+        Instant::now() >= self.0
+    }
+
+    fn complete(&self, _slot: &mut Slot) -> Instant {
+        self.0
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        Some(self.0)
+    }
+}
+
+#[test]
+fn smoke() {
+    let cancel = Cancel::default();
+    let mut sel = Select::new();
+    let index = sel.recv_custom(&cancel);
+    assert!(sel.try_select().is_err());
+
+    cancel.cancel();
+    let oper = sel.try_select().unwrap();
+    assert_eq!(oper.index(), index);
+    oper.recv_custom(&cancel);
+}
+
+#[test]
+fn slot() {
+    let queue = Queue::default();
+    queue.push(1);
+    queue.push(2);
+
+    let mut sel = Select::new();
+    sel.recv_custom(&queue);
+    assert_eq!(sel.select().recv_custom(&queue), 1);
+    assert_eq!(sel.select().recv_custom(&queue), 2);
+    assert!(sel.try_select().is_err());
+}
+
+#[test]
+fn wake_blocked() {
+    let cancel = Cancel::default();
+    let (_s, r) = unbounded::<i32>();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            cancel.cancel();
+        });
+
+        let mut sel = Select::new();
+        sel.recv(&r);
+        let index = sel.recv_custom(&cancel);
+
+        let oper = sel.select_timeout(ms(2000)).unwrap();
+        assert_eq!(oper.index(), index);
+        oper.recv_custom(&cancel);
+    })
+    .unwrap();
+
+    assert_eq!(cancel.inner.lock().unwrap().1.len(), 0);
+}
+
+#[test]
+fn channel_wins() {
+    let cancel = Cancel::default();
+    let (s, r) = unbounded();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            s.send(7).unwrap();
+        });
+
+        select! {
+            recv(r) -> msg => assert_eq!(msg, Ok(7)),
+            recv_custom(cancel) -> _ => panic!(),
+        }
+    })
+    .unwrap();
+
+    // The registration was removed after the channel operation got selected.
+    assert_eq!(cancel.inner.lock().unwrap().1.len(), 0);
+}
+
+#[test]
+fn select_macro() {
+    let cancel = Cancel::default();
+    let queue = Queue::default();
+    let (_s, r) = unbounded::<i32>();
+
+    select! {
+        recv_custom(cancel) -> _ => panic!(),
+        recv_custom(&queue) -> _ => panic!(),
+        default => {}
+    }
+
+    queue.push(5);
+    select! {
+        recv(r) -> _ => panic!(),
+        recv_custom(cancel) -> _ => panic!(),
+        recv_custom(&queue) -> item => assert_eq!(item, 5),
+    }
+
+    cancel.cancel();
+    select! {
+        recv_custom(cancel) -> () => {}
+        default(ms(1000)) => panic!(),
+    }
+}
+
+#[test]
+fn biased() {
+    let cancel = Cancel::default();
+    let queue = Queue::default();
+    cancel.cancel();
+
+    for i in 0..100 {
+        queue.push(i);
+        select_biased! {
+            recv_custom(queue) -> item => assert_eq!(item, i),
+            recv_custom(cancel) -> _ => panic!(),
+        }
+    }
+}
+
+#[test]
+fn deadline() {
+    let start = Instant::now();
+    let source = Deadline(start + ms(500));
+
+    select! {
+        recv(never::<i32>()) -> _ => panic!(),
+        recv_custom(source) -> when => assert_eq!(when, source.0),
+    }
+    assert!(Instant::now() >= start + ms(500));
+}
+
+#[test]
+fn ready() {
+    let cancel = Cancel::default();
+    let (_s, r) = unbounded::<i32>();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            cancel.cancel();
+        });
+
+        let mut sel = Select::new();
+        sel.recv(&r);
+        let index = sel.recv_custom(&cancel);
+        assert_eq!(sel.try_ready(), Err(TryReadyError));
+        assert_eq!(sel.ready_timeout(ms(2000)), Ok(index));
+    })
+    .unwrap();
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let queue = Queue::default();
+    let (s, r) = unbounded::<usize>();
+    let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+    scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                for _ in 0..2 * COUNT {
+                    let n = select! {
+                        recv(r) -> msg => msg.unwrap(),
+                        recv_custom(queue) -> item => item,
+                    };
+                    v[n].fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                for i in 0..COUNT {
+                    queue.push(i);
+                    s.send(i).unwrap();
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(queue.registrations(), 0);
+    for c in v {
+        assert_eq!(c.load(Ordering::SeqCst), 2 * THREADS);
+    }
+}
@@ -0,0 +1,318 @@
+//! Tests for message expiry.
+
+use std::collections::VecDeque;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, bounded_with_expiry, priority_unbounded, unbounded};
+use crossbeam_channel::{select, unbounded_with_expiry, Expiry, Receiver, Sender};
+use crossbeam_channel::{RecvTimeoutError, TryRecvError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Returns an expiry policy with the given time-to-live that sends expired messages into a
+/// dead-letter channel.
+fn dead_letter(ttl: Duration) -> (Expiry<i32>, Receiver<i32>) {
+    let (s, r) = unbounded();
+    let expiry = Expiry::new()
+        .ttl(ttl)
+        .on_expired(move |msg| s.send(msg).unwrap());
+    (expiry, r)
+}
+
+fn smoke(s: Sender<i32>, r: Receiver<i32>, dead: Receiver<i32>) {
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(r.len(), 2);
+    thread::sleep(ms(200));
+    s.send(3).unwrap();
+
+    // Expired messages still count towards the length until a receiver reaches them.
+    assert_eq!(r.len(), 3);
+    assert_eq!(r.recv(), Ok(3));
+    assert_eq!(r.len(), 0);
+    assert_eq!(dead.try_iter().collect::<Vec<_>>(), [1, 2]);
+
+    s.send(4).unwrap();
+    thread::sleep(ms(200));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(dead.try_recv(), Ok(4));
+
+    s.send(5).unwrap();
+    drop(s);
+    thread::sleep(ms(200));
+    assert_eq!(r.recv_timeout(ms(100)), Err(RecvTimeoutError::Disconnected));
+    assert_eq!(dead.try_recv(), Ok(5));
+}
+
+#[test]
+fn smoke_array() {
+    let (expiry, dead) = dead_letter(ms(100));
+    let (s, r) = bounded_with_expiry(4, expiry);
+    smoke(s, r, dead);
+}
+
+#[test]
+fn smoke_list() {
+    let (expiry, dead) = dead_letter(ms(100));
+    let (s, r) = unbounded_with_expiry(expiry);
+    smoke(s, r, dead);
+}
+
+#[test]
+fn send_with_ttl() {
+    let (expiry, dead) = dead_letter(ms(100));
+    let (s1, r1) = bounded_with_expiry(4, expiry);
+    let (expiry, dead2) = dead_letter(ms(100));
+    let (s2, r2) = unbounded_with_expiry(expiry);
+
+    for (s, r, dead) in &[(s1, r1, dead), (s2, r2, dead2)] {
+        // A message's own time-to-live takes precedence over the channel's.
+        s.send_with_ttl(1, ms(60_000)).unwrap();
+        s.send(2).unwrap();
+        s.send_with_ttl(3, ms(1)).unwrap();
+        thread::sleep(ms(200));
+
+        assert_eq!(r.try_recv(), Ok(1));
+        assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(dead.try_iter().collect::<Vec<_>>(), [2, 3]);
+    }
+
+    // Channels without an expiry policy drop messages sent with a time-to-live.
+    for (s, r) in &[bounded(4), unbounded()] {
+        s.send_with_ttl(Arc::new(1), ms(100)).unwrap();
+        s.send(Arc::new(2)).unwrap();
+        let msg = Arc::new(3);
+        s.send_with_ttl(msg.clone(), ms(100)).unwrap();
+        thread::sleep(ms(200));
+
+        assert_eq!(*r.recv().unwrap(), 2);
+        assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(Arc::strong_count(&msg), 1);
+    }
+}
+
+#[test]
+fn wrap_around() {
+    // Expire messages across several laps of a bounded channel and several blocks of an unbounded
+    // one.
+    let (expiry, dead) = dead_letter(ms(60_000));
+    let (s1, r1) = bounded_with_expiry(3, expiry);
+    let (expiry, dead2) = dead_letter(ms(60_000));
+    let (s2, r2) = unbounded_with_expiry(expiry);
+
+    for (s, r, dead) in &[(s1, r1, dead), (s2, r2, dead2)] {
+        for i in 0..100 {
+            if i % 2 == 0 {
+                s.send_with_ttl(i, ms(1)).unwrap();
+            } else {
+                thread::sleep(ms(2));
+                s.send(i).unwrap();
+                assert_eq!(r.recv(), Ok(i));
+                assert_eq!(dead.try_recv(), Ok(i - 1));
+            }
+        }
+    }
+}
+
+#[test]
+fn recv_waits() {
+    for &cap in &[None, Some(2)] {
+        let (expiry, dead) = dead_letter(ms(100));
+        let (s, r) = match cap {
+            None => unbounded_with_expiry(expiry),
+            Some(cap) => bounded_with_expiry(cap, expiry),
+        };
+        s.send(1).unwrap();
+
+        scope(|scope| {
+            scope.spawn(|_| {
+                thread::sleep(ms(500));
+                s.send(2).unwrap();
+            });
+
+            // The expired message doesn't wake the receiver up.
+            thread::sleep(ms(200));
+            let start = Instant::now();
+            assert_eq!(r.recv(), Ok(2));
+            assert!(Instant::now() - start >= ms(200));
+            assert_eq!(dead.try_recv(), Ok(1));
+        })
+        .unwrap();
+    }
+}
+
+#[test]
+fn batches() {
+    let (expiry, dead) = dead_letter(ms(100));
+    let (s1, r1) = bounded_with_expiry(40, expiry);
+    let (expiry, dead2) = dead_letter(ms(100));
+    let (s2, r2) = unbounded_with_expiry(expiry);
+
+    for (s, r, dead) in &[(s1, r1, dead), (s2, r2, dead2)] {
+        let mut msgs = (0..35).collect::<VecDeque<_>>();
+        assert_eq!(s.try_send_many(&mut msgs), Ok(35));
+        thread::sleep(ms(200));
+        s.send_with_ttl(35, ms(60_000)).unwrap();
+        s.send_with_ttl(36, ms(60_000)).unwrap();
+
+        let mut buf = Vec::new();
+        assert_eq!(r.try_recv_many(&mut buf, 10), Ok(2));
+        assert_eq!(buf, [35, 36]);
+        assert_eq!(dead.try_iter().count(), 35);
+        assert_eq!(r.try_recv_many(&mut buf, 10), Err(TryRecvError::Empty));
+
+        s.send(37).unwrap();
+        thread::sleep(ms(200));
+        s.send(38).unwrap();
+        buf.clear();
+        assert_eq!(r.recv_many(&mut buf, 10), Ok(1));
+        assert_eq!(buf, [38]);
+        assert_eq!(dead.try_recv(), Ok(37));
+    }
+}
+
+#[test]
+fn peek() {
+    let (expiry, dead) = dead_letter(ms(100));
+    let (s1, r1) = bounded_with_expiry(4, expiry);
+    let (expiry, dead2) = dead_letter(ms(100));
+    let (s2, r2) = unbounded_with_expiry(expiry);
+
+    for (s, r, dead) in &[(s1, r1, dead), (s2, r2, dead2)] {
+        s.send(1).unwrap();
+        s.send(2).unwrap();
+        thread::sleep(ms(200));
+        s.send(3).unwrap();
+
+        assert_eq!(r.try_peek_with(|x| *x), Ok(3));
+        assert_eq!(dead.try_iter().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(r.try_recv_if(|x| *x == 3), Ok(Some(3)));
+
+        s.send(4).unwrap();
+        thread::sleep(ms(200));
+        assert_eq!(r.try_recv_if(|_| true), Err(TryRecvError::Empty));
+        assert_eq!(dead.try_recv(), Ok(4));
+    }
+}
+
+#[test]
+fn select() {
+    let (expiry, dead) = dead_letter(ms(100));
+    let (s1, r1) = bounded_with_expiry(4, expiry);
+    let (expiry, dead2) = dead_letter(ms(100));
+    let (s2, r2) = unbounded_with_expiry(expiry);
+
+    s1.send(1).unwrap();
+    s2.send(2).unwrap();
+    thread::sleep(ms(200));
+
+    select! {
+        recv(r1) -> _ => panic!(),
+        recv(r2) -> _ => panic!(),
+        default => {}
+    }
+    assert_eq!(dead.try_recv(), Ok(1));
+    assert_eq!(dead2.try_recv(), Ok(2));
+
+    s2.send(3).unwrap();
+    select! {
+        recv(r1) -> _ => panic!(),
+        recv(r2) -> msg => assert_eq!(msg, Ok(3)),
+    }
+}
+
+#[test]
+fn other_flavors() {
+    // A zero-capacity channel hands messages over directly.
+    let (expiry, dead) = dead_letter(ms(1));
+    let (s, r) = bounded_with_expiry(0, expiry);
+    assert_eq!(s.capacity(), Some(0));
+    scope(|scope| {
+        scope.spawn(|_| s.send_with_ttl(1, ms(1)).unwrap());
+        thread::sleep(ms(100));
+        assert_eq!(r.recv(), Ok(1));
+    })
+    .unwrap();
+    // The expiry policy isn't kept, so the dead-letter channel gets disconnected.
+    assert_eq!(dead.try_recv(), Err(TryRecvError::Disconnected));
+
+    let (s, r) = priority_unbounded();
+    s.send_with_ttl(1, ms(1)).unwrap();
+    s.send_with_ttl(2, ms(1)).unwrap();
+    thread::sleep(ms(100));
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(r.recv(), Ok(1));
+}
+
+#[test]
+fn panicking_handler() {
+    let expiry = Expiry::new().on_expired(|msg: i32| {
+        if msg == 1 {
+            panic!()
+        }
+    });
+    let (s, r) = unbounded_with_expiry(expiry);
+    s.send_with_ttl(1, ms(1)).unwrap();
+    s.send_with_ttl(2, ms(1)).unwrap();
+    thread::sleep(ms(100));
+    s.send(3).unwrap();
+
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| r.try_recv()));
+    assert!(res.is_err());
+    assert_eq!(r.try_recv(), Ok(3));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    for &cap in &[None, Some(3)] {
+        let expired = Arc::new(AtomicUsize::new(0));
+        let counter = expired.clone();
+        let expiry = Expiry::new().on_expired(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        let (s, r) = match cap {
+            None => unbounded_with_expiry(expiry),
+            Some(cap) => bounded_with_expiry(cap, expiry),
+        };
+        let received = AtomicUsize::new(0);
+
+        scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|_| {
+                    for _ in r.iter() {
+                        received.fetch_add(1, Ordering::SeqCst);
+                    }
+                });
+            }
+            for _ in 0..THREADS {
+                let s = s.clone();
+                scope.spawn(move |_| {
+                    for i in 0..COUNT {
+                        // Every other message expires right away.
+                        if i % 2 == 0 {
+                            s.send_with_ttl(i, Duration::from_secs(0)).unwrap();
+                        } else {
+                            s.send(i).unwrap();
+                        }
+                    }
+                });
+            }
+            drop(s);
+        })
+        .unwrap();
+
+        assert_eq!(received.load(Ordering::SeqCst), COUNT * THREADS / 2);
+        assert_eq!(expired.load(Ordering::SeqCst), COUNT * THREADS / 2);
+    }
+}
@@ -0,0 +1,229 @@
+//! Tests for selection over file descriptors.
+
+#![cfg(target_os = "linux")]
+
+use std::io::{Read, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{select, unbounded, Readable, Select, Writable};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Both ends of a pipe, closed on drop.
+struct Pipe {
+    reader: RawFd,
+    writer: RawFd,
+}
+
+impl Pipe {
+    fn new() -> Pipe {
+        let mut fds = [0; 2];
+        assert_eq!(
+            unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK) },
+            0
+        );
+        Pipe {
+            reader: fds[0],
+            writer: fds[1],
+        }
+    }
+
+    fn write(&self, buf: &[u8]) -> isize {
+        unsafe { libc::write(self.writer, buf.as_ptr() as *const _, buf.len()) }
+    }
+
+    fn read(&self, buf: &mut [u8]) -> isize {
+        unsafe { libc::read(self.reader, buf.as_mut_ptr() as *mut _, buf.len()) }
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.reader);
+            libc::close(self.writer);
+        }
+    }
+}
+
+#[test]
+fn smoke() {
+    let (mut a, b) = UnixStream::pair().unwrap();
+
+    let mut sel = Select::new();
+    let index = sel.readable(&b);
+    assert!(sel.try_select().is_err());
+
+    a.write_all(b"x").unwrap();
+    let oper = sel.try_select().unwrap();
+    assert_eq!(oper.index(), index);
+    oper.readable(&b);
+}
+
+#[test]
+fn readable_pipe() {
+    let pipe = Pipe::new();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            assert_eq!(pipe.write(b"hello"), 5);
+        });
+
+        let mut sel = Select::new();
+        let index = sel.readable(&pipe.reader);
+        let oper = sel.select_timeout(ms(2000)).unwrap();
+        assert_eq!(oper.index(), index);
+        oper.readable(&pipe.reader);
+
+        let mut buf = [0; 5];
+        assert_eq!(pipe.read(&mut buf), 5);
+        assert_eq!(&buf, b"hello");
+    })
+    .unwrap();
+}
+
+#[test]
+fn writable_pipe() {
+    let pipe = Pipe::new();
+
+    // Fill the pipe so that it stops being writable.
+    while pipe.write(&[0; 4096]) > 0 {}
+
+    let mut sel = Select::new();
+    let index = sel.writable(&pipe.writer);
+    assert!(sel.try_select().is_err());
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            let mut buf = [0; 4096];
+            while pipe.read(&mut buf) > 0 {}
+        });
+
+        let oper = sel.select_timeout(ms(2000)).unwrap();
+        assert_eq!(oper.index(), index);
+        oper.writable(&pipe.writer);
+    })
+    .unwrap();
+}
+
+#[test]
+fn hangup() {
+    let (a, b) = UnixStream::pair().unwrap();
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            thread::sleep(ms(500));
+            drop(a);
+        });
+
+        select! {
+            recv_custom(Readable(b.as_raw_fd())) -> _ => {}
+            default(ms(2000)) => panic!(),
+        }
+    })
+    .unwrap();
+
+    let mut buf = [0; 1];
+    assert_eq!((&b).read(&mut buf).unwrap(), 0);
+}
+
+#[test]
+fn timeout() {
+    let (_a, b) = UnixStream::pair().unwrap();
+
+    let mut sel = Select::new();
+    sel.readable(&b);
+    assert!(sel.select_timeout(ms(500)).is_err());
+    assert!(sel.ready_timeout(ms(500)).is_err());
+}
+
+#[test]
+fn channel_and_fd() {
+    let (mut a, b) = UnixStream::pair().unwrap();
+    let (s, r) = unbounded();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            s.send(1).unwrap();
+            thread::sleep(ms(500));
+            a.write_all(b"x").unwrap();
+        });
+
+        select! {
+            recv(r) -> msg => assert_eq!(msg, Ok(1)),
+            recv_custom(Readable(b.as_raw_fd())) -> _ => panic!(),
+        }
+        select! {
+            recv(r) -> _ => panic!(),
+            recv_custom(Readable(b.as_raw_fd())) -> _ => {}
+        }
+    })
+    .unwrap();
+
+    // A writable socket is ready right away.
+    select! {
+        recv(r) -> _ => panic!(),
+        recv_custom(Writable(b.as_raw_fd())) -> _ => {}
+    }
+}
+
+#[test]
+fn ready() {
+    let pipe = Pipe::new();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            assert_eq!(pipe.write(b"x"), 1);
+        });
+
+        let mut sel = Select::new();
+        let index = sel.readable(&pipe.reader);
+        assert_eq!(sel.ready_timeout(ms(2000)), Ok(index));
+    })
+    .unwrap();
+}
+
+#[test]
+fn many_threads() {
+    const THREADS: usize = 8;
+    const COUNT: usize = 100;
+
+    let pairs = (0..THREADS)
+        .map(|_| UnixStream::pair().unwrap())
+        .collect::<Vec<_>>();
+
+    scope(|scope| {
+        for (_, b) in &pairs {
+            scope.spawn(move |_| {
+                let mut b = b;
+                for _ in 0..COUNT {
+                    let mut sel = Select::new();
+                    sel.readable(b);
+                    sel.select().readable(b);
+
+                    let mut buf = [0; 1];
+                    b.read_exact(&mut buf).unwrap();
+                }
+            });
+        }
+
+        for _ in 0..COUNT {
+            for (a, _) in &pairs {
+                let mut a = a;
+                a.write_all(b"x").unwrap();
+            }
+            thread::sleep(ms(1));
+        }
+    })
+    .unwrap();
+}
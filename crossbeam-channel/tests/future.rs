@@ -0,0 +1,375 @@
+//! Tests for the asynchronous send and receive operations.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{after, bounded, broadcast, merge, never, tick, unbounded};
+use crossbeam_channel::{RecvError, SendError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Returns a waker that unparks the current thread.
+fn thread_waker() -> Waker {
+    unsafe fn clone(data: *const ()) -> RawWaker {
+        let thread = Arc::from_raw(data as *const Thread);
+        let cloned = thread.clone();
+        std::mem::forget(thread);
+        RawWaker::new(Arc::into_raw(cloned) as *const (), &VTABLE)
+    }
+    unsafe fn wake(data: *const ()) {
+        Arc::from_raw(data as *const Thread).unpark();
+    }
+    unsafe fn wake_by_ref(data: *const ()) {
+        (*(data as *const Thread)).unpark();
+    }
+    unsafe fn drop(data: *const ()) {
+        Arc::from_raw(data as *const Thread);
+    }
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+
+    let thread = Arc::new(thread::current());
+    unsafe { Waker::from_raw(RawWaker::new(Arc::into_raw(thread) as *const (), &VTABLE)) }
+}
+
+/// Polls the future on the current thread until it completes.
+fn block_on<F: Future + Unpin>(mut fut: F) -> F::Output {
+    let waker = thread_waker();
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(res) = Pin::new(&mut fut).poll(&mut cx) {
+            return res;
+        }
+        thread::park();
+    }
+}
+
+/// Polls the future exactly once.
+fn poll_once<F: Future + Unpin>(fut: &mut F) -> Poll<F::Output> {
+    let waker = thread_waker();
+    let mut cx = Context::from_waker(&waker);
+    Pin::new(fut).poll(&mut cx)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = unbounded();
+    block_on(s.send_async(7)).unwrap();
+    assert_eq!(block_on(r.recv_async()), Ok(7));
+
+    let (s, r) = bounded(1);
+    block_on(s.send_async(8)).unwrap();
+    assert_eq!(block_on(r.recv_async()), Ok(8));
+}
+
+#[test]
+fn disconnected() {
+    for cap in 0..3 {
+        let (s, r) = bounded::<i32>(cap);
+        drop(r);
+        assert_eq!(block_on(s.send_async(1)), Err(SendError(1)));
+
+        let (s, r) = bounded::<i32>(cap);
+        drop(s);
+        assert_eq!(block_on(r.recv_async()), Err(RecvError));
+    }
+}
+
+#[test]
+fn pending_until_ready() {
+    let (s, r) = bounded(1);
+
+    let mut recv = r.recv_async();
+    assert!(poll_once(&mut recv).is_pending());
+    s.send(1).unwrap();
+    assert_eq!(poll_once(&mut recv), Poll::Ready(Ok(1)));
+
+    s.send(2).unwrap();
+    let mut send = s.send_async(3);
+    assert!(poll_once(&mut send).is_pending());
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(poll_once(&mut send), Poll::Ready(Ok(())));
+    assert_eq!(r.recv(), Ok(3));
+}
+
+#[test]
+fn wakes_on_disconnect() {
+    for cap in 0..3 {
+        let (s, r) = bounded::<i32>(cap);
+
+        scope(|scope| {
+            scope.spawn(|_| {
+                thread::sleep(ms(500));
+                drop(s);
+            });
+            assert_eq!(block_on(r.recv_async()), Err(RecvError));
+        })
+        .unwrap();
+    }
+}
+
+#[test]
+fn threads_and_tasks() {
+    const COUNT: usize = 10_000;
+
+    for cap in 0..3 {
+        let (s, r) = bounded(cap);
+
+        scope(|scope| {
+            scope.spawn(|_| {
+                for i in 0..COUNT {
+                    s.send(i).unwrap();
+                }
+            });
+            scope.spawn(|_| {
+                for i in 0..COUNT {
+                    block_on(s.send_async(i)).unwrap();
+                }
+            });
+
+            let mut sum = 0;
+            for i in 0..COUNT {
+                if i % 2 == 0 {
+                    sum += r.recv().unwrap();
+                } else {
+                    sum += block_on(r.recv_async()).unwrap();
+                }
+            }
+            for _ in 0..COUNT {
+                sum += block_on(r.recv_async()).unwrap();
+            }
+            assert_eq!(sum, COUNT * (COUNT - 1));
+        })
+        .unwrap();
+    }
+}
+
+#[test]
+fn mpmc_tasks() {
+    const COUNT: usize = 5000;
+    const THREADS: usize = 4;
+
+    for cap in 0..4 {
+        let (s, r) = bounded::<usize>(cap);
+        let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+        scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|_| {
+                    for _ in 0..COUNT {
+                        let n = block_on(r.recv_async()).unwrap();
+                        v[n].fetch_add(1, Ordering::SeqCst);
+                    }
+                });
+            }
+            for _ in 0..THREADS {
+                scope.spawn(|_| {
+                    for i in 0..COUNT {
+                        block_on(s.send_async(i)).unwrap();
+                    }
+                });
+            }
+        })
+        .unwrap();
+
+        for c in v {
+            assert_eq!(c.load(Ordering::SeqCst), THREADS);
+        }
+    }
+}
+
+#[test]
+fn zero_pairs_tasks() {
+    let (s, r) = bounded(0);
+
+    let mut recv = r.recv_async();
+    assert!(poll_once(&mut recv).is_pending());
+
+    let mut send = s.send_async(5);
+    assert_eq!(poll_once(&mut send), Poll::Ready(Ok(())));
+    assert_eq!(poll_once(&mut recv), Poll::Ready(Ok(5)));
+
+    let mut send = s.send_async(6);
+    assert!(poll_once(&mut send).is_pending());
+    assert_eq!(r.try_recv(), Ok(6));
+    assert_eq!(poll_once(&mut send), Poll::Ready(Ok(())));
+}
+
+#[test]
+fn cancel_passes_wakeup_on() {
+    for cap in 1..3 {
+        let (s, r) = bounded(cap);
+
+        let mut first = r.recv_async();
+        assert!(poll_once(&mut first).is_pending());
+
+        scope(|scope| {
+            // A receiver that blocks after the future has been registered.
+            let blocked = scope.spawn(|_| r.recv_timeout(ms(2000)));
+            thread::sleep(ms(100));
+
+            // The message wakes up the future, which is then dropped without receiving, so the
+            // blocked receiver must get the notification instead.
+            let start = Instant::now();
+            s.send(1).unwrap();
+            drop(first);
+            assert_eq!(blocked.join().unwrap(), Ok(1));
+            assert!(Instant::now() - start < ms(1000));
+        })
+        .unwrap();
+    }
+}
+
+#[test]
+fn cancel_keeps_message() {
+    let (s, r) = bounded(0);
+
+    let mut send = s.send_async(1);
+    assert!(poll_once(&mut send).is_pending());
+    drop(send);
+    assert!(r.try_recv().is_err());
+
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+
+    let mut send = s.send_async(2);
+    assert!(poll_once(&mut send).is_pending());
+    drop(send);
+    assert_eq!(r.try_recv(), Ok(1));
+    assert!(r.try_recv().is_err());
+}
+
+#[test]
+fn broadcast_wakes_all_tasks() {
+    let (s, r1) = broadcast(1);
+    let r2 = r1.clone();
+
+    let mut recv1 = r1.recv_async();
+    let mut recv2 = r2.recv_async();
+    assert!(poll_once(&mut recv1).is_pending());
+    assert!(poll_once(&mut recv2).is_pending());
+
+    block_on(s.send_async(1)).unwrap();
+    assert_eq!(poll_once(&mut recv1), Poll::Ready(Ok(1)));
+    assert_eq!(poll_once(&mut recv2), Poll::Ready(Ok(1)));
+
+    drop(s);
+    assert_eq!(block_on(r1.recv_async()), Err(RecvError));
+}
+
+#[test]
+fn never_is_pending() {
+    let r = never::<i32>();
+    let mut recv = r.recv_async();
+    assert!(poll_once(&mut recv).is_pending());
+}
+
+#[test]
+fn timers() {
+    let start = Instant::now();
+    assert!(block_on(after(ms(200)).recv_async()).is_ok());
+    assert!(Instant::now() - start >= ms(200));
+
+    let r = tick(ms(100));
+    for _ in 0..3 {
+        assert!(block_on(r.recv_async()).is_ok());
+    }
+    assert!(Instant::now() - start >= ms(500));
+
+    // Timers wrapped in other receivers wake tasks up too.
+    let (_s, r1) = unbounded();
+    let r = merge(vec![r1, after(ms(100))]).map(move |t| t - start);
+    assert!(block_on(r.recv_async()).unwrap() >= ms(600));
+}
+
+#[test]
+fn dropped_timer_releases_waker() {
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe fn clone(_: *const ()) -> RawWaker {
+        LIVE.fetch_add(1, Ordering::SeqCst);
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    unsafe fn wake(data: *const ()) {
+        drop(data);
+    }
+    unsafe fn wake_by_ref(_: *const ()) {}
+    unsafe fn drop(_: *const ()) {
+        LIVE.fetch_sub(1, Ordering::SeqCst);
+    }
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+
+    let waker = unsafe { Waker::from_raw(clone(std::ptr::null())) };
+    let mut cx = Context::from_waker(&waker);
+
+    let r = after(Duration::from_secs(3600));
+    for _ in 0..10 {
+        let mut recv = r.recv_async();
+        for _ in 0..3 {
+            assert!(Pin::new(&mut recv).poll(&mut cx).is_pending());
+        }
+        assert!(LIVE.load(Ordering::SeqCst) > 1);
+
+        // The background thread lets go of the waker once the future is dropped.
+        std::mem::drop(recv);
+        assert_eq!(LIVE.load(Ordering::SeqCst), 1);
+    }
+}
+
+#[test]
+fn merge_and_tee() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = bounded(0);
+    let r = merge(vec![r1, r2]);
+
+    let mut recv = r.recv_async();
+    assert!(poll_once(&mut recv).is_pending());
+    scope(|scope| {
+        scope.spawn(|_| s2.send(1).unwrap());
+        assert_eq!(block_on(&mut recv), Ok(1));
+    })
+    .unwrap();
+    drop(recv);
+
+    let rs = r.tee(2);
+    let mut recv1 = rs[0].recv_async();
+    let mut recv2 = rs[1].recv_async();
+    assert!(poll_once(&mut recv1).is_pending());
+    assert!(poll_once(&mut recv2).is_pending());
+
+    s1.send(2).unwrap();
+    assert_eq!(block_on(recv1), Ok(2));
+    assert_eq!(block_on(recv2), Ok(2));
+
+    drop(s1);
+    drop(s2);
+    assert_eq!(block_on(rs[0].recv_async()), Err(RecvError));
+    assert_eq!(block_on(rs[1].recv_async()), Err(RecvError));
+}
+
+#[test]
+fn map_and_filter() {
+    let (s, r) = bounded(0);
+    let r = r.filter(|x| x % 2 == 0).map(|x| x * 10);
+
+    let mut recv = r.recv_async();
+    assert!(poll_once(&mut recv).is_pending());
+    scope(|scope| {
+        scope.spawn(|_| {
+            s.send(1).unwrap();
+            s.send(2).unwrap();
+        });
+        assert_eq!(block_on(&mut recv), Ok(20));
+    })
+    .unwrap();
+
+    drop(s);
+    assert_eq!(block_on(r.recv_async()), Err(RecvError));
+}
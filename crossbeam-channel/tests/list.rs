@@ -1,6 +1,7 @@
 //! Tests for the list channel flavor.
 
 use std::any::Any;
+use std::collections::VecDeque;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::thread;
@@ -200,6 +201,180 @@ fn recv_after_disconnect() {
     assert_eq!(r.recv(), Err(RecvError));
 }
 
+#[test]
+fn recv_many() {
+    let (s, r) = unbounded::<usize>();
+    let mut buf = Vec::new();
+
+    assert_eq!(r.try_recv_many(&mut buf, 10), Err(TryRecvError::Empty));
+    assert_eq!(r.try_recv_many(&mut buf, 0), Ok(0));
+
+    // Go through many blocks so that claimed runs end at block boundaries.
+    let mut next = 0;
+    for i in 0..100 {
+        let n = i % 3 + 1;
+        for _ in 0..n {
+            s.send(next).unwrap();
+            next += 1;
+        }
+        assert_eq!(r.try_recv_many(&mut buf, 2), Ok(n.min(2)));
+        if n == 3 {
+            assert_eq!(r.recv_many(&mut buf, 2), Ok(1));
+        }
+    }
+    assert_eq!(buf, (0..next).collect::<Vec<_>>());
+
+    s.send(next).unwrap();
+    drop(s);
+    assert_eq!(r.recv_many(&mut buf, 10), Ok(1));
+    assert_eq!(r.recv_many(&mut buf, 10), Err(RecvError));
+    assert_eq!(
+        r.try_recv_many(&mut buf, 10),
+        Err(TryRecvError::Disconnected)
+    );
+}
+
+#[test]
+fn recv_many_blocks() {
+    let (s, r) = unbounded();
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            let mut buf = Vec::new();
+            assert_eq!(r.recv_many(&mut buf, 10), Ok(1));
+            assert_eq!(buf, [7]);
+            assert_eq!(r.recv_many(&mut buf, 10), Err(RecvError));
+        });
+        scope.spawn(move |_| {
+            thread::sleep(ms(1000));
+            s.send(7).unwrap();
+            thread::sleep(ms(1000));
+            drop(s);
+        });
+    })
+    .unwrap();
+}
+
+#[test]
+fn mpmc_recv_many() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = unbounded::<usize>();
+    let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+    let received = AtomicUsize::new(0);
+
+    scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                let mut buf = Vec::new();
+                while let Ok(n) = r.recv_many(&mut buf, 5) {
+                    for i in buf.drain(..) {
+                        v[i].fetch_add(1, Ordering::SeqCst);
+                    }
+                    // The last receiver to finish wakes up the others.
+                    if received.fetch_add(n, Ordering::SeqCst) + n == COUNT * THREADS {
+                        r.close();
+                    }
+                }
+            });
+        }
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                for i in 0..COUNT {
+                    s.send(i).unwrap();
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    for c in v {
+        assert_eq!(c.load(Ordering::SeqCst), THREADS);
+    }
+}
+
+#[test]
+fn send_many() {
+    let (s, r) = unbounded::<usize>();
+    let mut msgs = VecDeque::new();
+    assert_eq!(s.try_send_many(&mut msgs), Ok(0));
+
+    // Send batches of various sizes so that reserved runs span multiple blocks.
+    let mut next = 0;
+    for i in 0..50 {
+        let n = i * 7 % 100 + 1;
+        msgs.extend(next..next + n);
+        next += n;
+        assert_eq!(s.try_send_many(&mut msgs), Ok(n));
+        assert!(msgs.is_empty());
+        assert_eq!(r.len(), n);
+        for j in next - n..next {
+            assert_eq!(r.try_recv(), Ok(j));
+        }
+    }
+
+    drop(r);
+    msgs.extend(0..3);
+    assert_eq!(
+        s.try_send_many(&mut msgs),
+        Err(TrySendError::Disconnected(()))
+    );
+    assert_eq!(msgs, [0, 1, 2]);
+    assert_eq!(s.send_iter(msgs), Err(SendError(0)));
+}
+
+#[test]
+fn send_iter_wakes_receiver() {
+    let (s, r) = unbounded();
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            assert_eq!(r.recv(), Ok(0));
+            assert_eq!(r.iter().collect::<Vec<_>>(), (1..100).collect::<Vec<_>>());
+        });
+        scope.spawn(move |_| {
+            thread::sleep(ms(1000));
+            assert_eq!(s.send_iter(0..100), Ok(()));
+        });
+    })
+    .unwrap();
+}
+
+#[test]
+fn mpmc_send_many() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = unbounded::<usize>();
+    let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+    scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                for _ in 0..COUNT {
+                    let n = r.recv().unwrap();
+                    v[n].fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                let mut msgs = VecDeque::new();
+                for i in (0..COUNT).step_by(10) {
+                    msgs.extend(i..i + 10);
+                    assert_eq!(s.try_send_many(&mut msgs), Ok(10));
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    for c in v {
+        assert_eq!(c.load(Ordering::SeqCst), THREADS);
+    }
+}
+
 #[test]
 fn len() {
     let (s, r) = unbounded();
@@ -0,0 +1,323 @@
+//! Tests for mapped and filtered receivers.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, merge, select, tick, unbounded, MappedReceiver, Select};
+use crossbeam_channel::{RecvError, RecvTimeoutError, TryRecvError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = unbounded();
+    let r: MappedReceiver<String> = r.map(|x: i32| x.to_string());
+
+    s.send(1).unwrap();
+    assert_eq!(r.try_recv(), Ok("1".to_string()));
+    s.send(2).unwrap();
+    assert_eq!(r.recv(), Ok("2".to_string()));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(r.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+
+    drop(s);
+    assert!(r.is_closed());
+    assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn filter() {
+    let (s, r) = bounded(10);
+    let r = r.filter(|x| x % 3 == 0);
+
+    for i in 0..10 {
+        s.send(i).unwrap();
+    }
+    // Messages that don't match are still in the channel until they're reached.
+    assert_eq!(r.len(), 10);
+    assert_eq!(r.try_recv(), Ok(0));
+    assert_eq!(r.recv(), Ok(3));
+    assert_eq!(r.recv_timeout(ms(100)), Ok(6));
+    assert_eq!(r.len(), 3);
+    assert_eq!(r.try_iter().collect::<Vec<_>>(), [9]);
+    assert!(r.is_empty());
+
+    s.send(10).unwrap();
+    drop(s);
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn recv_wakes_up() {
+    let (s, r) = bounded(0);
+    let r = r.filter(|x| x % 2 == 0).map(|x| x * 10);
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            s.send(1).unwrap();
+            s.send(2).unwrap();
+            thread::sleep(ms(500));
+            s.send(4).unwrap();
+        });
+
+        assert_eq!(r.recv(), Ok(20));
+        assert_eq!(r.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+        assert_eq!(r.recv_timeout(ms(1000)), Ok(40));
+    })
+    .unwrap();
+}
+
+#[test]
+fn select() {
+    let (s1, r1) = bounded(0);
+    let (s2, r2) = unbounded();
+    let r1 = r1.map(|x: i32| x + 100);
+    let r2 = r2.filter(|x| *x > 0);
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            s1.send(1).unwrap();
+            thread::sleep(ms(500));
+            s2.send(0).unwrap();
+            s2.send(2).unwrap();
+        });
+
+        select! {
+            recv(r1) -> msg => assert_eq!(msg, Ok(101)),
+            recv(r2) -> _ => panic!(),
+        }
+        select! {
+            recv(r1) -> _ => panic!(),
+            recv(r2) -> msg => assert_eq!(msg, Ok(2)),
+        }
+    })
+    .unwrap();
+
+    s2.send(-1).unwrap();
+    let mut sel = Select::new();
+    let oper1 = sel.recv(&r1);
+    let oper2 = sel.recv(&r2);
+    assert!(sel.try_select().is_err());
+
+    s2.send(3).unwrap();
+    let oper = sel.select_timeout(ms(1000)).unwrap();
+    assert_eq!(oper.index(), oper2);
+    assert_eq!(oper.recv(&r2), Ok(3));
+
+    drop(s1);
+    let oper = sel.select_timeout(ms(1000)).unwrap();
+    assert_eq!(oper.index(), oper1);
+    assert_eq!(oper.recv(&r1), Err(RecvError));
+}
+
+#[test]
+fn transform_runs_once() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let (s, r) = unbounded();
+    let r = {
+        let calls = calls.clone();
+        r.map(move |x: i32| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            x
+        })
+    };
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    let mut sel = Select::new();
+    sel.recv(&r);
+    let oper = sel.select();
+    assert_eq!(oper.recv(&r), Ok(1));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    assert_eq!(r.try_peek_with(|x| *x), Ok(2));
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn peek() {
+    let (s, r) = unbounded();
+    let r = r.filter(|x| x % 2 == 0);
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(r.try_peek_with(|x| *x), Ok(2));
+    assert_eq!(r.try_recv_if(|x| *x == 3), Ok(None));
+    assert_eq!(r.try_recv_if(|x| *x == 2), Ok(Some(2)));
+    assert_eq!(r.try_recv_if(|_| true), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn peek_mapped() {
+    let transforms = Arc::new(AtomicUsize::new(0));
+    let (s, r) = unbounded();
+    let r = {
+        let transforms = transforms.clone();
+        r.map(move |x| {
+            transforms.fetch_add(1, Ordering::SeqCst);
+            x + 1
+        })
+    };
+
+    s.send(4).unwrap();
+    s.send(6).unwrap();
+    assert_eq!(r.len(), 2);
+    assert_eq!(r.try_peek_with(|x| *x), Ok(5));
+    assert_eq!(r.try_recv_if(|x| *x == 7), Ok(None));
+
+    // The inspected message is transformed only once and stays in front of the source.
+    assert_eq!(transforms.load(Ordering::SeqCst), 1);
+    assert_eq!(r.len(), 2);
+    assert_eq!(r.try_recv_if(|x| *x == 5), Ok(Some(5)));
+
+    drop(s);
+    assert_eq!(r.try_peek_with(|x| *x), Ok(7));
+    assert_eq!(r.recv(), Ok(7));
+    assert_eq!(transforms.load(Ordering::SeqCst), 2);
+    assert_eq!(r.try_recv_if(|_| true), Err(TryRecvError::Disconnected));
+    assert_eq!(r.try_peek_with(|x| *x), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn select_peeked() {
+    let (s, r) = unbounded();
+    let r = r.map(|x: i32| x * 10);
+    s.send(1).unwrap();
+    assert_eq!(r.try_peek_with(|x| *x), Ok(10));
+
+    // Selection sees the inspected message even though the source is empty.
+    assert!(s.is_empty());
+    select! {
+        recv(r) -> msg => assert_eq!(msg, Ok(10)),
+        default(ms(1000)) => panic!(),
+    }
+
+    // A blocked receiver wakes up when a rejected message is put back.
+    scope(|scope| {
+        scope.spawn(|_| assert_eq!(r.recv(), Ok(20)));
+        thread::sleep(ms(100));
+        let r2 = r.clone();
+        s.send(2).unwrap();
+        let _ = r2.try_recv_if(|_| false);
+    })
+    .unwrap();
+}
+
+#[test]
+fn counts() {
+    let (s, r) = bounded::<i32>(3);
+    let r = r.map(|x| x as i64);
+
+    assert_eq!(r.capacity(), Some(3));
+    assert_eq!(r.sender_count(), 1);
+    assert_eq!(r.receiver_count(), 1);
+
+    let r2 = r.clone();
+    assert_eq!(r.receiver_count(), 2);
+    assert!(r.same_channel(&r2));
+
+    s.send(1).unwrap();
+    assert_eq!(r.len(), 1);
+    assert_eq!(r2.recv(), Ok(1));
+    assert!(r.is_empty());
+}
+
+#[test]
+fn close_and_weak() {
+    let (s, r) = unbounded::<i32>();
+    let r = r.map(|x| x * 2).filter(|x| *x > 2);
+    let w = r.downgrade();
+    assert!(w.upgrade().is_some());
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert!(r.close());
+    assert!(s.send(3).is_err());
+    assert_eq!(r.recv(), Ok(4));
+    assert_eq!(r.recv(), Err(RecvError));
+    assert!(w.upgrade().is_none());
+
+    // Dropping the last mapped receiver drops the source.
+    let (s, r) = unbounded::<i32>();
+    let w = r.map(|x| x).downgrade();
+    assert!(w.upgrade().is_none());
+    assert!(s.send(1).is_err());
+}
+
+#[test]
+fn other_flavors() {
+    let r = tick(ms(50)).map(|_| ());
+    assert_eq!(r.recv(), Ok(()));
+
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+    let r = merge(vec![r1.map(|x: i32| x * 10), r2]).filter(|x| *x != 2);
+    s1.send(1).unwrap();
+    s2.send(2).unwrap();
+    s2.send(3).unwrap();
+    let mut msgs = r.try_iter().collect::<Vec<_>>();
+    msgs.sort();
+    assert_eq!(msgs, [3, 10]);
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    for &cap in &[None, Some(0), Some(3)] {
+        let (s, r) = match cap {
+            None => unbounded(),
+            Some(cap) => bounded(cap),
+        };
+        let r = r.filter(|x: &usize| x % 2 == 0).map(|x| x / 2);
+        let received = (0..COUNT / 2)
+            .map(|_| AtomicUsize::new(0))
+            .collect::<Vec<_>>();
+
+        scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|_| {
+                    for _ in 0..COUNT / THREADS {
+                        select! {
+                            recv(r) -> msg => {
+                                if let Ok(n) = msg {
+                                    received[n].fetch_add(1, Ordering::SeqCst);
+                                }
+                            }
+                            default(ms(10)) => {}
+                        }
+                    }
+                    for n in r.iter() {
+                        received[n].fetch_add(1, Ordering::SeqCst);
+                    }
+                });
+            }
+            for _ in 0..THREADS {
+                let s = s.clone();
+                scope.spawn(move |_| {
+                    for i in 0..COUNT {
+                        s.send(i).unwrap();
+                    }
+                });
+            }
+            drop(s);
+        })
+        .unwrap();
+
+        for c in &received {
+            assert_eq!(c.load(Ordering::SeqCst), THREADS);
+        }
+    }
+}
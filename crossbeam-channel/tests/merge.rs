@@ -0,0 +1,281 @@
+//! Tests for merged receivers.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{after, bounded, merge, never, select, tick, unbounded, Receiver};
+use crossbeam_channel::{RecvError, RecvTimeoutError, TryRecvError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = bounded(1);
+    let r = merge(vec![r1, r2]);
+
+    s1.send(1).unwrap();
+    assert_eq!(r.try_recv(), Ok(1));
+    s2.send(2).unwrap();
+    assert_eq!(r.recv(), Ok(2));
+
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(r.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+}
+
+#[test]
+fn disconnect_when_all_inputs_disconnect() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = bounded(1);
+    let r = merge(vec![r1, r2]);
+
+    s1.send(1).unwrap();
+    drop(s1);
+    assert!(!r.is_closed());
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(r.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+
+    s2.send(2).unwrap();
+    drop(s2);
+    assert!(r.is_closed());
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(r.recv(), Err(RecvError));
+
+    let r = merge::<i32>(Vec::new());
+    assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn recv_wakes_up() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = bounded(0);
+    let r = merge(vec![r1, r2]);
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            s2.send(1).unwrap();
+            thread::sleep(ms(500));
+            s1.send(2).unwrap();
+            thread::sleep(ms(500));
+            drop(s1);
+            drop(s2);
+        });
+
+        assert_eq!(r.recv(), Ok(1));
+        assert_eq!(r.recv(), Ok(2));
+        assert_eq!(r.recv(), Err(RecvError));
+    })
+    .unwrap();
+}
+
+#[test]
+fn fairness() {
+    const COUNT: usize = 1000;
+
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+    let r = merge(vec![r1, r2]);
+
+    for _ in 0..COUNT {
+        s1.send(1).unwrap();
+        s2.send(2).unwrap();
+    }
+
+    let mut hits = [0usize; 2];
+    for _ in 0..COUNT {
+        hits[r.recv().unwrap() - 1] += 1;
+    }
+    assert!(hits.iter().all(|x| *x >= COUNT / 4));
+}
+
+#[test]
+fn counts() {
+    let (s1, r1) = bounded::<i32>(2);
+    let (s2, r2) = bounded(3);
+    let _s3 = s2.clone();
+    let r = merge(vec![r1, r2]);
+
+    assert_eq!(r.capacity(), Some(5));
+    assert_eq!(r.sender_count(), 3);
+    assert_eq!(r.receiver_count(), 1);
+    assert!(r.is_empty());
+
+    s1.send(1).unwrap();
+    s1.send(2).unwrap();
+    s2.send(3).unwrap();
+    assert_eq!(r.len(), 3);
+    assert!(!r.is_full());
+
+    let r2 = r.clone();
+    assert_eq!(r.receiver_count(), 2);
+    assert!(r.same_channel(&r2));
+
+    let (_s, r3) = unbounded();
+    assert_eq!(merge(vec![r2, r3]).capacity(), None);
+}
+
+#[test]
+fn close() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = bounded(0);
+    let r = merge(vec![r1, r2]);
+
+    s1.send(1).unwrap();
+    assert!(r.close());
+    assert!(!r.close());
+    assert!(s1.send(2).is_err());
+    assert!(s2.send(3).is_err());
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn drop_inputs() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded::<i32>();
+    let r = merge(vec![r1, r2]);
+    let w = r.downgrade();
+
+    assert!(w.upgrade().is_some());
+    drop(r);
+    assert!(w.upgrade().is_none());
+    assert!(s1.send(1).is_err());
+    assert!(s2.send(2).is_err());
+}
+
+#[test]
+fn peek() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+    let r = merge(vec![r1, merge(vec![r2])]);
+
+    s1.send(1).unwrap();
+    s2.send(2).unwrap();
+    let mut msgs = Vec::new();
+    while let Ok(Some(msg)) = r.try_recv_if(|_| true) {
+        msgs.push(msg);
+    }
+    msgs.sort();
+    assert_eq!(msgs, [1, 2]);
+
+    s2.send(3).unwrap();
+    assert_eq!(r.try_recv_if(|x| *x == 4), Ok(None));
+    assert_eq!(r.try_peek_with(|x| *x), Ok(3));
+    assert_eq!(r.try_recv(), Ok(3));
+}
+
+#[test]
+fn select() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = bounded(0);
+    let (s3, r3) = unbounded();
+    let r = merge(vec![r1, r2]);
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            s2.send(1).unwrap();
+            thread::sleep(ms(500));
+            s3.send(2).unwrap();
+        });
+
+        select! {
+            recv(r) -> msg => assert_eq!(msg, Ok(1)),
+            recv(r3) -> _ => panic!(),
+        }
+        select! {
+            recv(r) -> _ => panic!(),
+            recv(r3) -> msg => assert_eq!(msg, Ok(2)),
+        }
+    })
+    .unwrap();
+
+    drop(s1);
+    drop(s2);
+    select! {
+        recv(r) -> msg => assert_eq!(msg, Err(RecvError)),
+        default(ms(1000)) => panic!(),
+    }
+}
+
+#[test]
+fn timers() {
+    let start = Instant::now();
+    let r = merge(vec![never(), after(ms(300)), tick(ms(200))]);
+
+    // The tick fires first and the merged receiver never gets disconnected.
+    assert!(r.recv().is_ok());
+    assert!(Instant::now() - start >= ms(200));
+    assert!(r.recv().is_ok());
+    assert!(r.recv().is_ok());
+    assert!(Instant::now() - start >= ms(400));
+    assert!(!r.is_closed());
+}
+
+#[test]
+fn nested() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = unbounded();
+    let (s3, r3) = bounded(0);
+    let r = merge(vec![merge(vec![r1, r2]), r3]);
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(200));
+            s2.send(1).unwrap();
+            s3.send(2).unwrap();
+        });
+
+        let mut msgs = vec![r.recv().unwrap(), r.recv().unwrap()];
+        msgs.sort();
+        assert_eq!(msgs, [1, 2]);
+    })
+    .unwrap();
+
+    drop(s1);
+    drop(s2);
+    drop(s3);
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (senders, receivers): (Vec<_>, Vec<Receiver<usize>>) = (0..THREADS)
+        .map(|i| if i % 2 == 0 { unbounded() } else { bounded(i) })
+        .unzip();
+    let r = merge(receivers);
+    let received = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+    scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                for n in r.iter() {
+                    received[n].fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+        for s in senders {
+            scope.spawn(move |_| {
+                for i in 0..COUNT {
+                    s.send(i).unwrap();
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    for c in &received {
+        assert_eq!(c.load(Ordering::SeqCst), THREADS);
+    }
+}
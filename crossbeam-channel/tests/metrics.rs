@@ -0,0 +1,277 @@
+//! Tests for channel metrics.
+
+#![cfg(feature = "metrics")]
+
+use std::collections::VecDeque;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{after, bounded, bounded_with_policy, never, select, tick, unbounded};
+use crossbeam_channel::{broadcast, priority_unbounded, ChannelStats, Overflow};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = bounded(2);
+    let stats = s.stats();
+    assert_eq!(stats.sent(), 0);
+    assert_eq!(stats.peak_len(), 0);
+    assert_eq!(stats.senders(), 1);
+    assert_eq!(stats.receivers(), 1);
+
+    s.send(1).unwrap();
+    s.try_send(2).unwrap();
+    assert!(s.try_send(3).is_err());
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.try_recv(), Ok(2));
+    assert!(r.try_recv().is_err());
+
+    let stats = s.stats();
+    assert_eq!(stats, r.stats());
+    assert_eq!(stats.sent(), 2);
+    assert_eq!(stats.received(), 2);
+    assert_eq!(stats.try_send_failures(), 1);
+    assert_eq!(stats.try_recv_failures(), 1);
+    assert_eq!(stats.send_waits(), 0);
+    assert_eq!(stats.recv_waits(), 0);
+    assert_eq!(stats.send_wait_time(), Duration::from_secs(0));
+    assert_eq!(stats.peak_len(), 2);
+}
+
+#[test]
+fn handles() {
+    let (s1, r1) = unbounded::<i32>();
+    let s2 = s1.clone();
+    let r2 = r1.clone();
+    let r3 = r2.clone();
+    assert_eq!(s1.stats().senders(), 2);
+    assert_eq!(s1.stats().receivers(), 3);
+
+    drop(s2);
+    drop(r1);
+    drop(r3);
+    assert_eq!(r2.stats().senders(), 1);
+    assert_eq!(r2.stats().receivers(), 1);
+
+    drop(s1);
+    assert_eq!(r2.stats().senders(), 0);
+}
+
+#[test]
+fn send_waits() {
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            assert_eq!(r.recv(), Ok(1));
+        });
+
+        s.send(2).unwrap();
+    })
+    .unwrap();
+
+    assert!(s.send_timeout(3, ms(100)).is_err());
+
+    let stats = s.stats();
+    assert_eq!(stats.sent(), 2);
+    assert_eq!(stats.send_waits(), 2);
+    assert!(stats.send_wait_time() >= ms(500));
+    assert_eq!(stats.recv_waits(), 0);
+    assert_eq!(stats.try_send_failures(), 0);
+}
+
+#[test]
+fn recv_waits() {
+    let (s, r) = unbounded();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            s.send(1).unwrap();
+        });
+
+        assert_eq!(r.recv(), Ok(1));
+    })
+    .unwrap();
+
+    assert!(r.recv_timeout(ms(100)).is_err());
+
+    // Receiving a ready message doesn't count as a wait.
+    s.send(2).unwrap();
+    assert_eq!(r.recv(), Ok(2));
+
+    let stats = r.stats();
+    assert_eq!(stats.received(), 2);
+    assert_eq!(stats.recv_waits(), 2);
+    assert!(stats.recv_wait_time() >= ms(500));
+    assert_eq!(stats.send_waits(), 0);
+    assert_eq!(stats.try_recv_failures(), 0);
+}
+
+#[test]
+fn batches() {
+    let (s, r) = bounded(4);
+
+    let mut msgs = (0..6).collect::<VecDeque<_>>();
+    assert_eq!(s.try_send_many(&mut msgs), Ok(4));
+    assert!(s.try_send_many(&mut msgs).is_err());
+    assert_eq!(s.stats().sent(), 4);
+    assert_eq!(s.stats().try_send_failures(), 1);
+
+    let mut buf = Vec::new();
+    assert_eq!(r.try_recv_many(&mut buf, 3), Ok(3));
+    assert_eq!(r.recv_many(&mut buf, 3), Ok(1));
+    assert!(r.try_recv_many(&mut buf, 3).is_err());
+    assert_eq!(r.stats().received(), 4);
+    assert_eq!(r.stats().try_recv_failures(), 1);
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            for _ in 0..10 {
+                r.recv().unwrap();
+            }
+        });
+
+        s.send_iter(0..10).unwrap();
+    })
+    .unwrap();
+
+    let stats = s.stats();
+    assert_eq!(stats.sent(), 14);
+    assert_eq!(stats.received(), 14);
+    assert_eq!(stats.try_send_failures(), 1);
+    assert_eq!(stats.peak_len(), 4);
+}
+
+#[test]
+fn permits() {
+    let (s, r) = bounded(1);
+
+    let permit = s.reserve().unwrap();
+    assert!(s.try_reserve().is_err());
+    drop(permit);
+    assert_eq!(s.stats().sent(), 0);
+    assert_eq!(s.stats().try_send_failures(), 1);
+
+    s.reserve().unwrap().send(1).unwrap();
+    assert_eq!(s.stats().sent(), 1);
+    assert_eq!(r.try_recv_if(|_| false), Ok(None));
+    assert_eq!(r.try_recv_if(|_| true), Ok(Some(1)));
+    assert!(r.try_recv_if(|_| true).is_err());
+
+    let stats = r.stats();
+    assert_eq!(stats.received(), 1);
+    assert_eq!(stats.try_recv_failures(), 1);
+}
+
+#[test]
+fn overflow() {
+    let (s, r) = bounded_with_policy(2, Overflow::DropOldest);
+
+    for i in 0..5 {
+        s.send(i).unwrap();
+    }
+    assert_eq!(s.force_send(5), Ok(Some(3)));
+    assert_eq!(r.recv(), Ok(4));
+    assert_eq!(r.recv(), Ok(5));
+
+    let stats = r.stats();
+    assert_eq!(stats.sent(), 6);
+    assert_eq!(stats.received(), 2);
+    assert_eq!(stats.peak_len(), 2);
+}
+
+#[test]
+fn select() {
+    let (s1, r1) = unbounded();
+    let (s2, r2) = bounded(1);
+
+    select! {
+        send(s2, 1) -> res => res.unwrap(),
+    }
+    s1.send(2).unwrap();
+    select! {
+        recv(r1) -> msg => assert_eq!(msg, Ok(2)),
+    }
+
+    assert_eq!(s2.stats().sent(), 1);
+    assert_eq!(r1.stats().received(), 1);
+    assert_eq!(r2.recv(), Ok(1));
+}
+
+#[test]
+fn other_flavors() {
+    let (s, r) = bounded(0);
+    scope(|scope| {
+        scope.spawn(|_| s.send(1).unwrap());
+        assert_eq!(r.recv(), Ok(1));
+    })
+    .unwrap();
+    assert_eq!(r.stats().sent(), 1);
+    assert_eq!(r.stats().received(), 1);
+    assert_eq!(r.stats().peak_len(), 0);
+
+    let (s, r) = priority_unbounded();
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(r.stats().sent(), 2);
+    assert_eq!(r.stats().received(), 1);
+    assert_eq!(r.stats().peak_len(), 2);
+
+    let (s, r1) = broadcast(4);
+    let r2 = r1.clone();
+    s.send(1).unwrap();
+    assert_eq!(r1.recv(), Ok(1));
+    assert_eq!(r2.recv(), Ok(1));
+    assert_eq!(s.stats().sent(), 1);
+    assert_eq!(s.stats().received(), 2);
+    assert_eq!(s.stats().receivers(), 2);
+
+    let r = after(ms(50));
+    r.recv().unwrap();
+    assert_eq!(r.stats(), ChannelStats::default());
+    let r = tick(ms(50));
+    r.recv().unwrap();
+    assert_eq!(r.stats(), ChannelStats::default());
+    let r = never::<i32>();
+    assert!(r.try_recv().is_err());
+    assert_eq!(r.stats(), ChannelStats::default());
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    for (s, r) in &[bounded::<usize>(3), unbounded()] {
+        scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|_| {
+                    for _ in 0..COUNT {
+                        r.recv().unwrap();
+                    }
+                });
+            }
+            for _ in 0..THREADS {
+                scope.spawn(|_| {
+                    for i in 0..COUNT {
+                        s.send(i).unwrap();
+                    }
+                });
+            }
+        })
+        .unwrap();
+
+        let stats = s.stats();
+        assert_eq!(stats.sent(), COUNT * THREADS);
+        assert_eq!(stats.received(), COUNT * THREADS);
+        assert!(stats.peak_len() <= s.capacity().unwrap_or(COUNT * THREADS));
+    }
+}
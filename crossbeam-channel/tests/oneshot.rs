@@ -0,0 +1,193 @@
+//! Tests for oneshot channels.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{oneshot, select, unbounded, Select};
+use crossbeam_channel::{RecvError, RecvTimeoutError, SendError, TryRecvError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = oneshot();
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+
+    s.send(7).unwrap();
+    assert_eq!(r.try_recv(), Ok(7));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn sender_dropped() {
+    let (s, r) = oneshot::<i32>();
+    drop(s);
+
+    assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(r.recv(), Err(RecvError));
+    assert_eq!(r.recv_timeout(ms(100)), Err(RecvTimeoutError::Disconnected));
+}
+
+#[test]
+fn receiver_dropped() {
+    let (s, r) = oneshot();
+    drop(r);
+    assert_eq!(s.send(1), Err(SendError(1)));
+}
+
+#[test]
+fn recv() {
+    let (s, r) = oneshot();
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            thread::sleep(ms(500));
+            s.send(7).unwrap();
+        });
+
+        assert_eq!(r.recv(), Ok(7));
+    })
+    .unwrap();
+
+    let (s, r) = oneshot::<i32>();
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            thread::sleep(ms(500));
+            drop(s);
+        });
+
+        assert_eq!(r.recv(), Err(RecvError));
+    })
+    .unwrap();
+}
+
+#[test]
+fn recv_timeout() {
+    let (s, r) = oneshot();
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            thread::sleep(ms(1000));
+            s.send(7).unwrap();
+        });
+
+        let start = Instant::now();
+        assert_eq!(r.recv_timeout(ms(500)), Err(RecvTimeoutError::Timeout));
+        assert!(Instant::now() >= start + ms(500));
+        assert_eq!(r.recv_timeout(ms(1000)), Ok(7));
+        assert_eq!(
+            r.recv_timeout(ms(1000)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    })
+    .unwrap();
+}
+
+#[test]
+fn select() {
+    let (s1, r1) = oneshot::<i32>();
+    let (s2, r2) = oneshot();
+    let (_s, r) = unbounded::<i32>();
+
+    select! {
+        recv_custom(r1) -> _ => panic!(),
+        recv_custom(r2) -> _ => panic!(),
+        default => {}
+    }
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            thread::sleep(ms(500));
+            s2.send(2).unwrap();
+        });
+
+        select! {
+            recv(r) -> _ => panic!(),
+            recv_custom(r1) -> _ => panic!(),
+            recv_custom(r2) -> msg => assert_eq!(msg, Ok(2)),
+        }
+    })
+    .unwrap();
+
+    drop(s1);
+    let mut sel = Select::new();
+    sel.recv(&r);
+    let index = sel.recv_custom(&r1);
+    let oper = sel.select_timeout(ms(1000)).unwrap();
+    assert_eq!(oper.index(), index);
+    assert_eq!(oper.recv_custom(&r1), Err(RecvError));
+}
+
+#[test]
+fn drops() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct DropCounter;
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    // Received.
+    let (s, r) = oneshot();
+    s.send(DropCounter).unwrap();
+    drop(r.recv().unwrap());
+    drop(r);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+
+    // Never received.
+    let (s, r) = oneshot();
+    s.send(DropCounter).unwrap();
+    drop(r);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+
+    // Sent after the receiver was dropped.
+    let (s, r) = oneshot();
+    drop(r);
+    drop(s.send(DropCounter));
+    assert_eq!(DROPS.load(Ordering::SeqCst), 3);
+
+    // Never sent.
+    let (s, r) = oneshot::<DropCounter>();
+    drop(s);
+    drop(r);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn stress() {
+    const COUNT: usize = 10_000;
+
+    let (s, r) = unbounded();
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            for i in 0..COUNT {
+                let (reply_s, reply_r) = oneshot();
+                s.send((i, reply_s)).unwrap();
+                if i % 2 == 0 {
+                    assert_eq!(reply_r.recv(), Ok(i * 2));
+                } else {
+                    drop(reply_r);
+                }
+            }
+        });
+
+        for (i, reply) in r {
+            let res = reply.send(i * 2);
+            if res.is_err() {
+                assert_eq!(i % 2, 1);
+            }
+        }
+    })
+    .unwrap();
+}
@@ -0,0 +1,210 @@
+//! Tests for bounded channels with overflow policies.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, bounded_with_policy, select, Overflow};
+use crossbeam_channel::{RecvError, SendError, TryRecvError, TrySendError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+#[should_panic(expected = "capacity must be positive")]
+fn zero_capacity() {
+    let _ = bounded_with_policy::<()>(0, Overflow::DropOldest);
+}
+
+#[test]
+fn drop_oldest() {
+    let (s, r) = bounded_with_policy(3, Overflow::DropOldest);
+    assert_eq!(s.capacity(), Some(3));
+
+    for i in 0..3 {
+        assert_eq!(s.force_send(i), Ok(None));
+    }
+    assert!(s.is_full());
+
+    // Go around the buffer a few times.
+    for i in 3..10 {
+        assert_eq!(s.force_send(i), Ok(Some(i - 3)));
+        assert_eq!(s.len(), 3);
+    }
+
+    assert_eq!(s.send(10), Ok(()));
+    assert_eq!(s.try_send(11), Ok(()));
+    assert_eq!(s.send_timeout(12, ms(0)), Ok(()));
+    for i in 10..13 {
+        assert_eq!(r.recv(), Ok(i));
+    }
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn drop_newest() {
+    let (s, r) = bounded_with_policy(3, Overflow::DropNewest);
+
+    for i in 0..3 {
+        assert_eq!(s.force_send(i), Ok(None));
+    }
+    for i in 3..10 {
+        assert_eq!(s.force_send(i), Ok(Some(i)));
+    }
+    assert_eq!(s.send(10), Ok(()));
+    assert_eq!(s.try_send(11), Ok(()));
+
+    for i in 0..3 {
+        assert_eq!(r.recv(), Ok(i));
+    }
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn interleaved_recv() {
+    let (s, r) = bounded_with_policy(2, Overflow::DropOldest);
+    let mut expected = VecDeque::new();
+
+    for i in 0..100 {
+        s.send(i).unwrap();
+        expected.push_back(i);
+        if expected.len() > 2 {
+            expected.pop_front();
+        }
+
+        if i % 3 == 0 {
+            assert_eq!(r.recv().ok(), expected.pop_front());
+        }
+    }
+    assert_eq!(r.try_iter().collect::<VecDeque<_>>(), expected);
+}
+
+#[test]
+fn force_send_without_policy() {
+    let (s, r) = bounded(1);
+    assert_eq!(s.force_send(1), Ok(None));
+    assert_eq!(s.try_send(2), Err(TrySendError::Full(2)));
+
+    scope(|scope| {
+        scope.spawn(|_| assert_eq!(s.force_send(2), Ok(None)));
+        thread::sleep(ms(500));
+        assert_eq!(r.recv(), Ok(1));
+    })
+    .unwrap();
+    assert_eq!(r.recv(), Ok(2));
+}
+
+#[test]
+fn disconnected() {
+    for &overflow in &[Overflow::DropOldest, Overflow::DropNewest] {
+        let (s, r) = bounded_with_policy(1, overflow);
+        s.send(1).unwrap();
+        drop(r);
+        assert_eq!(s.force_send(2), Err(SendError(2)));
+        assert_eq!(s.try_send(3), Err(TrySendError::Disconnected(3)));
+
+        let (s, r) = bounded_with_policy(1, overflow);
+        s.send(1).unwrap();
+        s.send(2).unwrap();
+        drop(s);
+        assert!(r.recv().is_ok());
+        assert_eq!(r.recv(), Err(RecvError));
+    }
+}
+
+#[test]
+fn select_send_is_ready() {
+    let (s, r) = bounded_with_policy(1, Overflow::DropOldest);
+    s.send(1).unwrap();
+
+    select! {
+        send(s, 2) -> res => assert_eq!(res, Ok(())),
+        default => panic!(),
+    }
+    assert_eq!(r.recv(), Ok(2));
+
+    s.send(3).unwrap();
+    select! {
+        send(s, 4) -> res => assert_eq!(res, Ok(())),
+        default(ms(1000)) => panic!(),
+    }
+    assert_eq!(r.recv(), Ok(4));
+}
+
+#[test]
+fn drops() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct DropCounter;
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    for &overflow in &[Overflow::DropOldest, Overflow::DropNewest] {
+        DROPS.store(0, Ordering::SeqCst);
+        let (s, r) = bounded_with_policy(5, overflow);
+
+        for _ in 0..100 {
+            s.send(DropCounter).unwrap();
+        }
+        assert_eq!(DROPS.load(Ordering::SeqCst), 95);
+
+        drop(s);
+        drop(r);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 100);
+    }
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    for &overflow in &[Overflow::DropOldest, Overflow::DropNewest] {
+        let (s, r) = bounded_with_policy::<usize>(3, overflow);
+        let received = AtomicUsize::new(0);
+        let evicted = AtomicUsize::new(0);
+
+        scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|_| {
+                    // Messages from a single sender arrive in order.
+                    let mut last = [None; THREADS];
+                    for (sender, i) in r.iter().map(|n| (n / COUNT, n % COUNT)) {
+                        if let Some(l) = last[sender] {
+                            assert!(l < i);
+                        }
+                        last[sender] = Some(i);
+                        received.fetch_add(1, Ordering::SeqCst);
+                    }
+                });
+            }
+
+            for sender in 0..THREADS {
+                let s = s.clone();
+                let evicted = &evicted;
+                scope.spawn(move |_| {
+                    for i in 0..COUNT {
+                        if s.force_send(sender * COUNT + i).unwrap().is_some() {
+                            evicted.fetch_add(1, Ordering::SeqCst);
+                        }
+                    }
+                });
+            }
+            drop(s);
+        })
+        .unwrap();
+
+        assert_eq!(
+            received.load(Ordering::SeqCst) + evicted.load(Ordering::SeqCst),
+            COUNT * THREADS
+        );
+    }
+}
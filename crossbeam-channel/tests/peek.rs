@@ -0,0 +1,246 @@
+//! Tests for peeking at messages and conditional receiving.
+
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{after, bounded, broadcast, never, priority_unbounded, tick, unbounded};
+use crossbeam_channel::{bounded_with_policy, Overflow};
+use crossbeam_channel::{Receiver, TryRecvError, TrySendError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn smoke(s: crossbeam_channel::Sender<i32>, r: Receiver<i32>) {
+    assert_eq!(r.try_peek_with(|x| *x), Err(TryRecvError::Empty));
+    assert_eq!(r.try_recv_if(|_| true), Err(TryRecvError::Empty));
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(r.try_peek_with(|x| *x), Ok(1));
+    assert_eq!(r.try_peek_with(|x| *x), Ok(1));
+    assert_eq!(r.len(), 2);
+
+    assert_eq!(r.try_recv_if(|x| *x == 2), Ok(None));
+    assert_eq!(r.try_recv_if(|x| *x == 1), Ok(Some(1)));
+    assert_eq!(r.try_peek_with(|x| *x), Ok(2));
+    assert_eq!(r.recv(), Ok(2));
+
+    s.send(3).unwrap();
+    drop(s);
+    assert_eq!(r.try_peek_with(|x| *x), Ok(3));
+    assert_eq!(r.try_recv_if(|_| true), Ok(Some(3)));
+    assert_eq!(r.try_peek_with(|x| *x), Err(TryRecvError::Disconnected));
+    assert_eq!(r.try_recv_if(|_| true), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn smoke_array() {
+    let (s, r) = bounded(2);
+    smoke(s, r);
+}
+
+#[test]
+fn smoke_list() {
+    let (s, r) = unbounded();
+    smoke(s, r);
+}
+
+#[test]
+fn wrap_around() {
+    // Peek across several laps of a bounded channel and several blocks of an unbounded one.
+    for (s, r) in &[bounded(3), unbounded()] {
+        for i in 0..100 {
+            s.send(i).unwrap();
+            if i % 2 == 1 {
+                assert_eq!(r.try_peek_with(|x| *x), Ok(i - 1));
+                assert_eq!(r.try_recv_if(|x| x % 2 == 1), Ok(None));
+                assert_eq!(r.try_recv_if(|x| x % 2 == 0), Ok(Some(i - 1)));
+                assert_eq!(r.try_recv_if(|x| x % 2 == 1), Ok(Some(i)));
+            }
+        }
+        assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    }
+}
+
+#[test]
+fn permits() {
+    let (s, r) = bounded(3);
+
+    let permit = s.reserve().unwrap();
+    s.send(2).unwrap();
+    assert_eq!(r.try_peek_with(|x| *x), Err(TryRecvError::Empty));
+    permit.send(1).unwrap();
+    assert_eq!(r.try_peek_with(|x| *x), Ok(1));
+    assert_eq!(r.try_recv(), Ok(1));
+
+    // Slots of dropped permits are skipped.
+    let permit = s.reserve().unwrap();
+    s.send(3).unwrap();
+    drop(permit);
+    assert_eq!(r.try_recv_if(|x| *x == 2), Ok(Some(2)));
+    assert_eq!(r.try_peek_with(|x| *x), Ok(3));
+}
+
+#[test]
+fn other_flavors() {
+    let (s, r) = bounded(0);
+    scope(|scope| {
+        scope.spawn(|_| s.send(1).unwrap());
+        thread::sleep(ms(200));
+        // A zero-capacity channel has no message to inspect.
+        assert_eq!(r.try_peek_with(|x| *x), Err(TryRecvError::Empty));
+        assert_eq!(r.recv(), Ok(1));
+    })
+    .unwrap();
+    drop(s);
+    assert_eq!(r.try_peek_with(|x| *x), Err(TryRecvError::Disconnected));
+
+    let (s, r) = priority_unbounded();
+    s.send(1).unwrap();
+    s.send(3).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(r.try_peek_with(|x| *x), Ok(3));
+    assert_eq!(r.try_recv_if(|x| *x < 3), Ok(None));
+    assert_eq!(r.try_recv_if(|x| *x == 3), Ok(Some(3)));
+    assert_eq!(r.try_peek_with(|x| *x), Ok(2));
+
+    let (s, r1) = broadcast(4);
+    let r2 = r1.clone();
+    s.send(1).unwrap();
+    assert_eq!(r1.try_recv_if(|x| *x == 1), Ok(Some(1)));
+    assert_eq!(r1.try_peek_with(|x| *x), Err(TryRecvError::Empty));
+    assert_eq!(r2.try_peek_with(|x| *x), Ok(1));
+    assert_eq!(r2.try_recv_if(|x| *x == 2), Ok(None));
+    assert_eq!(r2.try_recv(), Ok(1));
+
+    let r = after(ms(100));
+    assert_eq!(r.try_peek_with(|_| ()), Err(TryRecvError::Empty));
+    thread::sleep(ms(200));
+    let when = r.try_peek_with(|t| *t).unwrap();
+    assert_eq!(r.try_recv_if(|t| *t != when), Ok(None));
+    assert_eq!(r.try_recv_if(|t| *t == when), Ok(Some(when)));
+    assert_eq!(r.try_peek_with(|t| *t), Err(TryRecvError::Empty));
+
+    let r = tick(ms(100));
+    thread::sleep(ms(150));
+    let when = r.try_peek_with(|t| *t).unwrap();
+    assert!(when <= Instant::now());
+    assert_eq!(r.try_recv(), Ok(when));
+
+    let r = never::<i32>();
+    assert_eq!(r.try_peek_with(|x| *x), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn receivers_wait() {
+    for (s, r) in &[bounded(1), unbounded()] {
+        s.send(1).unwrap();
+
+        scope(|scope| {
+            scope.spawn(|_| {
+                r.try_peek_with(|_| thread::sleep(ms(500))).unwrap();
+            });
+
+            thread::sleep(ms(100));
+            let start = Instant::now();
+            assert_eq!(r.recv(), Ok(1));
+            assert!(Instant::now() - start >= ms(300));
+        })
+        .unwrap();
+    }
+}
+
+#[test]
+fn send_while_peeking() {
+    // A full channel is still full while its oldest message is being inspected.
+    let (s, r) = bounded(2);
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(
+        r.try_peek_with(|_| s.try_send(3)),
+        Ok(Err(TrySendError::Full(3)))
+    );
+    assert_eq!(
+        r.try_recv_if(|_| s.send_timeout(3, ms(100)).is_ok()),
+        Ok(None)
+    );
+    assert_eq!(r.try_recv(), Ok(1));
+    assert_eq!(r.try_peek_with(|_| s.try_send(3)), Ok(Ok(())));
+    assert_eq!(r.try_recv(), Ok(2));
+    assert_eq!(r.try_recv(), Ok(3));
+
+    // The oldest message can't be evicted while it's being inspected, so the new one is.
+    let (s, r) = bounded_with_policy(2, Overflow::DropOldest);
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(r.try_peek_with(|_| s.force_send(3)), Ok(Ok(Some(3))));
+    assert_eq!(s.force_send(4), Ok(Some(1)));
+    assert_eq!(r.try_recv(), Ok(2));
+    assert_eq!(r.try_recv(), Ok(4));
+}
+
+#[test]
+fn panic_unlocks() {
+    for (s, r) in &[bounded(2), unbounded()] {
+        s.send(1).unwrap();
+        s.send(2).unwrap();
+
+        let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            r.try_recv_if(|_| panic!()).unwrap();
+        }));
+        assert!(res.is_err());
+
+        assert_eq!(r.try_recv(), Ok(1));
+        assert_eq!(r.try_recv(), Ok(2));
+    }
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    for (s, r) in &[bounded::<usize>(3), unbounded()] {
+        let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+        let received = AtomicUsize::new(0);
+
+        scope(|scope| {
+            // Some receivers only take even messages, others take anything.
+            for t in 0..THREADS {
+                let (v, received) = (&v, &received);
+                scope.spawn(move |_| {
+                    while received.load(Ordering::SeqCst) < COUNT {
+                        let res = if t % 2 == 0 {
+                            r.try_recv_if(|x| x % 2 == 0)
+                        } else {
+                            r.recv_timeout(ms(10))
+                                .map(Some)
+                                .map_err(|_| TryRecvError::Empty)
+                        };
+                        match res {
+                            Ok(Some(n)) => {
+                                v[n].fetch_add(1, Ordering::SeqCst);
+                                received.fetch_add(1, Ordering::SeqCst);
+                            }
+                            Ok(None) => thread::yield_now(),
+                            Err(_) => {}
+                        }
+                    }
+                });
+            }
+
+            for i in 0..COUNT {
+                s.send(i).unwrap();
+            }
+        })
+        .unwrap();
+
+        for c in &v {
+            assert_eq!(c.load(Ordering::SeqCst), 1);
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! Tests for the priority channel flavor.
+
+use std::cmp::Ordering as CmpOrdering;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{priority_bounded, priority_unbounded, select, Select};
+use crossbeam_channel::{RecvError, RecvTimeoutError, TryRecvError};
+use crossbeam_channel::{SendError, SendTimeoutError, TrySendError};
+use crossbeam_utils::thread::scope;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = priority_unbounded();
+    s.try_send(7).unwrap();
+    assert_eq!(r.try_recv(), Ok(7));
+
+    s.send(8).unwrap();
+    assert_eq!(r.recv(), Ok(8));
+
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(r.recv_timeout(ms(1000)), Err(RecvTimeoutError::Timeout));
+}
+
+#[test]
+#[should_panic(expected = "capacity must be positive")]
+fn zero_capacity() {
+    let _ = priority_bounded::<()>(0);
+}
+
+#[test]
+fn capacity() {
+    let (s, r) = priority_unbounded::<()>();
+    assert_eq!(s.capacity(), None);
+    assert_eq!(r.capacity(), None);
+
+    for i in 1..10 {
+        let (s, r) = priority_bounded::<()>(i);
+        assert_eq!(s.capacity(), Some(i));
+        assert_eq!(r.capacity(), Some(i));
+    }
+}
+
+#[test]
+fn len_empty_full() {
+    let (s, r) = priority_bounded(2);
+
+    assert_eq!(s.len(), 0);
+    assert!(s.is_empty());
+    assert!(!s.is_full());
+
+    s.send(()).unwrap();
+    s.send(()).unwrap();
+
+    assert_eq!(r.len(), 2);
+    assert!(!r.is_empty());
+    assert!(r.is_full());
+
+    r.recv().unwrap();
+
+    assert_eq!(r.len(), 1);
+    assert!(!r.is_full());
+}
+
+#[test]
+fn highest_priority_first() {
+    let (s, r) = priority_unbounded();
+
+    let mut v = (0..1000).collect::<Vec<_>>();
+    v.shuffle(&mut thread_rng());
+    for &i in &v {
+        s.send(i).unwrap();
+    }
+
+    for i in (0..1000).rev() {
+        assert_eq!(r.recv(), Ok(i));
+    }
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn equal_priorities_in_order() {
+    #[derive(Debug, PartialEq, Eq)]
+    struct Msg(u32, usize);
+
+    impl PartialOrd for Msg {
+        fn partial_cmp(&self, other: &Msg) -> Option<CmpOrdering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Msg {
+        fn cmp(&self, other: &Msg) -> CmpOrdering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    let (s, r) = priority_unbounded();
+    for i in 0..100 {
+        s.send(Msg(i as u32 % 3, i)).unwrap();
+    }
+
+    for p in (0..3).rev() {
+        for i in (0..100).filter(|i| i % 3 == p as usize) {
+            assert_eq!(r.recv(), Ok(Msg(p, i)));
+        }
+    }
+}
+
+#[test]
+fn urgent_overtakes_bulk() {
+    let (s, r) = priority_bounded(10);
+
+    s.send((0, "bulk 1")).unwrap();
+    s.send((0, "bulk 2")).unwrap();
+    s.send((1, "urgent")).unwrap();
+
+    assert_eq!(r.recv(), Ok((1, "urgent")));
+    assert_eq!(r.recv(), Ok((0, "bulk 2")));
+    assert_eq!(r.recv(), Ok((0, "bulk 1")));
+}
+
+#[test]
+fn try_send() {
+    let (s, r) = priority_bounded(1);
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            assert_eq!(s.try_send(1), Ok(()));
+            assert_eq!(s.try_send(2), Err(TrySendError::Full(2)));
+            thread::sleep(ms(1500));
+            assert_eq!(s.try_send(3), Ok(()));
+            thread::sleep(ms(500));
+            assert_eq!(s.try_send(4), Err(TrySendError::Disconnected(4)));
+        });
+        scope.spawn(move |_| {
+            thread::sleep(ms(1000));
+            assert_eq!(r.try_recv(), Ok(1));
+            assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+            assert_eq!(r.recv(), Ok(3));
+        });
+    })
+    .unwrap();
+}
+
+#[test]
+fn send_blocks_while_full() {
+    let (s, r) = priority_bounded(1);
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            s.send(1).unwrap();
+            s.send(2).unwrap();
+            assert_eq!(
+                s.send_timeout(3, ms(500)),
+                Err(SendTimeoutError::Timeout(3))
+            );
+        });
+        scope.spawn(|_| {
+            thread::sleep(ms(1000));
+            assert_eq!(r.recv(), Ok(1));
+            thread::sleep(ms(1000));
+            assert_eq!(r.recv(), Ok(2));
+        });
+    })
+    .unwrap();
+}
+
+#[test]
+fn recv_timeout() {
+    let (s, r) = priority_unbounded::<i32>();
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            assert_eq!(r.recv_timeout(ms(1000)), Err(RecvTimeoutError::Timeout));
+            assert_eq!(r.recv_timeout(ms(1000)), Ok(7));
+            assert_eq!(
+                r.recv_timeout(ms(1000)),
+                Err(RecvTimeoutError::Disconnected)
+            );
+        });
+        scope.spawn(move |_| {
+            thread::sleep(ms(1500));
+            s.send(7).unwrap();
+        });
+    })
+    .unwrap();
+}
+
+#[test]
+fn recv_after_disconnect() {
+    let (s, r) = priority_bounded(10);
+
+    s.send(1).unwrap();
+    s.send(3).unwrap();
+    s.send(2).unwrap();
+    drop(s);
+
+    assert_eq!(r.recv(), Ok(3));
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv(), Err(RecvError));
+}
+
+#[test]
+fn send_after_disconnect() {
+    let (s, r) = priority_unbounded();
+    drop(r);
+
+    assert_eq!(s.send(1), Err(SendError(1)));
+    assert_eq!(s.try_send(2), Err(TrySendError::Disconnected(2)));
+}
+
+#[test]
+fn disconnect_wakes_sender() {
+    let (s, r) = priority_bounded(1);
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            assert_eq!(s.send(()), Ok(()));
+            assert_eq!(s.send(()), Err(SendError(())));
+        });
+        scope.spawn(move |_| {
+            thread::sleep(ms(1000));
+            drop(r);
+        });
+    })
+    .unwrap();
+}
+
+#[test]
+fn disconnect_wakes_receiver() {
+    let (s, r) = priority_unbounded::<()>();
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            assert_eq!(r.recv(), Err(RecvError));
+        });
+        scope.spawn(move |_| {
+            thread::sleep(ms(1000));
+            drop(s);
+        });
+    })
+    .unwrap();
+}
+
+#[test]
+fn many() {
+    let (s, r) = priority_bounded(5);
+    let mut buf = Vec::new();
+
+    let mut msgs = (0..10).collect();
+    assert_eq!(s.try_send_many(&mut msgs), Ok(5));
+    assert_eq!(msgs, [5, 6, 7, 8, 9]);
+
+    assert_eq!(r.try_recv_many(&mut buf, 3), Ok(3));
+    assert_eq!(buf, [4, 3, 2]);
+    assert_eq!(s.try_send_many(&mut msgs), Ok(3));
+    assert_eq!(r.recv_many(&mut buf, 10), Ok(5));
+    assert_eq!(buf, [4, 3, 2, 7, 6, 5, 1, 0]);
+}
+
+#[test]
+fn select() {
+    let (s1, r1) = priority_unbounded();
+    let (s2, r2) = priority_bounded(1);
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            s1.send(1).unwrap();
+            thread::sleep(ms(500));
+            s2.send(2).unwrap();
+        });
+
+        select! {
+            recv(r1) -> msg => assert_eq!(msg, Ok(1)),
+            recv(r2) -> _ => panic!(),
+        }
+        select! {
+            recv(r1) -> _ => panic!(),
+            recv(r2) -> msg => assert_eq!(msg, Ok(2)),
+        }
+    })
+    .unwrap();
+
+    s2.send(3).unwrap();
+    select! {
+        send(s2, 4) -> _ => panic!(),
+        default => {}
+    }
+
+    let mut sel = Select::new();
+    sel.recv(&r2);
+    let oper = sel.select_timeout(ms(1000)).unwrap();
+    assert_eq!(oper.recv(&r2), Ok(3));
+
+    select! {
+        send(s2, 5) -> res => assert_eq!(res, Ok(())),
+        default => panic!(),
+    }
+    assert_eq!(r2.recv(), Ok(5));
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = priority_bounded::<usize>(3);
+    let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+    scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                for _ in 0..COUNT {
+                    let n = r.recv().unwrap();
+                    v[n].fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                for i in 0..COUNT {
+                    s.send(i).unwrap();
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    for c in v {
+        assert_eq!(c.load(Ordering::SeqCst), THREADS);
+    }
+}
+
+#[test]
+fn drops() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct DropCounter(usize);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut rng = thread_rng();
+
+    for _ in 0..100 {
+        let steps = rng.gen_range(0..10_000);
+        let additional = rng.gen_range(0..50);
+
+        DROPS.store(0, Ordering::SeqCst);
+        let (s, r) = priority_bounded::<DropCounter>(50);
+
+        scope(|scope| {
+            scope.spawn(|_| {
+                for _ in 0..steps {
+                    r.recv().unwrap();
+                }
+            });
+
+            scope.spawn(|_| {
+                for i in 0..steps {
+                    s.send(DropCounter(i)).unwrap();
+                }
+            });
+        })
+        .unwrap();
+
+        for i in 0..additional {
+            s.send(DropCounter(i)).unwrap();
+        }
+
+        assert_eq!(DROPS.load(Ordering::SeqCst), steps);
+        drop(s);
+        drop(r);
+        assert_eq!(DROPS.load(Ordering::SeqCst), steps + additional);
+    }
+}
@@ -0,0 +1,310 @@
+//! Tests for reserving capacity with permits.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, bounded_with_policy, priority_bounded, unbounded, Overflow};
+use crossbeam_channel::{
+    RecvTimeoutError, SendError, SendTimeoutError, TryRecvError, TrySendError,
+};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = bounded(1);
+
+    let permit = s.try_reserve().unwrap();
+    assert!(s.is_full());
+    assert_eq!(s.try_reserve().unwrap_err(), TrySendError::Full(()));
+    assert_eq!(s.try_send(2), Err(TrySendError::Full(2)));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+
+    permit.send(1).unwrap();
+    assert_eq!(r.try_recv(), Ok(1));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn drop_releases() {
+    let (s, r) = bounded(2);
+
+    for _ in 0..10 {
+        drop(s.reserve().unwrap());
+        assert!(s.is_empty());
+    }
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv(), Ok(2));
+}
+
+#[test]
+fn order() {
+    let (s, r) = bounded(3);
+
+    let p1 = s.reserve().unwrap();
+    s.send(2).unwrap();
+    let p3 = s.reserve().unwrap();
+    assert_eq!(s.try_send(4), Err(TrySendError::Full(4)));
+
+    // Messages are received in the order of reservation.
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    p3.send(3).unwrap();
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    p1.send(1).unwrap();
+
+    assert_eq!(r.try_recv(), Ok(1));
+    assert_eq!(r.try_recv(), Ok(2));
+    assert_eq!(r.try_recv(), Ok(3));
+}
+
+#[test]
+fn skip_dropped() {
+    let (s, r) = bounded(3);
+
+    let p1 = s.reserve().unwrap();
+    s.send(2).unwrap();
+    let p3 = s.reserve().unwrap();
+
+    // Neither permit is the last reservation, so receivers skip their slots.
+    drop(p1);
+    assert_eq!(r.try_recv(), Ok(2));
+    s.send(4).unwrap();
+    drop(p3);
+    assert_eq!(r.try_recv(), Ok(4));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+
+    // All capacity is available again.
+    for i in 0..3 {
+        s.send(i).unwrap();
+    }
+    assert_eq!(s.try_send(3), Err(TrySendError::Full(3)));
+    for i in 0..3 {
+        assert_eq!(r.recv(), Ok(i));
+    }
+}
+
+#[test]
+fn recv_waits_for_permit() {
+    let (s, r) = bounded(2);
+
+    scope(|scope| {
+        let permit = s.reserve().unwrap();
+        s.send(2).unwrap();
+
+        scope.spawn(|_| {
+            assert_eq!(r.recv_timeout(ms(200)), Err(RecvTimeoutError::Timeout));
+            assert_eq!(r.recv(), Ok(1));
+            assert_eq!(r.recv(), Ok(2));
+        });
+
+        thread::sleep(ms(500));
+        permit.send(1).unwrap();
+    })
+    .unwrap();
+
+    scope(|scope| {
+        let permit = s.reserve().unwrap();
+        s.send(3).unwrap();
+
+        scope.spawn(|_| {
+            assert_eq!(r.recv(), Ok(3));
+        });
+
+        thread::sleep(ms(500));
+        drop(permit);
+    })
+    .unwrap();
+}
+
+#[test]
+fn reserve_blocks() {
+    let (s, r) = bounded(1);
+    s.send(1).unwrap();
+
+    assert_eq!(s.try_reserve().unwrap_err(), TrySendError::Full(()));
+    assert_eq!(
+        s.reserve_timeout(ms(200)).unwrap_err(),
+        SendTimeoutError::Timeout(())
+    );
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            assert_eq!(r.recv(), Ok(1));
+            assert_eq!(r.recv(), Ok(2));
+        });
+
+        let permit = s.reserve().unwrap();
+        permit.send(2).unwrap();
+    })
+    .unwrap();
+}
+
+#[test]
+fn disconnected() {
+    let (s, r) = bounded(1);
+    let permit = s.reserve().unwrap();
+    drop(r);
+    assert_eq!(permit.send(1), Err(SendError(1)));
+    assert_eq!(s.try_reserve().unwrap_err(), TrySendError::Disconnected(()));
+    assert_eq!(s.reserve().unwrap_err(), SendError(()));
+
+    let (s, r) = bounded::<i32>(1);
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            drop(r);
+        });
+
+        s.send(1).unwrap();
+        assert_eq!(s.reserve().unwrap_err(), SendError(()));
+    })
+    .unwrap();
+
+    let (s, r) = bounded(2);
+    s.send(1).unwrap();
+    let permit = s.reserve().unwrap();
+    assert!(s.close());
+    assert_eq!(permit.send(2), Err(SendError(2)));
+    assert_eq!(r.recv(), Ok(1));
+    assert!(r.recv().is_err());
+}
+
+#[test]
+fn other_flavors() {
+    let (s, r) = unbounded();
+    let p1 = s.try_reserve().unwrap();
+    let p2 = s.reserve().unwrap();
+    p2.send(2).unwrap();
+    p1.send(1).unwrap();
+    assert_eq!(r.recv(), Ok(2));
+    assert_eq!(r.recv(), Ok(1));
+
+    let (s, r) = bounded_with_policy(1, Overflow::DropOldest);
+    s.send(1).unwrap();
+    s.reserve().unwrap().send(2).unwrap();
+    assert_eq!(r.recv(), Ok(2));
+
+    let (s, r) = bounded(0);
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            assert_eq!(r.recv(), Ok(1));
+        });
+
+        s.reserve().unwrap().send(1).unwrap();
+    })
+    .unwrap();
+
+    drop(r);
+    assert_eq!(s.reserve().unwrap_err(), SendError(()));
+}
+
+#[test]
+fn priority() {
+    let (s, r) = priority_bounded(2);
+    let p1 = s.try_reserve().unwrap();
+    let p2 = s.reserve().unwrap();
+    assert_eq!(s.try_reserve().unwrap_err(), TrySendError::Full(()));
+    assert_eq!(s.try_send(0), Err(TrySendError::Full(0)));
+    assert_eq!(
+        s.reserve_timeout(ms(200)).unwrap_err(),
+        SendTimeoutError::Timeout(())
+    );
+
+    // Messages are ordered by priority, not by reservation.
+    p1.send(1).unwrap();
+    p2.send(2).unwrap();
+    assert_eq!(r.try_recv(), Ok(2));
+
+    // Dropping a permit releases its slot.
+    let permit = s.reserve().unwrap();
+    drop(permit);
+    s.try_send(3).unwrap();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            assert_eq!(r.recv(), Ok(3));
+        });
+
+        s.reserve().unwrap().send(4).unwrap();
+    })
+    .unwrap();
+    assert_eq!(r.try_recv(), Ok(4));
+
+    let permit = s.reserve().unwrap();
+    drop(r);
+    assert_eq!(permit.send(5), Err(SendError(5)));
+    assert_eq!(s.try_reserve().unwrap_err(), TrySendError::Disconnected(()));
+}
+
+#[test]
+fn drops() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    struct DropCounter;
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let (s, r) = bounded(4);
+    let p1 = s.reserve().unwrap();
+    s.send(DropCounter).unwrap();
+    let p3 = s.reserve().unwrap();
+    s.send(DropCounter).unwrap();
+    drop(p1);
+    drop(p3);
+
+    assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+    drop(s);
+    drop(r);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = bounded::<usize>(3);
+    let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+    scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                for _ in 0..COUNT / 2 {
+                    let n = r.recv().unwrap();
+                    v[n].fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                for i in 0..COUNT {
+                    let permit = s.reserve().unwrap();
+                    if i % 2 == 0 {
+                        permit.send(i).unwrap();
+                    }
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    for (i, c) in v.iter().enumerate() {
+        let expected = if i % 2 == 0 { THREADS } else { 0 };
+        assert_eq!(c.load(Ordering::SeqCst), expected);
+    }
+}
@@ -1205,6 +1205,85 @@ fn fairness2() {
     .unwrap();
 }
 
+#[test]
+fn biased() {
+    const COUNT: usize = 1000;
+
+    let (s1, r1) = unbounded::<()>();
+    let (s2, r2) = unbounded::<()>();
+    let (s3, r3) = unbounded::<()>();
+
+    for _ in 0..COUNT {
+        s1.send(()).unwrap();
+        s2.send(()).unwrap();
+        s3.send(()).unwrap();
+    }
+
+    let mut sel = Select::new_biased();
+    let oper1 = sel.recv(&r1);
+    let oper2 = sel.recv(&r2);
+    let oper3 = sel.recv(&r3);
+
+    for &(oper, r) in &[(oper1, &r1), (oper2, &r2), (oper3, &r3)] {
+        for _ in 0..COUNT {
+            assert_eq!(sel.ready(), oper);
+            assert_eq!(sel.try_ready(), Ok(oper));
+
+            let selected = sel.select_timeout(ms(1000)).unwrap();
+            assert_eq!(selected.index(), oper);
+            selected.recv(r).unwrap();
+        }
+    }
+    assert!(sel.try_select().is_err());
+}
+
+#[test]
+fn biased_remove_keeps_order() {
+    let rs = (0..5).map(|_| unbounded::<i32>()).collect::<Vec<_>>();
+    for (s, _) in &rs {
+        s.send(0).unwrap();
+    }
+
+    let mut sel = Select::new_biased();
+    let opers = rs.iter().map(|(_, r)| sel.recv(r)).collect::<Vec<_>>();
+    sel.remove(opers[0]);
+
+    for i in 1..5 {
+        let oper = sel.select();
+        assert_eq!(oper.index(), opers[i]);
+        oper.recv(&rs[i].1).unwrap();
+        sel.remove(opers[i]);
+    }
+}
+
+#[test]
+fn biased_blocking() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded::<i32>();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            s2.send(2).unwrap();
+        });
+
+        let mut sel = Select::new_biased();
+        let oper1 = sel.recv(&r1);
+        let oper2 = sel.recv(&r2);
+
+        let oper = sel.select();
+        assert_eq!(oper.index(), oper2);
+        assert_eq!(oper.recv(&r2), Ok(2));
+
+        s2.send(3).unwrap();
+        s1.send(1).unwrap();
+        let oper = sel.select();
+        assert_eq!(oper.index(), oper1);
+        assert_eq!(oper.recv(&r1), Ok(1));
+    })
+    .unwrap();
+}
+
 #[test]
 fn sync_and_clone() {
     const THREADS: usize = 20;
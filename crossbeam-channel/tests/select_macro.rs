@@ -8,7 +8,7 @@ use std::ops::Deref;
 use std::thread;
 use std::time::{Duration, Instant};
 
-use crossbeam_channel::{after, bounded, never, select, tick, unbounded};
+use crossbeam_channel::{after, bounded, never, select, select_biased, tick, unbounded};
 use crossbeam_channel::{Receiver, RecvError, SendError, Sender, TryRecvError};
 use crossbeam_utils::thread::scope;
 
@@ -1417,6 +1417,65 @@ fn disconnect_wakes_sender() {
     .unwrap();
 }
 
+#[test]
+fn biased() {
+    const COUNT: usize = 1000;
+
+    let (s1, r1) = unbounded::<()>();
+    let (s2, r2) = unbounded::<()>();
+
+    for _ in 0..COUNT {
+        s1.send(()).unwrap();
+        s2.send(()).unwrap();
+    }
+
+    for _ in 0..COUNT {
+        select_biased! {
+            recv(r1) -> msg => msg.unwrap(),
+            recv(r2) -> _ => panic!(),
+        }
+    }
+    for _ in 0..COUNT {
+        select_biased! {
+            recv(r1) -> _ => panic!(),
+            recv(r2) -> msg => msg.unwrap(),
+            default => panic!(),
+        }
+    }
+    select_biased! {
+        recv(r1) -> _ => panic!(),
+        recv(r2) -> _ => panic!(),
+        default(ms(100)) => {}
+    }
+}
+
+#[test]
+fn biased_shutdown_wins() {
+    let (shutdown_s, shutdown_r) = bounded::<()>(1);
+    let (data_s, data_r) = unbounded::<i32>();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            for i in 0.. {
+                if data_s.send(i).is_err() {
+                    break;
+                }
+            }
+        });
+
+        thread::sleep(ms(100));
+        shutdown_s.send(()).unwrap();
+
+        // Once the shutdown signal is sent, it always beats the data.
+        select_biased! {
+            recv(shutdown_r) -> msg => assert_eq!(msg, Ok(())),
+            recv(data_r) -> _ => panic!(),
+        }
+        drop(data_r);
+    })
+    .unwrap();
+}
+
 #[test]
 fn disconnect_wakes_receiver() {
     let (s, r) = bounded::<()>(0);
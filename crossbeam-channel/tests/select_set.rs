@@ -0,0 +1,403 @@
+//! Tests for the `SelectSet` struct.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{after, bounded, broadcast, merge, never, tick, unbounded};
+use crossbeam_channel::{SelectSet, TryRecvError, TrySendError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded::<i32>();
+
+    let mut set = SelectSet::new();
+    set.insert_recv("one", r1);
+    set.insert_recv("two", r2);
+    assert!(set.try_ready().is_err());
+
+    s1.send(1).unwrap();
+    assert_eq!(set.ready(), "one");
+    assert_eq!(set.receiver(&"one").unwrap().try_recv(), Ok(1));
+    assert!(set.try_ready().is_err());
+
+    s2.send(2).unwrap();
+    assert_eq!(set.try_ready(), Ok("two"));
+    assert_eq!(set.receiver(&"two").unwrap().try_recv(), Ok(2));
+    assert!(set.ready_timeout(ms(100)).is_err());
+}
+
+#[test]
+fn insert_and_remove() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded::<i32>();
+    let (s3, r3) = bounded::<i32>(1);
+
+    let mut set = SelectSet::new();
+    assert!(set.is_empty());
+    assert!(set.insert_recv(1, r1));
+    assert!(set.insert_send(2, s3));
+    assert_eq!(set.len(), 2);
+    assert!(set.contains(&1));
+    assert!(!set.contains(&3));
+
+    assert!(set.receiver::<i32>(&1).is_some());
+    assert!(set.receiver::<u32>(&1).is_none());
+    assert!(set.receiver::<i32>(&2).is_none());
+    assert!(set.sender::<i32>(&2).is_some());
+    assert!(set.sender::<i32>(&3).is_none());
+
+    // Replacing an operation drops the old receiver.
+    assert!(!set.insert_recv(1, r2));
+    assert_eq!(set.len(), 2);
+    assert!(s1.send(1).is_err());
+    s2.send(2).unwrap();
+    assert_eq!(set.ready(), 2);
+    assert_eq!(set.sender(&2).unwrap().try_send(0), Ok(()));
+    assert_eq!(set.ready(), 1);
+
+    // Removing an operation drops its sender or receiver.
+    assert!(set.remove(&1));
+    assert!(!set.remove(&1));
+    assert!(s2.send(3).is_err());
+    assert!(set.remove(&2));
+    assert!(set.is_empty());
+    assert_eq!(r3.try_recv(), Ok(0));
+    assert_eq!(r3.try_recv(), Err(TryRecvError::Disconnected));
+    assert!(set.try_ready().is_err());
+}
+
+#[test]
+#[should_panic(expected = "no operations have been added to `SelectSet`")]
+fn ready_on_empty_set() {
+    let mut set = SelectSet::<i32>::new();
+    set.ready();
+}
+
+#[test]
+fn ready_wakes_up() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = bounded::<i32>(0);
+
+    let mut set = SelectSet::new();
+    set.insert_recv(1, r1);
+    set.insert_recv(2, r2);
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            s2.send(2).unwrap();
+            thread::sleep(ms(500));
+            s1.send(1).unwrap();
+        });
+
+        assert_eq!(set.ready(), 2);
+        assert_eq!(set.receiver(&2).unwrap().recv(), Ok(2));
+        assert!(set.ready_timeout(ms(100)).is_err());
+        assert_eq!(set.ready_timeout(ms(1000)), Ok(1));
+        assert_eq!(set.receiver(&1).unwrap().try_recv(), Ok(1));
+    })
+    .unwrap();
+}
+
+#[test]
+fn send() {
+    let (s, r) = bounded::<i32>(1);
+
+    let mut set = SelectSet::new();
+    set.insert_send(0, s);
+    assert_eq!(set.try_ready(), Ok(0));
+    assert_eq!(set.sender(&0).unwrap().try_send(1), Ok(()));
+    assert!(set.try_ready().is_err());
+    assert_eq!(
+        set.sender(&0).unwrap().try_send(2),
+        Err(TrySendError::Full(2))
+    );
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            assert_eq!(r.recv(), Ok(1));
+        });
+
+        assert_eq!(set.ready(), 0);
+        assert_eq!(set.sender(&0).unwrap().try_send(3), Ok(()));
+    })
+    .unwrap();
+
+    drop(r);
+    assert_eq!(set.ready(), 0);
+    assert_eq!(
+        set.sender(&0).unwrap().try_send(4),
+        Err(TrySendError::Disconnected(4))
+    );
+}
+
+#[test]
+fn stays_ready_until_drained() {
+    let (s, r) = unbounded::<i32>();
+
+    let mut set = SelectSet::new();
+    set.insert_recv(0, r);
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    // Nothing was received, so the operation is still ready.
+    assert_eq!(set.ready(), 0);
+    assert_eq!(set.ready(), 0);
+    assert_eq!(set.receiver(&0).unwrap().try_recv(), Ok(1));
+    assert_eq!(set.ready(), 0);
+    assert_eq!(set.receiver(&0).unwrap().try_recv(), Ok(2));
+    assert!(set.try_ready().is_err());
+
+    drop(s);
+    assert_eq!(set.ready(), 0);
+    assert_eq!(set.ready(), 0);
+    assert_eq!(
+        set.receiver::<i32>(&0).unwrap().try_recv(),
+        Err(TryRecvError::Disconnected)
+    );
+}
+
+#[test]
+fn fairness() {
+    const COUNT: usize = 1000;
+
+    let senders = (0..4).map(|_| unbounded::<()>()).collect::<Vec<_>>();
+
+    let mut set = SelectSet::new();
+    for (i, (s, r)) in senders.iter().enumerate() {
+        for _ in 0..COUNT {
+            s.send(()).unwrap();
+        }
+        set.insert_recv(i, r.clone());
+    }
+
+    let mut hits = [0usize; 4];
+    for _ in 0..COUNT {
+        let i = set.ready();
+        hits[i] += 1;
+        set.receiver::<()>(&i).unwrap().try_recv().unwrap();
+    }
+    assert!(hits.iter().all(|x| *x >= COUNT / 8));
+}
+
+#[test]
+fn remove_notified() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded::<i32>();
+
+    let mut set = SelectSet::new();
+    set.insert_recv(1, r1);
+    set.insert_recv(2, r2.clone());
+
+    // The first operation gets notified, and is removed before the notification is seen. The slot
+    // is then reused by a new operation.
+    s1.send(1).unwrap();
+    set.remove(&1);
+    set.insert_recv(3, r2);
+    assert!(set.try_ready().is_err());
+
+    s2.send(2).unwrap();
+    let key = set.ready();
+    assert!(key == 2 || key == 3);
+    assert!(!set.contains(&1));
+}
+
+#[test]
+fn remove_queued() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = unbounded::<i32>();
+    let (s3, r3) = unbounded::<i32>();
+    for s in &[&s1, &s2, &s3] {
+        s.send(0).unwrap();
+    }
+
+    let mut set = SelectSet::new();
+    set.insert_recv(1, r1);
+    set.insert_recv(2, r2);
+
+    // The first operation is queued when it's removed, and its slot is reused by an operation that
+    // gets queued too. The new operation must be queued only once.
+    set.remove(&1);
+    set.insert_recv(3, r3);
+
+    let mut hits = [0usize; 4];
+    for _ in 0..10 {
+        hits[set.ready()] += 1;
+    }
+    assert_eq!(hits, [0, 0, 5, 5]);
+}
+
+#[test]
+fn timers() {
+    let start = Instant::now();
+
+    let mut set = SelectSet::new();
+    set.insert_recv("never", never::<Instant>());
+    set.insert_recv("after", after(ms(500)));
+    set.insert_recv("tick", tick(ms(200)));
+
+    assert_eq!(set.ready(), "tick");
+    assert!(Instant::now() - start >= ms(200));
+    set.receiver::<Instant>(&"tick").unwrap().recv().unwrap();
+
+    assert_eq!(set.ready(), "tick");
+    assert!(Instant::now() - start >= ms(400));
+    set.receiver::<Instant>(&"tick").unwrap().recv().unwrap();
+
+    assert_eq!(set.ready(), "after");
+    assert!(Instant::now() - start >= ms(500));
+    set.remove(&"after");
+    set.remove(&"tick");
+
+    assert!(set.ready_timeout(ms(100)).is_err());
+}
+
+#[test]
+fn timeout() {
+    let (_s, r) = unbounded::<i32>();
+
+    let mut set = SelectSet::new();
+    set.insert_recv(0, r);
+
+    let start = Instant::now();
+    assert!(set.ready_timeout(ms(300)).is_err());
+    assert!(Instant::now() - start >= ms(300));
+
+    let deadline = Instant::now() + ms(300);
+    assert!(set.ready_deadline(deadline).is_err());
+    assert!(Instant::now() >= deadline);
+}
+
+#[test]
+fn other_flavors() {
+    let (s1, r1) = broadcast::<i32>(4);
+    let (s2, r2) = unbounded::<i32>();
+    let (s3, r3) = bounded::<i32>(0);
+
+    let mut set = SelectSet::new();
+    set.insert_recv("broadcast", r1);
+    set.insert_recv("merge", merge(vec![r2.map(|x| x * 10), r3]));
+
+    s1.send(1).unwrap();
+    assert_eq!(set.ready(), "broadcast");
+    assert_eq!(set.receiver(&"broadcast").unwrap().try_recv(), Ok(1));
+
+    s2.send(2).unwrap();
+    assert_eq!(set.ready(), "merge");
+    assert_eq!(set.receiver(&"merge").unwrap().try_recv(), Ok(20));
+
+    scope(|scope| {
+        scope.spawn(|_| s3.send(3).unwrap());
+
+        assert_eq!(set.ready(), "merge");
+        assert_eq!(set.receiver(&"merge").unwrap().recv(), Ok(3));
+    })
+    .unwrap();
+}
+
+#[test]
+fn drop_set() {
+    let (s1, r1) = unbounded::<i32>();
+    let (s2, r2) = bounded::<i32>(0);
+
+    let mut set = SelectSet::new();
+    set.insert_recv(1, r1.clone());
+    set.insert_send(2, s2.clone());
+    drop(set);
+
+    // The channels are no longer watched by the set.
+    s1.send(1).unwrap();
+    assert_eq!(r1.try_recv(), Ok(1));
+    assert_eq!(s2.try_send(2), Err(TrySendError::Full(2)));
+    drop(r2);
+}
+
+#[test]
+fn dynamic() {
+    const COUNT: usize = 100;
+    const ROUNDS: usize = 10;
+
+    let mut set = SelectSet::new();
+    let mut received = 0;
+
+    scope(|scope| {
+        // Every round, a new batch of channels is added to the set and removed once disconnected.
+        for round in 0..ROUNDS {
+            for i in 0..COUNT {
+                let key = round * COUNT + i;
+                let (s, r) = unbounded();
+                set.insert_recv(key, r);
+                scope.spawn(move |_| s.send(key).unwrap());
+            }
+
+            while !set.is_empty() {
+                let key = set.ready();
+                match set.receiver::<usize>(&key).unwrap().try_recv() {
+                    Ok(msg) => {
+                        assert_eq!(msg, key);
+                        received += 1;
+                    }
+                    Err(TryRecvError::Empty) => {}
+                    Err(TryRecvError::Disconnected) => {
+                        set.remove(&key);
+                    }
+                }
+            }
+        }
+    })
+    .unwrap();
+
+    assert_eq!(received, COUNT * ROUNDS);
+}
+
+#[test]
+fn stress() {
+    const COUNT: usize = 10_000;
+    const CHANNELS: usize = 50;
+
+    let (senders, receivers): (Vec<_>, Vec<_>) = (0..CHANNELS)
+        .map(|i| {
+            if i % 2 == 0 {
+                unbounded()
+            } else {
+                bounded(i % 5)
+            }
+        })
+        .unzip();
+
+    let mut set = SelectSet::new();
+    for (i, r) in receivers.into_iter().enumerate() {
+        set.insert_recv(i, r);
+    }
+
+    scope(|scope| {
+        for s in senders {
+            scope.spawn(move |_| {
+                for i in 0..COUNT / CHANNELS {
+                    s.send(i).unwrap();
+                }
+            });
+        }
+
+        let mut received = 0;
+        while !set.is_empty() {
+            let key = set.ready();
+            match set.receiver::<usize>(&key).unwrap().try_recv() {
+                Ok(_) => received += 1,
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {
+                    set.remove(&key);
+                }
+            }
+        }
+        assert_eq!(received, COUNT);
+    })
+    .unwrap();
+}
@@ -0,0 +1,311 @@
+//! Tests for the `Stream` and `Sink` implementations.
+
+#![cfg(feature = "futures")]
+
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{
+    bounded, broadcast, never, priority_bounded, tick, unbounded, Receiver, SendError, Sender,
+};
+use crossbeam_utils::thread::scope;
+use futures_core::Stream;
+use futures_sink::Sink;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Returns a waker that unparks the current thread.
+fn thread_waker() -> Waker {
+    unsafe fn clone(data: *const ()) -> RawWaker {
+        let thread = Arc::from_raw(data as *const Thread);
+        let cloned = thread.clone();
+        std::mem::forget(thread);
+        RawWaker::new(Arc::into_raw(cloned) as *const (), &VTABLE)
+    }
+    unsafe fn wake(data: *const ()) {
+        Arc::from_raw(data as *const Thread).unpark();
+    }
+    unsafe fn wake_by_ref(data: *const ()) {
+        (*(data as *const Thread)).unpark();
+    }
+    unsafe fn drop(data: *const ()) {
+        Arc::from_raw(data as *const Thread);
+    }
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+
+    let thread = Arc::new(thread::current());
+    unsafe { Waker::from_raw(RawWaker::new(Arc::into_raw(thread) as *const (), &VTABLE)) }
+}
+
+/// Polls the stream once.
+fn poll_next<T>(r: &mut Receiver<T>) -> Poll<Option<T>> {
+    let waker = thread_waker();
+    Pin::new(r).poll_next(&mut Context::from_waker(&waker))
+}
+
+/// Polls the sink for flushing once.
+fn poll_flush<T>(s: &mut Sender<T>) -> Poll<Result<(), SendError<T>>> {
+    let waker = thread_waker();
+    Pin::new(s).poll_flush(&mut Context::from_waker(&waker))
+}
+
+/// Waits on the current thread for the next message in the stream.
+fn next<T>(r: &mut Receiver<T>) -> Option<T> {
+    loop {
+        if let Poll::Ready(msg) = poll_next(r) {
+            return msg;
+        }
+        thread::park();
+    }
+}
+
+/// Sends a message through the sink, waiting on the current thread until it's flushed.
+fn feed<T>(s: &mut Sender<T>, msg: T) -> Result<(), SendError<T>> {
+    let waker = thread_waker();
+    let cx = &mut Context::from_waker(&waker);
+
+    loop {
+        match Pin::new(&mut *s).poll_ready(cx) {
+            Poll::Ready(res) => break res?,
+            Poll::Pending => thread::park(),
+        }
+    }
+    Pin::new(&mut *s).start_send(msg)?;
+    loop {
+        match Pin::new(&mut *s).poll_flush(cx) {
+            Poll::Ready(res) => return res,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[test]
+fn stream_smoke() {
+    let (s, mut r) = unbounded();
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+
+    assert_eq!(poll_next(&mut r), Poll::Ready(Some(1)));
+    assert_eq!(poll_next(&mut r), Poll::Ready(Some(2)));
+    assert_eq!(poll_next(&mut r), Poll::Pending);
+
+    drop(s);
+    assert_eq!(poll_next(&mut r), Poll::Ready(None));
+    assert_eq!(poll_next(&mut r), Poll::Ready(None));
+}
+
+#[test]
+fn stream_woken_by_sender() {
+    let (s, mut r) = bounded(1);
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            thread::sleep(ms(500));
+            s.send(7).unwrap();
+            thread::sleep(ms(500));
+        });
+
+        assert_eq!(poll_next(&mut r), Poll::Pending);
+        assert_eq!(next(&mut r), Some(7));
+        assert_eq!(next(&mut r), None);
+    })
+    .unwrap();
+}
+
+#[test]
+fn stream_other_flavors() {
+    let (s, mut r) = broadcast(2);
+    let mut r2 = r.clone();
+    s.send(1).unwrap();
+    drop(s);
+    assert_eq!(next(&mut r), Some(1));
+    assert_eq!(next(&mut r), None);
+    assert_eq!(next(&mut r2), Some(1));
+    assert_eq!(next(&mut r2), None);
+
+    let (s, mut r) = priority_bounded(3);
+    for i in &[2, 3, 1] {
+        s.send(*i).unwrap();
+    }
+    drop(s);
+    assert_eq!(next(&mut r), Some(3));
+    assert_eq!(next(&mut r), Some(2));
+    assert_eq!(next(&mut r), Some(1));
+    assert_eq!(next(&mut r), None);
+
+    let mut r = never::<i32>();
+    assert_eq!(poll_next(&mut r), Poll::Pending);
+}
+
+#[test]
+fn stream_tick() {
+    let start = Instant::now();
+    let mut r = tick(ms(100));
+    assert!(next(&mut r).is_some());
+    assert!(next(&mut r).is_some());
+    assert!(Instant::now() - start >= ms(200));
+}
+
+#[test]
+fn stream_dropped_while_pending() {
+    let (s, mut r1) = bounded(1);
+    let mut r2 = r1.clone();
+
+    assert_eq!(poll_next(&mut r1), Poll::Pending);
+    assert_eq!(poll_next(&mut r2), Poll::Pending);
+    drop(r1);
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            s.send(1).unwrap();
+        });
+        assert_eq!(next(&mut r2), Some(1));
+    })
+    .unwrap();
+}
+
+#[test]
+fn sink_smoke() {
+    let (mut s, r) = unbounded();
+    feed(&mut s, 1).unwrap();
+    feed(&mut s, 2).unwrap();
+    assert_eq!(r.try_recv(), Ok(1));
+    assert_eq!(r.try_recv(), Ok(2));
+
+    drop(r);
+    assert_eq!(feed(&mut s, 3), Err(SendError(3)));
+}
+
+#[test]
+fn sink_backpressure() {
+    let (mut s, r) = bounded(1);
+    feed(&mut s, 1).unwrap();
+
+    // The channel is full, so the message stays in the sink.
+    Pin::new(&mut s).start_send(2).unwrap();
+    assert_eq!(poll_flush(&mut s), Poll::Pending);
+    assert_eq!(r.len(), 1);
+
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(poll_flush(&mut s), Poll::Ready(Ok(())));
+    assert_eq!(r.recv(), Ok(2));
+
+    // Closing the sink doesn't disconnect the channel.
+    let waker = thread_waker();
+    let cx = &mut Context::from_waker(&waker);
+    assert_eq!(Pin::new(&mut s).poll_close(cx), Poll::Ready(Ok(())));
+    assert_eq!(s.send(3), Ok(()));
+}
+
+#[test]
+fn sink_disconnected_while_pending() {
+    let (mut s, r) = bounded(1);
+    feed(&mut s, 1).unwrap();
+    Pin::new(&mut s).start_send(2).unwrap();
+    assert_eq!(poll_flush(&mut s), Poll::Pending);
+
+    drop(r);
+    assert_eq!(poll_flush(&mut s), Poll::Ready(Err(SendError(2))));
+    assert_eq!(poll_flush(&mut s), Poll::Ready(Ok(())));
+}
+
+#[test]
+#[should_panic(expected = "`start_send` called before `poll_ready` returned `Ready`")]
+fn start_send_without_poll_ready() {
+    let (mut s, _r) = bounded(1);
+    Pin::new(&mut s).start_send(1).unwrap();
+    Pin::new(&mut s).start_send(2).unwrap();
+}
+
+#[test]
+fn sink_dropped_with_pending_message() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct DropCounter;
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    for &cap in &[0, 1] {
+        DROPS.store(0, Ordering::SeqCst);
+        let (mut s, r) = bounded(cap);
+        if cap > 0 {
+            s.send(DropCounter).unwrap();
+        }
+
+        Pin::new(&mut s).start_send(DropCounter).unwrap();
+        assert_eq!(poll_flush(&mut s), Poll::Pending);
+        drop(s);
+
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+        assert_eq!(r.iter().count(), cap);
+    }
+}
+
+#[test]
+fn zero_capacity() {
+    const COUNT: usize = 1000;
+
+    let (mut s, mut r) = bounded(0);
+
+    scope(|scope| {
+        scope.spawn(move |_| {
+            for i in 0..COUNT {
+                feed(&mut s, i).unwrap();
+            }
+        });
+
+        for i in 0..COUNT {
+            assert_eq!(next(&mut r), Some(i));
+        }
+        assert_eq!(next(&mut r), None);
+    })
+    .unwrap();
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = bounded::<usize>(3);
+    let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+
+    scope(|scope| {
+        for _ in 0..THREADS {
+            let mut r = r.clone();
+            let v = &v;
+            scope.spawn(move |_| {
+                while let Some(n) = next(&mut r) {
+                    v[n].fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+        for _ in 0..THREADS {
+            let mut s = s.clone();
+            scope.spawn(move |_| {
+                for i in 0..COUNT {
+                    feed(&mut s, i).unwrap();
+                }
+            });
+        }
+        drop(s);
+        drop(r);
+    })
+    .unwrap();
+
+    for c in v {
+        assert_eq!(c.load(Ordering::SeqCst), THREADS);
+    }
+}
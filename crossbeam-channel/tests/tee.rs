@@ -0,0 +1,330 @@
+//! Tests for receivers split with `tee`.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, select, tick, unbounded};
+use crossbeam_channel::{RecvError, RecvTimeoutError, TryRecvError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = unbounded();
+    let rs = r.tee(3);
+    assert_eq!(rs.len(), 3);
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    for r in &rs {
+        assert_eq!(r.try_recv(), Ok(1));
+        assert_eq!(r.recv(), Ok(2));
+        assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(r.recv_timeout(ms(100)), Err(RecvTimeoutError::Timeout));
+    }
+
+    drop(s);
+    for r in &rs {
+        assert!(r.is_closed());
+        assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(r.recv(), Err(RecvError));
+    }
+}
+
+#[test]
+fn disconnect_after_buffered() {
+    let (s, r) = bounded(1);
+    let rs = r.tee(2);
+
+    s.send(1).unwrap();
+    assert_eq!(rs[0].recv(), Ok(1));
+    s.send(2).unwrap();
+    assert_eq!(rs[0].recv(), Ok(2));
+    drop(s);
+
+    assert_eq!(rs[0].recv(), Err(RecvError));
+    assert_eq!(rs[1].len(), 2);
+    assert_eq!(rs[1].recv(), Ok(1));
+    assert_eq!(rs[1].recv(), Ok(2));
+    assert_eq!(rs[1].recv(), Err(RecvError));
+}
+
+#[test]
+fn recv_wakes_up() {
+    let (s, r) = bounded(0);
+    let rs = r.tee(2);
+
+    scope(|scope| {
+        for r in &rs {
+            scope.spawn(move |_| {
+                assert_eq!(r.recv(), Ok(1));
+                assert_eq!(r.recv(), Ok(2));
+                assert_eq!(r.recv(), Err(RecvError));
+            });
+        }
+
+        thread::sleep(ms(500));
+        s.send(1).unwrap();
+        thread::sleep(ms(500));
+        s.send(2).unwrap();
+        thread::sleep(ms(500));
+        drop(s);
+    })
+    .unwrap();
+}
+
+#[test]
+fn drop_output() {
+    let msg = Arc::new(());
+    let (s, r) = unbounded();
+    let mut rs = r.tee(2);
+    let r1 = rs.pop().unwrap();
+    let r0 = rs.pop().unwrap();
+
+    s.send(msg.clone()).unwrap();
+    assert!(r0.recv().is_ok());
+    assert_eq!(Arc::strong_count(&msg), 2);
+
+    // A dropped output lets go of its copies and stops getting new ones.
+    let w = r1.downgrade();
+    drop(r1);
+    assert_eq!(Arc::strong_count(&msg), 1);
+    assert!(w.upgrade().is_none());
+    s.send(msg.clone()).unwrap();
+    assert!(r0.recv().is_ok());
+    assert_eq!(Arc::strong_count(&msg), 1);
+
+    // The source is dropped along with the last output.
+    drop(r0);
+    assert!(s.send(msg.clone()).is_err());
+}
+
+#[test]
+fn clones_share_output() {
+    let (s, r) = unbounded();
+    let rs = r.tee(2);
+    let r0 = rs[0].clone();
+    assert_eq!(rs[0].receiver_count(), 2);
+    assert_eq!(rs[1].receiver_count(), 1);
+    assert!(r0.same_channel(&rs[0]));
+    assert!(!r0.same_channel(&rs[1]));
+
+    s.send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(r0.recv(), Ok(1));
+    assert_eq!(rs[0].recv(), Ok(2));
+    assert_eq!(rs[1].recv(), Ok(1));
+    assert_eq!(rs[1].recv(), Ok(2));
+
+    let w = rs[0].downgrade();
+    drop(rs);
+    let r = w.upgrade().unwrap();
+    assert_eq!(r.receiver_count(), 2);
+    s.send(3).unwrap();
+    assert_eq!(r0.recv(), Ok(3));
+}
+
+#[test]
+fn close() {
+    let (s, r) = unbounded();
+    let rs = r.tee(2);
+
+    s.send(1).unwrap();
+    assert!(rs[1].close());
+    assert!(s.send(2).is_err());
+    for r in &rs {
+        assert_eq!(r.recv(), Ok(1));
+        assert_eq!(r.recv(), Err(RecvError));
+    }
+}
+
+#[test]
+fn peek() {
+    let (s, r) = unbounded();
+    let rs = r.tee(2);
+
+    s.send(1).unwrap();
+    assert_eq!(rs[0].try_recv_if(|x| *x == 2), Ok(None));
+    assert_eq!(rs[1].try_peek_with(|x| *x), Ok(1));
+    assert_eq!(rs[0].try_recv_if(|x| *x == 1), Ok(Some(1)));
+    assert_eq!(rs[1].try_recv_if(|x| *x == 1), Ok(Some(1)));
+    assert_eq!(rs[1].try_recv_if(|_| true), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn panicking_clone() {
+    #[derive(Debug, PartialEq)]
+    struct Bomb(i32);
+
+    impl Clone for Bomb {
+        fn clone(&self) -> Bomb {
+            if self.0 == 1 {
+                panic!("boom");
+            }
+            Bomb(self.0)
+        }
+    }
+
+    let (s, r) = unbounded();
+    let rs = r.tee(2);
+
+    s.send(Bomb(1)).unwrap();
+    s.send(Bomb(2)).unwrap();
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| rs[0].try_recv())).is_err());
+
+    // The outputs are still usable after the panic.
+    assert_eq!(rs[0].try_recv(), Ok(Bomb(2)));
+    assert_eq!(rs[1].try_recv(), Ok(Bomb(2)));
+    assert_eq!(rs[1].try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn panicking_clone_fan_out() {
+    static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct Bomb(i32);
+
+    // Panics on the second clone.
+    impl Clone for Bomb {
+        fn clone(&self) -> Bomb {
+            if CLONES.fetch_add(1, Ordering::SeqCst) == 1 {
+                panic!("boom");
+            }
+            Bomb(self.0)
+        }
+    }
+
+    let (s, r) = unbounded();
+    let rs = r.tee(3);
+
+    s.send(Bomb(1)).unwrap();
+    s.send(Bomb(2)).unwrap();
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| rs[0].try_recv())).is_err());
+
+    // The second output got its copy before the panic, and the third one skips the message.
+    assert_eq!(rs[1].try_recv(), Ok(Bomb(1)));
+    assert_eq!(rs[2].try_recv(), Ok(Bomb(2)));
+    assert_eq!(rs[0].try_recv(), Ok(Bomb(2)));
+    assert_eq!(rs[1].try_recv(), Ok(Bomb(2)));
+    for r in &rs {
+        assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    }
+}
+
+#[test]
+fn slow_clone() {
+    #[derive(Debug, PartialEq)]
+    struct Slow(i32);
+
+    impl Clone for Slow {
+        fn clone(&self) -> Slow {
+            thread::sleep(ms(500));
+            Slow(self.0)
+        }
+    }
+
+    let (s, r) = unbounded();
+    let rs = r.tee(2);
+    s.send(Slow(1)).unwrap();
+
+    scope(|scope| {
+        scope.spawn(|_| assert_eq!(rs[0].try_recv(), Ok(Slow(1))));
+
+        // The other output waits for its copy without holding up the lock.
+        thread::sleep(ms(100));
+        assert_eq!(rs[1].try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(rs[1].len(), 1);
+        assert_eq!(rs[1].recv(), Ok(Slow(1)));
+    })
+    .unwrap();
+}
+
+#[test]
+fn select() {
+    let (s, r) = unbounded();
+    let rs = r.tee(2);
+    let (s2, r2) = unbounded::<i32>();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            s.send(1).unwrap();
+        });
+
+        for r in &rs {
+            select! {
+                recv(r) -> msg => assert_eq!(msg, Ok(1)),
+                recv(r2) -> _ => panic!(),
+            }
+        }
+    })
+    .unwrap();
+
+    drop(s2);
+    drop(s);
+    select! {
+        recv(rs[0]) -> msg => assert_eq!(msg, Err(RecvError)),
+        default(ms(1000)) => panic!(),
+    }
+}
+
+#[test]
+fn timers() {
+    let rs = tick(ms(100)).tee(2);
+    for r in &rs {
+        assert!(r.recv().is_ok());
+        assert!(r.recv().is_ok());
+    }
+}
+
+#[test]
+fn mpmc() {
+    const COUNT: usize = 25_000;
+    const OUTPUTS: usize = 3;
+    const THREADS: usize = 2;
+
+    for &cap in &[None, Some(0), Some(3)] {
+        let (s, r) = match cap {
+            None => unbounded(),
+            Some(cap) => bounded::<usize>(cap),
+        };
+        let rs = r.tee(OUTPUTS);
+        let received = (0..OUTPUTS * COUNT)
+            .map(|_| AtomicUsize::new(0))
+            .collect::<Vec<_>>();
+
+        scope(|scope| {
+            for (i, r) in rs.iter().enumerate() {
+                for _ in 0..THREADS {
+                    let received = &received;
+                    scope.spawn(move |_| {
+                        for n in r.iter() {
+                            received[i * COUNT + n].fetch_add(1, Ordering::SeqCst);
+                        }
+                    });
+                }
+            }
+            for _ in 0..THREADS {
+                let s = s.clone();
+                scope.spawn(move |_| {
+                    for i in 0..COUNT {
+                        s.send(i).unwrap();
+                    }
+                });
+            }
+            drop(s);
+        })
+        .unwrap();
+
+        for c in &received {
+            assert_eq!(c.load(Ordering::SeqCst), THREADS);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Tests for resettable timers.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{select, unbounded, RecvTimeoutError, Select, Timer, TryRecvError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn fire() {
+    let start = Instant::now();
+    let timer = Timer::after(ms(50));
+    let r = timer.receiver();
+
+    assert_eq!(timer.deadline().map(|d| d >= start + ms(50)), Some(true));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    thread::sleep(ms(100));
+
+    let fired = r.try_recv().unwrap();
+    assert!(fired - start >= ms(50));
+    assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(timer.deadline(), None);
+}
+
+#[test]
+fn rearm() {
+    let timer = Timer::at(Instant::now());
+    let r = timer.receiver();
+
+    for _ in 0..10 {
+        let when = Instant::now() + ms(10);
+        timer.reset(when);
+        assert_eq!(timer.deadline(), Some(when));
+        assert_eq!(r.recv(), Ok(when));
+        assert_eq!(r.try_recv(), Err(TryRecvError::Empty));
+    }
+}
+
+#[test]
+fn cancel() {
+    let timer = Timer::after(ms(50));
+    timer.cancel();
+
+    assert_eq!(timer.deadline(), None);
+    assert_eq!(
+        timer.receiver().recv_timeout(ms(200)),
+        Err(RecvTimeoutError::Timeout)
+    );
+
+    let when = Instant::now();
+    timer.reset(when);
+    assert_eq!(timer.receiver().try_recv(), Ok(when));
+}
+
+#[test]
+fn reset_blocked_recv() {
+    let start = Instant::now();
+    let timer = Timer::after(ms(100));
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            // Fires at the postponed deadline.
+            let fired = timer.receiver().recv().unwrap();
+            assert_eq!(fired, start + ms(1000));
+            assert!(Instant::now() >= start + ms(1000));
+
+            // Fires at the earlier deadline.
+            let fired = timer.receiver().recv().unwrap();
+            assert_eq!(fired, start + ms(1500));
+            assert!(Instant::now() < start + ms(2500));
+        });
+
+        thread::sleep(ms(50));
+        timer.reset(start + ms(1000));
+
+        thread::sleep(ms(1200));
+        timer.reset(start + ms(60_000));
+        thread::sleep(ms(100));
+        timer.reset(start + ms(1500));
+    })
+    .unwrap();
+}
+
+#[test]
+fn reset_blocked_select() {
+    let start = Instant::now();
+    let timer = Timer::after(ms(60_000));
+    let (_s, r) = unbounded::<i32>();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            select! {
+                recv(r) -> _ => panic!(),
+                recv(timer.receiver()) -> msg => assert_eq!(msg, Ok(start + ms(500))),
+            }
+            assert!(Instant::now() < start + ms(1500));
+        });
+
+        thread::sleep(ms(300));
+        timer.reset(start + ms(500));
+    })
+    .unwrap();
+}
+
+#[test]
+fn cancel_blocked_select() {
+    let timer = Timer::after(ms(500));
+    let (_s, r) = unbounded::<i32>();
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            select! {
+                recv(r) -> _ => panic!(),
+                recv(timer.receiver()) -> _ => panic!(),
+                default(ms(1500)) => {}
+            }
+        });
+
+        thread::sleep(ms(100));
+        timer.cancel();
+    })
+    .unwrap();
+}
+
+#[test]
+fn reuse_select() {
+    let timer = Timer::at(Instant::now());
+    let (s, r) = unbounded();
+
+    let mut sel = Select::new();
+    let msg_index = sel.recv(&r);
+    let timer_index = sel.recv(timer.receiver());
+
+    let oper = sel.select();
+    assert_eq!(oper.index(), timer_index);
+    assert!(oper.recv(timer.receiver()).is_ok());
+
+    for i in 0..5 {
+        s.send(i).unwrap();
+        timer.reset(Instant::now() + ms(50));
+
+        let oper = sel.select();
+        assert_eq!(oper.index(), msg_index);
+        assert_eq!(oper.recv(&r), Ok(i));
+
+        let oper = sel.select();
+        assert_eq!(oper.index(), timer_index);
+        assert!(oper.recv(timer.receiver()).is_ok());
+    }
+}
+
+#[test]
+fn cloned_receivers() {
+    const THREADS: usize = 4;
+
+    let timer = Timer::after(ms(60_000));
+
+    scope(|scope| {
+        for _ in 0..THREADS {
+            let r = timer.receiver().clone();
+            scope.spawn(move |_| {
+                select! {
+                    recv(r) -> _ => {}
+                    default(ms(2000)) => panic!(),
+                }
+            });
+        }
+
+        // Every re-armed message is received by exactly one of the threads.
+        for _ in 0..THREADS {
+            thread::sleep(ms(100));
+            timer.reset(Instant::now());
+        }
+    })
+    .unwrap();
+
+    assert_eq!(timer.receiver().try_recv(), Err(TryRecvError::Empty));
+}
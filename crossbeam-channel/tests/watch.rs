@@ -0,0 +1,207 @@
+//! Tests for watch channels.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{select, unbounded, watch, RecvError, Select, SendError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn smoke() {
+    let (s, r) = watch(1);
+    assert_eq!(*r.borrow(), 1);
+    assert!(!r.has_changed());
+
+    s.send(2).unwrap();
+    assert!(r.has_changed());
+    assert_eq!(*r.borrow(), 2);
+    assert!(r.has_changed());
+
+    assert_eq!(r.changed(), Ok(()));
+    assert!(!r.has_changed());
+    assert_eq!(*r.borrow(), 2);
+}
+
+#[test]
+fn latest_value() {
+    let (s, r) = watch(0);
+
+    for i in 1..=10 {
+        s.send(i).unwrap();
+    }
+
+    // Changes are coalesced.
+    assert_eq!(r.changed(), Ok(()));
+    assert_eq!(*r.borrow(), 10);
+    assert!(!r.has_changed());
+}
+
+#[test]
+fn send_modify() {
+    let (s, r) = watch(vec![1]);
+
+    s.send_modify(|v| v.push(2));
+    assert!(r.has_changed());
+    assert_eq!(*r.borrow(), [1, 2]);
+
+    drop(r);
+    s.send_modify(|v| v.push(3));
+}
+
+#[test]
+fn disconnect() {
+    let (s, r) = watch(1);
+    drop(r);
+    assert_eq!(s.send(2), Err(SendError(2)));
+
+    let (s, r) = watch(1);
+    s.send(2).unwrap();
+    drop(s);
+
+    // The last change is still observed before the disconnection.
+    assert_eq!(r.changed(), Ok(()));
+    assert_eq!(r.changed(), Err(RecvError));
+    assert_eq!(*r.borrow(), 2);
+}
+
+#[test]
+fn changed_blocks() {
+    let (s, r) = watch(0);
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            assert_eq!(r.changed(), Ok(()));
+            assert_eq!(*r.borrow(), 1);
+            assert_eq!(r.changed(), Err(RecvError));
+        });
+
+        thread::sleep(ms(500));
+        s.send(1).unwrap();
+        thread::sleep(ms(500));
+        drop(s);
+    })
+    .unwrap();
+}
+
+#[test]
+fn clone_receivers() {
+    let (s, r1) = watch(0);
+    s.send(1).unwrap();
+
+    let r2 = r1.clone();
+    assert!(r2.has_changed());
+
+    assert_eq!(r1.changed(), Ok(()));
+    assert!(!r1.has_changed());
+    assert!(r2.has_changed());
+
+    let r3 = r1.clone();
+    assert!(!r3.has_changed());
+
+    drop(r1);
+    drop(r2);
+    assert!(s.send(2).is_ok());
+    drop(r3);
+    assert_eq!(s.send(3), Err(SendError(3)));
+}
+
+#[test]
+fn panicking_modify() {
+    let (s, r) = watch(vec![1]);
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        s.send_modify(|v| {
+            v.push(2);
+            panic!("boom");
+        })
+    }));
+    assert!(res.is_err());
+
+    // The change made before the panic is kept, but receivers aren't notified of it.
+    assert!(!r.has_changed());
+    assert_eq!(*r.borrow(), [1, 2]);
+
+    // The channel is still usable.
+    s.send(vec![3]).unwrap();
+    assert_eq!(r.changed(), Ok(()));
+    assert_eq!(*r.borrow(), [3]);
+    s.send_modify(|v| v.push(4));
+    assert_eq!(*r.borrow(), [3, 4]);
+}
+
+#[test]
+fn select() {
+    let (s, r) = watch(0);
+    let (_ts, tr) = unbounded::<i32>();
+
+    select! {
+        recv(tr) -> _ => panic!(),
+        recv_custom(r) -> _ => panic!(),
+        default => {}
+    }
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            thread::sleep(ms(500));
+            s.send(1).unwrap();
+        });
+
+        select! {
+            recv(tr) -> _ => panic!(),
+            recv_custom(r) -> res => assert_eq!(res, Ok(())),
+            default(ms(2000)) => panic!(),
+        }
+    })
+    .unwrap();
+
+    assert_eq!(*r.borrow(), 1);
+    assert!(!r.has_changed());
+
+    let mut sel = Select::new();
+    let index = sel.recv_custom(&r);
+    assert!(sel.try_select().is_err());
+
+    drop(s);
+    let oper = sel.try_select().unwrap();
+    assert_eq!(oper.index(), index);
+    assert_eq!(oper.recv_custom(&r), Err(RecvError));
+}
+
+#[test]
+fn many_receivers() {
+    const THREADS: usize = 8;
+    const COUNT: usize = 1000;
+
+    let (s, r) = watch(0);
+    let seen = AtomicUsize::new(0);
+
+    scope(|scope| {
+        for _ in 0..THREADS {
+            let r = r.clone();
+            let seen = &seen;
+            scope.spawn(move |_| {
+                let mut last = 0;
+                while r.changed().is_ok() {
+                    let v = *r.borrow();
+                    assert!(v >= last);
+                    last = v;
+                }
+                assert_eq!(last, COUNT);
+                seen.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+
+        for i in 1..=COUNT {
+            s.send(i).unwrap();
+        }
+        drop(s);
+    })
+    .unwrap();
+
+    assert_eq!(seen.load(Ordering::SeqCst), THREADS);
+}
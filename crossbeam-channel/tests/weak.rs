@@ -0,0 +1,185 @@
+//! Tests for weak senders and receivers.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crossbeam_channel::{after, bounded, broadcast, never, tick, unbounded};
+use crossbeam_channel::{RecvError, SendError, TryRecvError};
+use crossbeam_utils::thread::scope;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn upgrade() {
+    let (s, r) = unbounded();
+    let ws = s.downgrade();
+    let wr = r.downgrade();
+
+    let s2 = ws.upgrade().unwrap();
+    let r2 = wr.upgrade().unwrap();
+    assert!(s2.same_channel(&s));
+    assert!(r2.same_channel(&r));
+
+    s2.send(1).unwrap();
+    assert_eq!(r2.recv(), Ok(1));
+}
+
+#[test]
+fn weak_sender_does_not_keep_channel_connected() {
+    let (s, r) = unbounded();
+    let w = s.downgrade();
+    let w2 = w.clone();
+
+    s.send(1).unwrap();
+    drop(s);
+
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv(), Err(RecvError));
+    assert!(w.upgrade().is_none());
+    assert!(w2.upgrade().is_none());
+
+    let (s, r) = bounded::<i32>(1);
+    let w = s.downgrade();
+    drop(s);
+    assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    assert!(w.upgrade().is_none());
+
+    let (s, r) = bounded::<i32>(0);
+    let w = s.downgrade();
+    drop(s);
+    assert_eq!(r.try_recv(), Err(TryRecvError::Disconnected));
+    assert!(w.upgrade().is_none());
+}
+
+#[test]
+fn weak_receiver_does_not_keep_channel_connected() {
+    let (s, r) = unbounded();
+    let w = r.downgrade();
+    drop(r);
+    assert_eq!(s.send(1), Err(SendError(1)));
+    assert!(w.upgrade().is_none());
+
+    let (s, r) = bounded(1);
+    let w = r.downgrade();
+    drop(r);
+    assert_eq!(s.send(1), Err(SendError(1)));
+    assert!(w.upgrade().is_none());
+
+    let (s, r) = bounded(0);
+    let w = r.downgrade();
+    drop(r);
+    assert_eq!(s.send(1), Err(SendError(1)));
+    assert!(w.upgrade().is_none());
+}
+
+#[test]
+fn upgrade_after_other_side_dropped() {
+    let (s, r) = bounded::<i32>(1);
+    let w = s.downgrade();
+    drop(r);
+    assert!(w.upgrade().is_none());
+
+    let (s, r) = unbounded::<i32>();
+    let w = r.downgrade();
+    drop(s);
+    assert!(w.upgrade().is_none());
+    assert!(r.is_closed());
+}
+
+#[test]
+fn upgrade_after_close() {
+    let (s, r) = unbounded::<i32>();
+    let ws = s.downgrade();
+    let wr = r.downgrade();
+
+    assert!(s.close());
+    assert!(ws.upgrade().is_none());
+    assert!(wr.upgrade().is_none());
+}
+
+#[test]
+fn weak_outlives_channel() {
+    let (s, r) = bounded(10);
+    let ws = s.downgrade();
+    let wr = r.downgrade();
+
+    s.send(Box::new(1)).unwrap();
+    drop(s);
+    drop(r);
+
+    assert!(ws.upgrade().is_none());
+    assert!(wr.upgrade().is_none());
+    drop(ws);
+    drop(wr);
+}
+
+#[test]
+fn broadcast_receiver() {
+    let (s, r) = broadcast(10);
+    let w = r.downgrade();
+
+    s.send(1).unwrap();
+
+    // Upgraded receivers only see messages sent after the upgrade.
+    let r2 = w.upgrade().unwrap();
+    assert_eq!(r2.try_recv(), Err(TryRecvError::Empty));
+    s.send(2).unwrap();
+    assert_eq!(r2.recv(), Ok(2));
+    assert_eq!(r.recv(), Ok(1));
+    assert_eq!(r.recv(), Ok(2));
+
+    let ws = s.downgrade();
+    drop(r);
+    drop(r2);
+    assert!(w.upgrade().is_none());
+    assert!(ws.upgrade().is_none());
+}
+
+#[test]
+fn special_channels() {
+    let w = after(ms(0)).downgrade();
+    assert!(w.upgrade().unwrap().recv().is_ok());
+
+    let w = tick(ms(10)).downgrade();
+    assert!(w.upgrade().unwrap().recv().is_ok());
+
+    let w = never::<i32>().downgrade();
+    assert_eq!(
+        w.clone().upgrade().unwrap().try_recv(),
+        Err(TryRecvError::Empty)
+    );
+}
+
+#[test]
+fn concurrent_upgrade() {
+    const COUNT: usize = 10_000;
+    const THREADS: usize = 4;
+
+    let (s, r) = unbounded();
+    let w = s.downgrade();
+    let sent = AtomicUsize::new(0);
+
+    scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|_| {
+                while let Some(s) = w.upgrade() {
+                    if s.send(()).is_ok() {
+                        sent.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            });
+        }
+
+        for _ in 0..COUNT {
+            r.recv().unwrap();
+        }
+        drop(s);
+        drop(r);
+    })
+    .unwrap();
+
+    assert!(w.upgrade().is_none());
+    assert!(sent.load(Ordering::SeqCst) >= COUNT);
+}
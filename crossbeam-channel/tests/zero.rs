@@ -1,6 +1,7 @@
 //! Tests for the zero channel flavor.
 
 use std::any::Any;
+use std::collections::VecDeque;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::thread;
@@ -185,6 +186,53 @@ fn send_timeout() {
     .unwrap();
 }
 
+#[test]
+fn recv_many() {
+    let (s, r) = bounded(0);
+    let mut buf = Vec::new();
+
+    assert_eq!(r.try_recv_many(&mut buf, 10), Err(TryRecvError::Empty));
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            for i in 0..3 {
+                s.send(i).unwrap();
+            }
+            drop(s);
+        });
+
+        let mut count = 0;
+        while let Ok(n) = r.recv_many(&mut buf, 10) {
+            count += n;
+        }
+        assert_eq!(count, 3);
+        assert_eq!(buf, [0, 1, 2]);
+    })
+    .unwrap();
+}
+
+#[test]
+fn send_many() {
+    let (s, r) = bounded(0);
+    let mut msgs = (0..3).collect::<VecDeque<_>>();
+
+    assert_eq!(s.try_send_many(&mut msgs), Err(TrySendError::Full(())));
+    assert_eq!(msgs, [0, 1, 2]);
+
+    scope(|scope| {
+        scope.spawn(|_| {
+            assert_eq!(r.recv(), Ok(0));
+            assert_eq!(r.iter().collect::<Vec<_>>(), (1..10).collect::<Vec<_>>());
+        });
+        scope.spawn(|_| {
+            thread::sleep(ms(1000));
+            assert_eq!(s.send_iter(0..10), Ok(()));
+            drop(s);
+        });
+    })
+    .unwrap();
+}
+
 #[test]
 fn len() {
     const COUNT: usize = 25_000;